@@ -28,6 +28,13 @@ fn linear_from_gamma(srgb: vec3<f32>) -> vec3<f32> {
     return select(higher, lower, cutoff);
 }
 
+fn gamma_from_linear(rgb: vec3<f32>) -> vec3<f32> {
+    let cutoff = rgb < vec3<f32>(0.0031308);
+    let lower = rgb * vec3<f32>(12.92);
+    let higher = vec3<f32>(1.055) * pow(rgb, vec3<f32>(1.0 / 2.4)) - vec3<f32>(0.055);
+    return select(higher, lower, cutoff);
+}
+
 @vertex
 fn vs_main(
     @builtin(vertex_index) v_index: u32,
@@ -45,14 +52,42 @@ fn vs_main(
     return out;
 }
 
+const COLOR_TRANSFORM_SRGB_ENCODE: u32 = 1u;
+const COLOR_TRANSFORM_TONEMAP: u32 = 2u;
+
+struct TextureParams {
+    color_transform: u32,
+    exposure: f32,
+    padding: vec2<f32>,
+};
+var<uniform> r_texture_params: TextureParams;
+
 var r_texture: texture_2d<f32>;
 var r_sampler: sampler;
 
+// Bring the texel to the sRGB encoding of the egui textures.
+fn encode_texel(texel: vec4<f32>) -> vec4<f32> {
+    var color = max(texel.xyz, vec3<f32>(0.0));
+    switch (r_texture_params.color_transform) {
+        case COLOR_TRANSFORM_SRGB_ENCODE: {
+            color = gamma_from_linear(min(color, vec3<f32>(1.0)));
+        }
+        case COLOR_TRANSFORM_TONEMAP: {
+            let exposed = color * r_texture_params.exposure;
+            color = gamma_from_linear(exposed / (vec3<f32>(1.0) + exposed));
+        }
+        default: {
+            color = texel.xyz;
+        }
+    }
+    return vec4<f32>(color, texel.a);
+}
+
 @fragment
 fn fs_main(in: VertexOutput) -> @location(0) vec4<f32> {
     //Note: we always assume rendering to linear color space,
     // but Egui wants to blend in gamma space, see
     // https://github.com/emilk/egui/pull/2071
-    let blended = in.color * textureSample(r_texture, r_sampler, in.tex_coord);
+    let blended = in.color * encode_texel(textureSample(r_texture, r_sampler, in.tex_coord));
     return vec4f(linear_from_gamma(blended.xyz), blended.a);
 }
@@ -34,11 +34,50 @@ struct Globals {
     r_uniforms: Uniforms,
 }
 
+#[repr(C)]
+#[derive(Clone, Copy, bytemuck::Zeroable, bytemuck::Pod)]
+struct TextureParams {
+    color_transform: u32,
+    exposure: f32,
+    padding: [f32; 2],
+}
+
 #[derive(blade_macros::ShaderData)]
 struct Locals {
     r_vertex_data: blade_graphics::BufferPiece,
     r_texture: blade_graphics::TextureView,
     r_sampler: blade_graphics::Sampler,
+    r_texture_params: TextureParams,
+}
+
+/// Conversion of the texels of a user texture into the sRGB-encoded
+/// colors that egui works with, applied when sampling the texture.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum ColorTransform {
+    /// The texture is already sRGB-encoded, like the textures of egui.
+    #[default]
+    None,
+    /// The texture has linear colors in the [0, 1] range.
+    SrgbEncode,
+    /// The texture has linear HDR colors, like the output of a renderer.
+    /// They are scaled by the exposure and tone mapped with Reinhard
+    /// before the encoding.
+    Tonemap { exposure: f32 },
+}
+
+impl ColorTransform {
+    fn params(&self) -> TextureParams {
+        let (color_transform, exposure) = match *self {
+            Self::None => (0, 1.0),
+            Self::SrgbEncode => (1, 1.0),
+            Self::Tonemap { exposure } => (2, exposure),
+        };
+        TextureParams {
+            color_transform,
+            exposure,
+            padding: [0.0; 2],
+        }
+    }
 }
 
 #[derive(Debug, PartialEq)]
@@ -59,6 +98,7 @@ struct GuiTexture {
     allocation: blade_graphics::Texture,
     view: blade_graphics::TextureView,
     sampler: blade_graphics::Sampler,
+    color_transform: ColorTransform,
 }
 
 #[inline]
@@ -96,7 +136,20 @@ impl GuiTexture {
                 subresources: &blade_graphics::TextureSubresources::default(),
             },
         );
-        let sampler = context.create_sampler(blade_graphics::SamplerDesc {
+        Self {
+            allocation,
+            view,
+            sampler: Self::create_sampler(context, name, options),
+            color_transform: ColorTransform::None,
+        }
+    }
+
+    fn create_sampler(
+        context: &blade_graphics::Context,
+        name: &str,
+        options: egui::TextureOptions,
+    ) -> blade_graphics::Sampler {
+        context.create_sampler(blade_graphics::SamplerDesc {
             name,
             address_modes: {
                 let mode = match options.wrap_mode {
@@ -116,12 +169,7 @@ impl GuiTexture {
                 .unwrap_or_default(),
 
             ..Default::default()
-        });
-        Self {
-            allocation,
-            view,
-            sampler,
-        }
+        })
     }
 
     fn delete(self, context: &blade_graphics::Context) {
@@ -144,6 +192,7 @@ pub struct GuiPainter {
     //TODO: this could also look better
     textures_dropped: Vec<GuiTexture>,
     textures_to_delete: Vec<(GuiTexture, blade_graphics::SyncPoint)>,
+    next_user_texture_id: u64,
 }
 
 impl GuiPainter {
@@ -151,7 +200,10 @@ impl GuiPainter {
     pub fn destroy(&mut self, context: &blade_graphics::Context) {
         context.destroy_render_pipeline(&mut self.pipeline);
         self.belt.destroy(context);
-        for (_, gui_texture) in self.textures.drain() {
+        for (texture_id, gui_texture) in self.textures.drain() {
+            if let egui::TextureId::User(_) = texture_id {
+                log::warn!("User texture {:?} is still registered", texture_id);
+            }
             gui_texture.delete(context);
         }
         for gui_texture in self.textures_dropped.drain(..) {
@@ -215,6 +267,7 @@ impl GuiPainter {
             textures: Default::default(),
             textures_dropped: Vec::new(),
             textures_to_delete: Vec::new(),
+            next_user_texture_id: 0,
         }
     }
 
@@ -226,8 +279,45 @@ impl GuiPainter {
             .position(|&(_, ref sp)| !context.wait_for(sp, 0))
             .unwrap_or_default();
         for (texture, _) in self.textures_to_delete.drain(..valid_pos) {
-            context.destroy_texture_view(texture.view);
-            context.destroy_texture(texture.allocation);
+            texture.delete(context);
+        }
+    }
+
+    /// Register a texture to be drawn by egui, e.g. with `egui::Image`.
+    ///
+    /// The painter takes ownership of the texture and its view,
+    /// and releases them after `unregister_texture` once the GPU is done with them.
+    /// The `color_transform` brings the contents to what egui expects,
+    /// e.g. for showing an HDR image produced by a renderer.
+    pub fn register_texture(
+        &mut self,
+        texture: blade_graphics::Texture,
+        view: blade_graphics::TextureView,
+        options: egui::TextureOptions,
+        color_transform: ColorTransform,
+        context: &blade_graphics::Context,
+    ) -> egui::TextureId {
+        let index = self.next_user_texture_id;
+        self.next_user_texture_id += 1;
+        let name = format!("egui_user_image_{}", index);
+        let gui_texture = GuiTexture {
+            allocation: texture,
+            view,
+            sampler: GuiTexture::create_sampler(context, &name, options),
+            color_transform,
+        };
+        self.textures
+            .insert(egui::TextureId::User(index), gui_texture);
+        egui::TextureId::User(index)
+    }
+
+    /// Unregister a texture previously returned by `register_texture`.
+    ///
+    /// Its resources are released after the next submission is done on the GPU.
+    pub fn unregister_texture(&mut self, texture_id: egui::TextureId) {
+        match self.textures.remove(&texture_id) {
+            Some(texture) => self.textures_dropped.push(texture),
+            None => log::error!("Texture {:?} is not registered", texture_id),
         }
     }
 
@@ -267,11 +357,7 @@ impl GuiPainter {
             };
 
             let image_size = image_delta.image.size();
-            let extent = blade_graphics::Extent {
-                width: image_size[0] as u32,
-                height: image_size[1] as u32,
-                depth: 1,
-            };
+            let extent = blade_graphics::Extent::new_2d(image_size[0] as u32, image_size[1] as u32);
 
             let label = match texture_id {
                 egui::TextureId::Managed(m) => format!("egui_image_{}", m),
@@ -324,6 +410,12 @@ impl GuiPainter {
 
     /// Render the set of clipped primitives into a render pass.
     /// The `sd` must contain dimensions of the render target.
+    ///
+    /// The painting is state-neutral, so other draws can share the pass
+    /// before or after it. The blending is a part of each pipeline, so
+    /// egui's premultiplied alpha doesn't leak into the following draws.
+    /// The viewport and the scissor cover the whole target when it returns,
+    /// regardless of what they were before, since egui sets them as it goes.
     #[profiling::function]
     pub fn paint(
         &mut self,
@@ -333,7 +425,22 @@ impl GuiPainter {
         context: &blade_graphics::Context,
     ) {
         let logical_size = sd.logical_size();
+        let full_viewport = blade_graphics::Viewport {
+            x: 0.0,
+            y: 0.0,
+            w: sd.physical_size.0 as f32,
+            h: sd.physical_size.1 as f32,
+            depth: 0.0..1.0,
+        };
+        let full_scissor = blade_graphics::ScissorRect {
+            x: 0,
+            y: 0,
+            w: sd.physical_size.0,
+            h: sd.physical_size.1,
+        };
         let mut pc = pass.with(&self.pipeline);
+        // the pass may have been drawn into a smaller region before
+        pc.set_viewport(&full_viewport);
         pc.bind(
             0,
             &Globals {
@@ -383,6 +490,7 @@ impl GuiPainter {
                         r_vertex_data: vertex_buf,
                         r_texture: texture.view,
                         r_sampler: texture.sampler,
+                        r_texture_params: texture.color_transform.params(),
                     },
                 );
 
@@ -396,6 +504,10 @@ impl GuiPainter {
                 );
             }
         }
+
+        // leave the pass the way it started, for the draws that follow
+        pc.set_scissor_rect(&full_scissor);
+        pc.set_viewport(&full_viewport);
     }
 
     /// Call this after submitting work at the given `sync_point`.
@@ -272,6 +272,15 @@ impl crate::traits::CommandEncoder for super::CommandEncoder {
 
     fn init_texture(&mut self, _texture: super::Texture) {}
 
+    fn insert_barrier(
+        &mut self,
+        _texture: super::Texture,
+        _from: crate::TextureUsage,
+        _to: crate::TextureUsage,
+    ) {
+        self.commands.push(super::Command::Barrier);
+    }
+
     fn present(&mut self, frame: super::Frame) {
         self.present_frames.push(frame.platform);
     }
@@ -944,13 +953,22 @@ impl super::Command {
                 }
                 super::TextureInner::Texture { raw, target } => {
                     let mip_level = 0; //TODO
-                    gl.framebuffer_texture_2d(
-                        glow::DRAW_FRAMEBUFFER,
-                        attachment,
-                        target,
-                        Some(raw),
-                        mip_level,
-                    );
+                    match target {
+                        glow::TEXTURE_2D_ARRAY | glow::TEXTURE_3D => gl.framebuffer_texture_layer(
+                            glow::DRAW_FRAMEBUFFER,
+                            attachment,
+                            Some(raw),
+                            mip_level,
+                            view.array_layer as i32,
+                        ),
+                        _ => gl.framebuffer_texture_2d(
+                            glow::DRAW_FRAMEBUFFER,
+                            attachment,
+                            target,
+                            Some(raw),
+                            mip_level,
+                        ),
+                    }
                 }
             },
             Self::InvalidateAttachment(attachment) => {
@@ -1048,7 +1066,12 @@ impl super::Command {
                 (None, Some(s)) => gl.clear_buffer_i32_slice(glow::STENCIL, 0, &[s as i32]),
                 (None, None) => (),
             },
-            Self::Barrier => unimplemented!(),
+            Self::Barrier => {
+                // WebGL2 has no memory barriers
+                if !cfg!(target_arch = "wasm32") {
+                    gl.memory_barrier(glow::ALL_BARRIER_BITS);
+                }
+            }
             Self::SetViewport(ref vp) => {
                 gl.viewport(vp.x as i32, vp.y as i32, vp.w as i32, vp.h as i32);
                 gl.depth_range_f32(vp.depth.start, vp.depth.end);
@@ -1182,3 +1205,29 @@ fn map_primitive_topology(topology: crate::PrimitiveTopology) -> u32 {
         Pt::TriangleStrip => glow::TRIANGLE_STRIP,
     }
 }
+
+impl super::BundleEncoder {
+    pub fn start(&mut self) {}
+
+    pub fn render(
+        &mut self,
+        _desc: crate::RenderBundleDesc,
+    ) -> super::PassEncoder<super::RenderPipeline> {
+        unimplemented!("Command bundles are not supported")
+    }
+
+    pub fn finish(&mut self) -> super::CommandBundle {
+        super::CommandBundle {}
+    }
+}
+
+impl super::CommandEncoder {
+    pub fn execute_bundles(
+        &mut self,
+        _label: &str,
+        _targets: crate::RenderTargetSet,
+        _bundles: &[super::CommandBundle],
+    ) {
+        unimplemented!("Command bundles are not supported")
+    }
+}
@@ -284,6 +284,7 @@ impl super::Context {
                 },
                 renderbuf: guard.create_renderbuffer().unwrap(),
                 framebuf: guard.create_framebuffer().unwrap(),
+                frame_number: 0,
             }
         })
     }
@@ -320,6 +321,9 @@ impl super::Context {
     }
 
     pub fn reconfigure_surface(&self, surface: &mut super::Surface, config: crate::SurfaceConfig) {
+        if config.usage != crate::TextureUsage::TARGET {
+            log::warn!("Surface usage {:?} is not supported", config.usage);
+        }
         use raw_window_handle::RawWindowHandle as Rwh;
 
         let (mut temp_xlib_handle, mut temp_xcb_handle);
@@ -401,10 +405,11 @@ impl super::Context {
                 attributes.push(egl::GL_COLORSPACE_SRGB);
             }
         }
-        let alpha = if config.transparent {
-            crate::AlphaMode::PreMultiplied //TODO: verify
-        } else {
-            crate::AlphaMode::Ignored
+        let alpha = match config.composite_alpha {
+            crate::CompositeAlpha::Opaque => crate::AlphaMode::Ignored,
+            crate::CompositeAlpha::PreMultiplied | crate::CompositeAlpha::PostMultiplied => {
+                crate::AlphaMode::PreMultiplied //TODO: verify
+            }
         };
         attributes.push(egl::ATTRIB_NONE as i32);
 
@@ -525,7 +530,23 @@ impl super::Surface {
         self.platform.swapchain.as_ref().unwrap().info
     }
 
+    /// Get the usages of the frame textures. Only `TextureUsage::TARGET`
+    /// is supported, since the frames are backed by a renderbuffer.
+    pub fn usage(&self) -> crate::TextureUsage {
+        crate::TextureUsage::TARGET
+    }
+
+    /// Get the current size of the surface images.
+    ///
+    /// It may differ from the window size, e.g. while the window is being
+    /// moved between displays with different scale factors.
+    pub fn extent(&self) -> crate::Extent {
+        self.platform.swapchain.as_ref().unwrap().extent
+    }
+
     pub fn acquire_frame(&mut self) -> super::Frame {
+        let frame_number = self.frame_number;
+        self.frame_number += 1;
         let sc = self.platform.swapchain.as_ref().unwrap();
         super::Frame {
             platform: PlatformFrame {
@@ -539,6 +560,7 @@ impl super::Surface {
                 target_size: [sc.extent.width as u16, sc.extent.height as u16],
                 format: sc.info.format,
             },
+            frame_number,
         }
     }
 }
@@ -784,7 +806,8 @@ impl EglContext {
             is_software_emulated: false,
             device_name: vendor,
             driver_name: renderer,
-            driver_info: version,
+            driver_info: version.clone(),
+            driver_version: version,
         };
 
         let mut capabilities = super::Capabilities::empty();
@@ -819,6 +842,8 @@ impl EglContext {
         let limits = super::Limits {
             uniform_buffer_alignment: gl.get_parameter_i32(glow::UNIFORM_BUFFER_OFFSET_ALIGNMENT)
                 as u32,
+            max_array_texture_layers: gl.get_parameter_i32(glow::MAX_ARRAY_TEXTURE_LAYERS) as u32,
+            max_texture_size: gl.get_parameter_i32(glow::MAX_TEXTURE_SIZE) as u32,
         };
         (gl, capabilities, toggles, device_information, limits)
     }
@@ -25,6 +25,8 @@ bitflags::bitflags! {
 #[derive(Clone, Debug)]
 struct Limits {
     uniform_buffer_alignment: u32,
+    max_array_texture_layers: u32,
+    max_texture_size: u32,
 }
 
 #[derive(Debug, Default)]
@@ -45,6 +47,7 @@ pub struct Surface {
     platform: platform::PlatformSurface,
     renderbuf: glow::Renderbuffer,
     framebuf: glow::Framebuffer,
+    frame_number: u64,
 }
 
 #[derive(Clone, Copy, Debug, Hash, PartialEq)]
@@ -61,6 +64,16 @@ impl Buffer {
     pub fn data(&self) -> *mut u8 {
         self.data
     }
+
+    /// Get the native GL buffer object.
+    ///
+    /// # Safety
+    /// The buffer remains owned by Blade: it must not be deleted,
+    /// re-specified, or left mapped or bound when Blade records commands.
+    #[cfg(feature = "interop")]
+    pub unsafe fn raw_gles(&self) -> glow::Buffer {
+        self.raw
+    }
 }
 
 #[derive(Clone, Copy, Debug, Hash, PartialEq)]
@@ -92,11 +105,29 @@ pub struct Texture {
     format: crate::TextureFormat,
 }
 
+impl Texture {
+    /// Get the native GL texture object.
+    /// Returns `None` if the texture is backed by a renderbuffer.
+    ///
+    /// # Safety
+    /// The texture remains owned by Blade: it must not be deleted
+    /// or have its storage re-specified.
+    #[cfg(feature = "interop")]
+    pub unsafe fn raw_gles(&self) -> Option<glow::Texture> {
+        match self.inner {
+            TextureInner::Renderbuffer { .. } => None,
+            TextureInner::Texture { raw, .. } => Some(raw),
+        }
+    }
+}
+
 #[derive(Clone, Copy, Debug, Hash, PartialEq)]
 pub struct TextureView {
     inner: TextureInner,
     target_size: [u16; 2],
     aspects: crate::TexelAspects,
+    // attached as a render target, if the texture has layers
+    array_layer: u32,
 }
 
 #[derive(Clone, Copy, Debug, Hash, PartialEq)]
@@ -147,20 +178,38 @@ pub struct RenderPipeline {
 pub struct Frame {
     platform: platform::PlatformFrame,
     texture: Texture,
+    frame_number: u64,
 }
 
 impl Frame {
+    /// Texture of the frame. Only valid until the frame is presented,
+    /// so it shouldn't be kept across frames.
     pub fn texture(&self) -> Texture {
         self.texture
     }
 
+    /// View of the frame texture, valid until the frame is presented.
     pub fn texture_view(&self) -> TextureView {
         TextureView {
             inner: self.texture.inner,
             target_size: self.texture.target_size,
             aspects: crate::TexelAspects::COLOR,
+            array_layer: 0,
         }
     }
+
+    /// Index of the surface image of this frame. It's always zero,
+    /// since all the frames are rendered into the same renderbuffer.
+    pub fn image_index(&self) -> u32 {
+        0
+    }
+
+    /// Number of frames acquired from the surface before this one.
+    ///
+    /// Keeps increasing across surface reconfigurations.
+    pub fn frame_number(&self) -> u64 {
+        self.frame_number
+    }
 }
 
 #[derive(Clone, Debug)]
@@ -372,6 +421,10 @@ struct TimingData {
     queries: Box<[glow::Query]>,
 }
 
+pub struct BundleEncoder {}
+#[derive(Clone, Copy, Debug)]
+pub struct CommandBundle {}
+
 pub struct CommandEncoder {
     name: String,
     commands: Vec<Command>,
@@ -445,12 +498,73 @@ impl Context {
     pub fn capabilities(&self) -> crate::Capabilities {
         crate::Capabilities {
             ray_query: crate::ShaderVisibility::empty(),
+            sparse_textures: false,
+            command_bundles: false,
+            max_texture_array_layers: self.limits.max_array_texture_layers,
+            max_texture_2d_size: self.limits.max_texture_size,
         }
     }
 
+    pub fn get_sparse_tile_size(&self, _texture: super::Texture) -> crate::Extent {
+        unimplemented!("Sparse textures are not supported")
+    }
+
+    pub fn update_sparse(
+        &self,
+        _texture: super::Texture,
+        _mappings: &[crate::TileMapping],
+    ) -> Result<(), crate::MemoryError> {
+        unimplemented!("Sparse textures are not supported")
+    }
+
+    pub fn create_bundle_encoder(&self, _desc: super::CommandEncoderDesc) -> BundleEncoder {
+        unimplemented!("Command bundles are not supported")
+    }
+
+    pub fn destroy_bundle_encoder(&self, _bundle_encoder: &mut BundleEncoder) {}
+
     pub fn device_information(&self) -> &crate::DeviceInformation {
         &self.device_information
     }
+
+    pub fn backend(&self) -> crate::Backend {
+        crate::Backend::Gles
+    }
+
+    /// Create a buffer. The memory budget is not enforced on this backend.
+    pub fn try_create_buffer(&self, desc: crate::BufferDesc) -> Result<Buffer, crate::MemoryError> {
+        Ok(self.create_buffer(desc))
+    }
+
+    /// Create a texture. The memory budget is not enforced on this backend.
+    pub fn try_create_texture(
+        &self,
+        desc: crate::TextureDesc,
+    ) -> Result<Texture, crate::MemoryError> {
+        Ok(self.create_texture(desc))
+    }
+
+    /// Create an acceleration structure. The memory budget is not enforced on this backend.
+    pub fn try_create_acceleration_structure(
+        &self,
+        desc: crate::AccelerationStructureDesc,
+    ) -> Result<AccelerationStructure, crate::MemoryError> {
+        Ok(self.create_acceleration_structure(desc))
+    }
+
+    /// Frame capture is not supported on GLES.
+    pub fn trigger_capture(&self) {
+        log::warn!("Capture is not supported on GLES");
+    }
+
+    /// Block until all the submitted work is finished on the GPU.
+    ///
+    /// Useful for cleanup paths, where every resource is about to be freed.
+    pub fn wait_idle(&self) {
+        use glow::HasContext as _;
+        let gl = self.lock();
+        unsafe { gl.finish() };
+    }
 }
 
 #[hidden_trait::expose]
@@ -505,11 +619,19 @@ impl crate::traits::CommandDevice for Context {
     }
 
     fn submit(&self, encoder: &mut CommandEncoder) -> SyncPoint {
+        self.submit_with_waits(encoder, &[])
+    }
+
+    fn submit_with_waits(&self, encoder: &mut CommandEncoder, waits: &[SyncPoint]) -> SyncPoint {
         use glow::HasContext as _;
 
         let fence = {
             let gl = self.lock();
             encoder.finish(&gl);
+            for sp in waits {
+                // blocks the GL server, not the client
+                unsafe { gl.wait_sync(sp.fence, 0, glow::TIMEOUT_IGNORED) };
+            }
 
             let push_group = !encoder.name.is_empty() && gl.supports_debug();
             let ec = unsafe {
@@ -118,6 +118,7 @@ impl crate::traits::ResourceDevice for super::Context {
     }
 
     fn create_texture(&self, desc: crate::TextureDesc) -> super::Texture {
+        desc.validate(self.limits.max_array_texture_layers);
         let gl = self.lock();
         let format_desc = super::describe_texture_format(desc.format);
 
@@ -174,11 +175,12 @@ impl crate::traits::ResourceDevice for super::Context {
                 }
                 crate::TextureDimension::D2 => {
                     if desc.array_layer_count > 1 {
-                        if desc.sample_count <= 1 {
-                            glow::TEXTURE_2D_ARRAY
-                        } else {
-                            glow::TEXTURE_2D_MULTISAMPLE_ARRAY
-                        }
+                        assert_eq!(
+                            desc.sample_count, 1,
+                            "Texture array '{}' can't be multi-sampled",
+                            desc.name
+                        );
+                        glow::TEXTURE_2D_ARRAY
                     } else {
                         if desc.sample_count <= 1 {
                             glow::TEXTURE_2D
@@ -213,6 +215,16 @@ impl crate::traits::ResourceDevice for super::Context {
                             desc.size.depth as i32,
                         );
                     }
+                    crate::TextureDimension::D2 if desc.array_layer_count > 1 => {
+                        gl.tex_storage_3d(
+                            target,
+                            desc.mip_level_count as i32,
+                            format_desc.internal,
+                            desc.size.width as i32,
+                            desc.size.height as i32,
+                            desc.array_layer_count as i32,
+                        );
+                    }
                     crate::TextureDimension::D2 => {
                         if desc.sample_count <= 1 {
                             gl.tex_storage_2d(
@@ -283,6 +295,7 @@ impl crate::traits::ResourceDevice for super::Context {
             inner: texture.inner,
             target_size: texture.target_size,
             aspects: desc.format.aspects(),
+            array_layer: desc.subresources.base_array_layer,
         }
     }
 
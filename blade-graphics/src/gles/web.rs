@@ -23,7 +23,22 @@ impl super::Surface {
     pub fn info(&self) -> crate::SurfaceInfo {
         self.platform.info
     }
-    pub fn acquire_frame(&self) -> super::Frame {
+    /// Get the usages of the frame textures. Only `TextureUsage::TARGET`
+    /// is supported, since the frames are backed by a renderbuffer.
+    pub fn usage(&self) -> crate::TextureUsage {
+        crate::TextureUsage::TARGET
+    }
+
+    /// Get the current size of the surface images.
+    ///
+    /// It may differ from the window size, e.g. while the window is being
+    /// moved between displays with different scale factors.
+    pub fn extent(&self) -> crate::Extent {
+        self.platform.extent
+    }
+    pub fn acquire_frame(&mut self) -> super::Frame {
+        let frame_number = self.frame_number;
+        self.frame_number += 1;
         let size = self.platform.extent;
         super::Frame {
             platform: PlatformFrame {
@@ -37,6 +52,7 @@ impl super::Surface {
                 target_size: [size.width as u16, size.height as u16],
                 format: self.platform.info.format,
             },
+            frame_number,
         }
     }
 }
@@ -81,12 +97,17 @@ impl super::Context {
             uniform_buffer_alignment: unsafe {
                 glow.get_parameter_i32(glow::UNIFORM_BUFFER_OFFSET_ALIGNMENT) as u32
             },
+            max_array_texture_layers: unsafe {
+                glow.get_parameter_i32(glow::MAX_ARRAY_TEXTURE_LAYERS) as u32
+            },
+            max_texture_size: unsafe { glow.get_parameter_i32(glow::MAX_TEXTURE_SIZE) as u32 },
         };
         let device_information = crate::DeviceInformation {
             is_software_emulated: false,
             device_name: glow.get_parameter_string(glow::VENDOR),
             driver_name: glow.get_parameter_string(glow::RENDERER),
             driver_info: glow.get_parameter_string(glow::VERSION),
+            driver_version: glow.get_parameter_string(glow::VERSION),
         };
 
         Ok(super::Context {
@@ -114,6 +135,7 @@ impl super::Context {
                 platform,
                 renderbuf: self.platform.glow.create_renderbuffer().unwrap(),
                 framebuf: self.platform.glow.create_framebuffer().unwrap(),
+                frame_number: 0,
             }
         })
     }
@@ -121,6 +143,9 @@ impl super::Context {
     pub fn destroy_surface(&self, _surface: &mut super::Surface) {}
 
     pub fn reconfigure_surface(&self, surface: &mut super::Surface, config: crate::SurfaceConfig) {
+        if config.usage != crate::TextureUsage::TARGET {
+            log::warn!("Surface usage {:?} is not supported", config.usage);
+        }
         //TODO: create WebGL context here
         let format_desc = super::describe_texture_format(surface.platform.info.format);
         let gl = &self.platform.glow;
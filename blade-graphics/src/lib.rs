@@ -105,6 +105,18 @@ pub struct ContextDesc {
     pub overlay: bool,
     /// Force selection of a specific Device ID, unless 0.
     pub device_id: u32,
+    /// Minimum Vulkan API version, as `(major, minor)`.
+    /// Devices not supporting it are rejected. Only used on Vulkan.
+    pub vulkan_api_version: Option<(u32, u32)>,
+    /// Extra Vulkan device extensions to enable, on top of the ones
+    /// Blade requires itself (see the README).
+    /// Devices not supporting all of them are rejected. Only used on Vulkan.
+    pub vulkan_device_extensions: Vec<String>,
+    /// Maximum number of bytes of memory to allocate for resources.
+    /// Once reached, `try_create_buffer`, `try_create_texture`,
+    /// `try_create_acceleration_structure`, and `update_sparse`
+    /// return `MemoryError::OutOfBudget`. Only enforced on Vulkan.
+    pub memory_budget: Option<u64>,
 }
 
 #[derive(Debug)]
@@ -120,10 +132,38 @@ impl From<PlatformError> for NotSupportedError {
     }
 }
 
+/// Error of allocating memory for a resource.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum MemoryError {
+    /// The allocation would exceed `ContextDesc::memory_budget`.
+    OutOfBudget,
+    /// The device or the host is out of memory.
+    OutOfMemory,
+}
+
+impl std::fmt::Display for MemoryError {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match *self {
+            Self::OutOfBudget => write!(f, "memory budget is exceeded"),
+            Self::OutOfMemory => write!(f, "out of memory"),
+        }
+    }
+}
+
+impl std::error::Error for MemoryError {}
+
 #[derive(Clone, Debug, Default, PartialEq)]
 pub struct Capabilities {
     /// Which shader stages support ray queries
     pub ray_query: ShaderVisibility,
+    /// Support for textures with `TextureUsage::SPARSE`
+    pub sparse_textures: bool,
+    /// Support for recording `CommandBundle` objects on other threads
+    pub command_bundles: bool,
+    /// Max number of layers in a texture, see `TextureDesc::array_layer_count`
+    pub max_texture_array_layers: u32,
+    /// Max width and height of a 2D texture
+    pub max_texture_2d_size: u32,
 }
 
 #[derive(Clone, Debug, Default)]
@@ -136,9 +176,39 @@ pub struct DeviceInformation {
     pub driver_name: String,
     /// Further information about the driver
     pub driver_info: String,
+    /// Version of the driver, in the vendor's own format
+    pub driver_version: String,
+}
+
+/// Graphics API used by the context, see `Context::backend`.
+///
+/// This is the API blade talks to, so a Vulkan context may still be
+/// running on top of Metal by the means of MoltenVK. The `driver_name`
+/// of `DeviceInformation` tells those apart.
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub enum Backend {
+    Vulkan,
+    Metal,
+    /// OpenGL ES, or WebGL on the web.
+    Gles,
+}
+
+impl fmt::Display for Backend {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(match *self {
+            Self::Vulkan => "Vulkan",
+            Self::Metal => "Metal",
+            Self::Gles => "GLES",
+        })
+    }
 }
 
 impl Context {
+    /// Create a surface for the window and configure it.
+    ///
+    /// A context can have any number of surfaces, one per window.
+    /// Each of them is resized with `reconfigure_surface` and gives out
+    /// its own frames, which may all be presented by the same encoder.
     pub fn create_surface_configured<
         I: raw_window_handle::HasWindowHandle + raw_window_handle::HasDisplayHandle,
     >(
@@ -173,6 +243,8 @@ impl Memory {
 
 #[derive(Debug)]
 pub struct BufferDesc<'a> {
+    /// Debug name, shown by the validation layers and in GPU captures.
+    /// Nothing is set if it's empty.
     pub name: &'a str,
     pub size: u64,
     pub memory: Memory,
@@ -379,6 +451,22 @@ impl fmt::Display for Extent {
 }
 
 impl Extent {
+    /// Extent of a 2D texture, with a depth of 1.
+    pub const fn new_2d(width: u32, height: u32) -> Self {
+        Self {
+            width,
+            height,
+            depth: 1,
+        }
+    }
+    /// Returns true if any of the dimensions is zero.
+    pub fn is_empty(&self) -> bool {
+        self.width == 0 || self.height == 0 || self.depth == 0
+    }
+    /// Number of mip levels in a full chain down to 1x1x1.
+    pub fn mip_level_count(&self) -> u32 {
+        u32::BITS - self.width.max(self.height).max(self.depth).leading_zeros()
+    }
     pub fn max_mip_levels(&self) -> u32 {
         self.width
             .max(self.height)
@@ -397,19 +485,39 @@ impl Extent {
 
 bitflags::bitflags! {
     #[derive(Copy, Clone, Debug, Default, Hash, Eq, PartialEq)]
+    /// Operations that a texture can be used for.
+    ///
+    /// Using a texture in a way that wasn't declared at creation
+    /// is an error, which is validated on Vulkan.
     pub struct TextureUsage: u32 {
+        /// Source or destination of a transfer pass: copies from and to
+        /// buffers and other textures, including readback and uploads.
         const COPY = 1 << 0;
+        /// Color or depth-stencil attachment of a render pass,
+        /// including the resolve target of multi-sampled rendering.
         const TARGET = 1 << 1;
+        /// Sampled or loaded in shaders.
         const RESOURCE = 1 << 2;
+        /// Written in shaders as a storage texture.
         const STORAGE = 1 << 3;
+        /// Memory is bound per tile with `Context::update_sparse`,
+        /// instead of being allocated at creation.
+        const SPARSE = 1 << 4;
     }
 }
 
 #[derive(Debug)]
 pub struct TextureDesc<'a> {
+    /// Debug name, see `BufferDesc::name`.
     pub name: &'a str,
     pub format: TextureFormat,
     pub size: Extent,
+    /// Number of layers, each of the full size. Must be 1 for 3D textures,
+    /// and can't exceed `Capabilities::max_texture_array_layers`.
+    ///
+    /// The layers are sampled together with a `ViewDimension::D2Array` view,
+    /// and rendered into separately with a `ViewDimension::D2` view
+    /// of a single layer each.
     pub array_layer_count: u32,
     pub mip_level_count: u32,
     pub sample_count: u32,
@@ -417,6 +525,20 @@ pub struct TextureDesc<'a> {
     pub usage: TextureUsage,
 }
 
+/// Memory binding of a region in a sparse texture.
+#[derive(Clone, Debug, PartialEq)]
+pub struct TileMapping {
+    pub mip_level: u32,
+    pub array_layer: u32,
+    /// Start of the region in texels, aligned to the tile size.
+    pub origin: [u32; 3],
+    /// Size of the region in texels, aligned to the tile size,
+    /// unless it reaches the edge of the mip level.
+    pub size: Extent,
+    /// Bind new memory to the region if true, release it otherwise.
+    pub resident: bool,
+}
+
 #[derive(Clone, Debug, Default, Eq, PartialEq)]
 pub struct TextureSubresources {
     pub base_mip_level: u32,
@@ -593,6 +715,9 @@ pub struct Shader {
 pub struct ShaderFunction<'a> {
     pub shader: &'a Shader,
     pub entry_point: &'a str,
+    /// Values of the pipeline-overridable constants (`override` in WGSL),
+    /// by their names. Constants not listed here keep their default values.
+    pub constants: &'a [(&'a str, f64)],
 }
 
 impl ShaderFunction<'_> {
@@ -696,6 +821,16 @@ pub struct CommandEncoderDesc<'a> {
     pub buffer_count: u32,
 }
 
+/// Render target layout that a command bundle is recorded against.
+/// It has to match the targets of the pass executing the bundle.
+pub struct RenderBundleDesc<'a> {
+    pub color_formats: &'a [TextureFormat],
+    pub depth_stencil_format: Option<TextureFormat>,
+    pub sample_count: u32,
+    /// Size of the render targets, used for the initial viewport and scissor.
+    pub size: Extent,
+}
+
 pub struct ComputePipelineDesc<'a> {
     pub name: &'a str,
     pub data_layouts: &'a [&'a ShaderDataLayout],
@@ -1103,6 +1238,18 @@ pub enum DisplaySync {
     Tear,
 }
 
+/// How the presented frames are blended with whatever is behind the window.
+#[derive(Copy, Clone, Debug, Default, Hash, Eq, PartialEq)]
+pub enum CompositeAlpha {
+    /// The alpha channel is ignored, and the window is opaque.
+    #[default]
+    Opaque,
+    /// Colors are expected to be already multiplied by alpha.
+    PreMultiplied,
+    /// Colors are multiplied by alpha by the compositor.
+    PostMultiplied,
+}
+
 #[derive(Copy, Clone, Debug, Default, Hash, Eq, PartialEq)]
 pub enum ColorSpace {
     #[default]
@@ -1113,6 +1260,11 @@ pub enum ColorSpace {
 #[derive(Clone, Copy, Debug, Default, PartialEq)]
 pub struct SurfaceConfig {
     pub size: Extent,
+    /// Combination of the usages of the frame textures, e.g. `RESOURCE`
+    /// for reading them in a post-process, or `COPY` for screenshots.
+    ///
+    /// `TARGET` is always included. Usages that the surface doesn't support
+    /// are dropped with a warning, see `Surface::usage`.
     pub usage: TextureUsage,
     pub display_sync: DisplaySync,
     /// The color space that render output colors are expected to be in.
@@ -1122,7 +1274,13 @@ pub struct SurfaceConfig {
     /// For example, if the display expects sRGB space and we render
     /// in `ColorSpace::Linear` space, the returned format will be sRGB.
     pub color_space: ColorSpace,
-    pub transparent: bool,
+    /// Compositing of the window contents.
+    ///
+    /// When the requested transparent mode is not supported, the other
+    /// transparent mode is used if available. The actual mode is reported
+    /// in `SurfaceInfo::alpha`. Note that the window itself has to be
+    /// created as transparent by the windowing system.
+    pub composite_alpha: CompositeAlpha,
     pub allow_exclusive_full_screen: bool,
 }
 
@@ -1146,7 +1304,7 @@ pub enum IndexType {
     U32,
 }
 
-#[derive(Clone, Debug, PartialEq)]
+#[derive(Clone, Copy, Debug, PartialEq)]
 pub struct ScissorRect {
     pub x: i32,
     pub y: i32,
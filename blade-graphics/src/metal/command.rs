@@ -375,6 +375,15 @@ impl crate::traits::CommandEncoder for super::CommandEncoder {
 
     fn init_texture(&mut self, _texture: super::Texture) {}
 
+    fn insert_barrier(
+        &mut self,
+        _texture: super::Texture,
+        _from: crate::TextureUsage,
+        _to: crate::TextureUsage,
+    ) {
+        // Metal tracks hazards between passes for us
+    }
+
     fn present(&mut self, frame: super::Frame) {
         self.raw.as_mut().unwrap().presentDrawable(&frame.drawable);
     }
@@ -938,3 +947,26 @@ fn map_clear_color(color: crate::TextureColor) -> metal::MTLClearColor {
         },
     }
 }
+
+impl super::BundleEncoder {
+    pub fn start(&mut self) {}
+
+    pub fn render(&mut self, _desc: crate::RenderBundleDesc) -> super::RenderCommandEncoder {
+        unimplemented!("Command bundles are not supported")
+    }
+
+    pub fn finish(&mut self) -> super::CommandBundle {
+        super::CommandBundle {}
+    }
+}
+
+impl super::CommandEncoder {
+    pub fn execute_bundles(
+        &mut self,
+        _label: &str,
+        _targets: crate::RenderTargetSet,
+        _bundles: &[super::CommandBundle],
+    ) {
+        unimplemented!("Command bundles are not supported")
+    }
+}
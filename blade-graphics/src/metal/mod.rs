@@ -2,8 +2,12 @@ use objc2::{rc::Retained, runtime::ProtocolObject};
 use objc2_metal::{self as metal, MTLDevice};
 use std::{
     marker::PhantomData,
+    path::PathBuf,
     ptr,
-    sync::{Arc, Mutex},
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc, Mutex,
+    },
     thread, time,
 };
 
@@ -20,31 +24,59 @@ pub struct Surface {
     view: Option<objc2::rc::Retained<objc2::runtime::NSObject>>,
     render_layer: Retained<objc2_quartz_core::CAMetalLayer>,
     info: crate::SurfaceInfo,
+    usage: crate::TextureUsage,
+    // textures of the drawables seen since the last reconfiguration,
+    // in the order of their first appearance
+    images: Vec<Retained<ProtocolObject<dyn metal::MTLTexture>>>,
+    frame_number: u64,
 }
 
 #[derive(Debug)]
 pub struct Frame {
     drawable: Retained<ProtocolObject<dyn metal::MTLDrawable>>,
     texture: Retained<ProtocolObject<dyn metal::MTLTexture>>,
+    image_index: u32,
+    frame_number: u64,
 }
 
 unsafe impl Send for Frame {}
 unsafe impl Sync for Frame {}
 impl Frame {
+    /// Texture of the frame. Only valid until the frame is presented,
+    /// so it shouldn't be kept across frames.
     pub fn texture(&self) -> Texture {
         Texture {
             raw: Retained::as_ptr(&self.texture) as *mut _,
         }
     }
 
+    /// View of the frame texture, valid until the frame is presented.
     pub fn texture_view(&self) -> TextureView {
         TextureView {
             raw: Retained::as_ptr(&self.texture) as *mut _,
             aspects: crate::TexelAspects::COLOR,
         }
     }
+
+    /// Index of the drawable of this frame, in the order the layer
+    /// first handed the drawables out after the surface configuration.
+    pub fn image_index(&self) -> u32 {
+        self.image_index
+    }
+
+    /// Number of frames acquired from the surface before this one.
+    ///
+    /// Keeps increasing across surface reconfigurations.
+    pub fn frame_number(&self) -> u64 {
+        self.frame_number
+    }
 }
 
+// Same for all the GPU families, see the Metal feature set tables
+const MAX_TEXTURE_ARRAY_LAYERS: u32 = 2048;
+// Same for the Apple3+ and Mac2 families
+const MAX_TEXTURE_2D_SIZE: u32 = 16384;
+
 #[derive(Debug, Clone)]
 struct PrivateInfo {
     language_version: metal::MTLLanguageVersion,
@@ -56,6 +88,9 @@ pub struct Context {
     device: Mutex<Retained<ProtocolObject<dyn metal::MTLDevice>>>,
     queue: Arc<Mutex<Retained<ProtocolObject<dyn metal::MTLCommandQueue>>>>,
     capture: Option<Retained<metal::MTLCaptureManager>>,
+    /// Directory for the triggered captures, if enabled.
+    capture_dir: Option<PathBuf>,
+    is_capturing: AtomicBool,
     timestamp_counter_set: Option<Retained<ProtocolObject<dyn metal::MTLCounterSet>>>,
     info: PrivateInfo,
     device_information: crate::DeviceInformation,
@@ -90,6 +125,16 @@ impl Buffer {
         use metal::MTLBuffer as _;
         self.as_ref().contents().as_ptr() as *mut u8
     }
+
+    /// Get the native `MTLBuffer` object.
+    ///
+    /// # Safety
+    /// The buffer remains owned by Blade: it must not be released
+    /// or made purgeable through the returned object.
+    #[cfg(feature = "interop")]
+    pub unsafe fn raw_metal(&self) -> &ProtocolObject<dyn metal::MTLBuffer> {
+        self.as_ref()
+    }
 }
 
 #[derive(Clone, Copy, Debug, Hash, PartialEq)]
@@ -112,6 +157,16 @@ impl Texture {
     fn as_ref(&self) -> &ProtocolObject<dyn metal::MTLTexture> {
         unsafe { &*self.raw }
     }
+
+    /// Get the native `MTLTexture` object.
+    ///
+    /// # Safety
+    /// The texture remains owned by Blade: it must not be released
+    /// or made purgeable through the returned object.
+    #[cfg(feature = "interop")]
+    pub unsafe fn raw_metal(&self) -> &ProtocolObject<dyn metal::MTLTexture> {
+        self.as_ref()
+    }
 }
 
 #[derive(Clone, Copy, Debug, Hash, PartialEq)]
@@ -212,6 +267,10 @@ struct TimingData {
 }
 
 type RawCommandBuffer = Retained<ProtocolObject<dyn metal::MTLCommandBuffer>>;
+pub struct BundleEncoder {}
+#[derive(Clone, Copy, Debug)]
+pub struct CommandBundle {}
+
 pub struct CommandEncoder {
     raw: Option<RawCommandBuffer>,
     name: String,
@@ -446,9 +505,16 @@ impl Context {
         if desc.overlay {
             std::env::set_var("MTL_HUD_ENABLED", "1");
         }
+        if desc.capture {
+            // needed for capturing into a file outside of Xcode
+            std::env::set_var("MTL_CAPTURE_ENABLED", "1");
+        }
         if desc.device_id != 0 {
             log::warn!("Unable to filter devices by ID");
         }
+        if desc.memory_budget.is_some() {
+            log::warn!("Memory budget is not supported");
+        }
 
         let device = metal::MTLCreateSystemDefaultDevice()
             .ok_or(super::NotSupportedError::NoSupportedDeviceFound)?;
@@ -477,6 +543,10 @@ impl Context {
             device_name: device.name().to_string(),
             driver_name: "Metal".to_string(),
             driver_info: "".to_string(),
+            // the driver is a part of the OS
+            driver_version: objc2_foundation::NSProcessInfo::processInfo()
+                .operatingSystemVersionString()
+                .to_string(),
         };
 
         let mut timestamp_counter_set = None;
@@ -503,6 +573,12 @@ impl Context {
             device: Mutex::new(device),
             queue: Arc::new(Mutex::new(queue)),
             capture,
+            capture_dir: if desc.capture {
+                Some(std::env::temp_dir().join("blade"))
+            } else {
+                None
+            },
+            is_capturing: AtomicBool::new(false),
             timestamp_counter_set,
             info: PrivateInfo {
                 //TODO: determine based on OS version
@@ -527,19 +603,124 @@ impl Context {
             } else {
                 crate::ShaderVisibility::empty()
             },
+            sparse_textures: false,
+            command_bundles: false,
+            max_texture_array_layers: MAX_TEXTURE_ARRAY_LAYERS,
+            max_texture_2d_size: MAX_TEXTURE_2D_SIZE,
         }
     }
 
+    pub fn get_sparse_tile_size(&self, _texture: super::Texture) -> crate::Extent {
+        unimplemented!("Sparse textures are not supported")
+    }
+
+    pub fn update_sparse(
+        &self,
+        _texture: super::Texture,
+        _mappings: &[crate::TileMapping],
+    ) -> Result<(), crate::MemoryError> {
+        unimplemented!("Sparse textures are not supported")
+    }
+
+    pub fn create_bundle_encoder(&self, _desc: super::CommandEncoderDesc) -> BundleEncoder {
+        unimplemented!("Command bundles are not supported")
+    }
+
+    pub fn destroy_bundle_encoder(&self, _bundle_encoder: &mut BundleEncoder) {}
+
     pub fn device_information(&self) -> &crate::DeviceInformation {
         &self.device_information
     }
 
+    pub fn backend(&self) -> crate::Backend {
+        crate::Backend::Metal
+    }
+
     /// Get an MTLDevice of this context.
     /// This is platform specific API.
     pub fn metal_device(&self) -> Retained<ProtocolObject<dyn metal::MTLDevice>> {
         self.device.lock().unwrap().clone()
     }
 
+    /// Create a buffer. The memory budget is not enforced on this backend.
+    pub fn try_create_buffer(&self, desc: crate::BufferDesc) -> Result<Buffer, crate::MemoryError> {
+        Ok(self.create_buffer(desc))
+    }
+
+    /// Create a texture. The memory budget is not enforced on this backend.
+    pub fn try_create_texture(
+        &self,
+        desc: crate::TextureDesc,
+    ) -> Result<Texture, crate::MemoryError> {
+        Ok(self.create_texture(desc))
+    }
+
+    /// Create an acceleration structure. The memory budget is not enforced on this backend.
+    pub fn try_create_acceleration_structure(
+        &self,
+        desc: crate::AccelerationStructureDesc,
+    ) -> Result<AccelerationStructure, crate::MemoryError> {
+        Ok(self.create_acceleration_structure(desc))
+    }
+
+    /// Capture the work until the end of the next submission
+    /// into a ".gputrace" file, which can be opened with Xcode.
+    ///
+    /// Requires `ContextDesc::capture`. Otherwise, this does nothing.
+    pub fn trigger_capture(&self) {
+        let dir = match self.capture_dir {
+            Some(ref dir) => dir,
+            None => {
+                log::warn!("Capture is not enabled in the context");
+                return;
+            }
+        };
+        if self.is_capturing.load(Ordering::Acquire) {
+            return;
+        }
+        let _ = std::fs::create_dir_all(dir);
+        let seconds = time::SystemTime::now()
+            .duration_since(time::UNIX_EPOCH)
+            .map_or(0, |d| d.as_secs());
+        let path = dir.join(format!("capture-{seconds}.gputrace"));
+
+        objc2::rc::autoreleasepool(|_| {
+            let capture_manager = unsafe { metal::MTLCaptureManager::sharedCaptureManager() };
+            if !capture_manager.supportsDestination(metal::MTLCaptureDestination::GPUTraceDocument)
+            {
+                log::warn!("Capturing into a file is not supported");
+                return;
+            }
+            let capture_desc = metal::MTLCaptureDescriptor::new();
+            capture_desc.set_capture_device(&self.device.lock().unwrap());
+            let url = objc2_foundation::NSURL::from_file_path(&path).unwrap();
+            unsafe {
+                capture_desc.setDestination(metal::MTLCaptureDestination::GPUTraceDocument);
+                capture_desc.setOutputURL(Some(&url));
+            }
+            match capture_manager.startCaptureWithDescriptor_error(&capture_desc) {
+                Ok(()) => {
+                    log::info!("Capturing into {}", path.display());
+                    self.is_capturing.store(true, Ordering::Release);
+                }
+                Err(e) => log::error!("Unable to start the capture: {e:?}"),
+            }
+        });
+    }
+
+    /// Block until all the submitted work is finished on the GPU.
+    ///
+    /// Useful for cleanup paths, where every resource is about to be freed.
+    pub fn wait_idle(&self) {
+        use metal::{MTLCommandBuffer as _, MTLCommandQueue as _};
+        // Command buffers are executed in order, so waiting
+        // on an empty one covers everything submitted before it.
+        let cmd_buf =
+            objc2::rc::autoreleasepool(|_| self.queue.lock().unwrap().commandBuffer().unwrap());
+        cmd_buf.commit();
+        cmd_buf.waitUntilCompleted();
+    }
+
     /// Check if the device supports a specific texture sample count.
     pub fn supports_texture_sample_count(&self, sample_count: u32) -> bool {
         self.metal_device()
@@ -599,9 +780,21 @@ impl crate::traits::CommandDevice for Context {
     fn destroy_command_encoder(&self, _command_encoder: &mut CommandEncoder) {}
 
     fn submit(&self, encoder: &mut CommandEncoder) -> SyncPoint {
+        self.submit_with_waits(encoder, &[])
+    }
+
+    fn submit_with_waits(&self, encoder: &mut CommandEncoder, _waits: &[SyncPoint]) -> SyncPoint {
+        // All the command buffers go to the same queue, which executes them
+        // in the commit order, with the resource hazards tracked by Metal.
+        // The sync points are only produced after the commit, so there is
+        // nothing to wait on explicitly.
         use metal::MTLCommandBuffer as _;
         let cmd_buf = encoder.finish();
         cmd_buf.commit();
+        if self.is_capturing.swap(false, Ordering::AcqRel) {
+            let capture_manager = unsafe { metal::MTLCaptureManager::sharedCaptureManager() };
+            capture_manager.stopCapture();
+        }
         SyncPoint { cmd_buf }
     }
 
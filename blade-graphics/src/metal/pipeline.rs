@@ -196,10 +196,11 @@ impl super::Context {
     ) -> CompiledShader {
         let ep_index = sf.entry_point_index();
         let ep = &sf.shader.module.entry_points[ep_index];
-        let ep_info = sf.shader.info.get_entry_point(ep_index);
+        let (module, info) = sf.specialize();
+        let ep_info = info.get_entry_point(ep_index);
         let _ = sf.shader.source;
 
-        let mut module = sf.shader.module.clone();
+        let mut module = module.into_owned();
         crate::Shader::fill_resource_bindings(
             &mut module,
             &mut pipeline_layout.group_infos,
@@ -292,7 +293,7 @@ impl super::Context {
             vertex_buffer_mappings: Vec::new(),
         };
         let (source, info) =
-            msl::write_string(&module, &sf.shader.info, &naga_options, &pipeline_options).unwrap();
+            msl::write_string(&module, &info, &naga_options, &pipeline_options).unwrap();
 
         log::debug!(
             "Naga generated shader for entry point '{}' and stage {:?}\n{}",
@@ -208,6 +208,7 @@ impl crate::traits::ResourceDevice for super::Context {
     }
 
     fn create_texture(&self, desc: crate::TextureDesc) -> super::Texture {
+        desc.validate(super::MAX_TEXTURE_ARRAY_LAYERS);
         let mtl_format = super::map_texture_format(desc.format);
 
         let mtl_type = match desc.dimension {
@@ -19,14 +19,47 @@ impl super::Surface {
         self.info
     }
 
-    pub fn acquire_frame(&self) -> super::Frame {
+    /// Get the usages of the frame textures, which are the ones
+    /// requested by `SurfaceConfig::usage`.
+    pub fn usage(&self) -> crate::TextureUsage {
+        self.usage
+    }
+
+    /// Get the current size of the surface images.
+    ///
+    /// It may differ from the window size, e.g. while the window is being
+    /// moved between displays with different scale factors.
+    pub fn extent(&self) -> crate::Extent {
+        let size = unsafe { self.render_layer.drawableSize() };
+        crate::Extent::new_2d(size.width as u32, size.height as u32)
+    }
+
+    pub fn acquire_frame(&mut self) -> super::Frame {
         use objc2_quartz_core::CAMetalDrawable as _;
         let (drawable, texture) = objc2::rc::autoreleasepool(|_| unsafe {
             let drawable = self.render_layer.nextDrawable().unwrap();
             let texture = drawable.texture();
             (Retained::cast_unchecked(drawable), texture)
         });
-        super::Frame { drawable, texture }
+        let image_index = match self
+            .images
+            .iter()
+            .position(|image| Retained::as_ptr(image) == Retained::as_ptr(&texture))
+        {
+            Some(index) => index,
+            None => {
+                self.images.push(texture.clone());
+                self.images.len() - 1
+            }
+        };
+        let frame_number = self.frame_number;
+        self.frame_number += 1;
+        super::Frame {
+            drawable,
+            texture,
+            image_index: image_index as u32,
+            frame_number,
+        }
     }
 }
 
@@ -68,6 +101,9 @@ impl super::Context {
                     view: Some(Retained::cast_unchecked(view)),
                     render_layer,
                     info: SURFACE_INFO,
+                    usage: crate::TextureUsage::TARGET,
+                    images: Vec::new(),
+                    frame_number: 0,
                 }
             },
             #[cfg(target_os = "macos")]
@@ -97,6 +133,9 @@ impl super::Context {
                     view: Some(view.downcast().unwrap()),
                     render_layer,
                     info: SURFACE_INFO,
+                    usage: crate::TextureUsage::TARGET,
+                    images: Vec::new(),
+                    frame_number: 0,
                 }
             },
             _ => return Err(crate::NotSupportedError::PlatformNotSupported),
@@ -114,21 +153,25 @@ impl super::Context {
                 crate::ColorSpace::Linear => crate::TextureFormat::Bgra8UnormSrgb,
                 crate::ColorSpace::Srgb => crate::TextureFormat::Bgra8Unorm,
             },
-            alpha: if config.transparent {
-                crate::AlphaMode::PostMultiplied
-            } else {
+            alpha: match config.composite_alpha {
                 //Warning: it's not really ignored! Instead, it's assumed to be 1:
                 // https://developer.apple.com/documentation/quartzcore/calayer/1410763-isopaque
-                crate::AlphaMode::Ignored
+                crate::CompositeAlpha::Opaque => crate::AlphaMode::Ignored,
+                crate::CompositeAlpha::PreMultiplied => crate::AlphaMode::PreMultiplied,
+                crate::CompositeAlpha::PostMultiplied => crate::AlphaMode::PostMultiplied,
             },
         };
+        surface.usage = config.usage | crate::TextureUsage::TARGET;
+        surface.images.clear();
         let vsync = match config.display_sync {
             crate::DisplaySync::Block => true,
             crate::DisplaySync::Recent | crate::DisplaySync::Tear => false,
         };
 
         unsafe {
-            surface.render_layer.setOpaque(!config.transparent);
+            surface
+                .render_layer
+                .setOpaque(config.composite_alpha == crate::CompositeAlpha::Opaque);
             surface.render_layer.setDevice(Some(device.as_ref()));
             surface
                 .render_layer
@@ -13,48 +13,96 @@ impl super::Context {
         &self,
         desc: super::ShaderDesc,
     ) -> Result<super::Shader, &'static str> {
-        let module = naga::front::wgsl::parse_str(desc.source).map_err(|e| {
-            e.emit_to_stderr_with_path(desc.source, "");
-            "compilation failed"
-        })?;
-
         let device_caps = self.capabilities();
-
-        // Bindings are set up at pipeline creation, ignore here
-        let flags = naga::valid::ValidationFlags::all() ^ naga::valid::ValidationFlags::BINDINGS;
         let mut caps = naga::valid::Capabilities::empty();
         caps.set(
             naga::valid::Capabilities::RAY_QUERY | naga::valid::Capabilities::SAMPLED_TEXTURE_AND_STORAGE_BUFFER_ARRAY_NON_UNIFORM_INDEXING,
             !device_caps.ray_query.is_empty(),
         );
+        super::Shader::parse_with(desc.source, caps)
+    }
+
+    pub fn create_shader(&self, desc: super::ShaderDesc) -> super::Shader {
+        self.try_create_shader(desc).unwrap()
+    }
+}
+
+impl<'a> super::ShaderFunction<'a> {
+    /// Specialize the function with the values of pipeline-overridable constants.
+    pub fn with_constants(self, constants: &'a [(&'a str, f64)]) -> Self {
+        Self { constants, ..self }
+    }
+
+    /// Produce the module with all the overridable constants resolved.
+    pub(crate) fn specialize(
+        &self,
+    ) -> (
+        std::borrow::Cow<'a, naga::Module>,
+        std::borrow::Cow<'a, naga::valid::ModuleInfo>,
+    ) {
+        let constants = self
+            .constants
+            .iter()
+            .map(|&(name, value)| (name.to_string(), value))
+            .collect::<naga::back::PipelineConstants>();
+        naga::back::pipeline_constants::process_overrides(
+            &self.shader.module,
+            &self.shader.info,
+            &constants,
+        )
+        .unwrap_or_else(|e| {
+            panic!(
+                "Unable to specialize entry point '{}': {:?}",
+                self.entry_point, e
+            )
+        })
+    }
+}
+
+impl super::Shader {
+    fn parse_with(source: &str, caps: naga::valid::Capabilities) -> Result<Self, &'static str> {
+        let module = naga::front::wgsl::parse_str(source).map_err(|e| {
+            e.emit_to_stderr_with_path(source, "");
+            "compilation failed"
+        })?;
+
+        // Bindings are set up at pipeline creation, ignore here
+        let flags = naga::valid::ValidationFlags::all() ^ naga::valid::ValidationFlags::BINDINGS;
         let info = naga::valid::Validator::new(flags, caps)
             .validate(&module)
             .map_err(|e| {
-                crate::util::emit_annotated_error(&e, "", desc.source);
+                crate::util::emit_annotated_error(&e, "", source);
                 crate::util::print_err(&e);
                 "validation failed"
             })?;
 
-        Ok(super::Shader {
+        Ok(Self {
             module,
             info,
-            source: desc.source.to_owned(),
+            source: source.to_owned(),
         })
     }
 
-    pub fn create_shader(&self, desc: super::ShaderDesc) -> super::Shader {
-        self.try_create_shader(desc).unwrap()
+    /// Parse a shader without a device, assuming all the capabilities are present.
+    /// Such a shader can't be used for pipelines, but it's enough to check
+    /// the host structs against it with `check_struct_size`, e.g. in tests.
+    pub fn parse(source: &str) -> Result<Self, &'static str> {
+        Self::parse_with(source, naga::valid::Capabilities::all())
     }
-}
 
-impl super::Shader {
     pub fn at<'a>(&'a self, entry_point: &'a str) -> super::ShaderFunction<'a> {
         super::ShaderFunction {
             shader: self,
             entry_point,
+            constants: &[],
         }
     }
 
+    /// Get the WGSL source code this shader was created from.
+    pub fn source(&self) -> &str {
+        &self.source
+    }
+
     pub fn get_struct_size(&self, struct_name: &str) -> u32 {
         match self
             .module
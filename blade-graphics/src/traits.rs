@@ -44,6 +44,13 @@ pub trait CommandDevice {
     fn create_command_encoder(&self, desc: super::CommandEncoderDesc) -> Self::CommandEncoder;
     fn destroy_command_encoder(&self, encoder: &mut Self::CommandEncoder);
     fn submit(&self, encoder: &mut Self::CommandEncoder) -> Self::SyncPoint;
+    /// Submit the encoder, making its execution on the GPU wait until
+    /// all the given sync points are reached, without blocking the CPU.
+    fn submit_with_waits(
+        &self,
+        encoder: &mut Self::CommandEncoder,
+        waits: &[Self::SyncPoint],
+    ) -> Self::SyncPoint;
     fn wait_for(&self, sp: &Self::SyncPoint, timeout_ms: u32) -> bool;
 }
 
@@ -52,6 +59,15 @@ pub trait CommandEncoder {
     type Frame: Send + Sync + Debug;
     fn start(&mut self);
     fn init_texture(&mut self, texture: Self::Texture);
+    fn insert_barrier(
+        &mut self,
+        texture: Self::Texture,
+        from: super::TextureUsage,
+        to: super::TextureUsage,
+    );
+    /// Schedule the frame for presentation after the submission.
+    /// Frames of different surfaces can be presented by the same encoder.
+    /// The texture and the view of the frame can't be used afterwards.
     fn present(&mut self, frame: Self::Frame);
     fn timings(&self) -> &super::Timings;
 }
@@ -111,6 +111,78 @@ impl super::TextureFormat {
     }
 }
 
+impl super::TextureDesc<'_> {
+    /// Check that the requested usage is consistent with the rest of the description.
+    pub(crate) fn validate(&self, max_array_layers: u32) {
+        assert!(
+            !self.usage.is_empty(),
+            "Texture '{}' is created without any usage",
+            self.name
+        );
+        assert!(
+            !self.size.is_empty(),
+            "Texture '{}' is created with an empty size {}",
+            self.name,
+            self.size
+        );
+        assert!(
+            self.mip_level_count <= self.size.mip_level_count(),
+            "Texture '{}' of size {} can't have {} mip levels",
+            self.name,
+            self.size,
+            self.mip_level_count
+        );
+        assert!(
+            self.array_layer_count >= 1 && self.array_layer_count <= max_array_layers,
+            "Texture '{}' can't have {} array layers, the device supports up to {}",
+            self.name,
+            self.array_layer_count,
+            max_array_layers
+        );
+        if self.dimension == super::TextureDimension::D3 {
+            assert_eq!(
+                self.array_layer_count, 1,
+                "3D texture '{}' can't have array layers",
+                self.name
+            );
+        }
+        if self.format.block_info().dimensions != (1, 1) {
+            assert!(
+                !self
+                    .usage
+                    .intersects(super::TextureUsage::TARGET | super::TextureUsage::STORAGE),
+                "Texture '{}' of compressed format {:?} can't have {:?}",
+                self.name,
+                self.format,
+                self.usage,
+            );
+        }
+        if self.sample_count > 1 {
+            assert!(
+                !self.usage.contains(super::TextureUsage::STORAGE),
+                "Texture '{}' is multi-sampled and can't have {:?}",
+                self.name,
+                self.usage,
+            );
+        }
+    }
+}
+
+impl super::TextureUsage {
+    /// Panic with a descriptive message if a texture created with `self`
+    /// is used for an operation that requires `required`.
+    #[track_caller]
+    pub(crate) fn check(self, required: Self, operation: &str) {
+        assert!(
+            self.contains(required),
+            "Texture is used as {}, which requires {:?}, but it was only created with {:?}",
+            operation,
+            required,
+            self,
+        );
+    }
+}
+
 impl super::TextureColor {
     pub fn stencil_clear_value(&self) -> u32 {
         match self {
@@ -140,3 +212,70 @@ impl super::ComputePipeline {
         ]
     }
 }
+
+impl super::Context {
+    /// Read a region of a texture back into CPU memory.
+    ///
+    /// The texture needs `TextureUsage::COPY`. The rows of the result are
+    /// tightly packed, according to the block size of the `format`.
+    /// This blocks until the GPU has finished the copy, so it's meant for
+    /// debugging and tools rather than per-frame use.
+    pub fn read_texture_region(
+        &self,
+        src: super::TexturePiece,
+        format: super::TextureFormat,
+        size: super::Extent,
+    ) -> Vec<u8> {
+        let block_info = format.block_info();
+        let block_width = block_info.dimensions.0 as u32;
+        let block_height = block_info.dimensions.1 as u32;
+        let bytes_per_row = (size.width + block_width - 1) / block_width * block_info.size as u32;
+        let row_count = (size.height + block_height - 1) / block_height * size.depth;
+        let total_size = bytes_per_row as u64 * row_count as u64;
+
+        let buffer = self.create_buffer(super::BufferDesc {
+            name: "readback",
+            size: total_size,
+            memory: super::Memory::Shared,
+        });
+        let mut encoder = self.create_command_encoder(super::CommandEncoderDesc {
+            name: "readback",
+            buffer_count: 1,
+        });
+        encoder.start();
+        if let mut transfer = encoder.transfer("readback") {
+            transfer.copy_texture_to_buffer(src, buffer.into(), bytes_per_row, size);
+        }
+        let sync_point = self.submit(&mut encoder);
+        self.wait_for(&sync_point, !0);
+
+        let data =
+            unsafe { std::slice::from_raw_parts(buffer.data(), total_size as usize) }.to_vec();
+        self.destroy_command_encoder(&mut encoder);
+        self.destroy_buffer(buffer);
+        data
+    }
+
+    /// Read a whole mip level of a texture back into CPU memory.
+    ///
+    /// The `size` is the extent of the base level. Reading a small mip
+    /// is a cheap way to get a thumbnail or an average of the texture.
+    pub fn read_texture_mip(
+        &self,
+        texture: super::Texture,
+        format: super::TextureFormat,
+        size: super::Extent,
+        mip_level: u32,
+    ) -> Vec<u8> {
+        self.read_texture_region(
+            super::TexturePiece {
+                texture,
+                mip_level,
+                array_layer: 0,
+                origin: [0; 3],
+            },
+            format,
+            size.at_mip_level(mip_level),
+        )
+    }
+}
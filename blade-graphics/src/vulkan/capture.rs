@@ -0,0 +1,90 @@
+//! Minimal binding to the RenderDoc in-application API.
+//!
+//! RenderDoc is only used if it's already injected into the process,
+//! so this never loads the library on its own.
+
+use std::{
+    ffi, ptr,
+    sync::atomic::{AtomicBool, Ordering},
+};
+
+const API_VERSION_1_4_1: u32 = 10401;
+
+type GetApiFn = unsafe extern "C" fn(version: u32, out: *mut *mut ffi::c_void) -> i32;
+type FrameCaptureFn = unsafe extern "C" fn(device: *mut ffi::c_void, window: *mut ffi::c_void);
+type EndFrameCaptureFn =
+    unsafe extern "C" fn(device: *mut ffi::c_void, window: *mut ffi::c_void) -> u32;
+
+// Has to match `RENDERDOC_API_1_4_1` from "renderdoc_app.h",
+// up to the last used function.
+#[repr(C)]
+struct ApiTable {
+    _unused: [usize; 19],
+    start_frame_capture: FrameCaptureFn,
+    _is_frame_capturing: usize,
+    end_frame_capture: EndFrameCaptureFn,
+}
+
+pub(super) struct RenderDoc {
+    api: *const ApiTable,
+    is_capturing: AtomicBool,
+    _library: libloading::Library,
+}
+
+// The API table is static, and RenderDoc functions are thread-safe
+unsafe impl Send for RenderDoc {}
+unsafe impl Sync for RenderDoc {}
+
+impl RenderDoc {
+    pub(super) fn load() -> Option<Self> {
+        #[cfg(unix)]
+        let library = {
+            #[cfg(target_os = "android")]
+            const NAME: &str = "libVkLayer_GLES_RenderDoc.so";
+            #[cfg(not(target_os = "android"))]
+            const NAME: &str = "librenderdoc.so";
+            use libloading::os::unix as os;
+            unsafe { os::Library::open(Some(NAME), os::RTLD_NOW | os::RTLD_NOLOAD) }
+                .ok()?
+                .into()
+        };
+        #[cfg(windows)]
+        let library = libloading::os::windows::Library::open_already_loaded("renderdoc.dll")
+            .ok()?
+            .into();
+
+        let api = unsafe {
+            let get_api = library.get::<GetApiFn>(b"RENDERDOC_GetAPI\0").ok()?;
+            let mut api = ptr::null_mut();
+            if get_api(API_VERSION_1_4_1, &mut api) != 1 || api.is_null() {
+                log::warn!("RenderDoc is loaded, but the API version is not supported");
+                return None;
+            }
+            api as *const ApiTable
+        };
+        log::info!("RenderDoc is attached");
+        Some(Self {
+            api,
+            is_capturing: AtomicBool::new(false),
+            _library: library,
+        })
+    }
+
+    /// Start capturing all the work on any device and window.
+    pub(super) fn start(&self) {
+        if !self.is_capturing.swap(true, Ordering::AcqRel) {
+            unsafe { ((*self.api).start_frame_capture)(ptr::null_mut(), ptr::null_mut()) };
+        }
+    }
+
+    pub(super) fn finish(&self) {
+        if self.is_capturing.swap(false, Ordering::AcqRel) {
+            let ok = unsafe { ((*self.api).end_frame_capture)(ptr::null_mut(), ptr::null_mut()) };
+            if ok == 0 {
+                log::error!("RenderDoc failed to save the capture");
+            } else {
+                log::info!("RenderDoc capture is saved");
+            }
+        }
+    }
+}
@@ -159,6 +159,9 @@ fn make_buffer_image_copy(
 }
 
 fn map_render_target(rt: &crate::RenderTarget) -> vk::RenderingAttachmentInfo<'static> {
+    rt.view
+        .usage
+        .check(crate::TextureUsage::TARGET, "a render target");
     let mut vk_info = vk::RenderingAttachmentInfo::default()
         .image_view(rt.view.raw)
         .image_layout(vk::ImageLayout::GENERAL);
@@ -194,6 +197,9 @@ fn map_render_target(rt: &crate::RenderTarget) -> vk::RenderingAttachmentInfo<'s
     }
 
     if let crate::FinishOp::ResolveTo(resolve_view) = rt.finish_op {
+        resolve_view
+            .usage
+            .check(crate::TextureUsage::TARGET, "a resolve target");
         vk_info = vk_info
             .resolve_image_view(resolve_view.raw)
             .resolve_image_layout(vk::ImageLayout::GENERAL)
@@ -357,11 +363,12 @@ impl super::CommandEncoder {
         }
     }
 
-    pub fn render(
+    fn begin_rendering(
         &mut self,
         label: &str,
         targets: crate::RenderTargetSet,
-    ) -> super::RenderCommandEncoder {
+        flags: vk::RenderingFlags,
+    ) {
         self.begin_pass(label);
 
         let mut target_size = [0u16; 2];
@@ -373,6 +380,7 @@ impl super::CommandEncoder {
         }
 
         let mut rendering_info = vk::RenderingInfoKHR::default()
+            .flags(flags)
             .layer_count(1)
             .color_attachments(&color_attachments);
 
@@ -416,12 +424,45 @@ impl super::CommandEncoder {
                 .dynamic_rendering
                 .cmd_begin_rendering(cmd_buf.raw, &rendering_info);
         };
+    }
 
+    pub fn render(
+        &mut self,
+        label: &str,
+        targets: crate::RenderTargetSet,
+    ) -> super::RenderCommandEncoder {
+        self.begin_rendering(label, targets, vk::RenderingFlags::empty());
         super::RenderCommandEncoder {
-            cmd_buf,
+            cmd_buf: self.buffers.first_mut().unwrap(),
             device: &self.device,
             update_data: &mut self.update_data,
+            is_bundle: false,
+        }
+    }
+
+    /// Execute command bundles in a render pass.
+    ///
+    /// The bundles have to be recorded against the same target formats.
+    pub fn execute_bundles(
+        &mut self,
+        label: &str,
+        targets: crate::RenderTargetSet,
+        bundles: &[super::CommandBundle],
+    ) {
+        self.begin_rendering(
+            label,
+            targets,
+            vk::RenderingFlags::CONTENTS_SECONDARY_COMMAND_BUFFERS,
+        );
+        let raw_bundles = bundles.iter().map(|b| b.raw).collect::<Vec<_>>();
+        let cmd_buf = self.buffers.first_mut().unwrap();
+        unsafe {
+            self.device
+                .core
+                .cmd_execute_commands(cmd_buf.raw, &raw_bundles);
+            self.device.dynamic_rendering.cmd_end_rendering(cmd_buf.raw);
         }
+        end_pass(&self.device, cmd_buf.raw);
     }
 
     pub(super) fn check_gpu_crash<T>(&self, ret: Result<T, vk::Result>) -> Option<T> {
@@ -536,6 +577,42 @@ impl crate::traits::CommandEncoder for super::CommandEncoder {
         }
     }
 
+    fn insert_barrier(
+        &mut self,
+        texture: super::Texture,
+        from: crate::TextureUsage,
+        to: crate::TextureUsage,
+    ) {
+        let (src_stages, src_access) = super::map_texture_usage_to_sync(from);
+        let (dst_stages, dst_access) = super::map_texture_usage_to_sync(to);
+        let barrier = vk::ImageMemoryBarrier {
+            src_access_mask: src_access,
+            dst_access_mask: dst_access,
+            old_layout: vk::ImageLayout::GENERAL,
+            new_layout: vk::ImageLayout::GENERAL,
+            image: texture.raw,
+            subresource_range: vk::ImageSubresourceRange {
+                aspect_mask: super::map_aspects(texture.format.aspects()),
+                base_mip_level: 0,
+                level_count: vk::REMAINING_MIP_LEVELS,
+                base_array_layer: 0,
+                layer_count: vk::REMAINING_ARRAY_LAYERS,
+            },
+            ..Default::default()
+        };
+        unsafe {
+            self.device.core.cmd_pipeline_barrier(
+                self.buffers[0].raw,
+                src_stages,
+                dst_stages,
+                vk::DependencyFlags::empty(),
+                &[],
+                &[],
+                &[barrier],
+            );
+        }
+    }
+
     fn present(&mut self, frame: super::Frame) {
         let image_index = match frame.image_index {
             Some(index) => index,
@@ -545,9 +622,14 @@ impl crate::traits::CommandEncoder for super::CommandEncoder {
             }
         };
 
-        assert_eq!(self.present, None);
+        assert!(
+            self.present
+                .iter()
+                .all(|presentation| presentation.swapchain != frame.swapchain.raw),
+            "Frame of this surface is already presented"
+        );
         let wa = &self.device.workarounds;
-        self.present = Some(super::Presentation {
+        self.present.push(super::Presentation {
             acquire_semaphore: frame.internal.acquire_semaphore,
             swapchain: frame.swapchain.raw,
             image_index,
@@ -623,6 +705,12 @@ impl crate::traits::TransferEncoder for super::TransferCommandEncoder<'_> {
         dst: crate::TexturePiece,
         size: crate::Extent,
     ) {
+        src.texture
+            .usage
+            .check(crate::TextureUsage::COPY, "a copy source");
+        dst.texture
+            .usage
+            .check(crate::TextureUsage::COPY, "a copy destination");
         let copy = vk::ImageCopy {
             src_subresource: src.subresource_layers(),
             src_offset: map_origin(&src.origin),
@@ -649,6 +737,9 @@ impl crate::traits::TransferEncoder for super::TransferCommandEncoder<'_> {
         dst: crate::TexturePiece,
         size: crate::Extent,
     ) {
+        dst.texture
+            .usage
+            .check(crate::TextureUsage::COPY, "a copy destination");
         let copy = make_buffer_image_copy(&src, bytes_per_row, &dst, &size);
         unsafe {
             self.device.core.cmd_copy_buffer_to_image(
@@ -668,6 +759,9 @@ impl crate::traits::TransferEncoder for super::TransferCommandEncoder<'_> {
         bytes_per_row: u32,
         size: crate::Extent,
     ) {
+        src.texture
+            .usage
+            .check(crate::TextureUsage::COPY, "a copy source");
         let copy = make_buffer_image_copy(&dst, bytes_per_row, &src, &size);
         unsafe {
             self.device.core.cmd_copy_image_to_buffer(
@@ -825,8 +919,103 @@ impl<'a> super::RenderCommandEncoder<'a> {
     }
 }
 
+impl super::BundleEncoder {
+    /// Start recording a new bundle into the next buffer, resetting its descriptors.
+    ///
+    /// The bundle previously recorded into this buffer, i.e. `buffer_count` starts ago,
+    /// has to be done executing on the GPU. Wait for the sync point of the submission
+    /// that executed it before calling this.
+    pub fn start(&mut self) {
+        self.buffers.rotate_left(1);
+        let cmd_buf = self.buffers.first_mut().unwrap();
+        self.device
+            .reset_descriptor_pool(&mut cmd_buf.descriptor_pool);
+    }
+
+    /// Begin recording render commands for the targets described by `desc`.
+    pub fn render(&mut self, desc: crate::RenderBundleDesc) -> super::RenderCommandEncoder {
+        let color_formats = desc
+            .color_formats
+            .iter()
+            .map(|&format| super::map_texture_format(format))
+            .collect::<Vec<_>>();
+        let mut rendering_info = vk::CommandBufferInheritanceRenderingInfoKHR::default()
+            .color_attachment_formats(&color_formats)
+            .rasterization_samples(vk::SampleCountFlags::from_raw(desc.sample_count));
+        if let Some(format) = desc.depth_stencil_format {
+            let aspects = format.aspects();
+            if aspects.contains(crate::TexelAspects::DEPTH) {
+                rendering_info =
+                    rendering_info.depth_attachment_format(super::map_texture_format(format));
+            }
+            if aspects.contains(crate::TexelAspects::STENCIL) {
+                rendering_info =
+                    rendering_info.stencil_attachment_format(super::map_texture_format(format));
+            }
+        }
+        let inheritance_info =
+            vk::CommandBufferInheritanceInfo::default().push_next(&mut rendering_info);
+        let vk_info = vk::CommandBufferBeginInfo::default()
+            .flags(
+                vk::CommandBufferUsageFlags::ONE_TIME_SUBMIT
+                    | vk::CommandBufferUsageFlags::RENDER_PASS_CONTINUE,
+            )
+            .inheritance_info(&inheritance_info);
+
+        let render_area = crate::ScissorRect {
+            x: 0,
+            y: 0,
+            w: desc.size.width,
+            h: desc.size.height,
+        }
+        .to_vk();
+        let viewport = crate::Viewport {
+            x: 0.0,
+            y: 0.0,
+            w: desc.size.width as f32,
+            h: desc.size.height as f32,
+            depth: 0.0..1.0,
+        }
+        .to_vk();
+
+        let cmd_buf = self.buffers.first_mut().unwrap();
+        unsafe {
+            self.device
+                .core
+                .begin_command_buffer(cmd_buf.raw, &vk_info)
+                .unwrap();
+            self.device
+                .core
+                .cmd_set_viewport(cmd_buf.raw, 0, &[viewport]);
+            self.device
+                .core
+                .cmd_set_scissor(cmd_buf.raw, 0, &[render_area]);
+        }
+
+        super::RenderCommandEncoder {
+            cmd_buf,
+            device: &self.device,
+            update_data: &mut self.update_data,
+            is_bundle: true,
+        }
+    }
+
+    /// Finish recording, producing a bundle to be executed
+    /// by `CommandEncoder::execute_bundles`.
+    pub fn finish(&mut self) -> super::CommandBundle {
+        let cmd_buf = self.buffers.first_mut().unwrap();
+        unsafe {
+            self.device.core.end_command_buffer(cmd_buf.raw).unwrap();
+        }
+        super::CommandBundle { raw: cmd_buf.raw }
+    }
+}
+
 impl Drop for super::RenderCommandEncoder<'_> {
     fn drop(&mut self) {
+        if self.is_bundle {
+            return;
+        }
         unsafe {
             self.device
                 .dynamic_rendering
@@ -8,6 +8,9 @@ mod db {
     pub mod intel {
         pub const VENDOR: u32 = 0x8086;
     }
+    pub mod nvidia {
+        pub const VENDOR: u32 = 0x10DE;
+    }
 }
 mod layer {
     use std::ffi::CStr;
@@ -17,6 +20,26 @@ mod layer {
         unsafe { CStr::from_bytes_with_nul_unchecked(b"VK_LAYER_MESA_overlay\0") };
 }
 
+/// Decode the driver version, which is packed differently by some vendors.
+fn driver_version_string(vendor_id: u32, version: u32) -> String {
+    match vendor_id {
+        db::nvidia::VENDOR => format!(
+            "{}.{}.{}.{}",
+            version >> 22,
+            (version >> 14) & 0xFF,
+            (version >> 6) & 0xFF,
+            version & 0x3F
+        ),
+        db::intel::VENDOR if cfg!(windows) => format!("{}.{}", version >> 14, version & 0x3FFF),
+        _ => format!(
+            "{}.{}.{}",
+            vk::api_version_major(version),
+            vk::api_version_minor(version),
+            vk::api_version_patch(version)
+        ),
+    }
+}
+
 const REQUIRED_DEVICE_EXTENSIONS: &[&ffi::CStr] = &[
     vk::EXT_INLINE_UNIFORM_BLOCK_NAME,
     vk::KHR_TIMELINE_SEMAPHORE_NAME,
@@ -41,6 +64,7 @@ struct AdapterCapabilities {
     queue_family_index: u32,
     layered: bool,
     ray_tracing: bool,
+    sparse_residency: bool,
     buffer_marker: bool,
     shader_info: bool,
     full_screen_exclusive: bool,
@@ -99,6 +123,18 @@ unsafe fn inspect_adapter(
         log::warn!("\tRejected for API version {}", api_version);
         return None;
     }
+    if let Some((major, minor)) = desc.vulkan_api_version {
+        if api_version < vk::make_api_version(0, major, minor, 0) {
+            log::error!(
+                "\tRejected for API version {}.{} < requested {}.{}",
+                vk::api_version_major(api_version),
+                vk::api_version_minor(api_version),
+                major,
+                minor
+            );
+            return None;
+        }
+    }
 
     let supported_extension_properties = instance
         .core
@@ -117,6 +153,18 @@ unsafe fn inspect_adapter(
             return None;
         }
     }
+    for name in desc.vulkan_device_extensions.iter() {
+        if !supported_extensions
+            .iter()
+            .any(|ext| ext.to_bytes() == name.as_bytes())
+        {
+            log::error!(
+                "Rejected for requested device extension {} not supported",
+                name
+            );
+            return None;
+        }
+    }
 
     let bugs = SystemBugs {
         //Note: this is somewhat broad across X11/Wayland and different drivers.
@@ -155,6 +203,7 @@ unsafe fn inspect_adapter(
     instance
         .get_physical_device_properties2
         .get_physical_device_features2(phd, &mut features2_khr);
+    let core_features = features2_khr.features;
 
     if inline_uniform_block_properties.max_inline_uniform_block_size
         < crate::limits::PLAIN_DATA_SIZE
@@ -234,6 +283,24 @@ unsafe fn inspect_adapter(
         true
     };
 
+    let queue_family_properties = instance
+        .core
+        .get_physical_device_queue_family_properties(phd);
+    let sparse_residency = if core_features.sparse_binding == vk::FALSE
+        || core_features.sparse_residency_image2_d == vk::FALSE
+    {
+        log::info!("No sparse textures because of the features");
+        false
+    } else if !queue_family_properties[queue_family_index as usize]
+        .queue_flags
+        .contains(vk::QueueFlags::SPARSE_BINDING)
+    {
+        log::info!("No sparse textures because of the queue support");
+        false
+    } else {
+        true
+    };
+
     let buffer_marker = supported_extensions.contains(&vk::AMD_BUFFER_MARKER_NAME);
     let shader_info = supported_extensions.contains(&vk::AMD_SHADER_INFO_NAME);
     let full_screen_exclusive = supported_extensions.contains(&vk::EXT_FULL_SCREEN_EXCLUSIVE_NAME);
@@ -249,6 +316,7 @@ unsafe fn inspect_adapter(
         driver_info: ffi::CStr::from_ptr(driver_properties.driver_info.as_ptr())
             .to_string_lossy()
             .to_string(),
+        driver_version: driver_version_string(properties.vendor_id, properties.driver_version),
     };
 
     Some(AdapterCapabilities {
@@ -258,6 +326,7 @@ unsafe fn inspect_adapter(
         queue_family_index,
         layered: portability_subset_properties.min_vertex_input_binding_stride_alignment != 0,
         ray_tracing,
+        sparse_residency,
         buffer_marker,
         shader_info,
         full_screen_exclusive,
@@ -422,7 +491,12 @@ impl super::Context {
                 .queue_priorities(&[1.0]);
             let family_infos = [family_info];
 
-            let mut device_extensions = REQUIRED_DEVICE_EXTENSIONS.to_vec();
+            let user_extensions = desc
+                .vulkan_device_extensions
+                .iter()
+                .map(|name| ffi::CString::new(name.as_str()).unwrap())
+                .collect::<Vec<_>>();
+            let mut device_extensions: Vec<&ffi::CStr> = REQUIRED_DEVICE_EXTENSIONS.to_vec();
             if desc.presentation {
                 device_extensions.push(vk::KHR_SWAPCHAIN_NAME);
             }
@@ -450,6 +524,12 @@ impl super::Context {
             if capabilities.full_screen_exclusive {
                 device_extensions.push(vk::EXT_FULL_SCREEN_EXCLUSIVE_NAME);
             }
+            for extension in user_extensions.iter() {
+                if !device_extensions.contains(&extension.as_c_str()) {
+                    log::info!("Enabling requested extension {:?}", extension);
+                    device_extensions.push(extension.as_c_str());
+                }
+            }
 
             let str_pointers = device_extensions
                 .iter()
@@ -468,9 +548,15 @@ impl super::Context {
                 dynamic_rendering: vk::TRUE,
                 ..Default::default()
             };
+            let core_features = vk::PhysicalDeviceFeatures {
+                sparse_binding: capabilities.sparse_residency as vk::Bool32,
+                sparse_residency_image2_d: capabilities.sparse_residency as vk::Bool32,
+                ..Default::default()
+            };
             let mut device_create_info = vk::DeviceCreateInfo::default()
                 .queue_create_infos(&family_infos)
                 .enabled_extension_names(&str_pointers)
+                .enabled_features(&core_features)
                 .push_next(&mut ext_inline_uniform_block)
                 .push_next(&mut khr_timeline_semaphore)
                 .push_next(&mut khr_dynamic_rendering);
@@ -530,6 +616,9 @@ impl super::Context {
             } else {
                 None
             },
+            sparse_residency: capabilities.sparse_residency,
+            max_image_array_layers: capabilities.properties.limits.max_image_array_layers,
+            max_image_dimension_2d: capabilities.properties.limits.max_image_dimension2_d,
             buffer_marker: if capabilities.buffer_marker && desc.validation {
                 Some(amd::buffer_marker::Device::new(
                     &instance.core,
@@ -649,6 +738,9 @@ impl super::Context {
                 allocator: gpu_alloc::GpuAllocator::new(config, properties),
                 slab: slab::Slab::new(),
                 valid_ash_memory_types,
+                sparse_pages: Default::default(),
+                allocated: 0,
+                budget: desc.memory_budget.unwrap_or(u64::MAX),
             }
         };
 
@@ -697,6 +789,11 @@ impl super::Context {
             physical_device,
             naga_flags,
             shader_debug_path,
+            render_doc: if desc.capture {
+                super::capture::RenderDoc::load()
+            } else {
+                None
+            },
             instance,
             entry,
         })
@@ -720,12 +817,20 @@ impl super::Context {
                 Some(_) => crate::ShaderVisibility::all(),
                 None => crate::ShaderVisibility::empty(),
             },
+            sparse_textures: self.device.sparse_residency,
+            command_bundles: true,
+            max_texture_array_layers: self.device.max_image_array_layers,
+            max_texture_2d_size: self.device.max_image_dimension_2d,
         }
     }
 
     pub fn device_information(&self) -> &crate::DeviceInformation {
         &self.device.device_information
     }
+
+    pub fn backend(&self) -> crate::Backend {
+        crate::Backend::Vulkan
+    }
 }
 
 impl Drop for super::Context {
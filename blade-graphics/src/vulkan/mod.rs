@@ -2,8 +2,9 @@ use ash::{
     khr,
     vk::{self},
 };
-use std::{mem, num::NonZeroU32, path::PathBuf, ptr, sync::Mutex};
+use std::{collections::HashMap, mem, num::NonZeroU32, path::PathBuf, ptr, sync::Mutex};
 
+mod capture;
 mod command;
 mod descriptor;
 mod init;
@@ -55,6 +56,9 @@ struct Device {
     timeline_semaphore: khr::timeline_semaphore::Device,
     dynamic_rendering: khr::dynamic_rendering::Device,
     ray_tracing: Option<RayTracingDevice>,
+    sparse_residency: bool,
+    max_image_array_layers: u32,
+    max_image_dimension_2d: u32,
     buffer_marker: Option<ash::amd::buffer_marker::Device>,
     shader_info: Option<ash::amd::shader_info::Device>,
     full_screen_exclusive: Option<ash::ext::full_screen_exclusive::Device>,
@@ -63,10 +67,21 @@ struct Device {
     workarounds: Workarounds,
 }
 
+#[derive(Clone, Copy, Debug, Hash, Eq, PartialEq)]
+struct SparsePage {
+    image: vk::Image,
+    mip_level: u32,
+    array_layer: u32,
+    origin: [u32; 3],
+}
+
 struct MemoryManager {
     allocator: gpu_alloc::GpuAllocator<vk::DeviceMemory>,
     slab: slab::Slab<gpu_alloc::MemoryBlock<vk::DeviceMemory>>,
     valid_ash_memory_types: u32,
+    sparse_pages: HashMap<SparsePage, usize>,
+    allocated: u64,
+    budget: u64,
 }
 
 struct Queue {
@@ -88,6 +103,7 @@ struct Swapchain {
     raw: vk::SwapchainKHR,
     format: crate::TextureFormat,
     alpha: crate::AlphaMode,
+    usage: crate::TextureUsage,
     target_size: [u16; 2],
 }
 
@@ -98,6 +114,7 @@ pub struct Surface {
     next_semaphore: vk::Semaphore,
     swapchain: Swapchain,
     full_screen_exclusive: bool,
+    frame_number: u64,
 }
 
 #[derive(Clone, Copy, Debug, PartialEq)]
@@ -107,30 +124,51 @@ struct Presentation {
     acquire_semaphore: vk::Semaphore,
 }
 
-#[derive(Clone, Copy, Debug, PartialEq)]
+#[derive(Debug, PartialEq)]
 pub struct Frame {
     swapchain: Swapchain,
     image_index: Option<u32>,
     internal: InternalFrame,
+    frame_number: u64,
 }
 
 impl Frame {
+    /// Texture of the frame. Only valid until the frame is presented,
+    /// so it shouldn't be kept across frames.
     pub fn texture(&self) -> Texture {
         Texture {
             raw: self.internal.image,
             memory_handle: !0,
             target_size: self.swapchain.target_size,
             format: self.swapchain.format,
+            usage: self.swapchain.usage,
         }
     }
 
+    /// View of the frame texture, valid until the frame is presented.
     pub fn texture_view(&self) -> TextureView {
         TextureView {
             raw: self.internal.view,
             target_size: self.swapchain.target_size,
             aspects: crate::TexelAspects::COLOR,
+            usage: self.swapchain.usage,
         }
     }
+
+    /// Index of the swapchain image of this frame.
+    ///
+    /// If the surface was out of date, the frame can't be presented,
+    /// and this is zero.
+    pub fn image_index(&self) -> u32 {
+        self.image_index.unwrap_or(0)
+    }
+
+    /// Number of frames acquired from the surface before this one.
+    ///
+    /// Keeps increasing across surface reconfigurations.
+    pub fn frame_number(&self) -> u64 {
+        self.frame_number
+    }
 }
 
 fn map_timeout(millis: u32) -> u64 {
@@ -149,11 +187,33 @@ pub struct Context {
     physical_device: vk::PhysicalDevice,
     naga_flags: naga::back::spv::WriterFlags,
     shader_debug_path: Option<PathBuf>,
+    render_doc: Option<capture::RenderDoc>,
     instance: Instance,
     entry: ash::Entry,
 }
 
 impl Context {
+    /// Capture the work until the end of the next submission
+    /// with RenderDoc, which saves it into a file.
+    ///
+    /// Requires `ContextDesc::capture`, and the application
+    /// to be launched from RenderDoc. Otherwise, this does nothing.
+    pub fn trigger_capture(&self) {
+        match self.render_doc {
+            Some(ref render_doc) => render_doc.start(),
+            None => log::warn!("Capture is not available, RenderDoc is not attached"),
+        }
+    }
+
+    /// Block until all the submitted work is finished on the GPU.
+    ///
+    /// Useful for cleanup paths, where every resource is about to be freed.
+    pub fn wait_idle(&self) {
+        // `vkDeviceWaitIdle` requires external synchronization of the queue
+        let _queue = self.queue.lock().unwrap();
+        unsafe { self.device.core.device_wait_idle().unwrap() };
+    }
+
     /// Check if the device supports a specific texture sample count.
     pub fn supports_texture_sample_count(&self, sample_count: u32) -> bool {
         let properties = unsafe {
@@ -199,6 +259,16 @@ impl Buffer {
     pub fn data(&self) -> *mut u8 {
         self.mapped_data
     }
+
+    /// Get the native Vulkan buffer handle.
+    ///
+    /// # Safety
+    /// The buffer remains owned by Blade: it must not be destroyed,
+    /// and its memory must not be rebound through the returned handle.
+    #[cfg(feature = "interop")]
+    pub unsafe fn raw_vulkan(&self) -> vk::Buffer {
+        self.raw
+    }
 }
 
 unsafe impl Send for Buffer {}
@@ -210,6 +280,7 @@ pub struct Texture {
     memory_handle: usize,
     target_size: [u16; 2],
     format: crate::TextureFormat,
+    usage: crate::TextureUsage,
 }
 
 impl Default for Texture {
@@ -219,15 +290,30 @@ impl Default for Texture {
             memory_handle: !0,
             target_size: [0; 2],
             format: crate::TextureFormat::Rgba8Unorm,
+            usage: crate::TextureUsage::empty(),
         }
     }
 }
 
+impl Texture {
+    /// Get the native Vulkan image handle.
+    ///
+    /// # Safety
+    /// The image remains owned by Blade: it must not be destroyed.
+    /// Any layout transitions recorded outside of Blade have to return
+    /// the image to `GENERAL` layout before Blade uses it again.
+    #[cfg(feature = "interop")]
+    pub unsafe fn raw_vulkan(&self) -> vk::Image {
+        self.raw
+    }
+}
+
 #[derive(Clone, Copy, Debug, Default, Hash, PartialEq)]
 pub struct TextureView {
     raw: vk::ImageView,
     target_size: [u16; 2],
     aspects: crate::TexelAspects,
+    usage: crate::TextureUsage,
 }
 
 #[derive(Clone, Copy, Debug, Hash, PartialEq)]
@@ -306,11 +392,23 @@ pub struct CommandEncoder {
     buffers: Box<[CommandBuffer]>,
     device: Device,
     update_data: Vec<u8>,
-    present: Option<Presentation>,
+    present: Vec<Presentation>,
     crash_handler: Option<CrashHandler>,
     temp_label: Vec<u8>,
     timings: crate::Timings,
 }
+/// Records render commands into secondary command buffers,
+/// which can be done on a different thread than the main encoder.
+pub struct BundleEncoder {
+    pool: vk::CommandPool,
+    buffers: Box<[CommandBuffer]>,
+    device: Device,
+    update_data: Vec<u8>,
+}
+#[derive(Clone, Copy, Debug)]
+pub struct CommandBundle {
+    raw: vk::CommandBuffer,
+}
 pub struct TransferCommandEncoder<'a> {
     raw: vk::CommandBuffer,
     device: &'a Device,
@@ -331,6 +429,7 @@ pub struct RenderCommandEncoder<'a> {
     cmd_buf: &'a mut CommandBuffer,
     device: &'a Device,
     update_data: &'a mut Vec<u8>,
+    is_bundle: bool,
 }
 
 pub struct PipelineEncoder<'a, 'p> {
@@ -455,7 +554,7 @@ impl crate::traits::CommandDevice for Context {
             buffers,
             device: self.device.clone(),
             update_data: Vec::new(),
-            present: None,
+            present: Vec::new(),
             crash_handler,
             temp_label: Vec::new(),
             timings: Default::default(),
@@ -491,30 +590,38 @@ impl crate::traits::CommandDevice for Context {
     }
 
     fn submit(&self, encoder: &mut CommandEncoder) -> SyncPoint {
+        self.submit_with_waits(encoder, &[])
+    }
+
+    fn submit_with_waits(&self, encoder: &mut CommandEncoder, waits: &[SyncPoint]) -> SyncPoint {
         let raw_cmd_buf = encoder.finish();
         let mut queue = self.queue.lock().unwrap();
         queue.last_progress += 1;
         let progress = queue.last_progress;
         let command_buffers = [raw_cmd_buf];
-        let wait_values_all = [0];
-        let mut wait_semaphores_all = [vk::Semaphore::null()];
-        let wait_stages = [vk::PipelineStageFlags::ALL_COMMANDS];
+        // Wait for the image acquisition of every presented surface
+        let mut wait_semaphores = encoder
+            .present
+            .iter()
+            .map(|presentation| presentation.acquire_semaphore)
+            .collect::<Vec<_>>();
+        let mut wait_values = vec![0; wait_semaphores.len()];
+        // The timeline is monotonic, so waiting for the latest point covers the others
+        if let Some(progress) = waits.iter().map(|sp| sp.progress).max() {
+            wait_semaphores.push(queue.timeline_semaphore);
+            wait_values.push(progress);
+        }
+        let wait_stages = vec![vk::PipelineStageFlags::ALL_COMMANDS; wait_semaphores.len()];
         let signal_semaphores_all = [queue.timeline_semaphore, queue.present_semaphore];
         let signal_values_all = [progress, 0];
-        let (num_wait_semaphores, num_signal_sepahores) = match encoder.present {
-            Some(ref presentation) => {
-                wait_semaphores_all[0] = presentation.acquire_semaphore;
-                (1, 2)
-            }
-            None => (0, 1),
-        };
+        let num_signal_sepahores = if encoder.present.is_empty() { 1 } else { 2 };
         let mut timeline_info = vk::TimelineSemaphoreSubmitInfo::default()
-            .wait_semaphore_values(&wait_values_all[..num_wait_semaphores])
+            .wait_semaphore_values(&wait_values)
             .signal_semaphore_values(&signal_values_all[..num_signal_sepahores]);
         let vk_info = vk::SubmitInfo::default()
             .command_buffers(&command_buffers)
-            .wait_semaphores(&wait_semaphores_all[..num_wait_semaphores])
-            .wait_dst_stage_mask(&wait_stages[..num_wait_semaphores])
+            .wait_semaphores(&wait_semaphores)
+            .wait_dst_stage_mask(&wait_stages)
             .signal_semaphores(&signal_semaphores_all[..num_signal_sepahores])
             .push_next(&mut timeline_info);
         let ret = unsafe {
@@ -524,10 +631,13 @@ impl crate::traits::CommandDevice for Context {
         };
         encoder.check_gpu_crash(ret);
 
-        if let Some(presentation) = encoder.present.take() {
+        if !encoder.present.is_empty() {
             let khr_swapchain = self.device.swapchain.as_ref().unwrap();
-            let swapchains = [presentation.swapchain];
-            let image_indices = [presentation.image_index];
+            let (swapchains, image_indices): (Vec<_>, Vec<_>) = encoder
+                .present
+                .drain(..)
+                .map(|presentation| (presentation.swapchain, presentation.image_index))
+                .unzip();
             let wait_semaphores = [queue.present_semaphore];
             let present_info = vk::PresentInfoKHR::default()
                 .swapchains(&swapchains)
@@ -537,6 +647,9 @@ impl crate::traits::CommandDevice for Context {
             let _ = encoder.check_gpu_crash(ret);
         }
 
+        if let Some(ref render_doc) = self.render_doc {
+            render_doc.finish();
+        }
         SyncPoint { progress }
     }
 
@@ -559,6 +672,76 @@ impl crate::traits::CommandDevice for Context {
     }
 }
 
+impl Context {
+    /// Create an encoder for recording command bundles.
+    ///
+    /// Each thread is expected to have its own bundle encoder.
+    pub fn create_bundle_encoder(&self, desc: super::CommandEncoderDesc) -> BundleEncoder {
+        let pool_info = vk::CommandPoolCreateInfo {
+            flags: vk::CommandPoolCreateFlags::RESET_COMMAND_BUFFER,
+            ..Default::default()
+        };
+        let pool = unsafe {
+            self.device
+                .core
+                .create_command_pool(&pool_info, None)
+                .unwrap()
+        };
+        let cmd_buf_info = vk::CommandBufferAllocateInfo {
+            command_pool: pool,
+            level: vk::CommandBufferLevel::SECONDARY,
+            command_buffer_count: desc.buffer_count,
+            ..Default::default()
+        };
+        let cmd_buffers = unsafe {
+            self.device
+                .core
+                .allocate_command_buffers(&cmd_buf_info)
+                .unwrap()
+        };
+
+        let buffers = cmd_buffers
+            .into_iter()
+            .map(|raw| {
+                if !desc.name.is_empty() {
+                    self.set_object_name(raw, desc.name);
+                };
+                CommandBuffer {
+                    raw,
+                    descriptor_pool: self.device.create_descriptor_pool(),
+                    query_pool: vk::QueryPool::null(),
+                    timed_pass_names: Vec::new(),
+                }
+            })
+            .collect();
+
+        BundleEncoder {
+            pool,
+            buffers,
+            device: self.device.clone(),
+            update_data: Vec::new(),
+        }
+    }
+
+    pub fn destroy_bundle_encoder(&self, bundle_encoder: &mut BundleEncoder) {
+        for cmd_buf in bundle_encoder.buffers.iter_mut() {
+            let raw_cmd_buffers = [cmd_buf.raw];
+            unsafe {
+                self.device
+                    .core
+                    .free_command_buffers(bundle_encoder.pool, &raw_cmd_buffers);
+            }
+            self.device
+                .destroy_descriptor_pool(&mut cmd_buf.descriptor_pool);
+        }
+        unsafe {
+            self.device
+                .core
+                .destroy_command_pool(mem::take(&mut bundle_encoder.pool), None)
+        };
+    }
+}
+
 fn map_texture_format(format: crate::TextureFormat) -> vk::Format {
     use crate::TextureFormat as Tf;
     match format {
@@ -616,6 +799,39 @@ fn map_aspects(aspects: crate::TexelAspects) -> vk::ImageAspectFlags {
     flags
 }
 
+fn map_texture_usage_to_sync(
+    usage: crate::TextureUsage,
+) -> (vk::PipelineStageFlags, vk::AccessFlags) {
+    let mut stages = vk::PipelineStageFlags::empty();
+    let mut access = vk::AccessFlags::empty();
+    if usage.contains(crate::TextureUsage::COPY) {
+        stages |= vk::PipelineStageFlags::TRANSFER;
+        access |= vk::AccessFlags::TRANSFER_READ | vk::AccessFlags::TRANSFER_WRITE;
+    }
+    if usage.contains(crate::TextureUsage::TARGET) {
+        stages |= vk::PipelineStageFlags::COLOR_ATTACHMENT_OUTPUT
+            | vk::PipelineStageFlags::EARLY_FRAGMENT_TESTS
+            | vk::PipelineStageFlags::LATE_FRAGMENT_TESTS;
+        access |= vk::AccessFlags::COLOR_ATTACHMENT_READ
+            | vk::AccessFlags::COLOR_ATTACHMENT_WRITE
+            | vk::AccessFlags::DEPTH_STENCIL_ATTACHMENT_READ
+            | vk::AccessFlags::DEPTH_STENCIL_ATTACHMENT_WRITE;
+    }
+    if usage.intersects(crate::TextureUsage::RESOURCE | crate::TextureUsage::STORAGE) {
+        stages |= vk::PipelineStageFlags::VERTEX_SHADER
+            | vk::PipelineStageFlags::FRAGMENT_SHADER
+            | vk::PipelineStageFlags::COMPUTE_SHADER;
+        access |= vk::AccessFlags::SHADER_READ;
+    }
+    if usage.contains(crate::TextureUsage::STORAGE) {
+        access |= vk::AccessFlags::SHADER_WRITE;
+    }
+    if stages.is_empty() {
+        stages = vk::PipelineStageFlags::TOP_OF_PIPE;
+    }
+    (stages, access)
+}
+
 fn map_extent_3d(extent: &crate::Extent) -> vk::Extent3D {
     vk::Extent3D {
         width: extent.width,
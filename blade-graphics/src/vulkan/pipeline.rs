@@ -62,9 +62,10 @@ impl super::Context {
     ) -> CompiledShader {
         let ep_index = sf.entry_point_index();
         let ep = &sf.shader.module.entry_points[ep_index];
-        let ep_info = sf.shader.info.get_entry_point(ep_index);
+        let (module, info) = sf.specialize();
+        let ep_info = info.get_entry_point(ep_index);
 
-        let mut module = sf.shader.module.clone();
+        let mut module = module.into_owned();
         crate::Shader::fill_resource_bindings(
             &mut module,
             group_infos,
@@ -104,13 +105,7 @@ impl super::Context {
             naga_options_base
         };
 
-        let spv = spv::write_vec(
-            &module,
-            &sf.shader.info,
-            naga_options,
-            Some(&pipeline_options),
-        )
-        .unwrap();
+        let spv = spv::write_vec(&module, &info, naga_options, Some(&pipeline_options)).unwrap();
 
         if let Some(dump_prefix) = DUMP_PREFIX {
             let mut file_name = String::new();
@@ -14,8 +14,17 @@ impl super::Context {
         &self,
         requirements: vk::MemoryRequirements,
         memory: crate::Memory,
-    ) -> Allocation {
+    ) -> Result<Allocation, crate::MemoryError> {
         let mut manager = self.memory.lock().unwrap();
+        if manager.allocated + requirements.size > manager.budget {
+            log::error!(
+                "Allocating {} bytes would exceed the memory budget of {}, with {} in use",
+                requirements.size,
+                manager.budget,
+                manager.allocated
+            );
+            return Err(crate::MemoryError::OutOfBudget);
+        }
         let device_address_usage = if self.device.ray_tracing.is_some() {
             gpu_alloc::UsageFlags::DEVICE_ADDRESS
         } else {
@@ -49,8 +58,12 @@ impl super::Context {
                         memory_types,
                     },
                 )
-                .unwrap()
+                .map_err(|e| {
+                    log::error!("Unable to allocate {} bytes: {:?}", requirements.size, e);
+                    crate::MemoryError::OutOfMemory
+                })?
         };
+        manager.allocated += block.size();
 
         let data = match memory {
             crate::Memory::Device => ptr::null_mut(),
@@ -65,17 +78,18 @@ impl super::Context {
                     .as_ptr()
             },
         };
-        Allocation {
+        Ok(Allocation {
             memory: *block.memory(),
             offset: block.offset(),
             data,
             handle: manager.slab.insert(block),
-        }
+        })
     }
 
     fn free_memory(&self, handle: usize) {
         let mut manager = self.memory.lock().unwrap();
         let block = manager.slab.remove(handle);
+        manager.allocated -= block.size();
         unsafe {
             manager
                 .allocator
@@ -83,6 +97,368 @@ impl super::Context {
         }
     }
 
+    /// Create a buffer, or return an error if there is not enough memory.
+    pub fn try_create_buffer(
+        &self,
+        desc: crate::BufferDesc,
+    ) -> Result<super::Buffer, crate::MemoryError> {
+        use vk::BufferUsageFlags as Buf;
+        let mut vk_info = vk::BufferCreateInfo {
+            size: desc.size,
+            usage: Buf::TRANSFER_SRC
+                | Buf::TRANSFER_DST
+                | Buf::STORAGE_BUFFER
+                | Buf::INDEX_BUFFER
+                | Buf::VERTEX_BUFFER
+                | Buf::INDIRECT_BUFFER,
+            sharing_mode: vk::SharingMode::EXCLUSIVE,
+            ..Default::default()
+        };
+        if self.device.ray_tracing.is_some() {
+            vk_info.usage |=
+                Buf::SHADER_DEVICE_ADDRESS | Buf::ACCELERATION_STRUCTURE_BUILD_INPUT_READ_ONLY_KHR;
+        }
+
+        let raw = unsafe { self.device.core.create_buffer(&vk_info, None).unwrap() };
+        let requirements = unsafe { self.device.core.get_buffer_memory_requirements(raw) };
+        let allocation = match self.allocate_memory(requirements, desc.memory) {
+            Ok(allocation) => allocation,
+            Err(e) => {
+                unsafe { self.device.core.destroy_buffer(raw, None) };
+                return Err(e);
+            }
+        };
+
+        log::info!(
+            "Creating buffer {:?} of size {}, name '{}', handle {:?}",
+            raw,
+            desc.size,
+            desc.name,
+            allocation.handle
+        );
+        unsafe {
+            self.device
+                .core
+                .bind_buffer_memory(raw, allocation.memory, allocation.offset)
+                .unwrap()
+        };
+        if !desc.name.is_empty() {
+            self.set_object_name(raw, desc.name);
+        }
+
+        Ok(super::Buffer {
+            raw,
+            memory_handle: allocation.handle,
+            mapped_data: allocation.data,
+        })
+    }
+
+    /// Create a texture, or return an error if there is not enough memory.
+    pub fn try_create_texture(
+        &self,
+        desc: crate::TextureDesc,
+    ) -> Result<super::Texture, crate::MemoryError> {
+        desc.validate(self.device.max_image_array_layers);
+        let mut create_flags = vk::ImageCreateFlags::empty();
+        if desc.usage.contains(crate::TextureUsage::SPARSE) {
+            assert!(
+                self.device.sparse_residency,
+                "Sparse textures are not supported, see `Capabilities::sparse_textures`"
+            );
+            create_flags |=
+                vk::ImageCreateFlags::SPARSE_BINDING | vk::ImageCreateFlags::SPARSE_RESIDENCY;
+        }
+        if desc.dimension == crate::TextureDimension::D2
+            && desc.array_layer_count % 6 == 0
+            && desc.sample_count == 1
+            && desc.size.width == desc.size.height
+        {
+            create_flags |= vk::ImageCreateFlags::CUBE_COMPATIBLE;
+        }
+
+        let vk_info = vk::ImageCreateInfo {
+            flags: create_flags,
+            image_type: map_texture_dimension(desc.dimension),
+            format: super::map_texture_format(desc.format),
+            extent: super::map_extent_3d(&desc.size),
+            mip_levels: desc.mip_level_count,
+            array_layers: desc.array_layer_count,
+            samples: vk::SampleCountFlags::from_raw(desc.sample_count),
+            tiling: vk::ImageTiling::OPTIMAL,
+            usage: map_texture_usage(desc.usage, desc.format.aspects()),
+            sharing_mode: vk::SharingMode::EXCLUSIVE,
+            ..Default::default()
+        };
+
+        /*
+            TODO(ErikWDev): Support lazily allocated texture with transient allocation for efficient msaa?
+                            Measure bandwidth usage!
+        */
+        let raw = unsafe { self.device.core.create_image(&vk_info, None).unwrap() };
+        let memory_handle = if desc.usage.contains(crate::TextureUsage::SPARSE) {
+            // memory is bound later by `update_sparse`
+            log::info!(
+                "Creating sparse texture {:?} of size {} and format {:?}, name '{}'",
+                raw,
+                desc.size,
+                desc.format,
+                desc.name,
+            );
+            !0
+        } else {
+            let requirements = unsafe { self.device.core.get_image_memory_requirements(raw) };
+            let allocation = match self.allocate_memory(requirements, crate::Memory::Device) {
+                Ok(allocation) => allocation,
+                Err(e) => {
+                    unsafe { self.device.core.destroy_image(raw, None) };
+                    return Err(e);
+                }
+            };
+
+            log::info!(
+                "Creating texture {:?} of size {} and format {:?}, name '{}', handle {:?}",
+                raw,
+                desc.size,
+                desc.format,
+                desc.name,
+                allocation.handle
+            );
+            unsafe {
+                self.device
+                    .core
+                    .bind_image_memory(raw, allocation.memory, allocation.offset)
+                    .unwrap()
+            };
+            allocation.handle
+        };
+        if !desc.name.is_empty() {
+            self.set_object_name(raw, desc.name);
+        }
+
+        Ok(super::Texture {
+            raw,
+            memory_handle,
+            target_size: [desc.size.width as u16, desc.size.height as u16],
+            format: desc.format,
+            usage: desc.usage,
+        })
+    }
+
+    /// Create an acceleration structure, or return an error if there is not enough memory.
+    pub fn try_create_acceleration_structure(
+        &self,
+        desc: crate::AccelerationStructureDesc,
+    ) -> Result<super::AccelerationStructure, crate::MemoryError> {
+        let buffer_info = vk::BufferCreateInfo {
+            size: desc.size,
+            usage: vk::BufferUsageFlags::ACCELERATION_STRUCTURE_STORAGE_KHR
+                | vk::BufferUsageFlags::SHADER_DEVICE_ADDRESS,
+            sharing_mode: vk::SharingMode::EXCLUSIVE,
+            ..Default::default()
+        };
+
+        let buffer = unsafe { self.device.core.create_buffer(&buffer_info, None).unwrap() };
+        let requirements = unsafe { self.device.core.get_buffer_memory_requirements(buffer) };
+        let allocation = match self.allocate_memory(requirements, crate::Memory::Device) {
+            Ok(allocation) => allocation,
+            Err(e) => {
+                unsafe { self.device.core.destroy_buffer(buffer, None) };
+                return Err(e);
+            }
+        };
+
+        unsafe {
+            self.device
+                .core
+                .bind_buffer_memory(buffer, allocation.memory, allocation.offset)
+                .unwrap()
+        };
+
+        let raw_ty = match desc.ty {
+            crate::AccelerationStructureType::TopLevel => {
+                vk::AccelerationStructureTypeKHR::TOP_LEVEL
+            }
+            crate::AccelerationStructureType::BottomLevel => {
+                vk::AccelerationStructureTypeKHR::BOTTOM_LEVEL
+            }
+        };
+        let vk_info = vk::AccelerationStructureCreateInfoKHR {
+            ty: raw_ty,
+            buffer,
+            size: desc.size,
+            ..Default::default()
+        };
+
+        let rt = self.device.ray_tracing.as_ref().unwrap();
+        let raw = unsafe {
+            rt.acceleration_structure
+                .create_acceleration_structure(&vk_info, None)
+                .unwrap()
+        };
+
+        if !desc.name.is_empty() {
+            self.set_object_name(buffer, desc.name);
+            self.set_object_name(raw, desc.name);
+        }
+        Ok(super::AccelerationStructure {
+            raw,
+            buffer,
+            memory_handle: allocation.handle,
+        })
+    }
+
+    /// Get the size of a tile of a sparse texture, in texels.
+    pub fn get_sparse_tile_size(&self, texture: super::Texture) -> crate::Extent {
+        let requirements = unsafe {
+            self.device
+                .core
+                .get_image_sparse_memory_requirements(texture.raw)
+        };
+        let granularity = requirements[0].format_properties.image_granularity;
+        crate::Extent {
+            width: granularity.width,
+            height: granularity.height,
+            depth: granularity.depth,
+        }
+    }
+
+    /// Bind or release memory of regions in a sparse texture,
+    /// or return an error if there is not enough memory to bind them all,
+    /// in which case nothing is updated.
+    ///
+    /// Blocks until the GPU is done with the update.
+    /// Releasing has to use the same regions as binding.
+    /// The mip tail isn't supported yet.
+    pub fn update_sparse(
+        &self,
+        texture: super::Texture,
+        mappings: &[crate::TileMapping],
+    ) -> Result<(), crate::MemoryError> {
+        let requirements = unsafe { self.device.core.get_image_memory_requirements(texture.raw) };
+        let sparse_requirements = unsafe {
+            self.device
+                .core
+                .get_image_sparse_memory_requirements(texture.raw)
+        };
+        let sparse = &sparse_requirements[0];
+        let granularity = sparse.format_properties.image_granularity;
+        let aspect_mask = super::map_aspects(texture.format.aspects());
+
+        let mut binds = Vec::with_capacity(mappings.len());
+        let mut bound = Vec::new();
+        let mut released = Vec::new();
+        for mapping in mappings {
+            assert!(
+                mapping.mip_level < sparse.image_mip_tail_first_lod,
+                "Mip level {} is in the mip tail",
+                mapping.mip_level
+            );
+            let page = super::SparsePage {
+                image: texture.raw,
+                mip_level: mapping.mip_level,
+                array_layer: mapping.array_layer,
+                origin: mapping.origin,
+            };
+            let (memory, memory_offset) = if mapping.resident {
+                let tiles = |size: u32, tile: u32| (size + tile - 1) / tile;
+                let tile_count = tiles(mapping.size.width, granularity.width)
+                    * tiles(mapping.size.height, granularity.height)
+                    * tiles(mapping.size.depth, granularity.depth);
+                let allocation = match self.allocate_memory(
+                    vk::MemoryRequirements {
+                        size: tile_count as u64 * requirements.alignment,
+                        ..requirements
+                    },
+                    crate::Memory::Device,
+                ) {
+                    Ok(allocation) => allocation,
+                    Err(e) => {
+                        // undo the bookkeeping of the regions processed so far
+                        let handles = {
+                            let mut manager = self.memory.lock().unwrap();
+                            for (page, handle) in released {
+                                manager.sparse_pages.insert(page, handle);
+                            }
+                            bound
+                                .iter()
+                                .filter_map(|page| manager.sparse_pages.remove(page))
+                                .collect::<Vec<_>>()
+                        };
+                        for handle in handles {
+                            self.free_memory(handle);
+                        }
+                        return Err(e);
+                    }
+                };
+                let mut manager = self.memory.lock().unwrap();
+                let old = manager.sparse_pages.insert(page, allocation.handle);
+                assert!(old.is_none(), "Region {:?} is already resident", mapping);
+                bound.push(page);
+                (allocation.memory, allocation.offset)
+            } else {
+                let mut manager = self.memory.lock().unwrap();
+                match manager.sparse_pages.remove(&page) {
+                    Some(handle) => released.push((page, handle)),
+                    None => {
+                        log::warn!("Region {:?} is not resident", mapping);
+                        continue;
+                    }
+                }
+                (vk::DeviceMemory::null(), 0)
+            };
+            binds.push(vk::SparseImageMemoryBind {
+                subresource: vk::ImageSubresource {
+                    aspect_mask,
+                    mip_level: mapping.mip_level,
+                    array_layer: mapping.array_layer,
+                },
+                offset: vk::Offset3D {
+                    x: mapping.origin[0] as i32,
+                    y: mapping.origin[1] as i32,
+                    z: mapping.origin[2] as i32,
+                },
+                extent: super::map_extent_3d(&mapping.size),
+                memory,
+                memory_offset,
+                flags: vk::SparseMemoryBindFlags::empty(),
+            });
+        }
+
+        let image_binds = [vk::SparseImageMemoryBindInfo::default()
+            .image(texture.raw)
+            .binds(&binds)];
+        let sync_point = {
+            let mut queue = self.queue.lock().unwrap();
+            // Order the update after all the previous work on the queue
+            let wait_values = [queue.last_progress];
+            queue.last_progress += 1;
+            let signal_values = [queue.last_progress];
+            let semaphores = [queue.timeline_semaphore];
+            let mut timeline_info = vk::TimelineSemaphoreSubmitInfo::default()
+                .wait_semaphore_values(&wait_values)
+                .signal_semaphore_values(&signal_values);
+            let bind_info = vk::BindSparseInfo::default()
+                .wait_semaphores(&semaphores)
+                .image_binds(&image_binds)
+                .signal_semaphores(&semaphores)
+                .push_next(&mut timeline_info);
+            unsafe {
+                self.device
+                    .core
+                    .queue_bind_sparse(queue.raw, &[bind_info], vk::Fence::null())
+                    .unwrap()
+            };
+            super::SyncPoint {
+                progress: queue.last_progress,
+            }
+        };
+        self.wait_for(&sync_point, !0);
+        for (_, handle) in released {
+            self.free_memory(handle);
+        }
+        Ok(())
+    }
+
     //TODO: move these into `ResourceDevice` trait when ready
     pub fn get_bottom_level_acceleration_structure_sizes(
         &self,
@@ -191,49 +567,7 @@ impl crate::traits::ResourceDevice for super::Context {
     type AccelerationStructure = super::AccelerationStructure;
 
     fn create_buffer(&self, desc: crate::BufferDesc) -> super::Buffer {
-        use vk::BufferUsageFlags as Buf;
-        let mut vk_info = vk::BufferCreateInfo {
-            size: desc.size,
-            usage: Buf::TRANSFER_SRC
-                | Buf::TRANSFER_DST
-                | Buf::STORAGE_BUFFER
-                | Buf::INDEX_BUFFER
-                | Buf::VERTEX_BUFFER
-                | Buf::INDIRECT_BUFFER,
-            sharing_mode: vk::SharingMode::EXCLUSIVE,
-            ..Default::default()
-        };
-        if self.device.ray_tracing.is_some() {
-            vk_info.usage |=
-                Buf::SHADER_DEVICE_ADDRESS | Buf::ACCELERATION_STRUCTURE_BUILD_INPUT_READ_ONLY_KHR;
-        }
-
-        let raw = unsafe { self.device.core.create_buffer(&vk_info, None).unwrap() };
-        let requirements = unsafe { self.device.core.get_buffer_memory_requirements(raw) };
-        let allocation = self.allocate_memory(requirements, desc.memory);
-
-        log::info!(
-            "Creating buffer {:?} of size {}, name '{}', handle {:?}",
-            raw,
-            desc.size,
-            desc.name,
-            allocation.handle
-        );
-        unsafe {
-            self.device
-                .core
-                .bind_buffer_memory(raw, allocation.memory, allocation.offset)
-                .unwrap()
-        };
-        if !desc.name.is_empty() {
-            self.set_object_name(raw, desc.name);
-        }
-
-        super::Buffer {
-            raw,
-            memory_handle: allocation.handle,
-            mapped_data: allocation.data,
-        }
+        self.try_create_buffer(desc).unwrap()
     }
 
     fn sync_buffer(&self, _buffer: super::Buffer) {}
@@ -249,61 +583,7 @@ impl crate::traits::ResourceDevice for super::Context {
     }
 
     fn create_texture(&self, desc: crate::TextureDesc) -> super::Texture {
-        let mut create_flags = vk::ImageCreateFlags::empty();
-        if desc.dimension == crate::TextureDimension::D2
-            && desc.size.depth % 6 == 0
-            && desc.sample_count == 1
-            && desc.size.width == desc.size.height
-        {
-            create_flags |= vk::ImageCreateFlags::CUBE_COMPATIBLE;
-        }
-
-        let vk_info = vk::ImageCreateInfo {
-            flags: create_flags,
-            image_type: map_texture_dimension(desc.dimension),
-            format: super::map_texture_format(desc.format),
-            extent: super::map_extent_3d(&desc.size),
-            mip_levels: desc.mip_level_count,
-            array_layers: desc.array_layer_count,
-            samples: vk::SampleCountFlags::from_raw(desc.sample_count),
-            tiling: vk::ImageTiling::OPTIMAL,
-            usage: map_texture_usage(desc.usage, desc.format.aspects()),
-            sharing_mode: vk::SharingMode::EXCLUSIVE,
-            ..Default::default()
-        };
-
-        /*
-            TODO(ErikWDev): Support lazily allocated texture with transient allocation for efficient msaa?
-                            Measure bandwidth usage!
-        */
-        let raw = unsafe { self.device.core.create_image(&vk_info, None).unwrap() };
-        let requirements = unsafe { self.device.core.get_image_memory_requirements(raw) };
-        let allocation = self.allocate_memory(requirements, crate::Memory::Device);
-
-        log::info!(
-            "Creating texture {:?} of size {} and format {:?}, name '{}', handle {:?}",
-            raw,
-            desc.size,
-            desc.format,
-            desc.name,
-            allocation.handle
-        );
-        unsafe {
-            self.device
-                .core
-                .bind_image_memory(raw, allocation.memory, allocation.offset)
-                .unwrap()
-        };
-        if !desc.name.is_empty() {
-            self.set_object_name(raw, desc.name);
-        }
-
-        super::Texture {
-            raw,
-            memory_handle: allocation.handle,
-            target_size: [desc.size.width as u16, desc.size.height as u16],
-            format: desc.format,
-        }
+        self.try_create_texture(desc).unwrap()
     }
 
     fn destroy_texture(&self, texture: super::Texture) {
@@ -313,7 +593,23 @@ impl crate::traits::ResourceDevice for super::Context {
             texture.memory_handle
         );
         unsafe { self.device.core.destroy_image(texture.raw, None) };
-        self.free_memory(texture.memory_handle);
+        if texture.memory_handle == !0 {
+            let mut manager = self.memory.lock().unwrap();
+            let mut handles = Vec::new();
+            manager.sparse_pages.retain(|page, &mut handle| {
+                let keep = page.image != texture.raw;
+                if !keep {
+                    handles.push(handle);
+                }
+                keep
+            });
+            drop(manager);
+            for handle in handles {
+                self.free_memory(handle);
+            }
+        } else {
+            self.free_memory(texture.memory_handle);
+        }
     }
 
     fn create_texture_view(
@@ -343,6 +639,7 @@ impl crate::traits::ResourceDevice for super::Context {
                 (texture.target_size[1] >> desc.subresources.base_mip_level).max(1),
             ],
             aspects,
+            usage: texture.usage,
         }
     }
 
@@ -391,56 +688,7 @@ impl crate::traits::ResourceDevice for super::Context {
         &self,
         desc: crate::AccelerationStructureDesc,
     ) -> super::AccelerationStructure {
-        let buffer_info = vk::BufferCreateInfo {
-            size: desc.size,
-            usage: vk::BufferUsageFlags::ACCELERATION_STRUCTURE_STORAGE_KHR
-                | vk::BufferUsageFlags::SHADER_DEVICE_ADDRESS,
-            sharing_mode: vk::SharingMode::EXCLUSIVE,
-            ..Default::default()
-        };
-
-        let buffer = unsafe { self.device.core.create_buffer(&buffer_info, None).unwrap() };
-        let requirements = unsafe { self.device.core.get_buffer_memory_requirements(buffer) };
-        let allocation = self.allocate_memory(requirements, crate::Memory::Device);
-
-        unsafe {
-            self.device
-                .core
-                .bind_buffer_memory(buffer, allocation.memory, allocation.offset)
-                .unwrap()
-        };
-
-        let raw_ty = match desc.ty {
-            crate::AccelerationStructureType::TopLevel => {
-                vk::AccelerationStructureTypeKHR::TOP_LEVEL
-            }
-            crate::AccelerationStructureType::BottomLevel => {
-                vk::AccelerationStructureTypeKHR::BOTTOM_LEVEL
-            }
-        };
-        let vk_info = vk::AccelerationStructureCreateInfoKHR {
-            ty: raw_ty,
-            buffer,
-            size: desc.size,
-            ..Default::default()
-        };
-
-        let rt = self.device.ray_tracing.as_ref().unwrap();
-        let raw = unsafe {
-            rt.acceleration_structure
-                .create_acceleration_structure(&vk_info, None)
-                .unwrap()
-        };
-
-        if !desc.name.is_empty() {
-            self.set_object_name(buffer, desc.name);
-            self.set_object_name(raw, desc.name);
-        }
-        super::AccelerationStructure {
-            raw,
-            buffer,
-            memory_handle: allocation.handle,
-        }
+        self.try_create_acceleration_structure(desc).unwrap()
     }
 
     fn destroy_acceleration_structure(&self, acceleration_structure: super::AccelerationStructure) {
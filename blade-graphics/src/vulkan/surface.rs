@@ -9,6 +9,21 @@ impl super::Surface {
         }
     }
 
+    /// Get the usages of the frame textures, which are the ones
+    /// requested by `SurfaceConfig::usage` that the surface supports.
+    pub fn usage(&self) -> crate::TextureUsage {
+        self.swapchain.usage
+    }
+
+    /// Get the current size of the surface images.
+    ///
+    /// It may differ from the window size, e.g. while the window is being
+    /// moved between displays with different scale factors.
+    pub fn extent(&self) -> crate::Extent {
+        let [width, height] = self.swapchain.target_size;
+        crate::Extent::new_2d(width as u32, height as u32)
+    }
+
     unsafe fn deinit_swapchain(&mut self, raw_device: &ash::Device) {
         self.device
             .destroy_swapchain(mem::take(&mut self.swapchain.raw), None);
@@ -20,6 +35,8 @@ impl super::Surface {
 
     pub fn acquire_frame(&mut self) -> super::Frame {
         let acquire_semaphore = self.next_semaphore;
+        let frame_number = self.frame_number;
+        self.frame_number += 1;
         match unsafe {
             self.device.acquire_next_image(
                 self.swapchain.raw,
@@ -37,6 +54,7 @@ impl super::Surface {
                     internal: self.frames[index as usize],
                     swapchain: self.swapchain,
                     image_index: Some(index),
+                    frame_number,
                 }
             }
             Err(vk::Result::ERROR_OUT_OF_DATE_KHR) => {
@@ -45,6 +63,7 @@ impl super::Surface {
                     internal: self.frames[0],
                     swapchain: self.swapchain,
                     image_index: None,
+                    frame_number,
                 }
             }
             Err(other) => panic!("Aquire image error {}", other),
@@ -129,9 +148,11 @@ impl super::Context {
                 raw: vk::SwapchainKHR::null(),
                 format: crate::TextureFormat::Rgba8Unorm,
                 alpha: crate::AlphaMode::Ignored,
+                usage: crate::TextureUsage::empty(),
                 target_size: [0; 2],
             },
             full_screen_exclusive: fullscreen_exclusive_ext.full_screen_exclusive_supported != 0,
+            frame_number: 0,
         })
     }
 
@@ -167,38 +188,37 @@ impl super::Context {
             );
         }
 
-        let (alpha, composite_alpha) = if config.transparent {
-            if capabilities
-                .supported_composite_alpha
-                .contains(vk::CompositeAlphaFlagsKHR::POST_MULTIPLIED)
-            {
-                (
-                    crate::AlphaMode::PostMultiplied,
-                    vk::CompositeAlphaFlagsKHR::POST_MULTIPLIED,
-                )
-            } else if capabilities
-                .supported_composite_alpha
-                .contains(vk::CompositeAlphaFlagsKHR::PRE_MULTIPLIED)
-            {
-                (
-                    crate::AlphaMode::PreMultiplied,
-                    vk::CompositeAlphaFlagsKHR::PRE_MULTIPLIED,
-                )
-            } else {
-                log::error!(
-                    "No composite alpha flag for transparency: {:?}",
-                    capabilities.supported_composite_alpha
-                );
+        let supported_alpha = capabilities.supported_composite_alpha;
+        const POST_MULTIPLIED: (crate::AlphaMode, vk::CompositeAlphaFlagsKHR) = (
+            crate::AlphaMode::PostMultiplied,
+            vk::CompositeAlphaFlagsKHR::POST_MULTIPLIED,
+        );
+        const PRE_MULTIPLIED: (crate::AlphaMode, vk::CompositeAlphaFlagsKHR) = (
+            crate::AlphaMode::PreMultiplied,
+            vk::CompositeAlphaFlagsKHR::PRE_MULTIPLIED,
+        );
+        let preferences: &[_] = match config.composite_alpha {
+            crate::CompositeAlpha::Opaque => &[],
+            crate::CompositeAlpha::PreMultiplied => &[PRE_MULTIPLIED, POST_MULTIPLIED],
+            crate::CompositeAlpha::PostMultiplied => &[POST_MULTIPLIED, PRE_MULTIPLIED],
+        };
+        let (alpha, composite_alpha) = match preferences
+            .iter()
+            .find(|&&(_, flag)| supported_alpha.contains(flag))
+        {
+            Some(&pair) => pair,
+            None => {
+                if !preferences.is_empty() {
+                    log::error!(
+                        "No composite alpha flag for transparency: {:?}",
+                        supported_alpha
+                    );
+                }
                 (
                     crate::AlphaMode::Ignored,
                     vk::CompositeAlphaFlagsKHR::OPAQUE,
                 )
             }
-        } else {
-            (
-                crate::AlphaMode::Ignored,
-                vk::CompositeAlphaFlagsKHR::OPAQUE,
-            )
         };
 
         let (requested_frame_count, mode_preferences) = match config.display_sync {
@@ -293,13 +313,21 @@ impl super::Context {
             log::error!("Surface formats are incompatible: {:?}", supported_formats);
         }
 
-        let vk_usage = super::resource::map_texture_usage(config.usage, crate::TexelAspects::COLOR);
-        if !capabilities.supported_usage_flags.contains(vk_usage) {
-            log::error!(
-                "Surface usages are incompatible: {:?}",
-                capabilities.supported_usage_flags
-            );
+        // Keep the requested usages that the surface supports
+        let mut usage = crate::TextureUsage::TARGET;
+        for flag in config.usage.iter() {
+            let vk_flag = super::resource::map_texture_usage(flag, crate::TexelAspects::COLOR);
+            if capabilities.supported_usage_flags.contains(vk_flag) {
+                usage |= flag;
+            } else {
+                log::warn!(
+                    "Surface usage {:?} is not supported, only {:?}",
+                    flag,
+                    capabilities.supported_usage_flags
+                );
+            }
         }
+        let vk_usage = super::resource::map_texture_usage(usage, crate::TexelAspects::COLOR);
 
         let mut full_screen_exclusive_info = vk::SurfaceFullScreenExclusiveInfoEXT {
             full_screen_exclusive: if config.allow_exclusive_full_screen {
@@ -383,6 +411,7 @@ impl super::Context {
             raw: raw_swapchain,
             format,
             alpha,
+            usage,
             target_size,
         };
     }
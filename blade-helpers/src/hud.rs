@@ -13,6 +13,7 @@ impl ExposeHud for blade_render::RayConfig {
             &mut self.environment_importance_sampling,
             "Env importance sampling",
         );
+        ui.add(egui::Slider::new(&mut self.num_brdf_samples, 0..=16u32).text("Num BRDF samples"));
         ui.add(egui::widgets::Slider::new(&mut self.tap_count, 0..=10).text("Tap count"));
         ui.add(egui::widgets::Slider::new(&mut self.tap_radius, 1..=50).text("Tap radius (px)"));
         ui.add(
@@ -54,6 +55,105 @@ impl ExposeHud for blade_render::PostProcConfig {
                 .logarithmic(true),
         );
         ui.add(egui::Slider::new(&mut self.white_level, 0.1f32..=2f32).text("White level"));
+        let mut gamma = match self.output_transform {
+            blade_render::OutputTransform::Gamma(gamma) => gamma,
+            _ => 2.2,
+        };
+        egui::ComboBox::from_label("Output transform")
+            .selected_text(format!("{:?}", self.output_transform))
+            .show_ui(ui, |ui| {
+                for value in [
+                    blade_render::OutputTransform::None,
+                    blade_render::OutputTransform::Gamma(gamma),
+                    blade_render::OutputTransform::Tonemap,
+                ] {
+                    ui.selectable_value(&mut self.output_transform, value, format!("{value:?}"));
+                }
+            });
+        if let blade_render::OutputTransform::Gamma(_) = self.output_transform {
+            ui.add(egui::Slider::new(&mut gamma, 1.0f32..=3.0f32).text("Gamma"));
+            self.output_transform = blade_render::OutputTransform::Gamma(gamma);
+        }
+    }
+}
+
+impl ExposeHud for blade_render::AutoExposureConfig {
+    fn populate_hud(&mut self, ui: &mut egui::Ui) {
+        ui.checkbox(&mut self.enabled, "Auto exposure");
+        ui.add(egui::Slider::new(&mut self.min_ev, -10f32..=20f32).text("Min EV"));
+        ui.add(egui::Slider::new(&mut self.max_ev, -10f32..=20f32).text("Max EV"));
+        ui.add(
+            egui::Slider::new(&mut self.speed, 0.1f32..=10f32)
+                .text("Adaptation speed")
+                .logarithmic(true),
+        );
+    }
+}
+
+impl ExposeHud for blade_render::CausticsConfig {
+    fn populate_hud(&mut self, ui: &mut egui::Ui) {
+        ui.checkbox(&mut self.enabled, "Enable");
+        ui.add(
+            egui::Slider::new(&mut self.photon_count, 1024..=1 << 22)
+                .text("Photons")
+                .logarithmic(true),
+        );
+        ui.add(egui::Slider::new(&mut self.max_bounces, 1..=16u32).text("Max bounces"));
+        ui.add(egui::Slider::new(&mut self.intensity, 0.0f32..=4.0f32).text("Intensity"));
+    }
+}
+
+impl ExposeHud for blade_render::FogConfig {
+    fn populate_hud(&mut self, ui: &mut egui::Ui) {
+        ui.add(
+            egui::Slider::new(&mut self.density, 0.0f32..=1.0f32)
+                .text("Density")
+                .logarithmic(true),
+        );
+        ui.add(egui::Slider::new(&mut self.height_falloff, 0.0f32..=1.0f32).text("Height falloff"));
+        ui.horizontal(|ui| {
+            ui.label("Color:");
+            ui.color_edit_button_rgb(&mut self.color);
+        });
+        ui.horizontal(|ui| {
+            ui.label("Sun color:");
+            ui.color_edit_button_rgb(&mut self.sun_color);
+        });
+        ui.add(
+            egui::Slider::new(&mut self.sun_anisotropy, -0.95f32..=0.95f32).text("Sun anisotropy"),
+        );
+    }
+}
+
+impl ExposeHud for blade_render::GridConfig {
+    fn populate_hud(&mut self, ui: &mut egui::Ui) {
+        ui.add(
+            egui::Slider::new(&mut self.spacing, 0.01f32..=100.0f32)
+                .text("Spacing")
+                .logarithmic(true),
+        );
+        ui.add(
+            egui::Slider::new(&mut self.fade_distance, 1.0f32..=1000.0f32)
+                .text("Fade distance")
+                .logarithmic(true),
+        );
+        ui.add(egui::Slider::new(&mut self.height, -10.0f32..=10.0f32).text("Height"));
+        ui.horizontal(|ui| {
+            ui.label("Color:");
+            ui.color_edit_button_rgba_unmultiplied(&mut self.color);
+        });
+        ui.checkbox(&mut self.axes, "Axes");
+    }
+}
+
+impl ExposeHud for blade_render::AdaptiveConfig {
+    fn populate_hud(&mut self, ui: &mut egui::Ui) {
+        ui.add(
+            egui::Slider::new(&mut self.target_variance, 0.001f32..=1.0f32)
+                .text("Target variance")
+                .logarithmic(true),
+        );
+        ui.add(egui::Slider::new(&mut self.max_samples, 1..=32u32).text("Max samples"));
     }
 }
 
@@ -123,6 +223,14 @@ pub fn populate_debug_selection(
                 .trailing_zeros();
             ui.colored_label(egui::Color32::WHITE, format!("2^{}", power));
         });
+        ui.horizontal(|ui| {
+            let id = &selection.instance;
+            ui.label("Instance:");
+            ui.colored_label(
+                egui::Color32::WHITE,
+                format!("object {} geometry {}", id.object, id.geometry),
+            );
+        });
         ui.horizontal(|ui| {
             ui.label("Depth:");
             ui.colored_label(egui::Color32::WHITE, format!("{:.2}", selection.depth));
@@ -11,6 +11,7 @@ pub fn default_ray_config() -> blade_render::RayConfig {
     blade_render::RayConfig {
         num_environment_samples: 1,
         environment_importance_sampling: true,
+        num_brdf_samples: 1,
         tap_count: 2,
         tap_radius: 20,
         tap_confidence_near: 15,
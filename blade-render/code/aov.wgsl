@@ -0,0 +1,31 @@
+#include "quaternion.inc.wgsl"
+
+var t_depth: texture_2d<f32>;
+var t_basis: texture_2d<f32>;
+var t_albedo: texture_2d<f32>;
+var light_diffuse: texture_2d<f32>;
+var out_color: texture_storage_2d<rgba32float, write>;
+var out_albedo: texture_storage_2d<rgba32float, write>;
+var out_normal: texture_storage_2d<rgba32float, write>;
+var out_depth: texture_storage_2d<rgba32float, write>;
+
+// Converts the G-buffer into plain linear buffers for external denoisers.
+@compute @workgroup_size(8, 8)
+fn write_aovs(@builtin(global_invocation_id) global_id: vec3<u32>) {
+    if (any(global_id.xy >= textureDimensions(t_depth, 0))) {
+        return;
+    }
+    let pixel = vec2<i32>(global_id.xy);
+    let depth = textureLoad(t_depth, pixel, 0).x;
+    let albedo = textureLoad(t_albedo, pixel, 0);
+    let color = albedo.xyz * textureLoad(light_diffuse, pixel, 0).xyz;
+    var normal = vec3<f32>(0.0);
+    if (depth != 0.0) {
+        let basis = normalize(textureLoad(t_basis, pixel, 0));
+        normal = qrot(basis, vec3<f32>(0.0, 0.0, 1.0));
+    }
+    textureStore(out_color, global_id.xy, vec4<f32>(color, 1.0));
+    textureStore(out_albedo, global_id.xy, vec4<f32>(albedo.xyz, 1.0));
+    textureStore(out_normal, global_id.xy, vec4<f32>(normal, 0.0));
+    textureStore(out_depth, global_id.xy, vec4<f32>(depth, 0.0, 0.0, 0.0));
+}
@@ -0,0 +1,166 @@
+#include "quaternion.inc.wgsl"
+#include "random.inc.wgsl"
+#include "camera.inc.wgsl"
+#include "hit.inc.wgsl"
+#include "light.inc.wgsl"
+
+const PI: f32 = 3.1415926;
+// Fixed-point scale of the deposited lighting.
+// Has to match `CAUSTICS_SCALE` on the host!
+const CAUSTICS_SCALE: f32 = 1024.0;
+
+struct CausticsParams {
+    frame_index: u32,
+    photon_count: u32,
+    max_bounces: u32,
+    light_count: u32,
+    // metals under this roughness are reflecting like mirrors
+    mirror_roughness: f32,
+    // limit of a single photon contribution to a pixel
+    max_deposit: f32,
+    pad: vec2<u32>,
+}
+
+var<uniform> camera: CameraParams;
+var<uniform> params: CausticsParams;
+var acc_struct: acceleration_structure;
+var t_depth: texture_2d<f32>;
+// Lighting per pixel, as 3 fixed-point channels.
+var<storage, read_write> caustics: array<atomic<u32>>;
+
+fn trace_ray(origin: vec3<f32>, dir: vec3<f32>) -> RayIntersection {
+    var rq: ray_query;
+    rayQueryInitialize(&rq, acc_struct, RayDesc(RAY_FLAG_CULL_NO_OPAQUE, 0xFFu, 0.0, camera.depth, origin, dir));
+    rayQueryProceed(&rq);
+    return rayQueryGetCommittedIntersection(&rq);
+}
+
+// Uniformly distributed direction within a cone around the axis.
+fn sample_cone(axis: vec3<f32>, cos_max: f32, rng: ptr<function, RandomState>) -> vec3<f32> {
+    let cos_theta = 1.0 - random_gen(rng) * (1.0 - cos_max);
+    let sin_theta = sqrt(max(0.0, 1.0 - cos_theta * cos_theta));
+    let phi = 2.0 * PI * random_gen(rng);
+    let up = select(vec3<f32>(0.0, 1.0, 0.0), vec3<f32>(1.0, 0.0, 0.0), abs(axis.y) > 0.9);
+    let tangent = normalize(cross(up, axis));
+    let bitangent = cross(axis, tangent);
+    return sin_theta * (cos(phi) * tangent + sin(phi) * bitangent) + cos_theta * axis;
+}
+
+// Windowed falloff of KHR_lights_punctual, without the inverse square part,
+// which is accounted for by the density of the photons.
+fn get_range_window(distance: f32, range: f32) -> f32 {
+    if (range <= 0.0) {
+        return 1.0;
+    }
+    let ratio = distance / range;
+    let window = clamp(1.0 - ratio * ratio * ratio * ratio, 0.0, 1.0);
+    return window * window;
+}
+
+// Splat the photon into the pixel that sees the surface point, if any.
+fn deposit(position: vec3<f32>, normal: vec3<f32>, photon_dir: vec3<f32>, power: vec3<f32>) {
+    let to_camera = camera.position - position;
+    let distance = length(to_camera);
+    let view_dir = to_camera / distance;
+    // the photon has to arrive on the visible side
+    let cos_view = dot(normal, view_dir);
+    if (cos_view * dot(normal, -photon_dir) <= 0.0) {
+        return;
+    }
+    let pixel = get_projected_pixel(camera, position);
+    if (any(pixel < vec2<i32>(0)) || any(pixel >= vec2<i32>(camera.target_size))) {
+        return;
+    }
+    let depth = textureLoad(t_depth, pixel, 0).x;
+    if (depth == 0.0 || abs(depth - distance) > 0.01 * distance) {
+        return;
+    }
+    // area of the surface covered by the pixel
+    let local_dir = qrot(qinv(camera.orientation), -view_dir);
+    let pixel_size = 2.0 * tan(0.5 * camera.fov) / vec2<f32>(camera.target_size);
+    let cos_axis = -local_dir.z;
+    let solid_angle = pixel_size.x * pixel_size.y * cos_axis * cos_axis * cos_axis;
+    let area = solid_angle * distance * distance / abs(cos_view);
+    // the lighting is the irradiance over PI, to be modulated by the albedo
+    let lighting = min(power / (PI * area), vec3<f32>(params.max_deposit));
+    let fixed = vec3<u32>(lighting * CAUSTICS_SCALE + 0.5);
+    let base = 3u * (u32(pixel.y) * camera.target_size.x + u32(pixel.x));
+    atomicAdd(&caustics[base + 0u], fixed.x);
+    atomicAdd(&caustics[base + 1u], fixed.y);
+    atomicAdd(&caustics[base + 2u], fixed.z);
+}
+
+// Trace a photon from a random light, following the mirrors and the
+// transmissive surfaces. Only the paths that have been focused by at least
+// one of those are deposited, since the direct lighting is computed
+// by the main pass.
+@compute @workgroup_size(64, 1, 1)
+fn trace_photons(@builtin(global_invocation_id) global_id: vec3<u32>) {
+    if (global_id.x >= params.photon_count || params.light_count == 0u) {
+        return;
+    }
+    var rng = random_init(global_id.x, params.frame_index);
+    let light_index = min(u32(random_gen(&rng) * f32(params.light_count)), params.light_count - 1u);
+    let light = lights[light_index];
+    let is_spot = light.cos_outer > -1.0;
+    let cos_max = select(-1.0, light.cos_outer, is_spot);
+    let axis = select(vec3<f32>(0.0, 1.0, 0.0), light.direction, is_spot);
+    var dir = sample_cone(axis, cos_max, &rng);
+    let solid_angle = 2.0 * PI * (1.0 - cos_max);
+    var power = light.radiance * solid_angle * f32(params.light_count) / f32(params.photon_count);
+    if (is_spot) {
+        power *= smoothstep(light.cos_outer, light.cos_inner, dot(light.direction, dir));
+    }
+
+    var position = light.position;
+    var is_focused = false;
+    for (var i = 0u; i <= params.max_bounces; i += 1u) {
+        let intersection = trace_ray(position, dir);
+        if (intersection.kind == RAY_QUERY_INTERSECTION_NONE) {
+            return;
+        }
+        if (i == 0u) {
+            power *= get_range_window(intersection.t, light.range);
+        }
+        let entry = hit_entries[intersection.instance_custom_data + intersection.geometry_index];
+        let hit_position = position + intersection.t * dir;
+        let normal = get_hit_normal(intersection, entry);
+        let is_mirror = entry.metallic > 0.0 && entry.roughness <= params.mirror_roughness;
+        if (entry.transmission <= 0.0 && !is_mirror) {
+            if (is_focused) {
+                deposit(hit_position, normal, dir, power);
+            }
+            return;
+        }
+        is_focused = true;
+
+        let base_color = unpack4x8unorm(entry.base_color_factor).xyz;
+        let entering = dot(normal, dir) < 0.0;
+        let face_normal = select(-normal, normal, entering);
+        var side = 1.0;
+        if (entry.transmission > 0.0) {
+            let eta = select(entry.ior, 1.0 / entry.ior, entering);
+            let refracted = refract(dir, face_normal, eta);
+            // total internal reflection produces a zero vector
+            var reflectance = 1.0;
+            if (any(refracted != vec3<f32>(0.0))) {
+                let cos_theta = select(-dot(face_normal, dir), -dot(face_normal, refracted), eta > 1.0);
+                reflectance = fresnel_schlick(cos_theta, eta);
+            }
+            if (random_gen(&rng) < reflectance) {
+                dir = reflect(dir, face_normal);
+            } else {
+                dir = refracted;
+                side = -1.0;
+                power *= entry.transmission * base_color;
+            }
+        } else {
+            let cos_theta = saturate(-dot(face_normal, dir));
+            let f0 = mix(vec3<f32>(0.04), base_color, entry.metallic);
+            power *= f0 + (vec3<f32>(1.0) - f0) * pow(1.0 - cos_theta, 5.0);
+            dir = reflect(dir, face_normal);
+        }
+        let scale = max(1.0, max(abs(hit_position.x), max(abs(hit_position.y), abs(hit_position.z))));
+        position = hit_position + side * 1e-4 * scale * face_normal;
+    }
+}
@@ -5,6 +5,12 @@
 var<uniform> camera: CameraParams;
 var<storage> debug_lines: array<DebugLine>;
 
+struct ViewportParams {
+    origin: vec2<i32>,
+    pad: vec2<u32>,
+}
+var<uniform> viewport: ViewportParams;
+
 struct DebugVarying {
     @builtin(position) pos: vec4<f32>,
     @location(0) color: vec4<f32>,
@@ -35,7 +41,8 @@ var depth: texture_2d<f32>;
 @fragment
 fn debug_fs(in: DebugVarying) -> @location(0) vec4<f32> {
     let geo_dim = textureDimensions(depth);
-    let depth_itc = vec2<i32>(i32(in.pos.x), i32(geo_dim.y) - i32(in.pos.y));
+    let pos = vec2<i32>(in.pos.xy) - viewport.origin;
+    let depth_itc = vec2<i32>(pos.x, i32(geo_dim.y) - pos.y);
     let depth = textureLoad(depth, depth_itc, 0).x;
     let alpha = select(0.8, 0.2, depth != 0.0 && dot(in.dir, in.dir) > depth*depth);
     return vec4<f32>(in.color.xyz, alpha);
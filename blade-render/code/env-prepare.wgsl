@@ -1,5 +1,5 @@
 var source: texture_2d<f32>;
-var destination: texture_storage_2d<rgba16float, write>;
+var destination: texture_storage_2d<rgba32float, write>;
 struct EnvPreprocParams {
     target_level: u32,
 }
@@ -7,7 +7,6 @@ var<uniform> params: EnvPreprocParams;
 
 const PI: f32 = 3.1415926;
 const LUMA: vec3<f32> = vec3<f32>(0.299, 0.587, 0.114);
-const MAX_FP16: f32 = 65504.0;
 const SUM: vec4<f32> = vec4<f32>(0.25, 0.25, 0.25, 0.25);
 
 // Returns the weight of a pixel at the specified coordinates.
@@ -21,7 +20,9 @@ fn get_pixel_weight(pixel: vec2<u32>, src_size: vec2<u32>) -> f32 {
         let luma = max(0.0, dot(LUMA, color.xyz));
         let elevation = ((f32(pixel.y) + 0.5) / f32(src_size.y) - 0.5) * PI;
         let relative_solid_angle = cos(elevation);
-        return clamp(luma * relative_solid_angle, 0.0, MAX_FP16);
+        // Full float range is kept, so that a sun disk isn't flattened
+        // to the same weight as the bright sky around it.
+        return luma * relative_solid_angle;
     } else {
         return dot(SUM, color);
     }
@@ -0,0 +1,25 @@
+// Requires "quaternion.inc.wgsl" to be included first.
+
+// Has to match the host!
+struct EnvironmentParams {
+    // rotation from the environment map space to the world
+    rotation: vec4<f32>,
+    // multiplier of the environment radiance
+    intensity: f32,
+}
+var<uniform> environment: EnvironmentParams;
+
+fn map_equirect_dir_to_uv(world_dir: vec3<f32>) -> vec2<f32> {
+    let dir = qrot(qinv(environment.rotation), world_dir);
+    //Note: Y axis is up
+    let yaw = asin(dir.y);
+    let pitch = atan2(dir.x, dir.z);
+    return vec2<f32>(pitch + PI, -2.0 * yaw + PI) / (2.0 * PI);
+}
+
+fn map_equirect_uv_to_dir(uv: vec2<f32>) -> vec3<f32> {
+    let yaw = PI * (0.5 - uv.y);
+    let pitch = 2.0 * PI * (uv.x - 0.5);
+    let dir = vec3<f32>(cos(yaw) * sin(pitch), sin(yaw), cos(yaw) * cos(pitch));
+    return qrot(environment.rotation, dir);
+}
@@ -0,0 +1,67 @@
+struct ExposureParams {
+    min_log_lum: f32,
+    max_log_lum: f32,
+    // blend factor between the previous and the measured luminance
+    adaptation: f32,
+    pad: u32,
+}
+
+struct ExposureState {
+    average_lum: f32,
+    // log2 of the luminance measured this frame
+    measured_log_lum: f32,
+}
+
+var<uniform> params: ExposureParams;
+var t_albedo: texture_2d<f32>;
+var light_diffuse: texture_2d<f32>;
+var<storage, read_write> state: ExposureState;
+
+const GROUP_SIZE: u32 = 16u;
+// Only every N-th pixel in each dimension is measured
+const PIXEL_STRIDE: u32 = 4u;
+const LUMA: vec3<f32> = vec3<f32>(0.2126, 0.7152, 0.0722);
+
+var<workgroup> partial_sums: array<vec2<f32>, 256>;
+
+// Log-average luminance of the final image, reduced by a single workgroup.
+@compute @workgroup_size(16, 16)
+fn measure(
+    @builtin(local_invocation_id) local_id: vec3<u32>,
+    @builtin(local_invocation_index) local_index: u32,
+) {
+    let size = textureDimensions(light_diffuse, 0);
+    let step = GROUP_SIZE * PIXEL_STRIDE;
+    var sum = vec2<f32>(0.0);
+    for (var y = local_id.y * PIXEL_STRIDE; y < size.y; y += step) {
+        for (var x = local_id.x * PIXEL_STRIDE; x < size.x; x += step) {
+            let pixel = vec2<i32>(i32(x), i32(y));
+            let albedo = textureLoad(t_albedo, pixel, 0);
+            let color = albedo.xyz * textureLoad(light_diffuse, pixel, 0).xyz;
+            let log_lum = clamp(log2(dot(color, LUMA)), params.min_log_lum, params.max_log_lum);
+            sum += vec2<f32>(log_lum, 1.0);
+        }
+    }
+    partial_sums[local_index] = sum;
+    workgroupBarrier();
+
+    for (var offset = 128u; offset > 0u; offset >>= 1u) {
+        if (local_index < offset) {
+            partial_sums[local_index] += partial_sums[local_index + offset];
+        }
+        workgroupBarrier();
+    }
+
+    if (local_index == 0u) {
+        let total = partial_sums[0];
+        if (total.y > 0.0) {
+            let log_lum = total.x / total.y;
+            state.measured_log_lum = log_lum;
+            if (state.average_lum > 0.0) {
+                state.average_lum = exp2(mix(log2(state.average_lum), log_lum, params.adaptation));
+            } else {
+                state.average_lum = exp2(log_lum);
+            }
+        }
+    }
+}
@@ -1,66 +1,374 @@
 #include "quaternion.inc.wgsl"
+#include "random.inc.wgsl"
 #include "camera.inc.wgsl"
 #include "debug.inc.wgsl"
 #include "debug-param.inc.wgsl"
 #include "gbuf.inc.wgsl"
+#include "hit.inc.wgsl"
+#include "environment.inc.wgsl"
+#include "light.inc.wgsl"
 
-// Has to match the host!
-struct Vertex {
-    pos: vec3<f32>,
-    bitangent_sign: f32,
-    tex_coords: vec2<f32>,
-    normal: u32,
-    tangent: u32,
-}
-struct VertexBuffer {
-    data: array<Vertex>,
-}
-struct IndexBuffer {
-    data: array<u32>,
-}
-var<storage, read> vertex_buffers: binding_array<VertexBuffer>;
-var<storage, read> index_buffers: binding_array<IndexBuffer>;
 var textures: binding_array<texture_2d<f32>>;
 var sampler_linear: sampler;
 var sampler_nearest: sampler;
 
-struct HitEntry {
-    index_buf: u32,
-    vertex_buf: u32,
-    winding: f32,
-    // packed quaternion
-    geometry_to_world_rotation: u32,
-    geometry_to_object: mat4x3<f32>,
-    prev_object_to_world: mat4x3<f32>,
-    base_color_texture: u32,
-    // packed color factor
-    base_color_factor: u32,
-    normal_texture: u32,
-    normal_scale: f32,
-}
-var<storage, read> hit_entries: array<HitEntry>;
-
 var<uniform> camera: CameraParams;
 var<uniform> prev_camera: CameraParams;
 var<uniform> debug: DebugParams;
 var acc_struct: acceleration_structure;
 
+const MAX_CLIP_PLANES: u32 = 4u;
+// How many clipped surfaces a primary ray can pass through
+const MAX_CLIPPED_HITS: u32 = 8u;
+
+struct ClipParams {
+    planes: array<vec4<f32>, MAX_CLIP_PLANES>,
+    // zero alpha if the cuts are not capped
+    cap_color: vec4<f32>,
+    count: u32,
+}
+var<uniform> clip: ClipParams;
+
+struct LodParams {
+    // added to the mip level of every texture sample
+    mip_bias: f32,
+    // angle between the primary rays of adjacent pixels
+    spread_angle: f32,
+    pad: vec2<u32>,
+}
+var<uniform> lod_params: LodParams;
+
+const PI: f32 = 3.1415926;
+
+struct TransmissionParams {
+    frame_index: u32,
+    // non-zero if a wavelength is sampled for every path
+    dispersion: u32,
+    // how many surfaces a reflected or refracted path
+    // can cross before it's terminated
+    max_bounces: u32,
+    // metals under this roughness are reflecting like mirrors
+    mirror_roughness: f32,
+    light_count: u32,
+    // not a vector, which would align the struct to 16 bytes
+    pad0: u32,
+    pad1: u32,
+    pad2: u32,
+}
+var<uniform> transmission_params: TransmissionParams;
+var env_map: texture_2d<f32>;
+
 var out_depth: texture_storage_2d<r32float, write>;
 var out_flat_normal: texture_storage_2d<rgba8snorm, write>;
 var out_basis: texture_storage_2d<rgba8snorm, write>;
 var out_albedo: texture_storage_2d<rgba8unorm, write>;
 var out_motion: texture_storage_2d<rg8snorm, write>;
+var out_transmission: texture_storage_2d<rgba16float, write>;
+var out_instance: texture_storage_2d<r32uint, write>;
 var out_debug: texture_storage_2d<rgba8unorm, write>;
 
-fn decode_normal(raw: u32) -> vec3<f32> {
-    return unpack4x8snorm(raw).xyz;
-}
-
 fn debug_raw_normal(pos: vec3<f32>, normal_raw: u32, rotation: vec4<f32>, debug_len: f32, color: u32) {
     let nw = normalize(qrot(rotation, decode_normal(normal_raw)));
     debug_line(pos, pos + debug_len * nw, color);
 }
 
+fn is_clipped(position: vec3<f32>) -> bool {
+    for (var i = 0u; i < clip.count; i += 1u) {
+        let plane = clip.planes[i];
+        if (dot(plane.xyz, position) + plane.w > 0.0) {
+            return true;
+        }
+    }
+    return false;
+}
+
+// Find the closest clip plane crossing before `t_max`,
+// returns the plane normal and distance, or a negative distance if none.
+fn find_clip_cap(ray_dir: vec3<f32>, t_max: f32) -> vec4<f32> {
+    var cap = vec4<f32>(0.0, 0.0, 0.0, -1.0);
+    for (var i = 0u; i < clip.count; i += 1u) {
+        let plane = clip.planes[i];
+        let denom = dot(plane.xyz, ray_dir);
+        if (denom < 0.0) {
+            let t = -(dot(plane.xyz, camera.position) + plane.w) / denom;
+            if (t > cap.w && t < t_max) {
+                cap = vec4<f32>(normalize(plane.xyz), t);
+            }
+        }
+    }
+    return cap;
+}
+
+fn trace_primary(ray_dir: vec3<f32>, t_min: f32) -> RayIntersection {
+    var rq: ray_query;
+    rayQueryInitialize(&rq, acc_struct, RayDesc(RAY_FLAG_CULL_NO_OPAQUE, 0xFFu, t_min, camera.depth, camera.position, ray_dir));
+    rayQueryProceed(&rq);
+    return rayQueryGetCommittedIntersection(&rq);
+}
+
+// Mip level of a texture seen through a ray cone, without the texture size.
+// Following "Texture Level of Detail Strategies for Real-Time Ray Tracing"
+// from Ray Tracing Gems, with the triangle LOD constant computed per hit.
+fn compute_cone_lod(uvs: mat3x2<f32>, world_area: f32, cone_width: f32, cos_angle: f32) -> f32 {
+    let uv_area = abs(determinant(mat2x2<f32>(uvs[1] - uvs[0], uvs[2] - uvs[0])));
+    let triangle_lod = 0.5 * log2(max(uv_area, 1e-12) / max(world_area, 1e-12));
+    return triangle_lod + log2(cone_width / cos_angle) + lod_params.mip_bias;
+}
+
+fn texture_lod(cone_lod: f32, dim: vec2<u32>) -> f32 {
+    return cone_lod + 0.5 * log2(f32(dim.x) * f32(dim.y));
+}
+
+// Texture coordinates of the triangle corners, as seen by a texture
+// with the given bit in `tex_coord_sets` and index in `uv_transforms`.
+fn get_texture_uvs(entry: HitEntry, uvs0: mat3x2<f32>, uvs1: mat3x2<f32>, index: u32) -> mat3x2<f32> {
+    var uvs = uvs0;
+    if ((entry.tex_coord_sets & (1u << index)) != 0u) {
+        uvs = uvs1;
+    }
+    return entry.uv_transforms[index] * mat3x3<f32>(vec3<f32>(uvs[0], 1.0), vec3<f32>(uvs[1], 1.0), vec3<f32>(uvs[2], 1.0));
+}
+
+// Pick the channel of a packed material texture, given the bit offset in `channel_map`.
+fn get_mapped_channel(entry: HitEntry, texel: vec4<f32>, offset: u32) -> f32 {
+    return texel[(entry.channel_map >> offset) & 3u];
+}
+
+fn trace_ray_within(origin: vec3<f32>, ray_dir: vec3<f32>, t_max: f32) -> RayIntersection {
+    var rq: ray_query;
+    rayQueryInitialize(&rq, acc_struct, RayDesc(RAY_FLAG_CULL_NO_OPAQUE, 0xFFu, 0.0, t_max, origin, ray_dir));
+    rayQueryProceed(&rq);
+    return rayQueryGetCommittedIntersection(&rq);
+}
+
+fn trace_ray(origin: vec3<f32>, ray_dir: vec3<f32>) -> RayIntersection {
+    return trace_ray_within(origin, ray_dir, camera.depth);
+}
+
+// Weights of a wavelength, going from red at 0 to violet at 1,
+// that average to white when the wavelengths are sampled uniformly.
+fn get_wavelength_weight(wavelength: f32) -> vec3<f32> {
+    let s = 2.0 * wavelength;
+    return vec3<f32>(4.0 * max(0.0, 1.0 - s), 2.0 * (1.0 - abs(1.0 - s)), 4.0 * max(0.0, s - 1.0));
+}
+
+// Index of refraction at a wavelength, following the Cauchy fit
+// of the Abbe number defined by `KHR_materials_dispersion`.
+fn get_dispersed_ior(ior: f32, dispersion: f32, wavelength: f32) -> f32 {
+    let nm = mix(700.0, 400.0, wavelength);
+    return max(1.0, ior + (ior - 1.0) * dispersion / 20.0 * (523655.0 / (nm * nm) - 1.5168));
+}
+
+// Follow the path of light reflected and refracted by transmissive surfaces,
+// picking one of the directions at random according to the Fresnel term.
+// Returns the radiance seen along the path.
+// Note: opaque surfaces at the end of the path are only lit by the environment
+// along their normal and by a random punctual light, since these surfaces
+// aren't shaded until the main pass.
+fn trace_transmission(start_position: vec3<f32>, start_dir: vec3<f32>, start_normal: vec3<f32>, start_entry: HitEntry, rng: ptr<function, RandomState>) -> vec3<f32> {
+    var wavelength = -1.0;
+    var throughput = vec3<f32>(1.0);
+    if (transmission_params.dispersion != 0u) {
+        wavelength = random_gen(rng);
+        throughput = get_wavelength_weight(wavelength);
+    }
+
+    var position = start_position;
+    var dir = start_dir;
+    var normal = start_normal;
+    var entry = start_entry;
+    for (var i = 0u; i < transmission_params.max_bounces; i += 1u) {
+        var ior = entry.ior;
+        if (wavelength >= 0.0 && entry.dispersion > 0.0) {
+            ior = get_dispersed_ior(entry.ior, entry.dispersion, wavelength);
+        }
+        let entering = dot(normal, dir) < 0.0;
+        let face_normal = select(-normal, normal, entering);
+        let eta = select(ior, 1.0 / ior, entering);
+        let refracted = refract(dir, face_normal, eta);
+        // total internal reflection produces a zero vector
+        var reflectance = 1.0;
+        if (any(refracted != vec3<f32>(0.0))) {
+            let cos_theta = select(-dot(face_normal, dir), -dot(face_normal, refracted), eta > 1.0);
+            reflectance = fresnel_schlick(cos_theta, eta);
+        }
+        var side = 1.0;
+        if (random_gen(rng) < reflectance) {
+            dir = reflect(dir, face_normal);
+        } else {
+            dir = refracted;
+            side = -1.0;
+        }
+
+        let scale = max(1.0, max(abs(position.x), max(abs(position.y), abs(position.z))));
+        let origin = position + side * 1e-4 * scale * face_normal;
+        let intersection = trace_ray(origin, dir);
+        if (intersection.kind == RAY_QUERY_INTERSECTION_NONE) {
+            let env = environment.intensity * textureSampleLevel(env_map, sampler_linear, map_equirect_dir_to_uv(dir), 0.0).xyz;
+            return throughput * env;
+        }
+        entry = hit_entries[intersection.instance_custom_data + intersection.geometry_index];
+        position = origin + intersection.t * dir;
+        normal = get_hit_normal(intersection, entry);
+        if (entry.transmission <= 0.0) {
+            let surface_normal = select(-normal, normal, dot(normal, dir) < 0.0);
+            let lighting = get_exit_lighting(position, surface_normal, rng);
+            return throughput * unpack4x8unorm(entry.base_color_factor).xyz * lighting;
+        }
+        throughput *= entry.transmission;
+    }
+    // ran out of bounces
+    return vec3<f32>(0.0);
+}
+
+fn is_mirror(entry: HitEntry) -> bool {
+    return entry.metallic > 0.0 && entry.roughness <= transmission_params.mirror_roughness;
+}
+
+// Reflectance of a thin film on top of a surface with the `base` reflectance,
+// from the interference of the waves reflected by both sides of the film,
+// evaluated at a representative wavelength of each color channel.
+fn get_thin_film_reflectance(entry: HitEntry, cos_theta: f32, base: vec3<f32>) -> vec3<f32> {
+    let n_film = entry.iridescence_ior;
+    let sin2_film = (1.0 - cos_theta * cos_theta) / (n_film * n_film);
+    let cos_film = sqrt(max(0.0, 1.0 - sin2_film));
+    // amplitude coefficients at the top and the bottom of the film
+    let r_top = (cos_theta - n_film * cos_film) / (cos_theta + n_film * cos_film);
+    let r_bottom = sqrt(base);
+    let wavelengths = vec3<f32>(650.0, 510.0, 475.0);
+    let phase = 4.0 * PI * n_film * entry.iridescence_thickness * cos_film / wavelengths;
+    let cross = 2.0 * r_top * r_bottom * cos(phase);
+    let r_top2 = r_top * r_top;
+    return (r_top2 + base + cross) / (vec3<f32>(1.0) + r_top2 * base + cross);
+}
+
+// Fresnel reflectance of a mirror, using the base color as the metal tint.
+fn get_mirror_reflectance(entry: HitEntry, cos_theta: f32) -> vec3<f32> {
+    let base_color = unpack4x8unorm(entry.base_color_factor).xyz;
+    let f0 = mix(vec3<f32>(0.04), base_color, entry.metallic);
+    let fresnel = f0 + (vec3<f32>(1.0) - f0) * pow(1.0 - saturate(cos_theta), 5.0);
+    if (entry.iridescence > 0.0 && entry.iridescence_thickness > 0.0) {
+        let film = get_thin_film_reflectance(entry, saturate(cos_theta), fresnel);
+        return mix(fresnel, film, entry.iridescence);
+    }
+    return fresnel;
+}
+
+// Follow the path of light bouncing between mirrors, up to the bounce limit.
+// Returns the radiance seen along the path, scaled by the reflectance
+// of every mirror on the way, including the starting one.
+// Note: paths ending on other surfaces only receive the environment light
+// from the direction of their normal, since these surfaces aren't lit
+// until the main pass.
+fn trace_reflection(start_position: vec3<f32>, start_dir: vec3<f32>, start_normal: vec3<f32>, start_entry: HitEntry) -> vec3<f32> {
+    var throughput = vec3<f32>(1.0);
+    var position = start_position;
+    var dir = start_dir;
+    var normal = start_normal;
+    var entry = start_entry;
+    for (var i = 0u; i < transmission_params.max_bounces; i += 1u) {
+        let face_normal = select(-normal, normal, dot(normal, dir) < 0.0);
+        throughput *= get_mirror_reflectance(entry, -dot(face_normal, dir));
+        dir = reflect(dir, face_normal);
+
+        let scale = max(1.0, max(abs(position.x), max(abs(position.y), abs(position.z))));
+        let origin = position + 1e-4 * scale * face_normal;
+        let intersection = trace_ray(origin, dir);
+        if (intersection.kind == RAY_QUERY_INTERSECTION_NONE) {
+            let env = environment.intensity * textureSampleLevel(env_map, sampler_linear, map_equirect_dir_to_uv(dir), 0.0).xyz;
+            return throughput * env;
+        }
+        entry = hit_entries[intersection.instance_custom_data + intersection.geometry_index];
+        position = origin + intersection.t * dir;
+        normal = get_hit_normal(intersection, entry);
+        if (!is_mirror(entry)) {
+            let surface_normal = select(-normal, normal, dot(normal, dir) < 0.0);
+            let ambient = environment.intensity * textureSampleLevel(env_map, sampler_linear, map_equirect_dir_to_uv(surface_normal), 0.0).xyz;
+            return throughput * unpack4x8unorm(entry.base_color_factor).xyz * ambient;
+        }
+    }
+    // ran out of bounces
+    return vec3<f32>(0.0);
+}
+
+// Steps of the random walk under the surface before it's terminated.
+const MAX_SUBSURFACE_STEPS: u32 = 16u;
+
+fn sample_uniform_sphere(rng: ptr<function, RandomState>) -> vec3<f32> {
+    let h = 1.0 - 2.0 * random_gen(rng);
+    let phi = 2.0 * PI * random_gen(rng);
+    let r = sqrt(max(0.0, 1.0 - h * h));
+    return vec3<f32>(r * cos(phi), h, r * sin(phi));
+}
+
+fn sample_cosine_hemisphere(normal: vec3<f32>, rng: ptr<function, RandomState>) -> vec3<f32> {
+    let dir = normal + sample_uniform_sphere(rng);
+    let len = length(dir);
+    return select(normal, dir / len, len > 1e-4);
+}
+
+// Lighting of a diffuse surface at the point where light leaves the volume:
+// the environment along the normal, and one of the punctual lights
+// picked at random, with a shadow ray.
+fn get_exit_lighting(position: vec3<f32>, normal: vec3<f32>, rng: ptr<function, RandomState>) -> vec3<f32> {
+    var lighting = environment.intensity * textureSampleLevel(env_map, sampler_linear, map_equirect_dir_to_uv(normal), 0.0).xyz;
+    let light_count = transmission_params.light_count;
+    if (light_count == 0u) {
+        return lighting;
+    }
+    let light = lights[min(u32(random_gen(rng) * f32(light_count)), light_count - 1u)];
+    let scale = max(1.0, max(abs(position.x), max(abs(position.y), abs(position.z))));
+    let origin = position + 1e-4 * scale * normal;
+    let offset = light.position - origin;
+    let distance = length(offset);
+    let dir = offset / distance;
+    let cos_theta = dot(dir, normal);
+    if (distance <= 0.0 || cos_theta <= 0.0) {
+        return lighting;
+    }
+    let cone = smoothstep(light.cos_outer, light.cos_inner, dot(light.direction, -dir));
+    let attenuation = get_light_attenuation(distance, light.range) * select(cone, 1.0, light.cos_outer <= -1.0);
+    if (attenuation > 0.0 && trace_ray_within(origin, dir, distance).kind == RAY_QUERY_INTERSECTION_NONE) {
+        lighting += f32(light_count) * light.radiance * attenuation * cos_theta / PI;
+    }
+    return lighting;
+}
+
+// Random walk of the light under the surface, entering at `start_position`
+// and scattering isotropically after exponentially distributed steps
+// with the mean of `subsurface_distance`, tinted by the subsurface color
+// at each event. Returns the lighting at the point where the walk leaves
+// the volume, to be multiplied by the albedo like the surface lighting.
+fn trace_subsurface(start_position: vec3<f32>, start_normal: vec3<f32>, entry: HitEntry, rng: ptr<function, RandomState>) -> vec3<f32> {
+    let color = unpack4x8unorm(entry.subsurface_color).xyz;
+    var throughput = vec3<f32>(1.0);
+    let scale = max(1.0, max(abs(start_position.x), max(abs(start_position.y), abs(start_position.z))));
+    var position = start_position - 1e-4 * scale * start_normal;
+    var dir = -sample_cosine_hemisphere(start_normal, rng);
+    for (var i = 0u; i < MAX_SUBSURFACE_STEPS; i += 1u) {
+        let distance = -entry.subsurface_distance * log(max(1.0 - random_gen(rng), 1e-6));
+        let intersection = trace_ray_within(position, dir, distance);
+        if (intersection.kind != RAY_QUERY_INTERSECTION_NONE) {
+            let exit_entry = hit_entries[intersection.instance_custom_data + intersection.geometry_index];
+            let normal = get_hit_normal(intersection, exit_entry);
+            let exit_normal = select(-normal, normal, dot(normal, dir) > 0.0);
+            return throughput * get_exit_lighting(position + intersection.t * dir, exit_normal, rng);
+        }
+        position += distance * dir;
+        throughput *= color;
+        dir = sample_uniform_sphere(rng);
+    }
+    // absorbed, or ran out of steps
+    return vec3<f32>(0.0);
+}
+
+// Rough fit of the directional albedo of the "Charlie" sheen lobe
+// of `KHR_materials_sheen`, growing towards the grazing angles.
+fn get_sheen_albedo(cos_theta: f32, roughness: f32) -> f32 {
+    let r = max(roughness, 0.07);
+    return saturate(mix(0.05, 0.3, r) + 0.5 * (1.0 - 0.5 * r) * pow(1.0 - saturate(cos_theta), 3.0));
+}
+
 @compute @workgroup_size(8, 4)
 fn main(@builtin(global_invocation_id) global_id: vec3<u32>) {
     if (any(global_id.xy >= camera.target_size)) {
@@ -70,22 +378,35 @@ fn main(@builtin(global_invocation_id) global_id: vec3<u32>) {
         textureStore(out_debug, global_id.xy, vec4<f32>(0.0));
     }
 
-    var rq: ray_query;
     let ray_dir = get_ray_direction(camera, vec2<i32>(global_id.xy));
-    rayQueryInitialize(&rq, acc_struct, RayDesc(RAY_FLAG_CULL_NO_OPAQUE, 0xFFu, 0.0, camera.depth, camera.position, ray_dir));
-    rayQueryProceed(&rq);
-    let intersection = rayQueryGetCommittedIntersection(&rq);
+    var intersection = trace_primary(ray_dir, 0.0);
+    var was_clipped = false;
+    for (var i = 0u; i < MAX_CLIPPED_HITS && intersection.kind != RAY_QUERY_INTERSECTION_NONE; i += 1u) {
+        if (!is_clipped(camera.position + intersection.t * ray_dir)) {
+            break;
+        }
+        was_clipped = true;
+        intersection = trace_primary(ray_dir, intersection.t * 1.0001);
+    }
 
     var depth = 0.0;
     var basis = vec4<f32>(0.0);
     var flat_normal = vec3<f32>(0.0);
+    // the ray hits the geometric back side, before any flip of double-sided surfaces
+    var is_back_face = false;
     var albedo = vec3<f32>(1.0);
+    var occlusion = 1.0;
     var motion = vec2<f32>(0.0);
+    // traced radiance in RGB, and how much it replaces the surface lighting in A
+    var transmission = vec4<f32>(0.0);
+    // index of the instance plus one, zero for the sky
+    var instance = 0u;
     let enable_debug = all(global_id.xy == debug.mouse_pos);
 
     if (intersection.kind != RAY_QUERY_INTERSECTION_NONE) {
         let entry = hit_entries[intersection.instance_custom_data + intersection.geometry_index];
         depth = intersection.t;
+        instance = intersection.instance_id + 1u;
 
         var indices = intersection.primitive_index * 3u + vec3<u32>(0u, 1u, 2u);
         if (entry.index_buf != ~0u) {
@@ -106,16 +427,35 @@ fn main(@builtin(global_invocation_id) global_id: vec3<u32>) {
         let positions = intersection.object_to_world * mat3x4(
             vec4<f32>(positions_object[0], 1.0), vec4<f32>(positions_object[1], 1.0), vec4<f32>(positions_object[2], 1.0)
         );
-        flat_normal = entry.winding * normalize(cross(positions[1].xyz - positions[0].xyz, positions[2].xyz - positions[0].xyz));
+        // edges in world space, so that the area accounts for the instance scale
+        let world_cross = cross(positions[1].xyz - positions[0].xyz, positions[2].xyz - positions[0].xyz);
+        flat_normal = entry.winding * normalize(world_cross);
+        is_back_face = dot(flat_normal, ray_dir) > 0.0;
 
         let barycentrics = vec3<f32>(1.0 - intersection.barycentrics.x - intersection.barycentrics.y, intersection.barycentrics);
         let position_object = vec4<f32>(positions_object * barycentrics, 1.0);
-        let tex_coords = mat3x2(vertices[0].tex_coords, vertices[1].tex_coords, vertices[2].tex_coords) * barycentrics;
+        let uvs = mat3x2(vertices[0].tex_coords, vertices[1].tex_coords, vertices[2].tex_coords);
+        let uvs1 = mat3x2(vertices[0].tex_coords1, vertices[1].tex_coords1, vertices[2].tex_coords1);
+        let tex_coords = uvs * barycentrics;
+        let base_color_uvs = get_texture_uvs(entry, uvs, uvs1, 0u);
+        let normal_uvs = get_texture_uvs(entry, uvs, uvs1, 1u);
+        let occlusion_uvs = get_texture_uvs(entry, uvs, uvs1, 2u);
+        let metallic_roughness_uvs = get_texture_uvs(entry, uvs, uvs1, 3u);
+        let base_color_tc = base_color_uvs * barycentrics;
+        let normal_tc = normal_uvs * barycentrics;
+        let occlusion_tc = occlusion_uvs * barycentrics;
+        let metallic_roughness_tc = metallic_roughness_uvs * barycentrics;
         let normal_geo = normalize(mat3x3(decode_normal(vertices[0].normal), decode_normal(vertices[1].normal), decode_normal(vertices[2].normal)) * barycentrics);
         let tangent_geo = normalize(mat3x3(decode_normal(vertices[0].tangent), decode_normal(vertices[1].tangent), decode_normal(vertices[2].tangent)) * barycentrics);
         let bitangent_geo = normalize(cross(normal_geo, tangent_geo)) * vertices[0].bitangent_sign;
 
-        let lod = 0.0; //TODO: this is actually complicated
+        let world_area = length(world_cross);
+        let cone_width = lod_params.spread_angle * intersection.t;
+        let cos_angle = max(abs(dot(flat_normal, ray_dir)), 0.01);
+        let base_color_lod = texture_lod(compute_cone_lod(base_color_uvs, world_area, cone_width, cos_angle), textureDimensions(textures[entry.base_color_texture], 0));
+        let normal_lod = texture_lod(compute_cone_lod(normal_uvs, world_area, cone_width, cos_angle), textureDimensions(textures[entry.normal_texture], 0));
+        let occlusion_lod = texture_lod(compute_cone_lod(occlusion_uvs, world_area, cone_width, cos_angle), textureDimensions(textures[entry.occlusion_texture], 0));
+        let metallic_roughness_lod = texture_lod(compute_cone_lod(metallic_roughness_uvs, world_area, cone_width, cos_angle), textureDimensions(textures[entry.metallic_roughness_texture], 0));
 
         let geo_to_world_rot = normalize(unpack4x8snorm(entry.geometry_to_world_rotation));
         let tangent_space_geo = mat3x3(tangent_geo, bitangent_geo, normal_geo);
@@ -123,16 +463,36 @@ fn main(@builtin(global_invocation_id) global_id: vec3<u32>) {
         if ((debug.texture_flags & DebugTextureFlags_NORMAL) != 0u) {
             normal_local = vec3<f32>(0.0, 0.0, 1.0); // ignore normal map
         } else {
-            let raw_unorm = textureSampleLevel(textures[entry.normal_texture], sampler_linear, tex_coords, lod).xy;
+            let raw_unorm = textureSampleLevel(textures[entry.normal_texture], sampler_linear, normal_tc, normal_lod).xy;
             let n_xy = entry.normal_scale * (2.0 * raw_unorm - 1.0);
             normal_local = vec3<f32>(n_xy, sqrt(max(0.0, 1.0 - dot(n_xy, n_xy))));
         }
         var normal = qrot(geo_to_world_rot, tangent_space_geo * normal_local);
+        if ((entry.flags & HIT_FLAG_DOUBLE_SIDED) != 0u && is_back_face) {
+            // looking at the back side, flip the surface to face the ray
+            flat_normal = -flat_normal;
+            normal = -normal;
+        }
         basis = shortest_arc_quat(vec3<f32>(0.0, 0.0, 1.0), normalize(normal));
 
+        // the mirrors seen further along the paths only use the factors
+        var surface = entry;
+        let metallic_roughness = textureSampleLevel(textures[entry.metallic_roughness_texture], sampler_linear, metallic_roughness_tc, metallic_roughness_lod);
+        surface.roughness *= get_mapped_channel(entry, metallic_roughness, 2u);
+        surface.metallic *= get_mapped_channel(entry, metallic_roughness, 4u);
+
         let hit_position = camera.position + intersection.t * ray_dir;
+        if (entry.transmission > 0.0) {
+            var rng = random_init(global_id.y * camera.target_size.x + global_id.x, transmission_params.frame_index);
+            let radiance = trace_transmission(hit_position, ray_dir, normalize(normal), entry, &rng);
+            transmission = vec4<f32>(radiance, entry.transmission);
+        } else if (is_mirror(surface)) {
+            let radiance = trace_reflection(hit_position, ray_dir, normalize(normal), surface);
+            transmission = vec4<f32>(radiance, surface.metallic);
+        }
         if (enable_debug) {
             debug_buf.entry.custom_index = intersection.instance_custom_data;
+            debug_buf.entry.geometry_index = intersection.geometry_index;
             debug_buf.entry.depth = intersection.t;
             debug_buf.entry.tex_coords = tex_coords;
             debug_buf.entry.base_color_texture = entry.base_color_texture;
@@ -169,9 +529,40 @@ fn main(@builtin(global_invocation_id) global_id: vec3<u32>) {
         if ((debug.texture_flags & DebugTextureFlags_ALBEDO) != 0u) {
             albedo = base_color_factor.xyz;
         } else {
-            let base_color_sample = textureSampleLevel(textures[entry.base_color_texture], sampler_linear, tex_coords, lod);
+            let base_color_sample = textureSampleLevel(textures[entry.base_color_texture], sampler_linear, base_color_tc, base_color_lod);
             albedo = (base_color_factor * base_color_sample).xyz;
         }
+        let occlusion_texel = textureSampleLevel(textures[entry.occlusion_texture], sampler_linear, occlusion_tc, occlusion_lod);
+        let occlusion_sample = get_mapped_channel(entry, occlusion_texel, 0u);
+        occlusion = mix(1.0, occlusion_sample, entry.occlusion_strength);
+
+        let sheen_color = unpack4x8unorm(entry.sheen_color).xyz;
+        let has_sheen = any(sheen_color > vec3<f32>(0.0));
+        if (transmission.w == 0.0 && (entry.subsurface_distance > 0.0 || has_sheen)) {
+            let surface_normal = normalize(normal);
+            var subsurface_weight = 0.0;
+            var subsurface = vec3<f32>(0.0);
+            if (entry.subsurface_distance > 0.0) {
+                var rng = random_init(global_id.y * camera.target_size.x + global_id.x, transmission_params.frame_index);
+                subsurface_weight = 1.0 - surface.metallic;
+                subsurface = trace_subsurface(hit_position, surface_normal, entry, &rng);
+            }
+            var sheen_weight = 0.0;
+            var sheen = vec3<f32>(0.0);
+            if (has_sheen) {
+                let sheen_albedo = get_sheen_albedo(-dot(surface_normal, ray_dir), entry.sheen_roughness);
+                sheen_weight = sheen_albedo * max(sheen_color.x, max(sheen_color.y, sheen_color.z));
+                let ambient = environment.intensity * textureSampleLevel(env_map, sampler_linear, map_equirect_dir_to_uv(surface_normal), 0.0).xyz;
+                // the lighting gets multiplied by the albedo later
+                sheen = sheen_albedo * sheen_color * ambient / max(albedo, vec3<f32>(0.01));
+            }
+            // the sheen layer scales down everything under it
+            let weight = 1.0 - (1.0 - subsurface_weight) * (1.0 - sheen_weight);
+            if (weight > 0.0) {
+                let radiance = (subsurface_weight * (1.0 - sheen_weight) * subsurface + sheen) / weight;
+                transmission = vec4<f32>(radiance, weight);
+            }
+        }
 
         if (WRITE_DEBUG_IMAGE) {
             if (debug.view_mode == DebugMode_DiffuseAlbedoTexture) {
@@ -183,6 +574,9 @@ fn main(@builtin(global_invocation_id) global_id: vec3<u32>) {
             if (debug.view_mode == DebugMode_NormalTexture) {
                 textureStore(out_debug, global_id.xy, vec4<f32>(normal_local, 0.0));
             }
+            if (debug.view_mode == DebugMode_Occlusion) {
+                textureStore(out_debug, global_id.xy, vec4<f32>(occlusion));
+            }
             if (debug.view_mode == DebugMode_NormalScale) {
                 textureStore(out_debug, global_id.xy, vec4<f32>(entry.normal_scale));
             }
@@ -201,11 +595,29 @@ fn main(@builtin(global_invocation_id) global_id: vec3<u32>) {
             }
         }
 
-        let prev_position = (entry.prev_object_to_world * position_object).xyz;
+        // both the object and its pose may have moved since the last frame
+        let position_geo = vec4<f32>(mat3x3(vertices[0].pos, vertices[1].pos, vertices[2].pos) * barycentrics, 1.0);
+        let prev_position_object = vec4<f32>(entry.prev_geometry_to_object * position_geo, 1.0);
+        let prev_position = (entry.prev_object_to_world * prev_position_object).xyz;
         let prev_screen = get_projected_pixel_float(prev_camera, prev_position);
         //TODO: consider just storing integers here?
         //TODO: technically this "0.5" is just a waste compute on both packing and unpacking
         motion = prev_screen - vec2<f32>(global_id.xy) - 0.5;
+
+        // Seeing the back side through a cut means we are inside a solid
+        if (was_clipped && clip.cap_color.a > 0.0 && is_back_face) {
+            let cap = find_clip_cap(ray_dir, intersection.t);
+            if (cap.w > 0.0) {
+                depth = cap.w;
+                flat_normal = cap.xyz;
+                basis = shortest_arc_quat(vec3<f32>(0.0, 0.0, 1.0), cap.xyz);
+                albedo = clip.cap_color.xyz;
+                occlusion = 1.0;
+                transmission = vec4<f32>(0.0);
+                let cap_screen = get_projected_pixel_float(prev_camera, camera.position + cap.w * ray_dir);
+                motion = cap_screen - vec2<f32>(global_id.xy) - 0.5;
+            }
+        }
         if (WRITE_DEBUG_IMAGE && debug.view_mode == DebugMode_Motion) {
             textureStore(out_debug, global_id.xy, vec4<f32>(motion * MOTION_SCALE + vec2<f32>(0.5), 0.0, 1.0));
         }
@@ -219,6 +631,9 @@ fn main(@builtin(global_invocation_id) global_id: vec3<u32>) {
     textureStore(out_depth, global_id.xy, vec4<f32>(depth, 0.0, 0.0, 0.0));
     textureStore(out_basis, global_id.xy, basis);
     textureStore(out_flat_normal, global_id.xy, vec4<f32>(flat_normal, 0.0));
-    textureStore(out_albedo, global_id.xy, vec4<f32>(albedo, 0.0));
+    // baked occlusion goes into the alpha channel
+    textureStore(out_albedo, global_id.xy, vec4<f32>(albedo, occlusion));
     textureStore(out_motion, global_id.xy, vec4<f32>(motion * MOTION_SCALE, 0.0, 0.0));
+    textureStore(out_transmission, global_id.xy, transmission);
+    textureStore(out_instance, global_id.xy, vec4<u32>(instance, 0u, 0u, 0u));
 }
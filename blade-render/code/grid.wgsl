@@ -0,0 +1,68 @@
+#include "quaternion.inc.wgsl"
+#include "camera.inc.wgsl"
+
+struct GridParams {
+    color: vec4<f32>,
+    spacing: f32,
+    fade_distance: f32,
+    // height of the ground plane
+    height: f32,
+    // non-zero if the X and Z axes are highlighted
+    axes: u32,
+    viewport_origin: vec2<i32>,
+    pad: vec2<u32>,
+}
+
+var<uniform> camera: CameraParams;
+var<uniform> params: GridParams;
+var depth: texture_2d<f32>;
+
+struct GridVarying {
+    @builtin(position) pos: vec4<f32>,
+}
+
+@vertex
+fn grid_vs(@builtin(vertex_index) vi: u32) -> GridVarying {
+    var out: GridVarying;
+    out.pos = vec4<f32>(f32(vi & 1u) * 4.0 - 1.0, f32(vi & 2u) * 2.0 - 1.0, 0.0, 1.0);
+    return out;
+}
+
+// Coverage of the lines at integer coordinates, about a pixel wide.
+fn get_line_coverage(coord: f32, width: f32) -> f32 {
+    let w = max(fwidth(coord), 1e-6);
+    let distance = abs(fract(coord - 0.5) - 0.5);
+    return 1.0 - smoothstep(0.0, width * w, distance);
+}
+
+@fragment
+fn grid_fs(in: GridVarying) -> @location(0) vec4<f32> {
+    let pixel = vec2<i32>(in.pos.xy) - params.viewport_origin;
+    let ray_dir = get_ray_direction(camera, pixel);
+    // distance along the ray to the ground plane
+    let t = (params.height - camera.position.y) / ray_dir.y;
+    // Note: the derivatives are computed before any discards
+    let point = camera.position.xz + t * ray_dir.xz;
+    let cell = point / params.spacing;
+    let coverage = max(get_line_coverage(cell.x, 1.0), get_line_coverage(cell.y, 1.0));
+    var color = params.color;
+    color.a *= coverage;
+    if (params.axes != 0u) {
+        // the X axis lies along Z=0, and the Z axis along X=0
+        let along_x = get_line_coverage(cell.y, 2.0) * step(abs(cell.y), 0.5);
+        let along_z = get_line_coverage(cell.x, 2.0) * step(abs(cell.x), 0.5);
+        if (along_x > 0.0) {
+            color = vec4<f32>(0.9, 0.2, 0.2, along_x);
+        } else if (along_z > 0.0) {
+            color = vec4<f32>(0.2, 0.4, 0.9, along_z);
+        }
+    }
+    // fade with the distance, and at grazing angles where the lines alias
+    let fade = 1.0 - smoothstep(0.5 * params.fade_distance, params.fade_distance, t);
+    color.a *= fade * smoothstep(0.0, 0.1, abs(ray_dir.y));
+    let hit_depth = textureLoad(depth, pixel, 0).x;
+    if (t <= 0.0 || (hit_depth != 0.0 && t > hit_depth) || color.a <= 0.0) {
+        discard;
+    }
+    return color;
+}
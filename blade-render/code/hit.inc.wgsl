@@ -0,0 +1,92 @@
+// Requires "quaternion.inc.wgsl" to be included first.
+
+// Has to match the host!
+struct Vertex {
+    pos: vec3<f32>,
+    bitangent_sign: f32,
+    tex_coords: vec2<f32>,
+    normal: u32,
+    tangent: u32,
+    tex_coords1: vec2<f32>,
+    pad: vec2<u32>,
+}
+struct VertexBuffer {
+    data: array<Vertex>,
+}
+struct IndexBuffer {
+    data: array<u32>,
+}
+var<storage, read> vertex_buffers: binding_array<VertexBuffer>;
+var<storage, read> index_buffers: binding_array<IndexBuffer>;
+
+struct HitEntry {
+    index_buf: u32,
+    vertex_buf: u32,
+    winding: f32,
+    // packed quaternion
+    geometry_to_world_rotation: u32,
+    geometry_to_object: mat4x3<f32>,
+    prev_object_to_world: mat4x3<f32>,
+    prev_geometry_to_object: mat4x3<f32>,
+    base_color_texture: u32,
+    // packed color factor
+    base_color_factor: u32,
+    normal_texture: u32,
+    normal_scale: f32,
+    // bit per texture, set if it uses the secondary UV set
+    tex_coord_sets: u32,
+    occlusion_texture: u32,
+    occlusion_strength: f32,
+    flags: u32,
+    transmission: f32,
+    ior: f32,
+    dispersion: f32,
+    metallic: f32,
+    // UV transform of the base color, normal, occlusion, and metallic-roughness textures
+    uv_transforms: array<mat3x2<f32>, 4>,
+    roughness: f32,
+    iridescence: f32,
+    iridescence_ior: f32,
+    // in nanometers
+    iridescence_thickness: f32,
+    metallic_roughness_texture: u32,
+    // 2 bits per channel index of the occlusion, roughness, and metallic
+    channel_map: u32,
+    // packed color
+    sheen_color: u32,
+    sheen_roughness: f32,
+    // packed color
+    subsurface_color: u32,
+    // zero if there is no subsurface scattering
+    subsurface_distance: f32,
+    pad: vec2<u32>,
+}
+const HIT_FLAG_DOUBLE_SIDED: u32 = 1u;
+var<storage, read> hit_entries: array<HitEntry>;
+
+fn decode_normal(raw: u32) -> vec3<f32> {
+    return unpack4x8snorm(raw).xyz;
+}
+
+// Interpolated vertex normal of a hit, in world space.
+fn get_hit_normal(intersection: RayIntersection, entry: HitEntry) -> vec3<f32> {
+    var indices = intersection.primitive_index * 3u + vec3<u32>(0u, 1u, 2u);
+    if (entry.index_buf != ~0u) {
+        let iptr = &index_buffers[entry.index_buf].data;
+        indices = vec3<u32>((*iptr)[indices.x], (*iptr)[indices.y], (*iptr)[indices.z]);
+    }
+    let vptr = &vertex_buffers[entry.vertex_buf].data;
+    let normals = mat3x3(
+        decode_normal((*vptr)[indices.x].normal),
+        decode_normal((*vptr)[indices.y].normal),
+        decode_normal((*vptr)[indices.z].normal),
+    );
+    let barycentrics = vec3<f32>(1.0 - intersection.barycentrics.x - intersection.barycentrics.y, intersection.barycentrics);
+    let geo_to_world_rot = normalize(unpack4x8snorm(entry.geometry_to_world_rotation));
+    return normalize(qrot(geo_to_world_rot, normals * barycentrics));
+}
+
+fn fresnel_schlick(cos_theta: f32, eta: f32) -> f32 {
+    let f0 = pow((1.0 - eta) / (1.0 + eta), 2.0);
+    return f0 + (1.0 - f0) * pow(1.0 - cos_theta, 5.0);
+}
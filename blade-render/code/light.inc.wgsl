@@ -0,0 +1,24 @@
+// Has to match the host!
+struct LightEntry {
+    position: vec3<f32>,
+    // zero for unlimited range
+    range: f32,
+    direction: vec3<f32>,
+    // cosine of the outer cone angle, -1 for point lights
+    cos_outer: f32,
+    radiance: vec3<f32>,
+    cos_inner: f32,
+}
+var<storage, read> lights: array<LightEntry>;
+
+// Windowed inverse-square falloff, reaching zero at the light range,
+// as recommended by KHR_lights_punctual.
+fn get_light_attenuation(distance: f32, range: f32) -> f32 {
+    let d2 = max(distance * distance, 1e-4);
+    if (range <= 0.0) {
+        return 1.0 / d2;
+    }
+    let ratio = distance / range;
+    let window = clamp(1.0 - ratio * ratio * ratio * ratio, 0.0, 1.0);
+    return window * window / d2;
+}
@@ -0,0 +1,133 @@
+#include "quaternion.inc.wgsl"
+#include "camera.inc.wgsl"
+#include "random.inc.wgsl"
+
+const PI: f32 = 3.1415926;
+
+// Has to match the host!
+struct Particle {
+    position: vec3<f32>,
+    age: f32,
+    velocity: vec3<f32>,
+    // a particle is alive while the age is below the life
+    life: f32,
+}
+
+struct ParticleParams {
+    origin: vec3<f32>,
+    life: f32,
+    direction: vec3<f32>,
+    // half-angle of the emission cone, in radians
+    spread: f32,
+    acceleration: vec3<f32>,
+    speed: f32,
+    color: vec3<f32>,
+    size: f32,
+    time_delta: f32,
+    // range of the ring buffer slots that are emitted this frame
+    emit_start: u32,
+    emit_count: u32,
+    capacity: u32,
+    viewport_origin: vec2<i32>,
+    seed: u32,
+    pad: u32,
+}
+
+var<uniform> params: ParticleParams;
+var<storage, read_write> particles: array<Particle>;
+
+fn random_unit(seed: ptr<function, u32>) -> f32 {
+    *seed = hash_jenkins(*seed);
+    return f32(*seed >> 8u) / 16777216.0;
+}
+
+fn emit(index: u32) -> Particle {
+    var seed = hash_jenkins(index) ^ params.seed;
+    let cos_theta = 1.0 - random_unit(&seed) * (1.0 - cos(params.spread));
+    let sin_theta = sqrt(max(0.0, 1.0 - cos_theta * cos_theta));
+    let phi = 2.0 * PI * random_unit(&seed);
+    let dir = normalize(params.direction);
+    let up = select(vec3<f32>(0.0, 1.0, 0.0), vec3<f32>(1.0, 0.0, 0.0), abs(dir.y) > 0.9);
+    let tangent = normalize(cross(up, dir));
+    let bitangent = cross(dir, tangent);
+    let cone_dir = sin_theta * (cos(phi) * tangent + sin(phi) * bitangent) + cos_theta * dir;
+
+    var p: Particle;
+    p.position = params.origin;
+    p.velocity = params.speed * (0.5 + random_unit(&seed)) * cone_dir;
+    p.age = 0.0;
+    p.life = params.life * (0.5 + random_unit(&seed));
+    return p;
+}
+
+@compute @workgroup_size(64, 1, 1)
+fn update(@builtin(global_invocation_id) global_id: vec3<u32>) {
+    let index = global_id.x;
+    if (index >= params.capacity) {
+        return;
+    }
+    let ring_offset = (index + params.capacity - params.emit_start) % params.capacity;
+    if (ring_offset < params.emit_count) {
+        particles[index] = emit(index);
+        return;
+    }
+    var p = particles[index];
+    if (p.age < p.life) {
+        p.velocity += params.acceleration * params.time_delta;
+        p.position += p.velocity * params.time_delta;
+        p.age += params.time_delta;
+        particles[index] = p;
+    }
+}
+
+var<uniform> camera: CameraParams;
+var depth: texture_2d<f32>;
+
+struct ParticleVarying {
+    @builtin(position) pos: vec4<f32>,
+    // position within the quad, from -1 to 1
+    @location(0) corner: vec2<f32>,
+    @location(1) distance: f32,
+    @location(2) fade: f32,
+}
+
+fn project(world_pos: vec3<f32>) -> vec4<f32> {
+    let local_dir = qrot(qinv(camera.orientation), world_pos - camera.position);
+    return vec4<f32>(local_dir.xy / tan(0.5 * camera.fov), 0.0, -local_dir.z);
+}
+
+// Every particle is a camera-facing quad of 4 vertices.
+@vertex
+fn draw_vs(
+    @builtin(vertex_index) vertex_index: u32,
+    @builtin(instance_index) instance_index: u32,
+) -> ParticleVarying {
+    let p = particles[instance_index];
+    var out: ParticleVarying;
+    if (p.age >= p.life) {
+        // collapse the dead particles outside of the depth range
+        out.pos = vec4<f32>(0.0, 0.0, -1.0, 1.0);
+        return out;
+    }
+    let corner = vec2<f32>(vec2<u32>(vertex_index & 1u, vertex_index >> 1u)) * 2.0 - 1.0;
+    let right = qrot(camera.orientation, vec3<f32>(1.0, 0.0, 0.0));
+    let up = qrot(camera.orientation, vec3<f32>(0.0, 1.0, 0.0));
+    let world_pos = p.position + params.size * (corner.x * right + corner.y * up);
+    out.pos = project(world_pos);
+    out.corner = corner;
+    out.distance = length(p.position - camera.position);
+    out.fade = 1.0 - p.age / p.life;
+    return out;
+}
+
+@fragment
+fn draw_fs(in: ParticleVarying) -> @location(0) vec4<f32> {
+    let pixel = vec2<i32>(in.pos.xy) - params.viewport_origin;
+    let hit_depth = textureLoad(depth, pixel, 0).x;
+    let falloff = max(0.0, 1.0 - dot(in.corner, in.corner));
+    if ((hit_depth != 0.0 && in.distance > hit_depth) || falloff <= 0.0) {
+        discard;
+    }
+    // blended additively, so the alpha stays untouched
+    return vec4<f32>(params.color * (falloff * in.fade), 0.0);
+}
@@ -1,20 +1,64 @@
+#include "quaternion.inc.wgsl"
+#include "camera.inc.wgsl"
 #include "debug.inc.wgsl"
 #include "debug-param.inc.wgsl"
 
+const OUTPUT_TRANSFORM_NONE: u32 = 0u;
+const OUTPUT_TRANSFORM_GAMMA: u32 = 1u;
+const OUTPUT_TRANSFORM_TONEMAP: u32 = 2u;
+
 struct ToneMapParams {
-    enabled: u32,
+    // one of OUTPUT_TRANSFORM_*
+    output_transform: u32,
     average_lum: f32,
     key_value: f32,
     // minimum value of the pixels mapped to white brightness
     white_level: f32,
+    // blend factor of the color grading LUT
+    lut_strength: f32,
+    // maps [0,1] color into the texel centers of the LUT
+    lut_scale: f32,
+    lut_offset: f32,
+    // use the adapted luminance instead of `average_lum`
+    auto_exposure: u32,
+    gamma: f32,
+    // weight of the image from before a scene transition
+    fade: f32,
+    pad: vec2<u32>,
+}
+
+struct ExposureState {
+    average_lum: f32,
+    measured_log_lum: f32,
+}
+
+struct FogParams {
+    color_density: vec4<f32>,
+    sun_color_falloff: vec4<f32>,
+    sun_dir_anisotropy: vec4<f32>,
 }
 
+var<uniform> camera: CameraParams;
+var<uniform> fog: FogParams;
+var t_depth: texture_2d<f32>;
 var t_albedo: texture_2d<f32>;
 var light_diffuse: texture_2d<f32>;
 var t_debug: texture_2d<f32>;
+var color_lut: texture_3d<f32>;
+var t_fade_light: texture_2d<f32>;
+var t_fade_albedo: texture_2d<f32>;
+var t_fade_depth: texture_2d<f32>;
+var sampler_linear: sampler;
 var<uniform> tone_map_params: ToneMapParams;
+var<storage, read> exposure: ExposureState;
 var<uniform> debug_params: DebugParams;
 
+struct ViewportParams {
+    origin: vec2<i32>,
+    pad: vec2<u32>,
+}
+var<uniform> viewport: ViewportParams;
+
 struct VertexOutput {
     @builtin(position) clip_pos: vec4<f32>,
     @location(0) @interpolate(flat) input_size: vec2<u32>,
@@ -28,21 +72,77 @@ fn postfx_vs(@builtin(vertex_index) vi: u32) -> VertexOutput {
     return vo;
 }
 
+const PI: f32 = 3.1415926;
+
+fn henyey_greenstein(cos_theta: f32, g: f32) -> f32 {
+    let denom = 1.0 + g * g - 2.0 * g * cos_theta;
+    return (1.0 - g * g) / (4.0 * PI * denom * sqrt(denom));
+}
+
+// Exponential height fog, integrated analytically along the primary ray.
+fn apply_fog(color: vec3<f32>, pixel: vec2<i32>, depth: f32) -> vec3<f32> {
+    let density = fog.color_density.w;
+    if (density <= 0.0 || depth == 0.0) {
+        return color;
+    }
+    let dir = get_ray_direction(camera, pixel);
+    let falloff = fog.sun_color_falloff.w;
+    let height_start = density * exp(-falloff * camera.position.y);
+    let height_delta = falloff * dir.y * depth;
+    var optical_depth = height_start * depth;
+    if (abs(height_delta) > 0.001) {
+        optical_depth *= (1.0 - exp(-height_delta)) / height_delta;
+    }
+    let transmittance = exp(-optical_depth);
+    let phase = henyey_greenstein(dot(dir, fog.sun_dir_anisotropy.xyz), fog.sun_dir_anisotropy.w);
+    let in_scatter = fog.color_density.xyz + 4.0 * PI * phase * fog.sun_color_falloff.xyz;
+    return mix(in_scatter, color, transmittance);
+}
+
+//tonemap-begin
+// Extended Reinhard operator, mapping `white_level` to white.
+fn tonemap(color: vec3<f32>) -> vec3<f32> {
+    let l_white = tone_map_params.white_level;
+    return color * (1.0 + color / (l_white * l_white)) / (1.0 + color);
+}
+//tonemap-end
+
+fn apply_color_lut(color: vec3<f32>) -> vec3<f32> {
+    if (tone_map_params.lut_strength <= 0.0) {
+        return color;
+    }
+    let tc = clamp(color, vec3<f32>(0.0), vec3<f32>(1.0)) * tone_map_params.lut_scale + tone_map_params.lut_offset;
+    let graded = textureSampleLevel(color_lut, sampler_linear, tc, 0.0).xyz;
+    return mix(color, graded, tone_map_params.lut_strength);
+}
+
 @fragment
 fn postfx_fs(vo: VertexOutput) -> @location(0) vec4<f32> {
-    let tc = vec2<i32>(i32(vo.clip_pos.x), i32(vo.clip_pos.y));
+    let tc = vec2<i32>(vo.clip_pos.xy) - viewport.origin;
     let illumunation = textureLoad(light_diffuse, tc, 0);
     if (debug_params.view_mode == DebugMode_Final) {
-        let albedo = textureLoad(t_albedo, tc, 0).xyz;
-        let color = albedo.xyz * illumunation.xyz;
-        if (tone_map_params.enabled != 0u) {
-            // Following https://blog.en.uwa4d.com/2022/07/19/physically-based-renderingg-hdr-tone-mapping/
-            let l_adjusted = tone_map_params.key_value / tone_map_params.average_lum * color;
-            let l_white = tone_map_params.white_level;
-            let l_ldr = l_adjusted * (1.0 + l_adjusted / (l_white*l_white)) / (1.0 + l_adjusted);
-            return vec4<f32>(l_ldr, 1.0);
+        let albedo = textureLoad(t_albedo, tc, 0);
+        // the baked occlusion is already applied to the environment lighting by "ray-trace"
+        var color = apply_fog(albedo.xyz * illumunation.xyz, tc, textureLoad(t_depth, tc, 0).x);
+        if (tone_map_params.fade > 0.0) {
+            let fade_albedo = textureLoad(t_fade_albedo, tc, 0);
+            let fade_light = textureLoad(t_fade_light, tc, 0).xyz;
+            let fade_color = apply_fog(fade_albedo.xyz * fade_light, tc, textureLoad(t_fade_depth, tc, 0).x);
+            color = mix(color, fade_color, tone_map_params.fade);
+        }
+        if (tone_map_params.output_transform == OUTPUT_TRANSFORM_NONE) {
+            return vec4<f32>(clamp(color, vec3<f32>(0.0), vec3<f32>(1.0)), 1.0);
+        } else if (tone_map_params.output_transform == OUTPUT_TRANSFORM_GAMMA) {
+            return vec4<f32>(pow(max(color, vec3<f32>(0.0)), vec3<f32>(1.0 / tone_map_params.gamma)), 1.0);
         } else {
-            return vec4<f32>(color, 1.0);
+            // Following https://blog.en.uwa4d.com/2022/07/19/physically-based-renderingg-hdr-tone-mapping/
+            var average_lum = tone_map_params.average_lum;
+            if (tone_map_params.auto_exposure != 0u && exposure.average_lum > 0.0) {
+                average_lum = exposure.average_lum;
+            }
+            let l_adjusted = tone_map_params.key_value / average_lum * color;
+            let l_ldr = tonemap(l_adjusted);
+            return vec4<f32>(apply_color_lut(l_ldr), 1.0);
         }
     } else if (debug_params.view_mode == DebugMode_Variance) {
         return vec4<f32>(illumunation.w);
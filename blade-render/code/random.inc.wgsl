@@ -1,6 +1,9 @@
 struct RandomState {
     seed: u32,
     index: u32,
+    pixel: vec2<u32>,
+    frame_index: u32,
+    is_low_discrepancy: bool,
 }
 
 // 32 bit Jenkins hash
@@ -20,6 +23,16 @@ fn random_init(pixel_index: u32, frame_index: u32) -> RandomState {
     var rs: RandomState;
     rs.seed = hash_jenkins(pixel_index) + frame_index;
     rs.index = 0u;
+    rs.is_low_discrepancy = false;
+    return rs;
+}
+
+fn random_init_low_discrepancy(pixel: vec2<u32>, frame_index: u32) -> RandomState {
+    var rs: RandomState;
+    rs.index = 0u;
+    rs.pixel = pixel;
+    rs.frame_index = frame_index;
+    rs.is_low_discrepancy = true;
     return rs;
 }
 
@@ -56,7 +69,32 @@ fn murmur3(rng: ptr<function, RandomState>) -> u32 {
     return hash;
 }
 
+// Interleaved gradient noise rotates a Kronecker sequence over the frames per pixel,
+// which spreads the error over the screen as blue noise.
+// http://www.iryoku.com/next-generation-post-processing-in-call-of-duty-advanced-warfare
+fn low_discrepancy_gen(rng: ptr<function, RandomState>) -> f32 {
+    // Fractional parts of the square roots of primes. They are rationally independent,
+    // so any two dimensions advance at different rates over the frames, filling the unit
+    // square instead of staying on a diagonal line. The primes are ordered so that
+    // the dimensions next to each other form evenly spread 2D lattices.
+    var steps = array<f32, 16>(
+        0.55743852, 0.06651917, 0.84885780, 0.72308292,
+        0.81024968, 0.15724406, 0.11043358, 0.35889894,
+        0.76714533, 0.40312424, 0.85565460, 0.68154169,
+        0.88819442, 0.46207763, 0.76305461, 0.68114575,
+    );
+    let step = steps[(*rng).index % 16u];
+    (*rng).index += 1u;
+    let pos = vec2<f32>((*rng).pixel) + 5.588238 * f32((*rng).index);
+    let offset = fract(52.9829189 * fract(dot(pos, vec2<f32>(0.06711056, 0.00583715))));
+    // keep the frame index small to not lose the float precision
+    return fract(offset + step * f32((*rng).frame_index & 0xFFFu));
+}
+
 fn random_gen(rng: ptr<function, RandomState>) -> f32 {
+    if ((*rng).is_low_discrepancy) {
+        return low_discrepancy_gen(rng);
+    }
     let v = murmur3(rng);
     let one = bitcast<u32>(1.0);
     let mask = (1u << 23u) - 1u;
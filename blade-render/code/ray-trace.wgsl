@@ -1,3 +1,5 @@
+#use SampleStrategy
+#use RenderMode
 #include "quaternion.inc.wgsl"
 #include "random.inc.wgsl"
 #include "env-importance.inc.wgsl"
@@ -6,6 +8,12 @@
 #include "camera.inc.wgsl"
 #include "surface.inc.wgsl"
 #include "gbuf.inc.wgsl"
+#include "environment.inc.wgsl"
+#include "light.inc.wgsl"
+#include "shading.inc.wgsl"
+//shading-begin
+#include "shade.inc.wgsl"
+//shading-end
 
 const PI: f32 = 3.1415926;
 const MAX_RESERVOIRS: u32 = 4u;
@@ -28,15 +36,41 @@ struct MainParams {
     use_pairwise_mis: u32,
     defensive_mis: f32,
     use_motion_vectors: u32,
+    sample_strategy: u32,
+    firefly_clamp: f32,
+    light_count: u32,
+    // offset of the current tile, in pixels
+    tile_origin: vec2<u32>,
+    num_brdf_samples: u32,
+    // zero if the adaptive sampling is disabled
+    adaptive_max_samples: u32,
+    adaptive_target_variance: f32,
+    adaptive_reset: u32,
+    // converts the fixed-point caustics into lighting, zero if disabled
+    caustics_weight: f32,
+    // multiplier of the light samples, at least 1
+    samples_per_frame: u32,
 };
 
+struct PreviewParams {
+    sun_direction: vec3<f32>,
+    ambient: f32,
+    sun_color: vec3<f32>,
+    pad: u32,
+}
+
+// Specialized by the host for each pipeline variant.
+override RENDER_MODE: u32 = RenderMode_PathTraced;
+
 var<uniform> camera: CameraParams;
 var<uniform> prev_camera: CameraParams;
 var<uniform> parameters: MainParams;
+var<uniform> preview: PreviewParams;
 var<uniform> debug: DebugParams;
 var acc_struct: acceleration_structure;
 var prev_acc_struct: acceleration_structure;
 var env_map: texture_2d<f32>;
+var t_matcap: texture_2d<f32>;
 var sampler_linear: sampler;
 var sampler_nearest: sampler;
 
@@ -149,8 +183,18 @@ var t_prev_basis: texture_2d<f32>;
 var t_flat_normal: texture_2d<f32>;
 var t_prev_flat_normal: texture_2d<f32>;
 var t_motion: texture_2d<f32>;
+var t_albedo: texture_2d<f32>;
+var t_transmission: texture_2d<f32>;
+var t_instance: texture_2d<u32>;
+var<storage, read> instance_data: array<vec4<f32>>;
 var out_diffuse: texture_storage_2d<rgba16float, write>;
 var out_debug: texture_storage_2d<rgba8unorm, write>;
+var t_prev_variance: texture_2d<f32>;
+var out_variance: texture_storage_2d<rgba32float, write>;
+// Lighting focused by mirrors and refractions, deposited by "caustics"
+var<storage, read> caustics: array<u32>;
+// Cumulative distribution of the light power, normalized to end at 1
+var<storage, read> light_cdf: array<f32>;
 
 fn sample_circle(random: f32) -> vec2<f32> {
     let angle = 2.0 * PI * random;
@@ -161,21 +205,9 @@ fn square(v: f32) -> f32 {
     return v * v;
 }
 
-fn map_equirect_dir_to_uv(dir: vec3<f32>) -> vec2<f32> {
-    //Note: Y axis is up
-    let yaw = asin(dir.y);
-    let pitch = atan2(dir.x, dir.z);
-    return vec2<f32>(pitch + PI, -2.0 * yaw + PI) / (2.0 * PI);
-}
-fn map_equirect_uv_to_dir(uv: vec2<f32>) -> vec3<f32> {
-    let yaw = PI * (0.5 - uv.y);
-    let pitch = 2.0 * PI * (uv.x - 0.5);
-    return vec3<f32>(cos(yaw) * sin(pitch), sin(yaw), cos(yaw) * cos(pitch));
-}
-
 fn evaluate_environment(dir: vec3<f32>) -> vec3<f32> {
     let uv = map_equirect_dir_to_uv(dir);
-    return textureSampleLevel(env_map, sampler_linear, uv, 0.0).xyz;
+    return environment.intensity * textureSampleLevel(env_map, sampler_linear, uv, 0.0).xyz;
 }
 
 fn sample_light_from_sphere(rng: ptr<function, RandomState>) -> LightSample {
@@ -186,7 +218,7 @@ fn sample_light_from_sphere(rng: ptr<function, RandomState>) -> LightSample {
     var ls = LightSample();
     ls.uv = map_equirect_dir_to_uv(dir);
     ls.pdf = 1.0 / (4.0 * PI);
-    ls.radiance = textureSampleLevel(env_map, sampler_linear, ls.uv, 0.0).xyz;
+    ls.radiance = environment.intensity * textureSampleLevel(env_map, sampler_linear, ls.uv, 0.0).xyz;
     return ls;
 }
 
@@ -196,7 +228,7 @@ fn sample_light_from_environment(rng: ptr<function, RandomState>) -> LightSample
     var ls = LightSample();
     ls.pdf = es.pdf;
     // sample the incoming radiance
-    ls.radiance = textureLoad(env_map, es.pixel, 0).xyz;
+    ls.radiance = environment.intensity * textureLoad(env_map, es.pixel, 0).xyz;
     // for determining direction - offset randomly within the texel
     // this offset has to be uniformly distributed across the surface of the texel
     let u = (f32(es.pixel.x) + random_gen(rng)) / f32(dim.x);
@@ -206,6 +238,43 @@ fn sample_light_from_environment(rng: ptr<function, RandomState>) -> LightSample
     return ls;
 }
 
+fn get_environment_pdf(dir: vec3<f32>) -> f32 {
+    if (parameters.environment_importance_sampling == 0u) {
+        return 1.0 / (4.0 * PI);
+    }
+    let dim = textureDimensions(env_map, 0);
+    let uv = map_equirect_dir_to_uv(dir);
+    let pixel = min(vec2<i32>(uv * vec2<f32>(dim)), vec2<i32>(dim) - 1);
+    return compute_environment_sample_pdf(pixel, dim);
+}
+
+// Cosine-weighted hemisphere around the shading normal, which is
+// proportional to the Lambertian BRDF.
+fn sample_light_from_brdf(surface: Surface, rng: ptr<function, RandomState>) -> LightSample {
+    let r2 = random_gen(rng);
+    let disk = sqrt(r2) * sample_circle(random_gen(rng));
+    let cos_theta = sqrt(1.0 - r2);
+    let dir = qrot(surface.basis, vec3<f32>(disk, cos_theta));
+    var ls = LightSample();
+    ls.uv = map_equirect_dir_to_uv(dir);
+    ls.pdf = cos_theta / PI;
+    ls.radiance = environment.intensity * textureSampleLevel(env_map, sampler_linear, ls.uv, 0.0).xyz;
+    return ls;
+}
+
+fn get_brdf_pdf(surface: Surface, dir: vec3<f32>) -> f32 {
+    return max(0.0, qrot(qinv(surface.basis), dir).z) / PI;
+}
+
+// Effective PDF of a candidate drawn by one strategy, weighted against
+// the other one with the power heuristic. The reservoir divides the sum
+// of the candidate weights by the total number of them.
+fn get_mis_pdf(count: f32, pdf: f32, other_count: f32, other_pdf: f32) -> f32 {
+    let a = count * pdf;
+    let b = other_count * other_pdf;
+    return (a * a + b * b) / ((count + other_count) * a);
+}
+
 fn read_surface(pixel: vec2<i32>) -> Surface {
     var surface: Surface;
     surface.basis = normalize(textureLoad(t_basis, pixel, 0));
@@ -232,10 +301,14 @@ fn evaluate_brdf(surface: Surface, dir: vec3<f32>) -> f32 {
 var<private> debug_len: f32;
 
 fn check_ray_occluded(acs: acceleration_structure, position: vec3<f32>, direction: vec3<f32>, debug_len: f32, debug_color: u32) -> bool {
+    return check_ray_occluded_within(acs, position, direction, camera.depth, debug_len, debug_color);
+}
+
+fn check_ray_occluded_within(acs: acceleration_structure, position: vec3<f32>, direction: vec3<f32>, max_distance: f32, debug_len: f32, debug_color: u32) -> bool {
     var rq: ray_query;
     let flags = RAY_FLAG_TERMINATE_ON_FIRST_HIT | RAY_FLAG_CULL_NO_OPAQUE;
     rayQueryInitialize(&rq, acs,
-        RayDesc(flags, 0xFFu, parameters.t_start, camera.depth, position, direction)
+        RayDesc(flags, 0xFFu, parameters.t_start, max_distance, position, direction)
     );
     rayQueryProceed(&rq);
     let intersection = rayQueryGetCommittedIntersection(&rq);
@@ -258,7 +331,7 @@ fn evaluate_reflected_light(surface: Surface, light_index: u32, light_uv: vec2<f
         return vec3<f32>(0.0);
     }
     // Note: returns radiance not modulated by albedo
-    let radiance = textureSampleLevel(env_map, sampler_nearest, light_uv, 0.0).xyz;
+    let radiance = environment.intensity * textureSampleLevel(env_map, sampler_nearest, light_uv, 0.0).xyz;
     return radiance * brdf;
 }
 
@@ -300,7 +373,7 @@ fn estimate_target_score_with_occlusion(
         return TargetScore();
     } else {
         //Note: same as `evaluate_reflected_light`
-        let radiance = textureSampleLevel(env_map, sampler_nearest, light_uv, 0.0).xyz;
+        let radiance = environment.intensity * textureSampleLevel(env_map, sampler_nearest, light_uv, 0.0).xyz;
         return make_target_score(brdf * radiance);
     }
 }
@@ -336,7 +409,7 @@ struct RestirOutput {
     radiance: vec3<f32>,
 }
 
-fn compute_restir(surface: Surface, pixel: vec2<i32>, rng: ptr<function, RandomState>, enable_debug: bool) -> RestirOutput {
+fn compute_restir(surface: Surface, pixel: vec2<i32>, rng: ptr<function, RandomState>, sample_factor: u32, enable_debug: bool) -> RestirOutput {
     let ray_dir = get_ray_direction(camera, pixel);
     let pixel_index = get_reservoir_index(pixel, camera);
     if (surface.depth == 0.0) {
@@ -353,12 +426,27 @@ fn compute_restir(surface: Surface, pixel: vec2<i32>, rng: ptr<function, RandomS
     let debug_len = select(0.0, surface.depth * 0.2, enable_debug);
 
     var canonical = LiveReservoir();
-    for (var i = 0u; i < parameters.num_environment_samples; i += 1u) {
+    // Candidates come from both the light and the BRDF sampling,
+    // combined with multiple importance sampling.
+    let env_count = f32(parameters.num_environment_samples * sample_factor);
+    let brdf_count = f32(parameters.num_brdf_samples * sample_factor);
+    let round_count = parameters.num_environment_samples + parameters.num_brdf_samples;
+    for (var i = 0u; i < round_count * sample_factor; i += 1u) {
         var ls: LightSample;
-        if (parameters.environment_importance_sampling != 0u) {
-            ls = sample_light_from_environment(rng);
+        if (i % round_count >= parameters.num_environment_samples) {
+            ls = sample_light_from_brdf(surface, rng);
+            let env_pdf = get_environment_pdf(map_equirect_uv_to_dir(ls.uv));
+            ls.pdf = get_mis_pdf(brdf_count, ls.pdf, env_count, env_pdf);
         } else {
-            ls = sample_light_from_sphere(rng);
+            if (parameters.environment_importance_sampling != 0u) {
+                ls = sample_light_from_environment(rng);
+            } else {
+                ls = sample_light_from_sphere(rng);
+            }
+            if (parameters.num_brdf_samples != 0u) {
+                let brdf_pdf = get_brdf_pdf(surface, map_equirect_uv_to_dir(ls.uv));
+                ls.pdf = get_mis_pdf(env_count, ls.pdf, brdf_count, brdf_pdf);
+            }
         }
 
         let brdf = evaluate_sample(ls, surface, position, debug_len, 0x00FF00u);
@@ -487,25 +575,273 @@ fn compute_restir(surface: Surface, pixel: vec2<i32>, rng: ptr<function, RandomS
     return ro;
 }
 
+// Noise-free lighting with a single shadow ray, besides the punctual lights.
+// The ambient term is modulated by the baked `occlusion`.
+fn compute_direct_preview(surface: Surface, pixel: vec2<i32>, occlusion: f32, enable_debug: bool) -> vec3<f32> {
+    let ray_dir = get_ray_direction(camera, pixel);
+    let pixel_index = get_reservoir_index(pixel, camera);
+    // Don't leave stale samples for when the full quality is back
+    reservoirs[pixel_index] = StoredReservoir();
+    if (surface.depth == 0.0) {
+        return evaluate_environment(ray_dir);
+    }
+
+    let position = camera.position + surface.depth * ray_dir;
+    let debug_len = select(0.0, surface.depth * 0.2, enable_debug);
+    var color = vec3<f32>(preview.ambient * occlusion);
+    let dir = preview.sun_direction;
+    if (dot(dir, surface.flat_normal) > 0.0) {
+        let brdf = evaluate_brdf(surface, dir);
+        if (brdf > 0.0 && !check_ray_occluded(acc_struct, position, dir, debug_len, 0xFFFF00u)) {
+            // the light color is its irradiance, so cancel out the normalization
+            color += PI * brdf * preview.sun_color;
+        }
+    }
+    return color;
+}
+
+// Lighting looked up from the material capture by the view-space normal.
+fn compute_matcap(surface: Surface, pixel: vec2<i32>) -> vec3<f32> {
+    let pixel_index = get_reservoir_index(pixel, camera);
+    reservoirs[pixel_index] = StoredReservoir();
+    if (surface.depth == 0.0) {
+        return evaluate_environment(get_ray_direction(camera, pixel));
+    }
+    let normal = qrot(surface.basis, vec3<f32>(0.0, 0.0, 1.0));
+    let view_normal = qrot(qinv(camera.orientation), normal);
+    let tc = 0.5 * VFLIP * view_normal.xy + vec2<f32>(0.5);
+    return textureSampleLevel(t_matcap, sampler_linear, tc, 0.0).xyz;
+}
+
+// Up to this many punctual lights are all traced, and the rest are sampled.
+const MAX_TRACED_LIGHTS: u32 = 8u;
+// Lights drawn by power, out of which one is picked by its contribution.
+const LIGHT_CANDIDATES: u32 = 8u;
+
+struct LightContribution {
+    dir: vec3<f32>,
+    distance: f32,
+    // unshadowed, zero if the light doesn't reach the surface
+    radiance: vec3<f32>,
+}
+
+fn evaluate_punctual_light(light: LightEntry, surface: Surface, position: vec3<f32>) -> LightContribution {
+    var lc = LightContribution();
+    let offset = light.position - position;
+    lc.distance = length(offset);
+    if (lc.distance <= 0.0 || (light.range > 0.0 && lc.distance >= light.range)) {
+        return lc;
+    }
+    lc.dir = offset / lc.distance;
+    if (dot(lc.dir, surface.flat_normal) <= 0.0) {
+        return lc;
+    }
+    let cone = smoothstep(light.cos_outer, light.cos_inner, dot(light.direction, -lc.dir));
+    let attenuation = get_light_attenuation(lc.distance, light.range) * select(cone, 1.0, light.cos_outer <= -1.0);
+    let brdf = evaluate_brdf(surface, lc.dir);
+    lc.radiance = light.radiance * max(attenuation * brdf, 0.0);
+    return lc;
+}
+
+// Pick a light with the probability proportional to its power,
+// by a binary search in the cumulative distribution.
+fn sample_light_by_power(random: f32) -> u32 {
+    var lo = 0u;
+    var hi = parameters.light_count - 1u;
+    while (lo < hi) {
+        let mid = (lo + hi) / 2u;
+        if (light_cdf[mid] <= random) {
+            lo = mid + 1u;
+        } else {
+            hi = mid;
+        }
+    }
+    return lo;
+}
+
+fn get_light_power_pdf(index: u32) -> f32 {
+    return light_cdf[index] - select(0.0, light_cdf[index - 1u], index != 0u);
+}
+
+// Direct lighting from the punctual lights, with a shadow ray per light.
+// With many lights, a few candidates are drawn by their power, and one of them
+// is resampled (RIS) by its unshadowed contribution at the shading point,
+// needing a single shadow ray per sample.
+fn compute_punctual_lights(surface: Surface, position: vec3<f32>, rng: ptr<function, RandomState>, sample_factor: u32, debug_len: f32) -> vec3<f32> {
+    var radiance = vec3<f32>(0.0);
+    if (parameters.light_count <= MAX_TRACED_LIGHTS) {
+        for (var i = 0u; i < parameters.light_count; i += 1u) {
+            let lc = evaluate_punctual_light(lights[i], surface, position);
+            if (all(lc.radiance == vec3<f32>(0.0))) {
+                continue;
+            }
+            if (!check_ray_occluded_within(acc_struct, position, lc.dir, lc.distance, debug_len, 0xFF80FFu)) {
+                radiance += lc.radiance;
+            }
+        }
+        return radiance;
+    }
+
+    for (var s = 0u; s < sample_factor; s += 1u) {
+        var selected = LightContribution();
+        var selected_score = 0.0;
+        var weight_sum = 0.0;
+        for (var c = 0u; c < LIGHT_CANDIDATES; c += 1u) {
+            let index = sample_light_by_power(random_gen(rng));
+            let pdf = get_light_power_pdf(index);
+            if (pdf <= 0.0) {
+                continue;
+            }
+            let lc = evaluate_punctual_light(lights[index], surface, position);
+            let score = dot(lc.radiance, LUMA);
+            if (score <= 0.0) {
+                continue;
+            }
+            let weight = score / pdf;
+            weight_sum += weight;
+            if (random_gen(rng) * weight_sum < weight) {
+                selected = lc;
+                selected_score = score;
+            }
+        }
+        if (weight_sum <= 0.0) {
+            continue;
+        }
+        if (!check_ray_occluded_within(acc_struct, position, selected.dir, selected.distance, debug_len, 0xFF80FFu)) {
+            let ris_weight = weight_sum / (f32(LIGHT_CANDIDATES) * selected_score);
+            radiance += selected.radiance * ris_weight;
+        }
+    }
+    return radiance / f32(sample_factor);
+}
+
+const LUMA: vec3<f32> = vec3<f32>(0.2126, 0.7152, 0.0722);
+// Frames over which the luminance moments are averaged
+const MAX_ADAPTIVE_HISTORY: f32 = 32.0;
+// Frames needed before the variance is trusted
+const MIN_ADAPTIVE_HISTORY: f32 = 4.0;
+
+// Returns the mean luminance, the mean of its square, the number of frames,
+// and the sample factor used last, following the surface from the previous frame.
+fn read_prev_moments(surface: Surface, pixel: vec2<i32>) -> vec4<f32> {
+    if (surface.depth == 0.0 || parameters.adaptive_reset != 0u) {
+        return vec4<f32>(0.0);
+    }
+    let position = camera.position + surface.depth * get_ray_direction(camera, pixel);
+    let prev_pixel = vec2<i32>(get_prev_pixel(pixel, position));
+    if (any(prev_pixel < vec2<i32>(0)) || any(prev_pixel >= vec2<i32>(prev_camera.target_size))) {
+        return vec4<f32>(0.0);
+    }
+    return textureLoad(t_prev_variance, prev_pixel, 0);
+}
+
+// Multiplier of the light samples, so that the variance of a single one
+// is brought down to the target.
+fn get_sample_factor(moments: vec4<f32>) -> u32 {
+    if (moments.z < MIN_ADAPTIVE_HISTORY) {
+        return parameters.adaptive_max_samples;
+    }
+    let mean2 = moments.x * moments.x;
+    let relative_variance = max(0.0, moments.y - mean2) / max(mean2, 1e-4);
+    // the variance was measured with the previous factor
+    let factor = ceil(relative_variance * moments.w / parameters.adaptive_target_variance);
+    return u32(clamp(factor, 1.0, f32(parameters.adaptive_max_samples)));
+}
+
+// Scale down the samples brighter than the limit, preserving the hue.
+fn clamp_fireflies(color: vec3<f32>, max_luminance: f32) -> vec3<f32> {
+    let luminance = dot(color, LUMA);
+    if (luminance > max_luminance) {
+        return color * (max_luminance / luminance);
+    }
+    return color;
+}
+
 @compute @workgroup_size(8, 4)
-fn main(@builtin(global_invocation_id) global_id: vec3<u32>) {
+fn main(@builtin(global_invocation_id) dispatch_id: vec3<u32>) {
+    let global_id = dispatch_id.xy + parameters.tile_origin;
     if (any(global_id.xy >= camera.target_size)) {
         return;
     }
 
     let global_index = global_id.y * camera.target_size.x + global_id.x;
     var rng = random_init(global_index, parameters.frame_index);
+    if (parameters.sample_strategy == SampleStrategy_BlueNoise) {
+        rng = random_init_low_discrepancy(global_id.xy, parameters.frame_index);
+    }
 
     let surface = read_surface(vec2<i32>(global_id.xy));
     let enable_debug = DEBUG_MODE && all(global_id.xy == debug.mouse_pos);
     let enable_restir_debug = (debug.draw_flags & DebugDrawFlags_RESTIR) != 0u && enable_debug;
-    let ro = compute_restir(surface, vec2<i32>(global_id.xy), &rng, enable_restir_debug);
+    var moments = vec4<f32>(0.0);
+    var sample_factor = parameters.samples_per_frame;
+    if (parameters.adaptive_max_samples != 0u && surface.depth != 0.0) {
+        moments = read_prev_moments(surface, vec2<i32>(global_id.xy));
+        sample_factor = max(get_sample_factor(moments), parameters.samples_per_frame);
+    }
+    // Baked occlusion only applies to the environment lighting,
+    // so it's resolved here, before the direct lights are added.
+    var occlusion = 1.0;
+    if (surface.depth != 0.0) {
+        occlusion = textureLoad(t_albedo, vec2<i32>(global_id.xy), 0).w;
+    }
+    var radiance: vec3<f32>;
+    if (RENDER_MODE == RenderMode_DirectOnly) {
+        radiance = compute_direct_preview(surface, vec2<i32>(global_id.xy), occlusion, enable_debug);
+    } else if (RENDER_MODE == RenderMode_Matcap) {
+        radiance = occlusion * compute_matcap(surface, vec2<i32>(global_id.xy));
+    } else {
+        let ro = compute_restir(surface, vec2<i32>(global_id.xy), &rng, sample_factor, enable_restir_debug);
+        radiance = occlusion * ro.radiance;
+    }
 
-    let color = ro.radiance;
+    if (surface.depth != 0.0) {
+        let pixel = vec2<i32>(global_id.xy);
+        var input: ShadingInput;
+        input.view_dir = get_ray_direction(camera, pixel);
+        input.position = camera.position + surface.depth * input.view_dir;
+        if (RENDER_MODE == RenderMode_PathTraced || RENDER_MODE == RenderMode_DirectOnly) {
+            let debug_len = select(0.0, surface.depth * 0.2, enable_debug);
+            radiance += compute_punctual_lights(surface, input.position, &rng, sample_factor, debug_len);
+        }
+        if (RENDER_MODE == RenderMode_PathTraced && parameters.caustics_weight > 0.0) {
+            let base = 3u * global_index;
+            let deposited = vec3<u32>(caustics[base], caustics[base + 1u], caustics[base + 2u]);
+            radiance += parameters.caustics_weight * vec3<f32>(deposited);
+        }
+        if (RENDER_MODE != RenderMode_Matcap) {
+            // light coming through transmissive surfaces, traced by "fill-gbuf"
+            let transmission = textureLoad(t_transmission, pixel, 0);
+            radiance = mix(radiance, transmission.xyz, transmission.w);
+        }
+        input.depth = surface.depth;
+        input.normal = qrot(surface.basis, vec3<f32>(0.0, 0.0, 1.0));
+        input.flat_normal = surface.flat_normal;
+        input.albedo = textureLoad(t_albedo, pixel, 0).xyz;
+        input.lighting = radiance;
+        let instance = textureLoad(t_instance, pixel, 0).x;
+        if (instance != 0u) {
+            input.instance_data = instance_data[instance - 1u];
+        }
+        radiance = shade(input);
+    }
+
+    let color = clamp_fireflies(radiance, parameters.firefly_clamp);
     if (enable_debug) {
         debug_buf.variance.color_sum += color;
         debug_buf.variance.color2_sum += color * color;
         debug_buf.variance.count += 1u;
     }
+    if (parameters.adaptive_max_samples != 0u) {
+        let luminance = dot(color, LUMA);
+        let count = min(moments.z + 1.0, MAX_ADAPTIVE_HISTORY);
+        let new_moments = vec2<f32>(luminance, luminance * luminance);
+        let mean = mix(moments.xy, new_moments, 1.0 / count);
+        textureStore(out_variance, global_id.xy, vec4<f32>(mean, count, f32(sample_factor)));
+        if (WRITE_DEBUG_IMAGE && debug.view_mode == DebugMode_SampleDensity) {
+            let density = min(f32(sample_factor) / f32(parameters.adaptive_max_samples), 1.0);
+            textureStore(out_debug, global_id.xy, vec4<f32>(density, 0.0, 1.0 - density, 1.0));
+        }
+    }
     textureStore(out_diffuse, global_id.xy, vec4<f32>(color, 1.0));
 }
@@ -0,0 +1,5 @@
+// Default shading hook: keep the lighting computed by the renderer.
+// The returned value is modulated by the albedo in post-processing.
+fn shade(input: ShadingInput) -> vec3<f32> {
+    return input.lighting;
+}
@@ -0,0 +1,21 @@
+// Inputs of the shading hook, see `Renderer::set_shading_module`.
+// Custom shading modules can rely on this interface.
+struct ShadingInput {
+    // World-space position of the visible surface.
+    position: vec3<f32>,
+    // Distance from the camera to the surface.
+    depth: f32,
+    // Shading normal, with the normal map applied.
+    normal: vec3<f32>,
+    // Geometric normal of the triangle.
+    flat_normal: vec3<f32>,
+    // Direction from the camera towards the surface.
+    view_dir: vec3<f32>,
+    // Base color of the surface.
+    albedo: vec3<f32>,
+    // Diffuse lighting computed by the renderer, not modulated by the albedo.
+    // The baked occlusion is already applied to the environment part of it.
+    lighting: vec3<f32>,
+    // Custom data of the visible object, see `Renderer::set_instance_data`.
+    instance_data: vec4<f32>,
+}
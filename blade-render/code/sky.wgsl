@@ -0,0 +1,108 @@
+// Preetham analytic daylight model, baked into an equirectangular map.
+// "A Practical Analytic Model for Daylight", Preetham et al. 1999
+
+const PI: f32 = 3.1415926;
+// Angular radius of the sun disk, in radians.
+const SUN_ANGULAR_RADIUS: f32 = 0.00465;
+// Illuminance of the sun outside of the atmosphere,
+// in the units of the sky luminance (kcd/m^2) over a steradian.
+const SUN_ILLUMINANCE: f32 = 120.0;
+// Wavelengths of the RGB channels, in micrometers.
+const WAVELENGTHS: vec3<f32> = vec3<f32>(0.68, 0.55, 0.44);
+
+// Has to match the host!
+struct SkyParams {
+    // direction towards the sun, in the environment map space
+    sun_direction: vec3<f32>,
+    turbidity: f32,
+    ground_albedo: vec3<f32>,
+    pad: u32,
+}
+var<uniform> params: SkyParams;
+var output: texture_storage_2d<rgba32float, write>;
+
+// Distribution of the luminance (x) and the chromaticity (y, z) over the sky.
+fn perez(cos_theta: f32, gamma: f32, cos_gamma: f32, t: f32) -> vec3<f32> {
+    let a = vec3<f32>(0.1787, -0.0193, -0.0167) * t + vec3<f32>(-1.4630, -0.2592, -0.2608);
+    let b = vec3<f32>(-0.3554, -0.0665, -0.0950) * t + vec3<f32>(0.4275, 0.0008, 0.0092);
+    let c = vec3<f32>(-0.0227, -0.0004, -0.0079) * t + vec3<f32>(5.3251, 0.2125, 0.2102);
+    let d = vec3<f32>(0.1206, -0.0641, -0.0441) * t + vec3<f32>(-2.5771, -0.8989, -1.6537);
+    let e = vec3<f32>(-0.0670, -0.0033, -0.0109) * t + vec3<f32>(0.3703, 0.0452, 0.0529);
+    return (1.0 + a * exp(b / max(cos_theta, 0.01))) * (1.0 + c * exp(d * gamma) + e * cos_gamma * cos_gamma);
+}
+
+// Luminance and chromaticity at the zenith, given the sun zenith angle.
+fn zenith_yxy(theta_s: f32, t: f32) -> vec3<f32> {
+    let chi = (4.0 / 9.0 - t / 120.0) * (PI - 2.0 * theta_s);
+    let luminance = max(0.0, (4.0453 * t - 4.9710) * tan(chi) - 0.2155 * t + 2.4192);
+    let powers = vec4<f32>(theta_s * theta_s * theta_s, theta_s * theta_s, theta_s, 1.0);
+    let x = dot(t * t * vec4<f32>(0.00166, -0.00375, 0.00209, 0.0)
+        + t * vec4<f32>(-0.02903, 0.06377, -0.03202, 0.00394)
+        + vec4<f32>(0.11693, -0.21196, 0.06052, 0.25886), powers);
+    let y = dot(t * t * vec4<f32>(0.00275, -0.00610, 0.00317, 0.0)
+        + t * vec4<f32>(-0.04214, 0.08970, -0.04153, 0.00516)
+        + vec4<f32>(0.15346, -0.26756, 0.06670, 0.26688), powers);
+    return vec3<f32>(luminance, x, y);
+}
+
+fn yxy_to_linear_srgb(yxy: vec3<f32>) -> vec3<f32> {
+    let big_y = yxy.x;
+    let xyz = vec3<f32>(yxy.y / yxy.z * big_y, big_y, (1.0 - yxy.y - yxy.z) / yxy.z * big_y);
+    return max(vec3<f32>(0.0), vec3<f32>(
+        dot(vec3<f32>(3.2406, -1.5372, -0.4986), xyz),
+        dot(vec3<f32>(-0.9689, 1.8758, 0.0415), xyz),
+        dot(vec3<f32>(0.0557, -0.2040, 1.0570), xyz),
+    ));
+}
+
+// Transmittance of the atmosphere along the path to the sun,
+// from the Rayleigh and the aerosol (Mie) scattering.
+fn sun_transmittance(cos_theta_s: f32, t: f32) -> vec3<f32> {
+    let theta_deg = degrees(acos(max(cos_theta_s, 0.0)));
+    let air_mass = 1.0 / (max(cos_theta_s, 0.0) + 0.15 * pow(93.885 - theta_deg, -1.253));
+    let rayleigh = 0.008735 * pow(WAVELENGTHS, vec3<f32>(-4.08));
+    let mie = (0.04608 * t - 0.04586) * pow(WAVELENGTHS, vec3<f32>(-1.3));
+    return exp(-air_mass * (rayleigh + mie));
+}
+
+@compute
+@workgroup_size(8, 8)
+fn bake(@builtin(global_invocation_id) global_id: vec3<u32>) {
+    let size = textureDimensions(output);
+    if (any(global_id.xy >= size)) {
+        return;
+    }
+
+    // same mapping as `map_equirect_uv_to_dir`, in the environment space
+    let uv = (vec2<f32>(global_id.xy) + 0.5) / vec2<f32>(size);
+    let yaw = PI * (0.5 - uv.y);
+    let pitch = 2.0 * PI * (uv.x - 0.5);
+    let dir = vec3<f32>(cos(yaw) * sin(pitch), sin(yaw), cos(yaw) * cos(pitch));
+
+    let t = clamp(params.turbidity, 1.7, 10.0);
+    let sun = normalize(params.sun_direction);
+    // the model doesn't cover the sun below the horizon, so fade into the night
+    let daylight = smoothstep(-0.1, 0.02, sun.y);
+    let theta_s = min(acos(clamp(sun.y, -1.0, 1.0)), 0.5 * PI - 0.01);
+    let zenith = zenith_yxy(theta_s, t);
+    let sun_irradiance = SUN_ILLUMINANCE * sun_transmittance(sun.y, t);
+
+    var color: vec3<f32>;
+    if (dir.y >= 0.0) {
+        let cos_gamma = clamp(dot(dir, sun), -1.0, 1.0);
+        let yxy = zenith * perez(dir.y, acos(cos_gamma), cos_gamma, t) / perez(1.0, theta_s, cos(theta_s), t);
+        color = daylight * yxy_to_linear_srgb(yxy);
+        // The disk is widened to cover a few texels, keeping the irradiance.
+        let radius = max(SUN_ANGULAR_RADIUS, 1.5 * PI / f32(size.y));
+        if (cos_gamma > cos(radius) && sun.y > 0.0) {
+            color += sun_irradiance / (PI * radius * radius);
+        }
+    } else {
+        // Lambertian ground, lit by the sun and roughly by the sky,
+        // which is approximated by the zenith radiance.
+        let sky_radiance = daylight * yxy_to_linear_srgb(zenith);
+        let sun_radiance = sun_irradiance * max(sun.y, 0.0) / PI;
+        color = params.ground_albedo * (sun_radiance + sky_radiance);
+    }
+    textureStore(output, vec2<i32>(global_id.xy), vec4<f32>(color, 1.0));
+}
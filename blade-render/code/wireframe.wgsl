@@ -0,0 +1,86 @@
+#include "quaternion.inc.wgsl"
+#include "camera.inc.wgsl"
+
+// Has to match the host!
+struct Vertex {
+    pos: vec3<f32>,
+    bitangent_sign: f32,
+    tex_coords: vec2<f32>,
+    normal: u32,
+    tangent: u32,
+    tex_coords1: vec2<f32>,
+    pad: vec2<u32>,
+}
+
+struct WireframeParams {
+    // rows of the geometry-to-world transform
+    transform: mat3x4<f32>,
+    color: vec4<f32>,
+    thickness: f32,
+    indexed: u32,
+    viewport_origin: vec2<i32>,
+}
+
+var<uniform> camera: CameraParams;
+var<uniform> params: WireframeParams;
+var<storage, read> vertices: array<Vertex>;
+var<storage, read> indices: array<u32>;
+
+struct WireframeVarying {
+    @builtin(position) pos: vec4<f32>,
+    @location(0) dir: vec3<f32>,
+}
+
+fn project(world_pos: vec3<f32>) -> vec4<f32> {
+    let local_dir = qrot(qinv(camera.orientation), world_pos - camera.position);
+    return vec4<f32>(local_dir.xy / tan(0.5 * camera.fov), 0.0, -local_dir.z);
+}
+
+// Every edge of a triangle is expanded into a screen-space quad of 6 vertices.
+@vertex
+fn wire_vs(@builtin(vertex_index) vertex_id: u32) -> WireframeVarying {
+    let triangle = vertex_id / 18u;
+    let edge = (vertex_id / 6u) % 3u;
+    let corner = vertex_id % 6u;
+    let is_end = ((0x32u >> corner) & 1u) != 0u;
+    let side = select(-1.0, 1.0, ((0x2Cu >> corner) & 1u) != 0u);
+
+    var ia = triangle * 3u + edge;
+    var ib = triangle * 3u + (edge + 1u) % 3u;
+    if (params.indexed != 0u) {
+        ia = indices[ia];
+        ib = indices[ib];
+    }
+    let world_a = vec4<f32>(vertices[ia].pos, 1.0) * params.transform;
+    let world_b = vec4<f32>(vertices[ib].pos, 1.0) * params.transform;
+    let clip_a = project(world_a);
+    let clip_b = project(world_b);
+
+    let target_size = vec2<f32>(camera.target_size);
+    let screen_dir = (clip_b.xy / clip_b.w - clip_a.xy / clip_a.w) * target_size;
+    let dir = normalize(select(vec2<f32>(1.0, 0.0), screen_dir, dot(screen_dir, screen_dir) > 0.0));
+    let offset = side * vec2<f32>(-dir.y, dir.x) * params.thickness / target_size;
+
+    let world_pos = select(world_a, world_b, is_end);
+    let clip_pos = select(clip_a, clip_b, is_end);
+
+    var out: WireframeVarying;
+    out.pos = vec4<f32>(clip_pos.xy + offset * clip_pos.w, clip_pos.zw);
+    out.dir = world_pos - camera.position;
+    return out;
+}
+
+var depth: texture_2d<f32>;
+
+@fragment
+fn wire_fs(in: WireframeVarying) -> @location(0) vec4<f32> {
+    let geo_dim = textureDimensions(depth);
+    let pos = vec2<i32>(in.pos.xy) - params.viewport_origin;
+    let depth_itc = vec2<i32>(pos.x, i32(geo_dim.y) - pos.y);
+    let depth = textureLoad(depth, depth_itc, 0).x;
+    // Edges lie exactly on the traced surface, so allow a bit of slack.
+    if (depth != 0.0 && length(in.dir) > depth * 1.01) {
+        discard;
+    }
+    return params.color;
+}
@@ -0,0 +1,282 @@
+//! Playback of the glTF animations of a model.
+
+use crate::model::{AnimationChannel, AnimationPath, Interpolation, Model};
+
+impl AnimationChannel {
+    fn component_count(&self) -> usize {
+        match self.path {
+            AnimationPath::Rotation => 4,
+            AnimationPath::Translation | AnimationPath::Scale => 3,
+        }
+    }
+
+    fn element(&self, index: usize) -> glam::Vec4 {
+        let count = self.component_count();
+        let mut value = glam::Vec4::ZERO;
+        for (i, &component) in self.values[index * count..(index + 1) * count]
+            .iter()
+            .enumerate()
+        {
+            value[i] = component;
+        }
+        value
+    }
+
+    fn value(&self, key: usize) -> glam::Vec4 {
+        match self.interpolation {
+            // cubic splines store the tangents around each value
+            Interpolation::CubicSpline => self.element(key * 3 + 1),
+            Interpolation::Linear | Interpolation::Step => self.element(key),
+        }
+    }
+
+    /// Sample the channel at a given time, clamping to the first and last keyframes.
+    fn sample(&self, time: f32) -> glam::Vec4 {
+        let next = self.times.partition_point(|&t| t <= time);
+        if next == 0 {
+            return self.value(0);
+        }
+        if next == self.times.len() {
+            return self.value(next - 1);
+        }
+        let prev = next - 1;
+        let delta = self.times[next] - self.times[prev];
+        let s = if delta > 0.0 {
+            (time - self.times[prev]) / delta
+        } else {
+            0.0
+        };
+        match self.interpolation {
+            Interpolation::Step => self.value(prev),
+            Interpolation::Linear => match self.path {
+                AnimationPath::Rotation => {
+                    let a = glam::Quat::from_vec4(self.value(prev));
+                    let b = glam::Quat::from_vec4(self.value(next));
+                    glam::Vec4::from(a.slerp(b, s))
+                }
+                AnimationPath::Translation | AnimationPath::Scale => {
+                    self.value(prev).lerp(self.value(next), s)
+                }
+            },
+            Interpolation::CubicSpline => {
+                let s2 = s * s;
+                let s3 = s2 * s;
+                let value = (2.0 * s3 - 3.0 * s2 + 1.0) * self.value(prev)
+                    + (s3 - 2.0 * s2 + s) * delta * self.element(prev * 3 + 2)
+                    + (-2.0 * s3 + 3.0 * s2) * self.value(next)
+                    + (s3 - s2) * delta * self.element(next * 3);
+                match self.path {
+                    AnimationPath::Rotation => value.normalize_or_zero(),
+                    AnimationPath::Translation | AnimationPath::Scale => value,
+                }
+            }
+        }
+    }
+}
+
+/// Playback state of the animations of a model.
+///
+/// The player only tracks the time, so one model can be played
+/// by several objects, each at its own pace. The result is a pose,
+/// which is given to `Renderer::set_object_pose`.
+#[derive(Clone, Debug)]
+pub struct AnimationPlayer {
+    clip_index: usize,
+    duration: f32,
+    time: f32,
+    speed: f32,
+    is_playing: bool,
+    is_looping: bool,
+}
+
+impl AnimationPlayer {
+    /// Create a paused player of the first animation of a model.
+    pub fn new(model: &Model) -> Self {
+        Self {
+            clip_index: 0,
+            duration: model.animations.first().map_or(0.0, |a| a.duration),
+            time: 0.0,
+            speed: 1.0,
+            is_playing: false,
+            is_looping: true,
+        }
+    }
+
+    pub fn play(&mut self) {
+        self.is_playing = true;
+    }
+
+    pub fn pause(&mut self) {
+        self.is_playing = false;
+    }
+
+    pub fn is_playing(&self) -> bool {
+        self.is_playing
+    }
+
+    /// Jump to a given time, in seconds, clamped to the clip.
+    pub fn seek(&mut self, time: f32) {
+        self.time = time.clamp(0.0, self.duration);
+    }
+
+    /// Current time, in seconds.
+    pub fn time(&self) -> f32 {
+        self.time
+    }
+
+    /// Set the playback rate, where one is the real time.
+    /// Negative values play the clip backwards.
+    pub fn set_speed(&mut self, speed: f32) {
+        self.speed = speed;
+    }
+
+    pub fn speed(&self) -> f32 {
+        self.speed
+    }
+
+    /// Wrap around at the end of the clip, instead of stopping.
+    pub fn set_looping(&mut self, looping: bool) {
+        self.is_looping = looping;
+    }
+
+    pub fn is_looping(&self) -> bool {
+        self.is_looping
+    }
+
+    /// Switch to another animation of the model, starting from its beginning.
+    pub fn select_clip(&mut self, model: &Model, index: usize) {
+        self.clip_index = index;
+        self.duration = model.animations.get(index).map_or(0.0, |a| a.duration);
+        self.time = 0.0;
+    }
+
+    pub fn clip_index(&self) -> usize {
+        self.clip_index
+    }
+
+    /// Length of the current clip, in seconds.
+    pub fn duration(&self) -> f32 {
+        self.duration
+    }
+
+    /// Move the time forward by `delta` seconds of the real time,
+    /// if playing. Returns true if the time has changed.
+    pub fn advance(&mut self, delta: f32) -> bool {
+        if !self.is_playing || self.speed == 0.0 {
+            return false;
+        }
+        let old_time = self.time;
+        let time = self.time + delta * self.speed;
+        if self.duration <= 0.0 {
+            self.time = 0.0;
+        } else if self.is_looping {
+            self.time = time.rem_euclid(self.duration);
+        } else {
+            self.time = time.clamp(0.0, self.duration);
+            if self.time != time {
+                self.is_playing = false;
+            }
+        }
+        self.time != old_time
+    }
+
+    /// Compute the transform of every geometry of the model at the current time,
+    /// relative to the model space.
+    pub fn pose(&self, model: &Model) -> Vec<blade_graphics::Transform> {
+        let mut locals = model
+            .nodes
+            .iter()
+            .map(|node| {
+                (
+                    glam::Vec3::from(node.translation),
+                    glam::Quat::from_array(node.rotation),
+                    glam::Vec3::from(node.scale),
+                )
+            })
+            .collect::<Vec<_>>();
+        if let Some(animation) = model.animations.get(self.clip_index) {
+            for channel in animation.channels.iter() {
+                if channel.times.is_empty() {
+                    continue;
+                }
+                let value = channel.sample(self.time);
+                let local = &mut locals[channel.node_index];
+                match channel.path {
+                    AnimationPath::Translation => local.0 = value.truncate(),
+                    AnimationPath::Rotation => local.1 = glam::Quat::from_vec4(value),
+                    AnimationPath::Scale => local.2 = value.truncate(),
+                }
+            }
+        }
+
+        let mut globals = vec![None; model.nodes.len()];
+        model
+            .geometries
+            .iter()
+            .map(|geometry| {
+                let m = global_transform(geometry.node_index, model, &locals, &mut globals);
+                let col_matrix = mint::ColumnMatrix3x4 {
+                    x: m.x_axis.truncate().into(),
+                    y: m.y_axis.truncate().into(),
+                    z: m.z_axis.truncate().into(),
+                    w: m.w_axis.truncate().into(),
+                };
+                mint::RowMatrix3x4::from(col_matrix)
+            })
+            .collect()
+    }
+}
+
+fn global_transform(
+    node_index: usize,
+    model: &Model,
+    locals: &[(glam::Vec3, glam::Quat, glam::Vec3)],
+    globals: &mut [Option<glam::Mat4>],
+) -> glam::Mat4 {
+    if let Some(m) = globals[node_index] {
+        return m;
+    }
+    let (translation, rotation, scale) = locals[node_index];
+    let local = glam::Mat4::from_scale_rotation_translation(scale, rotation, translation);
+    let m = match model.nodes[node_index].parent {
+        Some(parent) => global_transform(parent, model, locals, globals) * local,
+        None => local,
+    };
+    globals[node_index] = Some(m);
+    m
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::model::{AnimationChannel, AnimationPath, Interpolation};
+
+    #[test]
+    fn sample_channels() {
+        let mut channel = AnimationChannel {
+            node_index: 0,
+            path: AnimationPath::Translation,
+            interpolation: Interpolation::Linear,
+            times: vec![1.0, 3.0],
+            values: vec![0.0, 0.0, 0.0, 2.0, 4.0, 6.0],
+        };
+        assert_eq!(channel.sample(0.0).truncate(), glam::Vec3::ZERO);
+        assert_eq!(
+            channel.sample(2.0).truncate(),
+            glam::Vec3::new(1.0, 2.0, 3.0)
+        );
+        assert_eq!(
+            channel.sample(5.0).truncate(),
+            glam::Vec3::new(2.0, 4.0, 6.0)
+        );
+        channel.interpolation = Interpolation::Step;
+        assert_eq!(channel.sample(2.9).truncate(), glam::Vec3::ZERO);
+        // zero tangents make the spline ease in and out
+        channel.interpolation = Interpolation::CubicSpline;
+        channel.values = vec![0.0; 18];
+        channel.values[12..15].copy_from_slice(&[2.0, 4.0, 6.0]);
+        assert_eq!(
+            channel.sample(2.0).truncate(),
+            glam::Vec3::new(1.0, 2.0, 3.0)
+        );
+    }
+}
@@ -1,5 +1,8 @@
 use blade_asset::AssetManager;
-use std::{path::Path, sync::Arc};
+use std::{io, path::Path, sync::Arc};
+
+/// Built-in scene, see `AssetHub::load_default_scene`.
+const DEFAULT_SCENE: &[u8] = include_bytes!("../data/cornell-box.gltf");
 
 /// A single hub to manage all assets.
 pub struct AssetHub {
@@ -36,6 +39,8 @@ impl AssetHub {
         let mut sh_baker = crate::shader::Baker::new(gpu_context);
         sh_baker.register_bool("DEBUG_MODE", cfg!(debug_assertions));
         sh_baker.register_enum::<crate::render::DebugMode>();
+        sh_baker.register_enum::<crate::render::SampleStrategy>();
+        sh_baker.register_enum::<crate::render::RenderMode>();
         sh_baker.register_bitflags::<crate::render::DebugDrawFlags>();
         sh_baker.register_bitflags::<crate::render::DebugTextureFlags>();
         let shaders = AssetManager::new(target, choir, sh_baker);
@@ -61,8 +66,50 @@ impl AssetHub {
         self.models.baker.flush(command_encoder, temp_buffers);
     }
 
+    /// Submit the pending geometry uploads and BLAS builds on their own,
+    /// so that recording them doesn't add up to the CPU cost of the next frame.
+    /// The work goes to the same GPU queue as the frames, so it still executes
+    /// ahead of the next submitted frame.
+    ///
+    /// Returns a sync point that signals when the newly loaded models
+    /// are ready. Polling it with `Context::wait_for` and a zero timeout
+    /// allows adding the models to the scene only once they are built.
+    /// Alternatively, the frame using them can be submitted with
+    /// `Context::submit_with_waits` on this sync point.
+    /// Returns `None` if there is nothing to submit.
+    pub fn submit_models(&self) -> Option<blade_graphics::SyncPoint> {
+        self.models.baker.submit()
+    }
+
+    /// Load the built-in Cornell box: a white room open towards +Z,
+    /// with a red wall on the left, a green wall on the right, two boxes,
+    /// and a point light under the ceiling.
+    ///
+    /// The model is embedded into the library, so it's always available
+    /// as a fallback scene. `Camera::default_scene` looks into the room.
+    pub fn load_default_scene(&self) -> (blade_asset::Handle<crate::Model>, &choir::RunningTask) {
+        self.models.load_data(
+            Path::new("cornell-box.gltf"),
+            DEFAULT_SCENE,
+            crate::model::Meta::default(),
+        )
+    }
+
+    /// Export the objects into a Wavefront OBJ file at the given path,
+    /// with their materials in an MTL file next to it.
+    ///
+    /// The geometries are read back from the GPU and written in world space,
+    /// which allows baking a composed scene into a single asset, or checking
+    /// what got imported. Only the first UV set and the basic material
+    /// parameters are written, with the textures referenced by their paths.
+    /// The models need to be loaded.
+    pub fn export_obj(&self, objects: &[crate::Object], path: &Path) -> io::Result<()> {
+        crate::model::obj::export_obj(&self.models, &self.textures, objects, path)
+    }
+
     /// Destroy the hub contents.
     pub fn destroy(&mut self) {
+        self.models.baker.retire(true);
         self.textures.clear();
         self.models.clear();
         self.shaders.clear();
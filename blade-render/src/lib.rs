@@ -10,17 +10,23 @@
     clippy::pattern_type_mismatch,
 )]
 
+mod animation;
 mod asset_hub;
 pub mod model;
 mod render;
 pub mod shader;
+#[cfg(feature = "asset")]
+pub mod testing;
 pub mod texture;
 pub mod util;
 
+pub use animation::AnimationPlayer;
 pub use asset_hub::*;
 pub use model::Model;
 pub use render::*;
 pub use shader::Shader;
+#[cfg(feature = "asset")]
+pub use testing::{benchmark, BenchReport};
 pub use texture::Texture;
 
 // Has to match the `Vertex` in shaders
@@ -32,9 +38,68 @@ pub struct Vertex {
     pub tex_coords: [f32; 2],
     pub normal: u32,
     pub tangent: u32,
+    /// Secondary UV set, i.e. `TEXCOORD_1` in glTF.
+    pub tex_coords1: [f32; 2],
+    pad: [u32; 2],
+}
+
+/// Axis-aligned bounding box. Empty boxes have `min` above `max`.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct BoundingBox {
+    pub min: mint::Vector3<f32>,
+    pub max: mint::Vector3<f32>,
+}
+
+impl Default for BoundingBox {
+    fn default() -> Self {
+        Self {
+            min: [f32::INFINITY; 3].into(),
+            max: [f32::NEG_INFINITY; 3].into(),
+        }
+    }
+}
+
+impl BoundingBox {
+    pub fn is_empty(&self) -> bool {
+        self.min.x > self.max.x || self.min.y > self.max.y || self.min.z > self.max.z
+    }
+
+    /// Extend the box to contain a point.
+    pub fn include(&mut self, point: mint::Vector3<f32>) {
+        let p = glam::Vec3::from(point);
+        self.min = glam::Vec3::from(self.min).min(p).into();
+        self.max = glam::Vec3::from(self.max).max(p).into();
+    }
+
+    /// Extend the box to contain another box.
+    pub fn union(&mut self, other: &Self) {
+        if !other.is_empty() {
+            self.include(other.min);
+            self.include(other.max);
+        }
+    }
+
+    /// Get the bounds of this box after applying a transform.
+    pub fn transformed(&self, transform: &blade_graphics::Transform) -> Self {
+        if self.is_empty() {
+            return *self;
+        }
+        let min = glam::Vec3::from(self.min);
+        let max = glam::Vec3::from(self.max);
+        let center = glam::Vec4::from(((min + max) * 0.5).extend(1.0));
+        let half = (max - min) * 0.5;
+        let rows = [transform.x, transform.y, transform.z].map(glam::Vec4::from);
+        let new_center = glam::Vec3::from(rows.map(|row| row.dot(center)));
+        let new_half = glam::Vec3::from(rows.map(|row| row.truncate().abs().dot(half)));
+        Self {
+            min: (new_center - new_half).into(),
+            max: (new_center + new_half).into(),
+        }
+    }
 }
 
 #[derive(Clone, Copy, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Camera {
     pub pos: mint::Vector3<f32>,
     pub rot: mint::Quaternion<f32>,
@@ -42,6 +107,173 @@ pub struct Camera {
     pub depth: f32,
 }
 
+impl Camera {
+    /// Distance to the near plane of `projection_matrix`.
+    /// The ray tracer doesn't clip anything close to the camera.
+    pub const NEAR_PLANE: f32 = 0.01;
+
+    /// Camera looking into the scene of `AssetHub::load_default_scene`.
+    pub fn default_scene() -> Self {
+        Self {
+            pos: [0.0, 1.0, 3.6].into(),
+            rot: [0.0, 0.0, 0.0, 1.0].into(),
+            fov_y: 0.75,
+            depth: 100.0,
+        }
+    }
+
+    /// Transform from world space to the camera space, which is right-handed
+    /// with X pointing right, Y up, and the camera looking towards -Z.
+    pub fn view_matrix(&self) -> mint::ColumnMatrix4<f32> {
+        glam::Mat4::from_rotation_translation(self.rot.into(), self.pos.into())
+            .inverse()
+            .into()
+    }
+
+    /// Perspective projection from the camera space into clip space,
+    /// with the depth range [0, 1] between `NEAR_PLANE` and `depth`.
+    ///
+    /// The `aspect` is the width of the view divided by the height.
+    pub fn projection_matrix(&self, aspect: f32) -> mint::ColumnMatrix4<f32> {
+        glam::Mat4::perspective_rh(self.fov_y, aspect, Self::NEAR_PLANE, self.depth).into()
+    }
+
+    /// Project a point from the world space onto the screen, using
+    /// the same convention as the renderer.
+    ///
+    /// The result is in the units of `viewport_size`, with the origin at the
+    /// top left corner. Returns `None` if the point is behind the camera.
+    pub fn world_to_screen(
+        &self,
+        point: mint::Vector3<f32>,
+        viewport_size: mint::Vector2<f32>,
+    ) -> Option<mint::Vector2<f32>> {
+        let local = glam::Quat::from(self.rot).inverse()
+            * (glam::Vec3::from(point) - glam::Vec3::from(self.pos));
+        if local.z >= 0.0 {
+            return None;
+        }
+        let size = glam::Vec2::from(viewport_size);
+        let tan_half_y = (0.5 * self.fov_y).tan();
+        let tan_half = glam::Vec2::new(tan_half_y * size.x / size.y, tan_half_y);
+        let ndc = local.truncate() / (-local.z * tan_half);
+        let screen = (glam::Vec2::new(ndc.x, -ndc.y) + 1.0) * 0.5 * size;
+        Some(screen.into())
+    }
+
+    /// Derive the left and the right eye cameras from this one, which is
+    /// between the eyes. The eyes are `ipd` apart along the local X axis,
+    /// and look in parallel, so the images converge at infinity.
+    pub fn stereo_pair(&self, ipd: f32) -> [Self; 2] {
+        let offset = glam::Quat::from(self.rot) * glam::Vec3::new(0.5 * ipd, 0.0, 0.0);
+        let center = glam::Vec3::from(self.pos);
+        [
+            Self {
+                pos: (center - offset).into(),
+                ..*self
+            },
+            Self {
+                pos: (center + offset).into(),
+                ..*self
+            },
+        ]
+    }
+
+    /// Interpolate between two cameras, for flying from one view to another.
+    ///
+    /// The position, the field of view, and the depth are interpolated
+    /// linearly, while the rotation follows the shortest arc.
+    /// The `t` goes from 0 at `a` to 1 at `b`, and can be shaped
+    /// with `Easing::apply` for a smoother motion.
+    pub fn lerp(a: &Self, b: &Self, t: f32) -> Self {
+        let rot_a = glam::Quat::from(a.rot).normalize();
+        let rot_b = glam::Quat::from(b.rot).normalize();
+        Self {
+            pos: glam::Vec3::from(a.pos)
+                .lerp(glam::Vec3::from(b.pos), t)
+                .into(),
+            rot: rot_a.slerp(rot_b, t).into(),
+            fov_y: a.fov_y + (b.fov_y - a.fov_y) * t,
+            depth: a.depth + (b.depth - a.depth) * t,
+        }
+    }
+}
+
+/// Shape of a transition over time, mapping the linear progress
+/// in [0, 1] to the interpolation parameter.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum Easing {
+    #[default]
+    Linear,
+    /// Accelerate at the start and decelerate at the end.
+    SmoothStep,
+    /// Like `SmoothStep`, with a faster middle part.
+    CubicInOut,
+}
+
+impl Easing {
+    /// Map the progress, clamped to [0, 1].
+    pub fn apply(self, t: f32) -> f32 {
+        let t = t.clamp(0.0, 1.0);
+        match self {
+            Self::Linear => t,
+            Self::SmoothStep => t * t * (3.0 - 2.0 * t),
+            Self::CubicInOut => {
+                if t < 0.5 {
+                    4.0 * t * t * t
+                } else {
+                    1.0 - 4.0 * (1.0 - t) * (1.0 - t) * (1.0 - t)
+                }
+            }
+        }
+    }
+}
+
+/// Plane in world space. Points with a positive
+/// `dot(normal, point) + offset` are on the positive side.
+#[derive(Clone, Copy, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Plane {
+    pub normal: mint::Vector3<f32>,
+    pub offset: f32,
+}
+
+/// Shape of the emission of a punctual light.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum LightKind {
+    /// Emits uniformly in all directions.
+    Point,
+    /// Emits in a cone around the direction, fading out
+    /// between the inner and the outer angles (in radians).
+    Spot {
+        inner_cone_angle: f32,
+        outer_cone_angle: f32,
+    },
+}
+
+/// Punctual light source, as defined by `KHR_lights_punctual`.
+///
+/// A scene with few lights traces a shadow ray to each of them.
+/// With more, the lights are drawn in proportion to their power,
+/// and resampled by their unshadowed contribution to the shaded point,
+/// so that a single shadow ray goes to the light that matters the most.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Light {
+    pub kind: LightKind,
+    pub position: mint::Vector3<f32>,
+    /// Direction the light is pointing to. Only used by spot lights.
+    pub direction: mint::Vector3<f32>,
+    /// Linear color of the light.
+    pub color: [f32; 3],
+    /// Luminous intensity, in candela.
+    pub intensity: f32,
+    /// Distance at which the light contribution reaches zero.
+    pub range: f32,
+}
+
+/// A model placed in the world. Scenes are composed of any number
+/// of objects, each with its own model and world transform.
+#[derive(Clone, Copy, Debug, PartialEq)]
 pub struct Object {
     pub model: blade_asset::Handle<Model>,
     pub transform: blade_graphics::Transform,
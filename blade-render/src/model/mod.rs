@@ -1,25 +1,86 @@
 use std::{
     borrow::Cow,
-    collections::hash_map::{Entry, HashMap},
+    collections::{
+        hash_map::{Entry, HashMap},
+        HashSet,
+    },
     fmt, hash, mem,
     ops::Range,
     ptr, str,
     sync::{Arc, Mutex},
 };
 
+pub(crate) mod obj;
+
 const PRELOAD_TEXTURES: bool = false;
+/// Index of refraction of the materials that don't specify it, per glTF.
+const DEFAULT_IOR: f32 = 1.5;
+/// Defaults of `KHR_materials_iridescence`.
+const DEFAULT_IRIDESCENCE_IOR: f32 = 1.3;
+const DEFAULT_IRIDESCENCE_THICKNESS: f32 = 400.0;
 
 const META_BASE_COLOR: crate::texture::Meta = crate::texture::Meta {
     format: blade_graphics::TextureFormat::Bc1UnormSrgb,
     generate_mips: true,
     y_flip: false,
+    max_dimension: None,
+    decode_srgb: false,
 };
 const META_NORMAL: crate::texture::Meta = crate::texture::Meta {
     //Note: "texpresso" doesn't know how to produce signed normalized
     format: blade_graphics::TextureFormat::Bc5Unorm,
     generate_mips: false,
     y_flip: false,
+    max_dimension: None,
+    decode_srgb: false,
+};
+const META_OCCLUSION: crate::texture::Meta = crate::texture::Meta {
+    format: blade_graphics::TextureFormat::Bc4Unorm,
+    generate_mips: true,
+    y_flip: false,
+    max_dimension: None,
+    decode_srgb: false,
 };
+/// Material properties packed into the channels, like the metallic-roughness.
+const META_PACKED: crate::texture::Meta = crate::texture::Meta {
+    format: blade_graphics::TextureFormat::Bc3Unorm,
+    generate_mips: true,
+    y_flip: false,
+    max_dimension: None,
+    decode_srgb: false,
+};
+
+/// Adapt the texture meta of a material slot to the color space of the data.
+fn color_space_meta(base: crate::texture::Meta, space: ColorSpace) -> crate::texture::Meta {
+    use blade_graphics::TextureFormat as Tf;
+    let srgb_format = matches!(
+        base.format,
+        Tf::Bc1UnormSrgb | Tf::Bc2UnormSrgb | Tf::Bc3UnormSrgb
+    );
+    match (space, srgb_format) {
+        (ColorSpace::Linear, true) => crate::texture::Meta {
+            format: match base.format {
+                Tf::Bc1UnormSrgb => Tf::Bc1Unorm,
+                Tf::Bc2UnormSrgb => Tf::Bc2Unorm,
+                _ => Tf::Bc3Unorm,
+            },
+            ..base
+        },
+        (ColorSpace::Srgb, false) => crate::texture::Meta {
+            decode_srgb: true,
+            ..base
+        },
+        (ColorSpace::Linear, false) | (ColorSpace::Srgb, true) => base,
+    }
+}
+
+/// The occlusion texture only needs a single channel, unless it's not the red one.
+fn occlusion_meta(channel: Channel) -> crate::texture::Meta {
+    match channel {
+        Channel::R => META_OCCLUSION,
+        Channel::G | Channel::B | Channel::A => META_PACKED,
+    }
+}
 
 fn pack4x8snorm(v: [f32; 4]) -> u32 {
     v.iter().rev().fold(0u32, |u, f| {
@@ -38,7 +99,100 @@ pub struct Geometry {
     pub index_type: Option<blade_graphics::IndexType>,
     pub triangle_count: u32,
     pub transform: blade_graphics::Transform,
+    /// Bounds of the vertices, before the `transform`.
+    pub bounds: crate::BoundingBox,
     pub material_index: usize,
+    /// Index of the glTF node this geometry was produced from.
+    pub node_index: usize,
+}
+
+/// Vertices and indices of a geometry, as read back from the GPU.
+#[derive(Clone, Debug, Default)]
+pub struct GeometryData {
+    pub vertices: Vec<crate::Vertex>,
+    /// Empty if the geometry isn't indexed, with the vertices
+    /// forming the triangles in order.
+    pub indices: Vec<u32>,
+}
+
+/// Node of the glTF hierarchy, in its rest pose.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Node {
+    pub parent: Option<usize>,
+    pub translation: [f32; 3],
+    /// Rotation quaternion, in XYZW order.
+    pub rotation: [f32; 4],
+    pub scale: [f32; 3],
+}
+
+/// Property of a node driven by an animation channel.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum AnimationPath {
+    Translation,
+    Rotation,
+    Scale,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Interpolation {
+    Linear,
+    Step,
+    CubicSpline,
+}
+
+/// Keyframes of a single property of a node.
+#[derive(Clone, Debug)]
+pub struct AnimationChannel {
+    pub node_index: usize,
+    pub path: AnimationPath,
+    pub interpolation: Interpolation,
+    /// Time of each keyframe, in seconds.
+    pub times: Vec<f32>,
+    /// Components of the keyframes, three or four per value. With the
+    /// cubic spline interpolation, every keyframe has the in-tangent,
+    /// the value, and the out-tangent, in this order.
+    pub values: Vec<f32>,
+}
+
+/// Animation clip, with the node transforms only.
+/// Skins and morph targets are not supported.
+#[derive(Clone, Debug)]
+pub struct Animation {
+    pub name: String,
+    pub channels: Vec<AnimationChannel>,
+    /// Time of the last keyframe, in seconds.
+    pub duration: f32,
+}
+
+/// Transform of the texture coordinates, from `KHR_texture_transform`.
+///
+/// The coordinates are scaled first, then rotated, and then offset.
+#[derive(blade_macros::Flat, Clone, Copy, Debug, PartialEq)]
+pub struct TextureTransform {
+    pub offset: [f32; 2],
+    /// Counter-clockwise rotation, in radians.
+    pub rotation: f32,
+    pub scale: [f32; 2],
+}
+
+impl Default for TextureTransform {
+    fn default() -> Self {
+        Self {
+            offset: [0.0; 2],
+            rotation: 0.0,
+            scale: [1.0; 2],
+        }
+    }
+}
+
+impl TextureTransform {
+    /// Get the affine matrix applied to the texture coordinates,
+    /// as 3 columns of 2 rows.
+    pub fn to_matrix(&self) -> [[f32; 2]; 3] {
+        let (sin, cos) = self.rotation.sin_cos();
+        let [sx, sy] = self.scale;
+        [[cos * sx, -sin * sx], [sin * sy, cos * sy], self.offset]
+    }
 }
 
 //TODO: move out into a separate asset type
@@ -48,17 +202,203 @@ pub struct Material {
     pub normal_texture: Option<blade_asset::Handle<crate::Texture>>,
     pub normal_scale: f32,
     pub transparent: bool,
+    /// Both sides of the surface are shaded, with the normal
+    /// facing the incoming ray.
+    pub double_sided: bool,
+    /// UV set used by the base color texture.
+    pub base_color_tex_coord: u32,
+    pub base_color_transform: TextureTransform,
+    /// UV set used by the normal texture.
+    pub normal_tex_coord: u32,
+    pub normal_transform: TextureTransform,
+    /// Baked ambient occlusion, in the channel given by `channel_map`.
+    pub occlusion_texture: Option<blade_asset::Handle<crate::Texture>>,
+    pub occlusion_strength: f32,
+    /// UV set used by the occlusion texture.
+    pub occlusion_tex_coord: u32,
+    pub occlusion_transform: TextureTransform,
+    /// Fraction of the light passing through the surface,
+    /// from `KHR_materials_transmission`.
+    pub transmission: f32,
+    /// Index of refraction, from `KHR_materials_ior`.
+    pub ior: f32,
+    /// Spread of the index of refraction over the wavelengths,
+    /// defined as 20 over the Abbe number, from `KHR_materials_dispersion`.
+    /// Only has an effect with `Renderer::set_dispersion`.
+    pub dispersion: f32,
+    /// Metalness factor of the glTF PBR model,
+    /// multiplied by the `metallic_roughness_texture`.
+    pub metallic: f32,
+    /// Roughness factor, multiplied by the `metallic_roughness_texture`.
+    /// Metals smoother than `MIRROR_ROUGHNESS` are traced as mirrors.
+    pub roughness: f32,
+    /// Roughness and metalness, in the channels given by `channel_map`.
+    pub metallic_roughness_texture: Option<blade_asset::Handle<crate::Texture>>,
+    /// UV set used by the metallic-roughness texture.
+    pub metallic_roughness_tex_coord: u32,
+    pub metallic_roughness_transform: TextureTransform,
+    /// Channels of the occlusion and the metallic-roughness textures.
+    pub channel_map: ChannelMap,
+    /// Strength of the thin-film interference on top of the reflections,
+    /// from `KHR_materials_iridescence`. Zero if there is no film.
+    /// Only affects the mirror-like surfaces.
+    pub iridescence: f32,
+    /// Index of refraction of the film.
+    pub iridescence_ior: f32,
+    /// Thickness of the film, in nanometers.
+    pub iridescence_thickness: f32,
+    /// Color of the velvet-like layer on top of the surface, like on cloth,
+    /// from `KHR_materials_sheen`. Black if there is no sheen.
+    pub sheen_color: [f32; 3],
+    pub sheen_roughness: f32,
+    /// Mean distance the light travels under the surface between scattering
+    /// events, for the opaque materials with `KHR_materials_volume`,
+    /// taken from its attenuation distance, or the thickness if unlimited.
+    /// Zero if there is no subsurface scattering.
+    pub subsurface_distance: f32,
+    /// Color the light takes at each scattering event under the surface,
+    /// from the attenuation color of the volume.
+    pub subsurface_color: [f32; 3],
+}
+
+/// Roughness under which a metallic surface reflects like a perfect mirror.
+pub const MIRROR_ROUGHNESS: f32 = 0.05;
+
+/// Summary of the contents of a loaded model.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct ModelStats {
+    pub geometry_count: usize,
+    pub vertex_count: u64,
+    pub triangle_count: u64,
+    pub material_count: usize,
+    /// Number of distinct textures referenced by the materials.
+    pub texture_count: usize,
+    /// GPU memory of the vertex, index, and transform buffers.
+    pub buffer_bytes: u64,
+    /// GPU memory of the bottom-level acceleration structure.
+    pub acceleration_structure_bytes: u64,
+    /// GPU memory of the referenced textures.
+    pub texture_bytes: u64,
 }
 
 pub struct Model {
     pub name: String,
     pub winding: f32,
+    /// Geometries in depth-first order of the glTF scene nodes,
+    /// with the primitives of each node following each other.
+    /// Every primitive has its own geometry and material, with the glTF
+    /// default material appended for the ones that don't specify it.
+    /// Primitives that aren't triangles are skipped.
     pub geometries: Vec<Geometry>,
     pub materials: Vec<Material>,
+    /// Bounds of all the geometries, in model space.
+    pub bounds: crate::BoundingBox,
+    /// Vertices of all the geometries. The shading fetches their attributes
+    /// on every hit, and the positions are read by the BLAS builds
+    /// and the wireframe overlay.
     pub vertex_buffer: blade_graphics::Buffer,
+    /// Indices of all the geometries, used the same way as the vertices.
     pub index_buffer: blade_graphics::Buffer,
+    /// Transforms of the geometries, only read by the BLAS build.
     pub transform_buffer: blade_graphics::Buffer,
+    /// Loaded with `Meta::geometry_only`. The buffers above are released
+    /// after the BLAS is built, and are left as defaults.
+    pub geometry_only: bool,
     pub acceleration_structure: blade_graphics::AccelerationStructure,
+    pub acceleration_structure_size: u64,
+    /// Punctual lights, in model space.
+    pub lights: Vec<crate::Light>,
+    /// All the nodes of the glTF document, in its order.
+    pub nodes: Vec<Node>,
+    pub animations: Vec<Animation>,
+    fingerprint: u64,
+}
+
+impl Model {
+    /// Stable hash of the geometry and the materials, as loaded.
+    ///
+    /// It covers the vertices, the indices, and the transforms of the geometries,
+    /// in the order of the glTF scene nodes, as well as all the material parameters
+    /// and the texture references, in the order of the glTF materials.
+    /// The textures are identified by their paths, or by the contents if embedded.
+    /// Doesn't depend on the cache, the threads, or the platform.
+    pub fn fingerprint(&self) -> u64 {
+        self.fingerprint
+    }
+
+    /// Gather the statistics of this model.
+    ///
+    /// The textures are looked up in the given manager, so they
+    /// need to be loaded.
+    pub fn stats(&self, textures: &blade_asset::AssetManager<crate::texture::Baker>) -> ModelStats {
+        let mut stats = ModelStats {
+            geometry_count: self.geometries.len(),
+            material_count: self.materials.len(),
+            acceleration_structure_bytes: self.acceleration_structure_size,
+            ..Default::default()
+        };
+        for geometry in self.geometries.iter() {
+            stats.vertex_count += (geometry.vertex_range.end - geometry.vertex_range.start) as u64;
+            stats.triangle_count += geometry.triangle_count as u64;
+            if geometry.index_type.is_some() {
+                stats.buffer_bytes += geometry.triangle_count as u64 * 3 * 4;
+            }
+        }
+        stats.buffer_bytes += stats.vertex_count * mem::size_of::<crate::Vertex>() as u64
+            + (self.geometries.len() * mem::size_of::<blade_graphics::Transform>()) as u64;
+        if self.geometry_only {
+            stats.buffer_bytes = 0;
+        }
+
+        let texture_handles = self
+            .materials
+            .iter()
+            .flat_map(|m| {
+                [
+                    m.base_color_texture,
+                    m.normal_texture,
+                    m.occlusion_texture,
+                    m.metallic_roughness_texture,
+                ]
+            })
+            .flatten()
+            .collect::<HashSet<_>>();
+        stats.texture_count = texture_handles.len();
+        stats.texture_bytes = texture_handles
+            .into_iter()
+            .map(|handle| textures[handle].data_size)
+            .sum();
+        stats
+    }
+
+    /// Meshes of the bottom-level acceleration structure, with the transforms
+    /// of the geometries taken from the given buffer, one after another.
+    pub(crate) fn acceleration_structure_meshes(
+        &self,
+        transform_buffer: blade_graphics::Buffer,
+    ) -> Vec<blade_graphics::AccelerationStructureMesh> {
+        let vertex_stride = mem::size_of::<crate::Vertex>() as u32;
+        self.geometries
+            .iter()
+            .enumerate()
+            .map(
+                |(index, geometry)| blade_graphics::AccelerationStructureMesh {
+                    vertex_data: self
+                        .vertex_buffer
+                        .at(geometry.vertex_range.start as u64 * vertex_stride as u64),
+                    vertex_format: blade_graphics::VertexFormat::F32Vec3,
+                    vertex_stride,
+                    vertex_count: geometry.vertex_range.end - geometry.vertex_range.start,
+                    index_data: self.index_buffer.at(geometry.index_offset),
+                    index_type: geometry.index_type,
+                    triangle_count: geometry.triangle_count,
+                    transform_data: transform_buffer
+                        .at((index * mem::size_of::<blade_graphics::Transform>()) as u64),
+                    is_opaque: !self.materials[geometry.material_index].transparent,
+                },
+            )
+            .collect()
+    }
 }
 
 #[derive(blade_macros::Flat, Default)]
@@ -76,6 +416,30 @@ struct CookedMaterial<'a> {
     normal: TextureReference<'a>,
     normal_scale: f32,
     transparent: bool,
+    double_sided: bool,
+    base_color_tex_coord: u32,
+    base_color_transform: TextureTransform,
+    normal_tex_coord: u32,
+    normal_transform: TextureTransform,
+    occlusion: TextureReference<'a>,
+    occlusion_strength: f32,
+    occlusion_tex_coord: u32,
+    occlusion_transform: TextureTransform,
+    transmission: f32,
+    ior: f32,
+    dispersion: f32,
+    metallic: f32,
+    roughness: f32,
+    iridescence: f32,
+    iridescence_ior: f32,
+    iridescence_thickness: f32,
+    metallic_roughness: TextureReference<'a>,
+    metallic_roughness_tex_coord: u32,
+    metallic_roughness_transform: TextureTransform,
+    sheen_color: [f32; 3],
+    sheen_roughness: f32,
+    subsurface_distance: f32,
+    subsurface_color: [f32; 3],
 }
 
 #[derive(blade_macros::Flat)]
@@ -85,6 +449,162 @@ struct CookedGeometry<'a> {
     indices: Cow<'a, [u32]>,
     transform: [f32; 12],
     material_index: u32,
+    node_index: u32,
+}
+
+/// FNV-1a hash, which unlike `DefaultHasher` is stable across Rust versions.
+struct Fingerprint(u64);
+
+impl Fingerprint {
+    fn new() -> Self {
+        Self(0xcbf29ce484222325)
+    }
+
+    fn write(&mut self, bytes: &[u8]) {
+        for &byte in bytes {
+            self.0 ^= byte as u64;
+            self.0 = self.0.wrapping_mul(0x100000001b3);
+        }
+    }
+
+    /// Write a slice prefixed by its length, so that the
+    /// boundaries between the slices are hashed as well.
+    fn write_slice<T: bytemuck::Pod>(&mut self, data: &[T]) {
+        self.write(&(data.len() as u64).to_le_bytes());
+        self.write(bytemuck::cast_slice(data));
+    }
+
+    fn write_transform(&mut self, transform: &TextureTransform) {
+        self.write_slice(&transform.offset);
+        self.write_slice(&[transform.rotation]);
+        self.write_slice(&transform.scale);
+    }
+}
+
+#[derive(blade_macros::Flat)]
+struct CookedNode {
+    /// `!0` for the roots.
+    parent: u32,
+    translation: [f32; 3],
+    rotation: [f32; 4],
+    scale: [f32; 3],
+}
+
+#[derive(blade_macros::Flat)]
+struct CookedChannel<'a> {
+    node_index: u32,
+    /// Zero for translation, one for rotation, two for scale.
+    path: u32,
+    /// Zero for linear, one for step, two for cubic spline.
+    interpolation: u32,
+    times: Cow<'a, [f32]>,
+    values: Cow<'a, [f32]>,
+}
+
+#[derive(blade_macros::Flat)]
+struct CookedAnimation<'a> {
+    name: Cow<'a, [u8]>,
+    channels: Vec<CookedChannel<'a>>,
+}
+
+#[derive(blade_macros::Flat)]
+struct CookedLight {
+    /// Zero for point lights, one for spot lights.
+    kind: u32,
+    position: [f32; 3],
+    direction: [f32; 3],
+    color: [f32; 3],
+    intensity: f32,
+    range: f32,
+    inner_cone_angle: f32,
+    outer_cone_angle: f32,
+}
+
+#[cfg(feature = "asset")]
+impl CookedMaterial<'_> {
+    /// Material used by the primitives that don't specify one,
+    /// as defined by the glTF spec.
+    fn default_gltf() -> Self {
+        let no_texture = || TextureReference {
+            source_index: !0,
+            ..Default::default()
+        };
+        Self {
+            base_color: no_texture(),
+            base_color_factor: [1.0; 4],
+            normal: no_texture(),
+            normal_scale: 0.0,
+            transparent: false,
+            double_sided: false,
+            base_color_tex_coord: 0,
+            base_color_transform: TextureTransform::default(),
+            normal_tex_coord: 0,
+            normal_transform: TextureTransform::default(),
+            occlusion: no_texture(),
+            occlusion_strength: 0.0,
+            occlusion_tex_coord: 0,
+            occlusion_transform: TextureTransform::default(),
+            transmission: 0.0,
+            ior: DEFAULT_IOR,
+            dispersion: 0.0,
+            metallic: 1.0,
+            roughness: 1.0,
+            iridescence: 0.0,
+            iridescence_ior: DEFAULT_IRIDESCENCE_IOR,
+            iridescence_thickness: DEFAULT_IRIDESCENCE_THICKNESS,
+            metallic_roughness: no_texture(),
+            metallic_roughness_tex_coord: 0,
+            metallic_roughness_transform: TextureTransform::default(),
+            sheen_color: [0.0; 3],
+            sheen_roughness: 0.0,
+            subsurface_distance: 0.0,
+            subsurface_color: [1.0; 3],
+        }
+    }
+}
+
+#[cfg(feature = "asset")]
+fn check_tex_coord(set: u32) -> u32 {
+    // `crate::Vertex` only carries 2 UV sets
+    if set < 2 {
+        set
+    } else {
+        log::warn!("UV set {set} is not supported, falling back to 0");
+        0
+    }
+}
+
+/// Parse the `KHR_texture_transform` extension of a texture reference,
+/// returning the transform and the UV set, which the extension can override.
+#[cfg(feature = "asset")]
+fn parse_texture_transform(
+    extension: Option<&gltf::json::Value>,
+    tex_coord: u32,
+) -> (TextureTransform, u32) {
+    let ext = match extension {
+        Some(ext) => ext,
+        None => return (TextureTransform::default(), check_tex_coord(tex_coord)),
+    };
+    let vec2 = |name: &str, default: [f32; 2]| match ext.get(name).and_then(|v| v.as_array()) {
+        Some(array) if array.len() == 2 => [
+            array[0].as_f64().unwrap_or(default[0] as f64) as f32,
+            array[1].as_f64().unwrap_or(default[1] as f64) as f32,
+        ],
+        _ => default,
+    };
+    let transform = TextureTransform {
+        offset: vec2("offset", [0.0; 2]),
+        rotation: ext
+            .get("rotation")
+            .and_then(|v| v.as_f64())
+            .map_or(0.0, |v| v as f32),
+        scale: vec2("scale", [1.0; 2]),
+    };
+    let set = ext
+        .get("texCoord")
+        .and_then(|v| v.as_u64())
+        .map_or(tex_coord, |v| v as u32);
+    (transform, check_tex_coord(set))
 }
 
 #[derive(Clone, PartialEq)]
@@ -93,6 +613,7 @@ struct GltfVertex {
     normal: [f32; 3],
     tangent: [f32; 4],
     tex_coords: [f32; 2],
+    tex_coords1: [f32; 2],
 }
 impl Default for GltfVertex {
     fn default() -> Self {
@@ -101,6 +622,7 @@ impl Default for GltfVertex {
             normal: [0.0, 1.0, 0.0],
             tangent: [1.0, 0.0, 0.0, 0.0],
             tex_coords: [0.0; 2],
+            tex_coords1: [0.0; 2],
         }
     }
 }
@@ -119,6 +641,9 @@ impl hash::Hash for GltfVertex {
         for f in self.tex_coords.iter() {
             f.to_bits().hash(state);
         }
+        for f in self.tex_coords1.iter() {
+            f.to_bits().hash(state);
+        }
     }
 }
 
@@ -147,6 +672,45 @@ impl mikktspace::Geometry for FlattenedGeometry {
 }
 #[cfg(feature = "asset")]
 impl FlattenedGeometry {
+    /// Compute the normals of the geometry that doesn't provide them.
+    /// Smooth normals are the area-weighted sums of the face normals
+    /// around each vertex position, while flat ones are per face.
+    #[profiling::function]
+    fn generate_normals(&mut self, flat: bool, winding: f32) {
+        let face_normals = self
+            .0
+            .chunks(3)
+            .map(|tri| {
+                let a = glam::Vec3::from(tri[0].position);
+                let b = glam::Vec3::from(tri[1].position);
+                let c = glam::Vec3::from(tri[2].position);
+                // the length is twice the area of the triangle
+                winding * (b - a).cross(c - a)
+            })
+            .collect::<Vec<_>>();
+        let key = |v: &GltfVertex| v.position.map(f32::to_bits);
+        let mut vertex_normals = HashMap::new();
+        if !flat {
+            for (tri, &fnormal) in self.0.chunks(3).zip(face_normals.iter()) {
+                for v in tri {
+                    *vertex_normals.entry(key(v)).or_insert(glam::Vec3::ZERO) += fnormal;
+                }
+            }
+        }
+        for (tri, &fnormal) in self.0.chunks_mut(3).zip(face_normals.iter()) {
+            for v in tri {
+                let normal = if flat {
+                    fnormal
+                } else {
+                    vertex_normals[&key(v)]
+                };
+                if let Some(n) = normal.try_normalize() {
+                    v.normal = n.into();
+                }
+            }
+        }
+    }
+
     #[profiling::function]
     fn reconstruct_indices(self) -> (Vec<u32>, Vec<crate::Vertex>) {
         let mut indices = Vec::with_capacity(self.0.len());
@@ -164,6 +728,8 @@ impl FlattenedGeometry {
                         tex_coords: v.tex_coords,
                         normal: encode_normal(v.normal),
                         tangent: encode_normal([t[0], t[1], t[2]]),
+                        tex_coords1: v.tex_coords1,
+                        ..Default::default()
                     });
                     *e.insert(i)
                 }
@@ -177,10 +743,83 @@ impl FlattenedGeometry {
 
 #[derive(blade_macros::Flat)]
 pub struct CookedModel<'a> {
-    name: &'a [u8],
+    name: Cow<'a, [u8]>,
     winding: f32,
+    /// Zero if unlimited.
+    max_texture_dimension: u32,
+    geometry_only: bool,
+    /// Packed with `ChannelMap::pack`.
+    channel_map: u32,
+    /// Packed with `ColorSpaces::pack`.
+    color_spaces: u32,
     materials: Vec<CookedMaterial<'a>>,
     geometries: Vec<CookedGeometry<'a>>,
+    lights: Vec<CookedLight>,
+    nodes: Vec<CookedNode>,
+    animations: Vec<CookedAnimation<'a>>,
+}
+
+impl CookedModel<'_> {
+    fn fingerprint(&self) -> u64 {
+        let mut fp = Fingerprint::new();
+        fp.write_slice(&[
+            self.geometries.len() as u32,
+            self.materials.len() as u32,
+            self.channel_map,
+            self.color_spaces,
+        ]);
+        for geometry in self.geometries.iter() {
+            fp.write_slice(&geometry.vertices);
+            fp.write_slice(&geometry.indices);
+            fp.write_slice(&geometry.transform);
+            fp.write_slice(&[geometry.material_index]);
+        }
+        for material in self.materials.iter() {
+            for texture in [
+                &material.base_color,
+                &material.normal,
+                &material.occlusion,
+                &material.metallic_roughness,
+            ] {
+                fp.write_slice(&texture.path);
+                fp.write_slice(&texture.embedded_data);
+            }
+            for transform in [
+                &material.base_color_transform,
+                &material.normal_transform,
+                &material.occlusion_transform,
+                &material.metallic_roughness_transform,
+            ] {
+                fp.write_transform(transform);
+            }
+            fp.write_slice(&material.base_color_factor);
+            fp.write_slice(&material.sheen_color);
+            fp.write_slice(&material.subsurface_color);
+            fp.write_slice(&[
+                material.normal_scale,
+                material.occlusion_strength,
+                material.transmission,
+                material.ior,
+                material.dispersion,
+                material.metallic,
+                material.roughness,
+                material.iridescence,
+                material.iridescence_ior,
+                material.iridescence_thickness,
+                material.sheen_roughness,
+                material.subsurface_distance,
+            ]);
+            fp.write_slice(&[
+                material.transparent as u32,
+                material.double_sided as u32,
+                material.base_color_tex_coord,
+                material.normal_tex_coord,
+                material.occlusion_tex_coord,
+                material.metallic_roughness_tex_coord,
+            ]);
+        }
+        fp.0
+    }
 }
 
 #[cfg(feature = "asset")]
@@ -191,10 +830,46 @@ impl CookedModel<'_> {
         parent_transform: glam::Mat4,
         data_buffers: &[Vec<u8>],
         flattened_geos: &mut Vec<FlattenedGeometry>,
+        default_material: &mut Option<u32>,
+        flat_normals: bool,
     ) {
         let local_transform = glam::Mat4::from_cols_array_2d(&g_node.transform().matrix());
         let global_transform = parent_transform * local_transform;
 
+        if let Some(g_light) = g_node.light() {
+            use gltf::khr_lights_punctual::Kind as Lk;
+            let cone = match g_light.kind() {
+                Lk::Point => Some((0, 0.0, 0.0)),
+                Lk::Spot {
+                    inner_cone_angle,
+                    outer_cone_angle,
+                } => Some((1, inner_cone_angle, outer_cone_angle)),
+                Lk::Directional => {
+                    log::warn!(
+                        "Skipping directional light '{}', use the environment map instead",
+                        g_light.name().unwrap_or("")
+                    );
+                    None
+                }
+            };
+            if let Some((kind, inner_cone_angle, outer_cone_angle)) = cone {
+                // lights point down the local -Z axis
+                let direction = global_transform
+                    .transform_vector3(glam::Vec3::NEG_Z)
+                    .normalize();
+                self.lights.push(CookedLight {
+                    kind,
+                    position: global_transform.w_axis.truncate().into(),
+                    direction: direction.into(),
+                    color: g_light.color(),
+                    intensity: g_light.intensity(),
+                    range: g_light.range().unwrap_or(f32::INFINITY),
+                    inner_cone_angle,
+                    outer_cone_angle,
+                });
+            }
+        }
+
         if let Some(g_mesh) = g_node.mesh() {
             let name = g_node.name().unwrap_or("");
             let col_matrix = mint::ColumnMatrix3x4 {
@@ -217,16 +892,14 @@ impl CookedModel<'_> {
                 }
                 let material_index = match g_primitive.material().index() {
                     Some(index) => index as u32,
-                    None => {
-                        log::warn!(
-                            "Skipping primitive '{}'[{}] for having default material",
-                            name,
-                            prim_index
-                        );
-                        continue;
-                    }
+                    None => *default_material.get_or_insert_with(|| {
+                        self.materials.push(CookedMaterial::default_gltf());
+                        self.materials.len() as u32 - 1
+                    }),
                 };
 
+                // The reader resolves sparse accessors: the indexed values are
+                // written over the base view, or over zeros if there is none.
                 let reader = g_primitive.reader(|buffer| Some(&data_buffers[buffer.index()]));
                 let vertex_count = g_primitive.get(&gltf::Semantic::Positions).unwrap().count();
 
@@ -251,6 +924,12 @@ impl CookedModel<'_> {
                     } else {
                         log::warn!("No tex coords in {name}");
                     }
+                    if let Some(iter) = reader.read_tex_coords(1) {
+                        for (v, tc) in pre_vertices.iter_mut().zip(iter.into_f32()) {
+                            v.tex_coords1 = tc;
+                        }
+                    }
+                    let has_normals = reader.read_normals().is_some();
                     if let Some(iter) = reader.read_normals() {
                         assert_eq!(
                             pre_vertices.len(),
@@ -261,19 +940,22 @@ impl CookedModel<'_> {
                             v.normal = normal;
                             assert_ne!(encode_normal(normal), 0);
                         }
-                    } else {
-                        log::warn!("No normals in {name}");
                     }
 
                     // Untangle from the index buffer
-                    match reader.read_indices() {
+                    let mut fg = match reader.read_indices() {
                         Some(read) => FlattenedGeometry(
                             read.into_u32()
                                 .map(|i| pre_vertices[i as usize].clone())
                                 .collect(),
                         ),
                         None => FlattenedGeometry(pre_vertices.into_boxed_slice()),
+                    };
+                    if !has_normals {
+                        log::info!("Generating normals for {name}");
+                        fg.generate_normals(flat_normals, self.winding);
                     }
+                    fg
                 });
 
                 self.geometries.push(CookedGeometry {
@@ -282,16 +964,30 @@ impl CookedModel<'_> {
                     indices: Cow::Borrowed(&[]),
                     transform,
                     material_index,
+                    node_index: g_node.index() as u32,
                 });
             }
         }
 
         for child in g_node.children() {
-            self.populate_gltf(child, global_transform, data_buffers, flattened_geos);
+            self.populate_gltf(
+                child,
+                global_transform,
+                data_buffers,
+                flattened_geos,
+                default_material,
+                flat_normals,
+            );
         }
     }
 }
 
+/// Vertex winding order of the front faces of a model.
+///
+/// Defines the direction of the geometric normals, which decides
+/// the side that is shaded for the single-sided materials.
+/// glTF assets are counter-clockwise, and the nodes with a mirroring
+/// transform flip the winding on top of this.
 #[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
 pub enum FrontFace {
     Clockwise,
@@ -303,10 +999,140 @@ impl Default for FrontFace {
     }
 }
 
+/// Encoding of the color values in a texture.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum ColorSpace {
+    Linear,
+    Srgb,
+}
+
+/// Color spaces of the material textures.
+///
+/// The default follows glTF: the base color is sRGB-encoded, while the
+/// normal, occlusion, and metallic-roughness textures hold linear data.
+/// The textures are decoded on load, so that the shaders always
+/// see the linear values.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct ColorSpaces {
+    pub base_color: ColorSpace,
+    pub normal: ColorSpace,
+    pub occlusion: ColorSpace,
+    pub metallic_roughness: ColorSpace,
+}
+impl Default for ColorSpaces {
+    fn default() -> Self {
+        Self {
+            base_color: ColorSpace::Srgb,
+            normal: ColorSpace::Linear,
+            occlusion: ColorSpace::Linear,
+            metallic_roughness: ColorSpace::Linear,
+        }
+    }
+}
+
+impl ColorSpaces {
+    /// Pack into a bit per texture, set for sRGB, in the order of the fields.
+    fn pack(&self) -> u32 {
+        [
+            self.base_color,
+            self.normal,
+            self.occlusion,
+            self.metallic_roughness,
+        ]
+        .iter()
+        .enumerate()
+        .fold(0, |packed, (i, &space)| packed | (space as u32) << i)
+    }
+
+    fn unpack(packed: u32) -> Self {
+        let space = |bit: u32| match (packed >> bit) & 1 {
+            0 => ColorSpace::Linear,
+            _ => ColorSpace::Srgb,
+        };
+        Self {
+            base_color: space(0),
+            normal: space(1),
+            occlusion: space(2),
+            metallic_roughness: space(3),
+        }
+    }
+}
+
+/// Channel of a texture.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Channel {
+    R,
+    G,
+    B,
+    A,
+}
+
+/// Channels holding the material properties in the packed textures.
+///
+/// The default follows glTF: occlusion in R of its texture, roughness in G
+/// and metalness in B of the metallic-roughness texture. Both textures are
+/// often the same ORM texture.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct ChannelMap {
+    pub occlusion: Channel,
+    pub roughness: Channel,
+    pub metallic: Channel,
+}
+impl Default for ChannelMap {
+    fn default() -> Self {
+        Self {
+            occlusion: Channel::R,
+            roughness: Channel::G,
+            metallic: Channel::B,
+        }
+    }
+}
+
+impl ChannelMap {
+    /// Pack into 2 bits per property, in the order of the fields.
+    pub(crate) fn pack(&self) -> u32 {
+        self.occlusion as u32 | (self.roughness as u32) << 2 | (self.metallic as u32) << 4
+    }
+
+    fn unpack(packed: u32) -> Self {
+        let channel = |shift: u32| match (packed >> shift) & 3 {
+            0 => Channel::R,
+            1 => Channel::G,
+            2 => Channel::B,
+            _ => Channel::A,
+        };
+        Self {
+            occlusion: channel(0),
+            roughness: channel(2),
+            metallic: channel(4),
+        }
+    }
+}
+
 #[derive(Clone, Debug, Default, PartialEq, Eq, Hash)]
 pub struct Meta {
     pub generate_tangents: bool,
+    /// Winding of the front faces, for the assets exported
+    /// with the opposite convention to glTF.
     pub front_face: FrontFace,
+    /// Generate flat normals for the geometries without the `NORMAL`
+    /// attribute, instead of the smooth ones.
+    pub flat_normals: bool,
+    /// Maximum size of either side of the model textures.
+    /// Larger textures are downscaled on load.
+    /// Defaults to the maximum 2D texture size of the device.
+    pub max_texture_dimension: Option<u32>,
+    /// Only keep the BLAS, for the models that are traced but never shaded,
+    /// like the shadow casters of a geometry-only pass. The vertex, index, and
+    /// transform buffers are released as soon as the BLAS is built, and the
+    /// textures aren't loaded. Such models can't be rendered by `Renderer`.
+    pub geometry_only: bool,
+    /// Channels of the packed material textures, for the assets
+    /// that don't follow the glTF convention.
+    pub channel_map: ChannelMap,
+    /// Color spaces of the material textures, for the assets
+    /// that mis-tag them.
+    pub color_spaces: ColorSpaces,
 }
 
 impl fmt::Display for Meta {
@@ -324,9 +1150,12 @@ struct Transfer {
 
 #[derive(Debug)]
 struct BlasConstruct {
+    name: String,
     meshes: Vec<blade_graphics::AccelerationStructureMesh>,
     scratch: blade_graphics::Buffer,
     dst: blade_graphics::AccelerationStructure,
+    /// Buffers that aren't needed after the build.
+    release: Vec<blade_graphics::Buffer>,
 }
 
 #[derive(Default)]
@@ -358,9 +1187,24 @@ impl TextureReference<'_> {
     }
 }
 
+/// Label of the pass building the BLAS of a model,
+/// which is also the name of its entry in the GPU timings.
+pub(crate) fn blas_pass_label(model_name: &str) -> String {
+    format!("BLAS {}", model_name)
+}
+
+/// Model uploads submitted separately from the frames,
+/// with the resources kept alive until the GPU is done.
+struct Submission {
+    encoder: blade_graphics::CommandEncoder,
+    sync_point: blade_graphics::SyncPoint,
+    temp_buffers: Vec<blade_graphics::Buffer>,
+}
+
 pub struct Baker {
     gpu_context: Arc<blade_graphics::Context>,
     pending_operations: Mutex<PendingOperations>,
+    submissions: Mutex<Vec<Submission>>,
     //TODO: change to asset materials
     asset_textures: Arc<blade_asset::AssetManager<crate::texture::Baker>>,
 }
@@ -373,6 +1217,7 @@ impl Baker {
         Self {
             gpu_context: Arc::clone(gpu_context),
             pending_operations: Mutex::new(PendingOperations::default()),
+            submissions: Mutex::new(Vec::new()),
             asset_textures: Arc::clone(asset_textures),
         }
     }
@@ -383,6 +1228,160 @@ impl Baker {
         temp_buffers: &mut Vec<blade_graphics::Buffer>,
     ) {
         let mut pending_ops = self.pending_operations.lock().unwrap();
+        Self::record(&mut pending_ops, encoder, temp_buffers);
+    }
+
+    /// Submit the pending uploads and BLAS builds in their own command buffer,
+    /// instead of recording them into the frame.
+    ///
+    /// Returns `None` if there is nothing to submit.
+    pub fn submit(&self) -> Option<blade_graphics::SyncPoint> {
+        self.retire(false);
+        let mut pending_ops = self.pending_operations.lock().unwrap();
+        if pending_ops.transfers.is_empty() && pending_ops.blas_constructs.is_empty() {
+            return None;
+        }
+        let mut encoder =
+            self.gpu_context
+                .create_command_encoder(blade_graphics::CommandEncoderDesc {
+                    name: "model upload",
+                    buffer_count: 1,
+                });
+        encoder.start();
+        let mut temp_buffers = Vec::new();
+        Self::record(&mut pending_ops, &mut encoder, &mut temp_buffers);
+        drop(pending_ops);
+
+        let sync_point = self.gpu_context.submit(&mut encoder);
+        self.submissions.lock().unwrap().push(Submission {
+            encoder,
+            sync_point: sync_point.clone(),
+            temp_buffers,
+        });
+        Some(sync_point)
+    }
+
+    /// Read the vertices and the indices of the model geometries back
+    /// from the GPU, in the order of `Model::geometries`.
+    ///
+    /// Records the pending uploads first, and blocks until the copy is done.
+    /// Returns `None` for the models loaded with `Meta::geometry_only`,
+    /// which don't keep their buffers.
+    pub fn read_geometries(&self, model: &Model) -> Option<Vec<GeometryData>> {
+        if model.geometry_only {
+            return None;
+        }
+        let gpu = &self.gpu_context;
+        let vertex_count = model
+            .geometries
+            .iter()
+            .map(|geo| geo.vertex_range.end)
+            .max()
+            .unwrap_or(0);
+        let vertex_size = vertex_count as u64 * mem::size_of::<crate::Vertex>() as u64;
+        let index_size = model
+            .geometries
+            .iter()
+            .filter(|geo| geo.index_type.is_some())
+            .map(|geo| geo.index_offset + geo.triangle_count as u64 * 3 * 4)
+            .max()
+            .unwrap_or(0);
+        let vertex_readback = gpu.create_buffer(blade_graphics::BufferDesc {
+            name: "vertex readback",
+            size: vertex_size.max(4),
+            memory: blade_graphics::Memory::Shared,
+        });
+        let index_readback = gpu.create_buffer(blade_graphics::BufferDesc {
+            name: "index readback",
+            size: index_size.max(4),
+            memory: blade_graphics::Memory::Shared,
+        });
+
+        let mut encoder = gpu.create_command_encoder(blade_graphics::CommandEncoderDesc {
+            name: "model readback",
+            buffer_count: 1,
+        });
+        encoder.start();
+        let mut temp_buffers = Vec::new();
+        // the model may still be waiting for its upload
+        self.flush(&mut encoder, &mut temp_buffers);
+        if vertex_size != 0 || index_size != 0 {
+            let mut transfer = encoder.transfer("readback");
+            if vertex_size != 0 {
+                transfer.copy_buffer_to_buffer(
+                    model.vertex_buffer.at(0),
+                    vertex_readback.at(0),
+                    vertex_size,
+                );
+            }
+            if index_size != 0 {
+                transfer.copy_buffer_to_buffer(
+                    model.index_buffer.at(0),
+                    index_readback.at(0),
+                    index_size,
+                );
+            }
+        }
+        let sync_point = gpu.submit(&mut encoder);
+        gpu.wait_for(&sync_point, !0);
+
+        let all_vertices = unsafe {
+            std::slice::from_raw_parts(
+                vertex_readback.data() as *const crate::Vertex,
+                vertex_count as usize,
+            )
+        };
+        let geometries = model
+            .geometries
+            .iter()
+            .map(|geo| GeometryData {
+                vertices: all_vertices
+                    [geo.vertex_range.start as usize..geo.vertex_range.end as usize]
+                    .to_vec(),
+                indices: match geo.index_type {
+                    Some(_) => unsafe {
+                        std::slice::from_raw_parts(
+                            index_readback.data().add(geo.index_offset as usize) as *const u32,
+                            geo.triangle_count as usize * 3,
+                        )
+                    }
+                    .to_vec(),
+                    None => Vec::new(),
+                },
+            })
+            .collect();
+
+        for buffer in temp_buffers {
+            gpu.destroy_buffer(buffer);
+        }
+        gpu.destroy_buffer(vertex_readback);
+        gpu.destroy_buffer(index_readback);
+        gpu.destroy_command_encoder(&mut encoder);
+        Some(geometries)
+    }
+
+    /// Free the resources of the completed submissions,
+    /// optionally waiting for all of them first.
+    pub fn retire(&self, wait: bool) {
+        let gpu = &self.gpu_context;
+        self.submissions.lock().unwrap().retain_mut(|submission| {
+            let timeout = if wait { !0 } else { 0 };
+            if !gpu.wait_for(&submission.sync_point, timeout) {
+                return true;
+            }
+            for buffer in submission.temp_buffers.drain(..) {
+                gpu.destroy_buffer(buffer);
+            }
+            gpu.destroy_command_encoder(&mut submission.encoder);
+            false
+        });
+    }
+
+    fn record(
+        pending_ops: &mut PendingOperations,
+        encoder: &mut blade_graphics::CommandEncoder,
+        temp_buffers: &mut Vec<blade_graphics::Buffer>,
+    ) {
         if !pending_ops.transfers.is_empty() {
             let mut pass = encoder.transfer("init models");
             for transfer in pending_ops.transfers.drain(..) {
@@ -394,12 +1393,13 @@ impl Baker {
                 temp_buffers.push(transfer.stage);
             }
         }
-        if !pending_ops.blas_constructs.is_empty() {
-            let mut pass = encoder.acceleration_structure("BLAS");
-            for construct in pending_ops.blas_constructs.drain(..) {
-                pass.build_bottom_level(construct.dst, &construct.meshes, construct.scratch.into());
-                temp_buffers.push(construct.scratch);
-            }
+        // A pass per build, so that each of them is timed separately
+        for construct in pending_ops.blas_constructs.drain(..) {
+            log::debug!("Recording BLAS build of '{}'", construct.name);
+            let mut pass = encoder.acceleration_structure(&blas_pass_label(&construct.name));
+            pass.build_bottom_level(construct.dst, &construct.meshes, construct.scratch.into());
+            temp_buffers.push(construct.scratch);
+            temp_buffers.extend(construct.release);
         }
     }
 
@@ -525,24 +1525,164 @@ impl blade_asset::Baker for Baker {
                     buffers.push(data);
                 }
 
+                let texture_meta = |base: crate::texture::Meta| crate::texture::Meta {
+                    max_dimension: meta.max_texture_dimension,
+                    ..base
+                };
                 let mut sources = slab::Slab::new();
+                // name the model after its scene, or its first mesh
+                let name = document
+                    .default_scene()
+                    .or_else(|| document.scenes().next())
+                    .and_then(|g_scene| g_scene.name())
+                    .or_else(|| document.meshes().find_map(|g_mesh| g_mesh.name()))
+                    .unwrap_or("");
                 let mut model = CookedModel {
-                    name: &[],
+                    name: Cow::Owned(name.as_bytes().to_owned()),
                     winding: match meta.front_face {
                         FrontFace::Clockwise => -1.0,
                         FrontFace::CounterClockwise => 1.0,
                     },
+                    max_texture_dimension: meta.max_texture_dimension.unwrap_or(0),
+                    geometry_only: meta.geometry_only,
+                    channel_map: meta.channel_map.pack(),
+                    color_spaces: meta.color_spaces.pack(),
                     materials: Vec::new(),
                     geometries: Vec::new(),
+                    lights: Vec::new(),
+                    nodes: Vec::new(),
+                    animations: Vec::new(),
                 };
                 for g_material in document.materials() {
                     let pbr = g_material.pbr_metallic_roughness();
+                    let iridescence_ext = g_material.extension_value("KHR_materials_iridescence");
+                    if let Some(ext) = iridescence_ext {
+                        if ext.get("iridescenceTexture").is_some()
+                            || ext.get("iridescenceThicknessTexture").is_some()
+                        {
+                            log::warn!(
+                                "Iridescence textures of material '{}' are not supported",
+                                g_material.name().unwrap_or("")
+                            );
+                        }
+                    }
+                    let iridescence_value = |name: &str, default: f32| {
+                        iridescence_ext
+                            .and_then(|ext| ext.get(name))
+                            .and_then(|value| value.as_f64())
+                            .map_or(default, |value| value as f32)
+                    };
+                    let sheen_ext = g_material.extension_value("KHR_materials_sheen");
+                    let volume_ext = g_material.extension_value("KHR_materials_volume");
+                    for (ext, textures) in [
+                        (
+                            sheen_ext,
+                            &["sheenColorTexture", "sheenRoughnessTexture"][..],
+                        ),
+                        (volume_ext, &["thicknessTexture"][..]),
+                    ] {
+                        if textures
+                            .iter()
+                            .any(|&name| ext.and_then(|e| e.get(name)).is_some())
+                        {
+                            log::warn!(
+                                "Sheen and volume textures of material '{}' are not supported",
+                                g_material.name().unwrap_or("")
+                            );
+                        }
+                    }
+                    let ext_value = |ext: Option<&gltf::json::Value>, name: &str, default: f32| {
+                        ext.and_then(|ext| ext.get(name))
+                            .and_then(|value| value.as_f64())
+                            .map_or(default, |value| value as f32)
+                    };
+                    let ext_color = |ext: Option<&gltf::json::Value>, name: &str, default: f32| {
+                        let mut color = [default; 3];
+                        if let Some(array) =
+                            ext.and_then(|ext| ext.get(name)).and_then(|v| v.as_array())
+                        {
+                            for (c, value) in color.iter_mut().zip(array) {
+                                *c = value.as_f64().map_or(default, |v| v as f32);
+                            }
+                        }
+                        color
+                    };
+                    let transmission = g_material
+                        .transmission()
+                        .map_or(0.0, |t| t.transmission_factor());
+                    // the volume of transmissive materials is traced as glass
+                    let subsurface_distance = match volume_ext {
+                        Some(_) if transmission <= 0.0 => {
+                            let distance =
+                                ext_value(volume_ext, "attenuationDistance", f32::INFINITY);
+                            if distance.is_finite() {
+                                distance
+                            } else {
+                                ext_value(volume_ext, "thicknessFactor", 0.0)
+                            }
+                        }
+                        _ => 0.0,
+                    };
+                    let (base_color_transform, base_color_tex_coord) =
+                        match pbr.base_color_texture() {
+                            Some(info) => match info.texture_transform() {
+                                Some(tt) => (
+                                    TextureTransform {
+                                        offset: tt.offset(),
+                                        rotation: tt.rotation(),
+                                        scale: tt.scale(),
+                                    },
+                                    check_tex_coord(tt.tex_coord().unwrap_or(info.tex_coord())),
+                                ),
+                                None => (
+                                    TextureTransform::default(),
+                                    check_tex_coord(info.tex_coord()),
+                                ),
+                            },
+                            None => (TextureTransform::default(), 0),
+                        };
+                    let (metallic_roughness_transform, metallic_roughness_tex_coord) =
+                        match pbr.metallic_roughness_texture() {
+                            Some(info) => match info.texture_transform() {
+                                Some(tt) => (
+                                    TextureTransform {
+                                        offset: tt.offset(),
+                                        rotation: tt.rotation(),
+                                        scale: tt.scale(),
+                                    },
+                                    check_tex_coord(tt.tex_coord().unwrap_or(info.tex_coord())),
+                                ),
+                                None => (
+                                    TextureTransform::default(),
+                                    check_tex_coord(info.tex_coord()),
+                                ),
+                            },
+                            None => (TextureTransform::default(), 0),
+                        };
+                    let (normal_transform, normal_tex_coord) = match g_material.normal_texture() {
+                        Some(info) => parse_texture_transform(
+                            info.extension_value("KHR_texture_transform"),
+                            info.tex_coord(),
+                        ),
+                        None => (TextureTransform::default(), 0),
+                    };
+                    let (occlusion_transform, occlusion_tex_coord) =
+                        match g_material.occlusion_texture() {
+                            Some(info) => parse_texture_transform(
+                                info.extension_value("KHR_texture_transform"),
+                                info.tex_coord(),
+                            ),
+                            None => (TextureTransform::default(), 0),
+                        };
                     model.materials.push(CookedMaterial {
                         base_color: TextureReference {
                             source_index: match pbr.base_color_texture() {
                                 Some(info) => sources.insert(self.cook_texture(
                                     info.texture(),
-                                    META_BASE_COLOR,
+                                    texture_meta(color_space_meta(
+                                        META_BASE_COLOR,
+                                        meta.color_spaces.base_color,
+                                    )),
                                     &cooker,
                                     &buffers,
                                 )),
@@ -555,7 +1695,10 @@ impl blade_asset::Baker for Baker {
                             source_index: match g_material.normal_texture() {
                                 Some(info) => sources.insert(self.cook_texture(
                                     info.texture(),
-                                    META_NORMAL,
+                                    texture_meta(color_space_meta(
+                                        META_NORMAL,
+                                        meta.color_spaces.normal,
+                                    )),
                                     &cooker,
                                     &buffers,
                                 )),
@@ -565,10 +1708,139 @@ impl blade_asset::Baker for Baker {
                         },
                         normal_scale: g_material.normal_texture().map_or(0.0, |info| info.scale()),
                         transparent: g_material.alpha_mode() != gltf::material::AlphaMode::Opaque,
+                        double_sided: g_material.double_sided(),
+                        base_color_tex_coord,
+                        base_color_transform,
+                        normal_tex_coord,
+                        normal_transform,
+                        occlusion: TextureReference {
+                            source_index: match g_material.occlusion_texture() {
+                                Some(info) => sources.insert(self.cook_texture(
+                                    info.texture(),
+                                    texture_meta(color_space_meta(
+                                        occlusion_meta(meta.channel_map.occlusion),
+                                        meta.color_spaces.occlusion,
+                                    )),
+                                    &cooker,
+                                    &buffers,
+                                )),
+                                None => !0,
+                            },
+                            ..Default::default()
+                        },
+                        occlusion_strength: g_material
+                            .occlusion_texture()
+                            .map_or(0.0, |info| info.strength()),
+                        occlusion_tex_coord,
+                        occlusion_transform,
+                        transmission,
+                        ior: g_material.ior().unwrap_or(DEFAULT_IOR),
+                        dispersion: g_material
+                            .extension_value("KHR_materials_dispersion")
+                            .and_then(|ext| ext.get("dispersion"))
+                            .and_then(|value| value.as_f64())
+                            .map_or(0.0, |value| value as f32),
+                        metallic: pbr.metallic_factor(),
+                        roughness: pbr.roughness_factor(),
+                        iridescence: iridescence_value("iridescenceFactor", 0.0),
+                        iridescence_ior: iridescence_value(
+                            "iridescenceIor",
+                            DEFAULT_IRIDESCENCE_IOR,
+                        ),
+                        // without the thickness texture, the film is at its maximum
+                        iridescence_thickness: iridescence_value(
+                            "iridescenceThicknessMaximum",
+                            DEFAULT_IRIDESCENCE_THICKNESS,
+                        ),
+                        metallic_roughness: TextureReference {
+                            source_index: match pbr.metallic_roughness_texture() {
+                                Some(info) => sources.insert(self.cook_texture(
+                                    info.texture(),
+                                    texture_meta(color_space_meta(
+                                        META_PACKED,
+                                        meta.color_spaces.metallic_roughness,
+                                    )),
+                                    &cooker,
+                                    &buffers,
+                                )),
+                                None => !0,
+                            },
+                            ..Default::default()
+                        },
+                        metallic_roughness_tex_coord,
+                        metallic_roughness_transform,
+                        sheen_color: ext_color(sheen_ext, "sheenColorFactor", 0.0),
+                        sheen_roughness: ext_value(sheen_ext, "sheenRoughnessFactor", 0.0),
+                        subsurface_distance,
+                        subsurface_color: ext_color(volume_ext, "attenuationColor", 1.0),
+                    });
+                }
+
+                for g_node in document.nodes() {
+                    let (translation, rotation, scale) = g_node.transform().decomposed();
+                    model.nodes.push(CookedNode {
+                        parent: !0,
+                        translation,
+                        rotation,
+                        scale,
+                    });
+                }
+                for g_node in document.nodes() {
+                    for child in g_node.children() {
+                        model.nodes[child.index()].parent = g_node.index() as u32;
+                    }
+                }
+                if document.skins().next().is_some() {
+                    log::warn!("Skins are not supported, only the node transforms are animated");
+                }
+                for g_animation in document.animations() {
+                    let mut channels = Vec::new();
+                    for g_channel in g_animation.channels() {
+                        use gltf::animation::{util::ReadOutputs, Interpolation, Property};
+
+                        let path = match g_channel.target().property() {
+                            Property::Translation => 0,
+                            Property::Rotation => 1,
+                            Property::Scale => 2,
+                            Property::MorphTargetWeights => {
+                                log::warn!("Morph target animations are not supported");
+                                continue;
+                            }
+                        };
+                        let reader = g_channel.reader(|buffer| Some(&buffers[buffer.index()]));
+                        let times = match reader.read_inputs() {
+                            Some(iter) => iter.collect::<Vec<_>>(),
+                            None => continue,
+                        };
+                        let values = match reader.read_outputs() {
+                            Some(ReadOutputs::Translations(iter)) => iter.flatten().collect(),
+                            Some(ReadOutputs::Rotations(rotations)) => {
+                                rotations.into_f32().flatten().collect()
+                            }
+                            Some(ReadOutputs::Scales(iter)) => iter.flatten().collect(),
+                            _ => continue,
+                        };
+                        channels.push(CookedChannel {
+                            node_index: g_channel.target().node().index() as u32,
+                            path,
+                            interpolation: match g_channel.sampler().interpolation() {
+                                Interpolation::Linear => 0,
+                                Interpolation::Step => 1,
+                                Interpolation::CubicSpline => 2,
+                            },
+                            times: Cow::Owned(times),
+                            values: Cow::Owned(values),
+                        });
+                    }
+                    let name = g_animation.name().unwrap_or("");
+                    model.animations.push(CookedAnimation {
+                        name: Cow::Owned(name.as_bytes().to_owned()),
+                        channels,
                     });
                 }
 
                 let mut flattened_geos = Vec::new();
+                let mut default_material = None;
                 for g_scene in document.scenes() {
                     for g_node in g_scene.nodes() {
                         model.populate_gltf(
@@ -576,6 +1848,8 @@ impl blade_asset::Baker for Baker {
                             glam::Mat4::IDENTITY,
                             &buffers,
                             &mut flattened_geos,
+                            &mut default_material,
+                            meta.flat_normals,
                         );
                     }
                 }
@@ -613,6 +1887,8 @@ impl blade_asset::Baker for Baker {
                     for material in model.materials.iter_mut() {
                         material.base_color.complete(&sources);
                         material.normal.complete(&sources);
+                        material.occlusion.complete(&sources);
+                        material.metallic_roughness.complete(&sources);
                     }
                     cooker.finish(model);
                 });
@@ -625,21 +1901,82 @@ impl blade_asset::Baker for Baker {
     }
 
     fn serve(&self, model: CookedModel<'_>, exe_context: &choir::ExecutionContext) -> Self::Output {
+        let texture_meta = |base: crate::texture::Meta| crate::texture::Meta {
+            max_dimension: match model.max_texture_dimension {
+                0 => None,
+                max => Some(max),
+            },
+            ..base
+        };
+        let serve_texture = |reference: &TextureReference, meta: crate::texture::Meta| {
+            if model.geometry_only {
+                None
+            } else {
+                self.serve_texture(reference, texture_meta(meta), exe_context)
+            }
+        };
+        let channel_map = ChannelMap::unpack(model.channel_map);
+        let color_spaces = ColorSpaces::unpack(model.color_spaces);
         let mut materials = Vec::with_capacity(model.materials.len());
         for material in model.materials.iter() {
             materials.push(Material {
-                base_color_texture: self.serve_texture(
+                base_color_texture: serve_texture(
                     &material.base_color,
-                    META_BASE_COLOR,
-                    exe_context,
+                    color_space_meta(META_BASE_COLOR, color_spaces.base_color),
                 ),
                 base_color_factor: material.base_color_factor,
-                normal_texture: self.serve_texture(&material.normal, META_NORMAL, exe_context),
+                normal_texture: serve_texture(
+                    &material.normal,
+                    color_space_meta(META_NORMAL, color_spaces.normal),
+                ),
                 normal_scale: material.normal_scale,
                 transparent: material.transparent,
+                double_sided: material.double_sided,
+                base_color_tex_coord: material.base_color_tex_coord,
+                base_color_transform: material.base_color_transform,
+                normal_tex_coord: material.normal_tex_coord,
+                normal_transform: material.normal_transform,
+                occlusion_texture: serve_texture(
+                    &material.occlusion,
+                    color_space_meta(
+                        occlusion_meta(channel_map.occlusion),
+                        color_spaces.occlusion,
+                    ),
+                ),
+                occlusion_strength: material.occlusion_strength,
+                occlusion_tex_coord: material.occlusion_tex_coord,
+                occlusion_transform: material.occlusion_transform,
+                transmission: material.transmission,
+                ior: material.ior,
+                dispersion: material.dispersion,
+                metallic: material.metallic,
+                roughness: material.roughness,
+                iridescence: material.iridescence,
+                iridescence_ior: material.iridescence_ior,
+                iridescence_thickness: material.iridescence_thickness,
+                metallic_roughness_texture: serve_texture(
+                    &material.metallic_roughness,
+                    color_space_meta(META_PACKED, color_spaces.metallic_roughness),
+                ),
+                metallic_roughness_tex_coord: material.metallic_roughness_tex_coord,
+                metallic_roughness_transform: material.metallic_roughness_transform,
+                channel_map,
+                sheen_color: material.sheen_color,
+                sheen_roughness: material.sheen_roughness,
+                subsurface_distance: material.subsurface_distance,
+                subsurface_color: material.subsurface_color,
             });
         }
 
+        let model_name = String::from_utf8_lossy(&model.name).into_owned();
+        // prefix the GPU resources with the model name, to find them in captures
+        let label = |suffix: &str| {
+            if model_name.is_empty() {
+                suffix.to_string()
+            } else {
+                format!("{model_name}/{suffix}")
+            }
+        };
         let total_vertices = model
             .geometries
             .iter()
@@ -647,12 +1984,12 @@ impl blade_asset::Baker for Baker {
             .sum::<usize>();
         let total_vertex_size = (total_vertices * mem::size_of::<crate::Vertex>()) as u64;
         let vertex_buffer = self.gpu_context.create_buffer(blade_graphics::BufferDesc {
-            name: "vertex",
+            name: &label("vertex"),
             size: total_vertex_size,
             memory: blade_graphics::Memory::Device,
         });
         let vertex_stage = self.gpu_context.create_buffer(blade_graphics::BufferDesc {
-            name: "vertex stage",
+            name: &label("vertex stage"),
             size: total_vertex_size,
             memory: blade_graphics::Memory::Upload,
         });
@@ -665,12 +2002,12 @@ impl blade_asset::Baker for Baker {
         let total_index_size = total_indices as u64 * 4
             + model.geometries.len() as u64 * blade_graphics::limits::STORAGE_BUFFER_ALIGNMENT;
         let index_buffer = self.gpu_context.create_buffer(blade_graphics::BufferDesc {
-            name: "index",
+            name: &label("index"),
             size: total_index_size,
             memory: blade_graphics::Memory::Device,
         });
         let index_stage = self.gpu_context.create_buffer(blade_graphics::BufferDesc {
-            name: "index stage",
+            name: &label("index stage"),
             size: total_index_size,
             memory: blade_graphics::Memory::Upload,
         });
@@ -678,12 +2015,12 @@ impl blade_asset::Baker for Baker {
         let total_transform_size =
             (model.geometries.len() * mem::size_of::<blade_graphics::Transform>()) as u64;
         let transform_buffer = self.gpu_context.create_buffer(blade_graphics::BufferDesc {
-            name: "transform",
+            name: &label("transform"),
             size: total_transform_size,
             memory: blade_graphics::Memory::Device,
         });
         let transform_stage = self.gpu_context.create_buffer(blade_graphics::BufferDesc {
-            name: "transform stage",
+            name: &label("transform stage"),
             size: total_transform_size,
             memory: blade_graphics::Memory::Upload,
         });
@@ -694,7 +2031,14 @@ impl blade_asset::Baker for Baker {
         let mut index_offset = 0;
         let mut transform_offset = 0;
         let mut geometries = Vec::with_capacity(model.geometries.len());
+        let mut bounds = crate::BoundingBox::default();
         for geometry in model.geometries.iter() {
+            let mut geo_bounds = crate::BoundingBox::default();
+            for vertex in geometry.vertices.iter() {
+                geo_bounds.include(vertex.position.into());
+            }
+            bounds.union(&geo_bounds.transformed(&geometry.transform.into()));
+
             index_offset = crate::util::align_to(
                 index_offset,
                 blade_graphics::limits::STORAGE_BUFFER_ALIGNMENT,
@@ -745,7 +2089,9 @@ impl blade_asset::Baker for Baker {
                 index_type,
                 triangle_count,
                 transform: geometry.transform.into(),
+                bounds: geo_bounds,
                 material_index: geometry.material_index as usize,
+                node_index: geometry.node_index as usize,
             });
             start_vertex += geometry.vertices.len() as u32;
             index_offset += geometry.indices.len() as u64 * 4;
@@ -760,16 +2106,24 @@ impl blade_asset::Baker for Baker {
             .get_bottom_level_acceleration_structure_sizes(&meshes);
         let acceleration_structure = self.gpu_context.create_acceleration_structure(
             blade_graphics::AccelerationStructureDesc {
-                name: str::from_utf8(model.name).unwrap(),
+                name: &label("BLAS"),
                 ty: blade_graphics::AccelerationStructureType::BottomLevel,
                 size: sizes.data,
             },
         );
         let scratch = self.gpu_context.create_buffer(blade_graphics::BufferDesc {
-            name: "BLAS scratch",
+            name: &label("BLAS scratch"),
             size: sizes.scratch,
             memory: blade_graphics::Memory::Device,
         });
+        log::info!(
+            "BLAS '{}' of {} geometries with {} triangles: {} KB, scratch {} KB",
+            model_name,
+            meshes.len(),
+            meshes.iter().map(|m| m.triangle_count as u64).sum::<u64>(),
+            sizes.data >> 10,
+            sizes.scratch >> 10,
+        );
 
         let mut pending_ops = self.pending_operations.lock().unwrap();
         pending_ops.transfers.push(Transfer {
@@ -787,29 +2141,338 @@ impl blade_asset::Baker for Baker {
             dst: transform_buffer,
             size: total_transform_size,
         });
+        let buffers = [vertex_buffer, index_buffer, transform_buffer];
         pending_ops.blas_constructs.push(BlasConstruct {
+            name: model_name.clone(),
             meshes,
             scratch,
             dst: acceleration_structure,
+            release: if model.geometry_only {
+                buffers.to_vec()
+            } else {
+                Vec::new()
+            },
         });
+        let [vertex_buffer, index_buffer, transform_buffer] = if model.geometry_only {
+            [blade_graphics::Buffer::default(); 3]
+        } else {
+            buffers
+        };
 
         Model {
-            name: String::from_utf8_lossy(model.name).into_owned(),
+            name: model_name,
             winding: model.winding,
             geometries,
             materials,
+            bounds,
             vertex_buffer,
             index_buffer,
             transform_buffer,
+            geometry_only: model.geometry_only,
+            fingerprint: model.fingerprint(),
             acceleration_structure,
+            acceleration_structure_size: sizes.data,
+            nodes: model
+                .nodes
+                .iter()
+                .map(|cn| Node {
+                    parent: if cn.parent == !0 {
+                        None
+                    } else {
+                        Some(cn.parent as usize)
+                    },
+                    translation: cn.translation,
+                    rotation: cn.rotation,
+                    scale: cn.scale,
+                })
+                .collect(),
+            animations: model
+                .animations
+                .iter()
+                .map(|ca| Animation {
+                    name: String::from_utf8_lossy(ca.name.as_ref()).into_owned(),
+                    channels: ca
+                        .channels
+                        .iter()
+                        .map(|cc| AnimationChannel {
+                            node_index: cc.node_index as usize,
+                            path: match cc.path {
+                                0 => AnimationPath::Translation,
+                                1 => AnimationPath::Rotation,
+                                _ => AnimationPath::Scale,
+                            },
+                            interpolation: match cc.interpolation {
+                                0 => Interpolation::Linear,
+                                1 => Interpolation::Step,
+                                _ => Interpolation::CubicSpline,
+                            },
+                            times: cc.times.to_vec(),
+                            values: cc.values.to_vec(),
+                        })
+                        .collect(),
+                    duration: ca
+                        .channels
+                        .iter()
+                        .filter_map(|cc| cc.times.last().copied())
+                        .fold(0.0, f32::max),
+                })
+                .collect(),
+            lights: model
+                .lights
+                .iter()
+                .map(|cl| crate::Light {
+                    kind: match cl.kind {
+                        0 => crate::LightKind::Point,
+                        _ => crate::LightKind::Spot {
+                            inner_cone_angle: cl.inner_cone_angle,
+                            outer_cone_angle: cl.outer_cone_angle,
+                        },
+                    },
+                    position: cl.position.into(),
+                    direction: cl.direction.into(),
+                    color: cl.color,
+                    intensity: cl.intensity,
+                    range: cl.range,
+                })
+                .collect(),
         }
     }
 
     fn delete(&self, model: Self::Output) {
         self.gpu_context
             .destroy_acceleration_structure(model.acceleration_structure);
-        self.gpu_context.destroy_buffer(model.vertex_buffer);
-        self.gpu_context.destroy_buffer(model.index_buffer);
-        self.gpu_context.destroy_buffer(model.transform_buffer);
+        if !model.geometry_only {
+            self.gpu_context.destroy_buffer(model.vertex_buffer);
+            self.gpu_context.destroy_buffer(model.index_buffer);
+            self.gpu_context.destroy_buffer(model.transform_buffer);
+        }
+    }
+}
+
+#[cfg(all(test, feature = "asset"))]
+mod tests {
+    /// A triangle with the last position replaced by a sparse accessor,
+    /// and the texture coordinates given only by a sparse accessor
+    /// without a base buffer view.
+    const SPARSE_GLTF: &str = r#"{
+        "asset": { "version": "2.0" },
+        "scene": 0,
+        "scenes": [{ "nodes": [0] }],
+        "nodes": [{ "name": "sparse", "mesh": 0 }],
+        "meshes": [{
+            "primitives": [{ "attributes": { "POSITION": 0, "TEXCOORD_0": 1 } }]
+        }],
+        "buffers": [{ "byteLength": 60 }],
+        "bufferViews": [
+            { "buffer": 0, "byteOffset": 0, "byteLength": 36 },
+            { "buffer": 0, "byteOffset": 36, "byteLength": 4 },
+            { "buffer": 0, "byteOffset": 40, "byteLength": 12 },
+            { "buffer": 0, "byteOffset": 52, "byteLength": 8 }
+        ],
+        "accessors": [
+            {
+                "bufferView": 0,
+                "componentType": 5126,
+                "count": 3,
+                "type": "VEC3",
+                "min": [0.0, 0.0, 0.0],
+                "max": [1.0, 1.0, 1.0],
+                "sparse": {
+                    "count": 1,
+                    "indices": { "bufferView": 1, "componentType": 5125 },
+                    "values": { "bufferView": 2 }
+                }
+            },
+            {
+                "componentType": 5126,
+                "count": 3,
+                "type": "VEC2",
+                "sparse": {
+                    "count": 1,
+                    "indices": { "bufferView": 1, "componentType": 5125 },
+                    "values": { "bufferView": 3 }
+                }
+            }
+        ]
+    }"#;
+
+    fn sparse_buffers() -> [Vec<u8>; 1] {
+        let mut data = Vec::new();
+        let base_positions: [[f32; 3]; 3] = [[0.0, 0.0, 0.0], [1.0, 0.0, 0.0], [0.0, 1.0, 0.0]];
+        data.extend_from_slice(bytemuck::cast_slice(&base_positions));
+        data.extend_from_slice(bytemuck::bytes_of(&2u32));
+        data.extend_from_slice(bytemuck::cast_slice(&[0.0f32, 0.0, 1.0]));
+        data.extend_from_slice(bytemuck::cast_slice(&[0.5f32, 0.25]));
+        [data]
+    }
+
+    /// Two triangles sharing an edge, folded along it,
+    /// with indices and no normals.
+    const NO_NORMALS_GLTF: &str = r#"{
+        "asset": { "version": "2.0" },
+        "scene": 0,
+        "scenes": [{ "nodes": [0] }],
+        "nodes": [{ "name": "folded", "mesh": 0 }],
+        "meshes": [{
+            "primitives": [{ "attributes": { "POSITION": 0 }, "indices": 1 }]
+        }],
+        "buffers": [{ "byteLength": 60 }],
+        "bufferViews": [
+            { "buffer": 0, "byteOffset": 0, "byteLength": 48 },
+            { "buffer": 0, "byteOffset": 48, "byteLength": 12 }
+        ],
+        "accessors": [
+            {
+                "bufferView": 0,
+                "componentType": 5126,
+                "count": 4,
+                "type": "VEC3",
+                "min": [0.0, 0.0, 0.0],
+                "max": [1.0, 1.0, 1.0]
+            },
+            {
+                "bufferView": 1,
+                "componentType": 5123,
+                "count": 6,
+                "type": "SCALAR"
+            }
+        ]
+    }"#;
+
+    fn no_normals_buffers() -> [Vec<u8>; 1] {
+        let mut data = Vec::new();
+        let positions: [[f32; 3]; 4] = [
+            [0.0, 0.0, 0.0],
+            [1.0, 0.0, 0.0],
+            [0.0, 1.0, 0.0],
+            [1.0, 1.0, 1.0],
+        ];
+        data.extend_from_slice(bytemuck::cast_slice(&positions));
+        data.extend_from_slice(bytemuck::cast_slice(&[0u16, 1, 2, 2, 1, 3]));
+        [data]
+    }
+
+    /// Cook the geometry of a glTF, without the tangents.
+    fn cook_geometry(
+        gltf_text: &str,
+        buffers: &[Vec<u8>],
+        flat_normals: bool,
+    ) -> (super::CookedModel<'static>, Vec<super::FlattenedGeometry>) {
+        use std::borrow::Cow;
+
+        let gltf::Gltf { document, .. } = gltf::Gltf::from_slice(gltf_text.as_bytes()).unwrap();
+        let mut model = super::CookedModel {
+            name: Cow::Borrowed(&[]),
+            winding: 1.0,
+            max_texture_dimension: 0,
+            geometry_only: false,
+            channel_map: 0,
+            color_spaces: 0,
+            materials: Vec::new(),
+            geometries: Vec::new(),
+            lights: Vec::new(),
+            nodes: Vec::new(),
+            animations: Vec::new(),
+        };
+        let mut flattened_geos = Vec::new();
+        for g_node in document.default_scene().unwrap().nodes() {
+            model.populate_gltf(
+                g_node,
+                glam::Mat4::IDENTITY,
+                buffers,
+                &mut flattened_geos,
+                &mut None,
+                flat_normals,
+            );
+        }
+        (model, flattened_geos)
+    }
+
+    #[test]
+    fn sparse_accessors() {
+        let (_, flattened_geos) = cook_geometry(SPARSE_GLTF, &sparse_buffers(), false);
+        assert_eq!(flattened_geos.len(), 1);
+        let vertices = &flattened_geos[0].0;
+        let positions = vertices.iter().map(|v| v.position).collect::<Vec<_>>();
+        assert_eq!(
+            positions,
+            [[0.0, 0.0, 0.0], [1.0, 0.0, 0.0], [0.0, 0.0, 1.0]]
+        );
+        let tex_coords = vertices.iter().map(|v| v.tex_coords).collect::<Vec<_>>();
+        assert_eq!(tex_coords, [[0.0, 0.0], [0.0, 0.0], [0.5, 0.25]]);
+    }
+
+    #[test]
+    fn generated_normals() {
+        let assert_normals = |flat_normals: bool, expected: [[f32; 3]; 6]| {
+            let (_, flattened_geos) =
+                cook_geometry(NO_NORMALS_GLTF, &no_normals_buffers(), flat_normals);
+            for (v, n) in flattened_geos[0].0.iter().zip(expected) {
+                let diff = glam::Vec3::from(v.normal) - glam::Vec3::from(n).normalize();
+                assert!(diff.length() < 1e-5, "{:?} != {:?}", v.normal, n);
+            }
+        };
+        let front = [0.0, 0.0, 1.0];
+        let back = [-1.0, -1.0, 1.0];
+        assert_normals(true, [front, front, front, back, back, back]);
+        // the shared vertices are weighted by the areas of both faces
+        let shared = [-1.0, -1.0, 2.0];
+        assert_normals(false, [front, shared, shared, shared, shared, back]);
+    }
+
+    #[test]
+    fn fingerprint() {
+        use std::borrow::Cow;
+
+        let fingerprint = |buffers: &[Vec<u8>]| {
+            let (mut model, flattened_geos) = cook_geometry(SPARSE_GLTF, buffers, false);
+            for (geometry, fg) in model.geometries.iter_mut().zip(flattened_geos) {
+                let (indices, vertices) = fg.reconstruct_indices();
+                geometry.vertices = Cow::Owned(vertices);
+                geometry.indices = Cow::Owned(indices);
+            }
+            model.fingerprint()
+        };
+        let buffers = sparse_buffers();
+        assert_eq!(fingerprint(&buffers), fingerprint(&buffers));
+        let mut changed = sparse_buffers();
+        // move the second vertex
+        changed[0][12] ^= 1;
+        assert_ne!(fingerprint(&buffers), fingerprint(&changed));
+    }
+
+    #[test]
+    fn color_spaces() {
+        use super::{ColorSpace, ColorSpaces};
+
+        let default = ColorSpaces::default();
+        assert_eq!(default.pack(), 1);
+        assert_eq!(ColorSpaces::unpack(default.pack()), default);
+        // normal map authored in sRGB
+        let custom = ColorSpaces {
+            normal: ColorSpace::Srgb,
+            ..default
+        };
+        assert_eq!(ColorSpaces::unpack(custom.pack()), custom);
+        let meta = super::color_space_meta(super::META_NORMAL, custom.normal);
+        assert!(meta.decode_srgb);
+        let meta = super::color_space_meta(super::META_BASE_COLOR, ColorSpace::Linear);
+        assert_eq!(meta.format, blade_graphics::TextureFormat::Bc1Unorm);
+    }
+
+    #[test]
+    fn channel_map() {
+        use super::{Channel, ChannelMap};
+
+        let default = ChannelMap::default();
+        assert_eq!(ChannelMap::unpack(default.pack()), default);
+        // roughness in R, as some tools export it
+        let custom = ChannelMap {
+            occlusion: Channel::A,
+            roughness: Channel::R,
+            metallic: Channel::G,
+        };
+        assert_eq!(custom.pack(), 0b01_00_11);
+        assert_eq!(ChannelMap::unpack(custom.pack()), custom);
     }
 }
@@ -0,0 +1,200 @@
+use std::{
+    collections::hash_map::{Entry, HashMap},
+    fs,
+    io::{self, Write as _},
+    path::Path,
+};
+
+/// Inverse of `pack4x8snorm`, for the first three components.
+fn decode_normal(v: u32) -> glam::Vec3 {
+    let c = |shift: u32| ((v >> shift) as u8 as i8) as f32 / 127.0;
+    glam::Vec3::new(c(0), c(8), c(16))
+}
+
+/// Write the vertices and the triangles of a geometry, placed by the given
+/// transform. The indices of OBJ are global, so `base` is the number of
+/// vertices written before, and it's advanced past this geometry.
+///
+/// The triangles are flipped if `winding` is negative, so that the
+/// front faces end up counter-clockwise.
+fn write_geometry(
+    out: &mut impl io::Write,
+    data: &super::GeometryData,
+    transform: glam::Mat4,
+    winding: f32,
+    base: &mut usize,
+) -> io::Result<()> {
+    let normal_transform = glam::Mat3::from_mat4(transform).inverse().transpose();
+    for vertex in data.vertices.iter() {
+        let p = transform.transform_point3(vertex.position.into());
+        writeln!(out, "v {} {} {}", p.x, p.y, p.z)?;
+    }
+    for vertex in data.vertices.iter() {
+        // OBJ has the origin of the texture coordinates at the bottom
+        let [u, v] = vertex.tex_coords;
+        writeln!(out, "vt {} {}", u, 1.0 - v)?;
+    }
+    for vertex in data.vertices.iter() {
+        let n = (normal_transform * decode_normal(vertex.normal)).normalize_or_zero();
+        writeln!(out, "vn {} {} {}", n.x, n.y, n.z)?;
+    }
+
+    let triangle_count = if data.indices.is_empty() {
+        data.vertices.len() / 3
+    } else {
+        data.indices.len() / 3
+    };
+    for triangle in 0..triangle_count {
+        let mut corners = [0, 1, 2].map(|i| {
+            let local = match data.indices.get(triangle * 3 + i) {
+                Some(&index) => index as usize,
+                None => triangle * 3 + i,
+            };
+            *base + local + 1
+        });
+        if winding < 0.0 {
+            corners.swap(1, 2);
+        }
+        let [a, b, c] = corners;
+        writeln!(out, "f {a}/{a}/{a} {b}/{b}/{b} {c}/{c}/{c}")?;
+    }
+    *base += data.vertices.len();
+    Ok(())
+}
+
+fn write_material(
+    out: &mut impl io::Write,
+    name: &str,
+    material: &super::Material,
+    textures: &blade_asset::AssetManager<crate::texture::Baker>,
+) -> io::Result<()> {
+    let [r, g, b, a] = material.base_color_factor;
+    writeln!(out, "newmtl {name}")?;
+    writeln!(out, "Kd {r} {g} {b}")?;
+    if material.transparent {
+        writeln!(out, "d {a}")?;
+    }
+    writeln!(out, "Ni {}", material.ior)?;
+    // physically-based extension of the format
+    writeln!(out, "Pr {}", material.roughness)?;
+    writeln!(out, "Pm {}", material.metallic)?;
+    let texture_path = |texture: Option<blade_asset::Handle<crate::Texture>>| {
+        texture.and_then(|handle| textures.get_main_source_path(handle))
+    };
+    if let Some(path) = texture_path(material.base_color_texture) {
+        writeln!(out, "map_Kd {}", path.display())?;
+    }
+    if let Some(path) = texture_path(material.normal_texture) {
+        writeln!(out, "norm -bm {} {}", material.normal_scale, path.display())?;
+    }
+    writeln!(out)
+}
+
+/// Names in OBJ end at the first whitespace.
+fn sanitize(name: &str) -> String {
+    name.chars()
+        .map(|c| if c.is_whitespace() { '_' } else { c })
+        .collect()
+}
+
+/// Write the geometry of the objects into a Wavefront OBJ file, with the
+/// vertices in world space, and the materials into an MTL file next to it.
+///
+/// Only the first UV set is written, without the texture transforms.
+/// Textures are referenced by their source paths, so the embedded ones
+/// are skipped, as well as the occlusion and metallic-roughness textures.
+/// Models loaded with `Meta::geometry_only` are skipped entirely.
+pub(crate) fn export_obj(
+    models: &blade_asset::AssetManager<super::Baker>,
+    textures: &blade_asset::AssetManager<crate::texture::Baker>,
+    objects: &[crate::Object],
+    path: &Path,
+) -> io::Result<()> {
+    let mtl_path = path.with_extension("mtl");
+    let mut obj = io::BufWriter::new(fs::File::create(path)?);
+    let mut mtl = io::BufWriter::new(fs::File::create(&mtl_path)?);
+    if let Some(file_name) = mtl_path.file_name() {
+        writeln!(obj, "mtllib {}", file_name.to_string_lossy())?;
+    }
+
+    let mut material_names = HashMap::new();
+    let mut base = 0;
+    for (object_index, object) in objects.iter().enumerate() {
+        let model = &models[object.model];
+        let geometries = match models.baker.read_geometries(model) {
+            Some(geometries) => geometries,
+            None => {
+                log::warn!("Model '{}' has no geometry to export", model.name);
+                continue;
+            }
+        };
+        let m4_object = crate::render::mat4_transform(&object.transform);
+        for (geometry, data) in model.geometries.iter().zip(geometries.iter()) {
+            let key = (object.model, geometry.material_index);
+            let material_count = material_names.len();
+            let material_name = match material_names.entry(key) {
+                Entry::Occupied(e) => e.into_mut(),
+                Entry::Vacant(e) => {
+                    let name = format!(
+                        "{}_{}_{}",
+                        sanitize(&model.name),
+                        material_count,
+                        geometry.material_index
+                    );
+                    write_material(
+                        &mut mtl,
+                        &name,
+                        &model.materials[geometry.material_index],
+                        textures,
+                    )?;
+                    e.insert(name)
+                }
+            };
+            let transform = m4_object * crate::render::mat4_transform(&geometry.transform);
+            let winding = model.winding * glam::Mat3::from_mat4(transform).determinant().signum();
+            writeln!(
+                obj,
+                "o {}/{}/{}",
+                object_index,
+                sanitize(&model.name),
+                sanitize(&geometry.name)
+            )?;
+            writeln!(obj, "usemtl {material_name}")?;
+            write_geometry(&mut obj, data, transform, winding, &mut base)?;
+        }
+    }
+    obj.flush()?;
+    mtl.flush()
+}
+
+#[cfg(test)]
+mod tests {
+    #[test]
+    fn mirrored_triangle() {
+        let vertex = |position: [f32; 3], tex_coords: [f32; 2]| crate::Vertex {
+            position,
+            tex_coords,
+            normal: super::super::encode_normal([0.0, 0.0, 1.0]),
+            ..Default::default()
+        };
+        let data = super::super::GeometryData {
+            vertices: vec![
+                vertex([0.0, 0.0, 0.0], [0.0, 0.0]),
+                vertex([1.0, 0.0, 0.0], [1.0, 0.0]),
+                vertex([0.0, 1.0, 0.0], [0.0, 1.0]),
+            ],
+            indices: vec![0, 1, 2],
+        };
+        let mut out = Vec::new();
+        let mut base = 1;
+        let mirror = glam::Mat4::from_scale(glam::Vec3::new(1.0, 1.0, -1.0));
+        super::write_geometry(&mut out, &data, mirror, -1.0, &mut base).unwrap();
+        assert_eq!(base, 4);
+        let text = String::from_utf8(out).unwrap();
+        let lines = text.lines().collect::<Vec<_>>();
+        assert_eq!(lines[1], "v 1 0 0");
+        assert_eq!(lines[3], "vt 0 1");
+        assert_eq!(lines[6], "vn 0 0 -1");
+        assert_eq!(lines[9], "f 2/2/2 4/4/4 3/3/3");
+    }
+}
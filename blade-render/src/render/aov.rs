@@ -0,0 +1,188 @@
+use std::num::NonZeroU32;
+
+const AOV_FORMAT: blade_graphics::TextureFormat = blade_graphics::TextureFormat::Rgba32Float;
+
+/// Arbitrary output variable of the renderer, meant for external
+/// denoisers such as Intel Open Image Denoise.
+///
+/// Every AOV is read back as 4 `f32` channels per pixel, with the rows
+/// going from the top of the image to the bottom.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Aov {
+    /// Linear HDR radiance in RGB, before exposure and tone mapping.
+    /// The alpha channel is 1.
+    Color,
+    /// Linear base color of the primary surface in RGB, in [0, 1] range.
+    /// The alpha channel is 1.
+    Albedo,
+    /// World-space shading normal of the primary surface in XYZ,
+    /// in [-1, 1] range. Zero where the primary ray missed.
+    Normal,
+    /// Distance from the camera to the primary surface along the ray
+    /// in the first channel. Zero where the primary ray missed.
+    Depth,
+}
+
+#[derive(blade_macros::ShaderData)]
+struct AovData {
+    t_depth: blade_graphics::TextureView,
+    t_basis: blade_graphics::TextureView,
+    t_albedo: blade_graphics::TextureView,
+    light_diffuse: blade_graphics::TextureView,
+    out_color: blade_graphics::TextureView,
+    out_albedo: blade_graphics::TextureView,
+    out_normal: blade_graphics::TextureView,
+    out_depth: blade_graphics::TextureView,
+}
+
+fn create_pipeline(
+    shader: &blade_graphics::Shader,
+    gpu: &blade_graphics::Context,
+) -> blade_graphics::ComputePipeline {
+    let layout = <AovData as blade_graphics::ShaderData>::layout();
+    gpu.create_compute_pipeline(blade_graphics::ComputePipelineDesc {
+        name: "aov",
+        data_layouts: &[&layout],
+        compute: shader.at("write_aovs"),
+    })
+}
+
+pub(super) struct AovTargets {
+    texture: blade_graphics::Texture,
+    views: [blade_graphics::TextureView; 4],
+    size: blade_graphics::Extent,
+}
+
+impl AovTargets {
+    pub(super) fn destroy(self, gpu: &blade_graphics::Context) {
+        for view in self.views {
+            gpu.destroy_texture_view(view);
+        }
+        gpu.destroy_texture(self.texture);
+    }
+}
+
+/// Writes the auxiliary buffers on demand, into a texture
+/// that is only allocated once they are requested.
+pub struct AovRender {
+    pipeline: blade_graphics::ComputePipeline,
+    pub(super) targets: Option<AovTargets>,
+}
+
+impl AovRender {
+    pub(super) fn init(gpu: &blade_graphics::Context, shader: &blade_graphics::Shader) -> Self {
+        Self {
+            pipeline: create_pipeline(shader, gpu),
+            targets: None,
+        }
+    }
+
+    pub(super) fn destroy(&mut self, gpu: &blade_graphics::Context) {
+        gpu.destroy_compute_pipeline(&mut self.pipeline);
+        self.release(gpu);
+    }
+
+    pub(super) fn recreate_pipeline(
+        &mut self,
+        shader: &blade_graphics::Shader,
+        gpu: &blade_graphics::Context,
+    ) {
+        self.pipeline = create_pipeline(shader, gpu);
+    }
+
+    /// Free the targets, for example when the screen is resized.
+    pub(super) fn release(&mut self, gpu: &blade_graphics::Context) {
+        if let Some(targets) = self.targets.take() {
+            targets.destroy(gpu);
+        }
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    pub(super) fn capture(
+        &mut self,
+        command_encoder: &mut blade_graphics::CommandEncoder,
+        size: blade_graphics::Extent,
+        t_depth: blade_graphics::TextureView,
+        t_basis: blade_graphics::TextureView,
+        t_albedo: blade_graphics::TextureView,
+        light_diffuse: blade_graphics::TextureView,
+        gpu: &blade_graphics::Context,
+    ) {
+        if self.targets.as_ref().map_or(false, |t| t.size != size) {
+            self.release(gpu);
+        }
+        let targets = self.targets.get_or_insert_with(|| {
+            let texture = gpu.create_texture(blade_graphics::TextureDesc {
+                name: "aov",
+                format: AOV_FORMAT,
+                size,
+                dimension: blade_graphics::TextureDimension::D2,
+                array_layer_count: 4,
+                mip_level_count: 1,
+                usage: blade_graphics::TextureUsage::STORAGE | blade_graphics::TextureUsage::COPY,
+                sample_count: 1,
+            });
+            let mut views = [blade_graphics::TextureView::default(); 4];
+            for (i, view) in views.iter_mut().enumerate() {
+                *view = gpu.create_texture_view(
+                    texture,
+                    blade_graphics::TextureViewDesc {
+                        name: &format!("aov{i}"),
+                        format: AOV_FORMAT,
+                        dimension: blade_graphics::ViewDimension::D2,
+                        subresources: &blade_graphics::TextureSubresources {
+                            base_array_layer: i as u32,
+                            array_layer_count: NonZeroU32::new(1),
+                            ..Default::default()
+                        },
+                    },
+                );
+            }
+            command_encoder.init_texture(texture);
+            AovTargets {
+                texture,
+                views,
+                size,
+            }
+        });
+
+        let mut pass = command_encoder.compute("aov");
+        let mut pc = pass.with(&self.pipeline);
+        let groups = self.pipeline.get_dispatch_for(size);
+        pc.bind(
+            0,
+            &AovData {
+                t_depth,
+                t_basis,
+                t_albedo,
+                light_diffuse,
+                out_color: targets.views[Aov::Color as usize],
+                out_albedo: targets.views[Aov::Albedo as usize],
+                out_normal: targets.views[Aov::Normal as usize],
+                out_depth: targets.views[Aov::Depth as usize],
+            },
+        );
+        pc.dispatch(groups);
+    }
+
+    pub(super) fn read(&self, aov: Aov, gpu: &blade_graphics::Context) -> Vec<f32> {
+        let targets = match self.targets {
+            Some(ref targets) => targets,
+            None => return Vec::new(),
+        };
+        let bytes = gpu.read_texture_region(
+            blade_graphics::TexturePiece {
+                texture: targets.texture,
+                mip_level: 0,
+                array_layer: aov as u32,
+                origin: [0; 3],
+            },
+            AOV_FORMAT,
+            targets.size,
+        );
+        bytes
+            .chunks_exact(4)
+            .map(|c| f32::from_ne_bytes([c[0], c[1], c[2], c[3]]))
+            .collect()
+    }
+}
@@ -0,0 +1,167 @@
+use super::MAX_RESOURCES;
+
+/// Fixed-point scale of the deposited lighting. Has to match the shader!
+const CAUSTICS_SCALE: f32 = 1024.0;
+/// Limit of the lighting a single photon can add to a pixel,
+/// which keeps the fixed-point sums from overflowing.
+const MAX_DEPOSIT: f32 = 64.0;
+
+/// Photon tracing from the punctual lights, capturing the light focused
+/// by mirrors and refractive surfaces onto the diffuse ones.
+///
+/// The photons are splatted into the pixels that see the surface they
+/// land on, and added to the lighting of the main pass, so they converge
+/// with the accumulation like the rest of the image.
+#[derive(Clone, Copy, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct CausticsConfig {
+    pub enabled: bool,
+    /// Number of photons traced per frame.
+    pub photon_count: u32,
+    /// How many mirrors and refractive surfaces a photon can cross.
+    pub max_bounces: u32,
+    /// Multiplier of the brightness, where one is physically based.
+    pub intensity: f32,
+}
+impl Default for CausticsConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            photon_count: 1 << 18,
+            max_bounces: 8,
+            intensity: 1.0,
+        }
+    }
+}
+
+impl CausticsConfig {
+    /// Scale applied by the main pass to the deposited caustics.
+    pub(super) fn weight(&self) -> f32 {
+        if self.enabled {
+            self.intensity / CAUSTICS_SCALE
+        } else {
+            0.0
+        }
+    }
+}
+
+#[repr(C)]
+#[derive(Clone, Copy, Debug, bytemuck::Zeroable, bytemuck::Pod)]
+struct CausticsParams {
+    frame_index: u32,
+    photon_count: u32,
+    max_bounces: u32,
+    light_count: u32,
+    mirror_roughness: f32,
+    max_deposit: f32,
+    pad: [u32; 2],
+}
+
+#[derive(blade_macros::ShaderData)]
+struct CausticsData<'a> {
+    camera: super::CameraParams,
+    params: CausticsParams,
+    acc_struct: blade_graphics::AccelerationStructure,
+    hit_entries: blade_graphics::BufferPiece,
+    index_buffers: &'a blade_graphics::BufferArray<MAX_RESOURCES>,
+    vertex_buffers: &'a blade_graphics::BufferArray<MAX_RESOURCES>,
+    lights: blade_graphics::BufferPiece,
+    t_depth: blade_graphics::TextureView,
+    caustics: blade_graphics::BufferPiece,
+}
+
+/// Scene resources the photons are traced through.
+pub(super) struct CausticsScene<'a> {
+    pub acc_struct: blade_graphics::AccelerationStructure,
+    pub hit_entries: blade_graphics::BufferPiece,
+    pub index_buffers: &'a blade_graphics::BufferArray<MAX_RESOURCES>,
+    pub vertex_buffers: &'a blade_graphics::BufferArray<MAX_RESOURCES>,
+    pub lights: blade_graphics::BufferPiece,
+    pub light_count: u32,
+}
+
+fn create_pipeline(
+    shader: &blade_graphics::Shader,
+    gpu: &blade_graphics::Context,
+) -> blade_graphics::ComputePipeline {
+    shader.check_struct_size::<CausticsParams>();
+    let layout = <CausticsData as blade_graphics::ShaderData>::layout();
+    gpu.create_compute_pipeline(blade_graphics::ComputePipelineDesc {
+        name: "caustics",
+        data_layouts: &[&layout],
+        compute: shader.at("trace_photons"),
+    })
+}
+
+/// Traces photons into a buffer of lighting per pixel.
+pub struct CausticsRender {
+    pipeline: blade_graphics::ComputePipeline,
+    pub(super) config: CausticsConfig,
+}
+
+impl CausticsRender {
+    pub(super) fn init(gpu: &blade_graphics::Context, shader: &blade_graphics::Shader) -> Self {
+        Self {
+            pipeline: create_pipeline(shader, gpu),
+            config: CausticsConfig::default(),
+        }
+    }
+
+    pub(super) fn destroy(&mut self, gpu: &blade_graphics::Context) {
+        gpu.destroy_compute_pipeline(&mut self.pipeline);
+    }
+
+    pub(super) fn recreate_pipeline(
+        &mut self,
+        shader: &blade_graphics::Shader,
+        gpu: &blade_graphics::Context,
+    ) {
+        self.pipeline = create_pipeline(shader, gpu);
+    }
+
+    /// Deposit the photons of a frame into the `caustics` buffer,
+    /// which is expected to be cleared.
+    pub(super) fn trace(
+        &self,
+        command_encoder: &mut blade_graphics::CommandEncoder,
+        scene: CausticsScene,
+        camera: super::CameraParams,
+        depth: blade_graphics::TextureView,
+        caustics: blade_graphics::BufferPiece,
+        frame_index: u32,
+    ) {
+        if !self.config.enabled || self.config.photon_count == 0 || scene.light_count == 0 {
+            return;
+        }
+        let mut pass = command_encoder.compute("caustics");
+        let mut pc = pass.with(&self.pipeline);
+        pc.bind(
+            0,
+            &CausticsData {
+                camera,
+                params: CausticsParams {
+                    frame_index,
+                    photon_count: self.config.photon_count,
+                    max_bounces: self.config.max_bounces,
+                    light_count: scene.light_count,
+                    mirror_roughness: crate::model::MIRROR_ROUGHNESS,
+                    max_deposit: MAX_DEPOSIT,
+                    pad: [0; 2],
+                },
+                acc_struct: scene.acc_struct,
+                hit_entries: scene.hit_entries,
+                index_buffers: scene.index_buffers,
+                vertex_buffers: scene.vertex_buffers,
+                lights: scene.lights,
+                t_depth: depth,
+                caustics,
+            },
+        );
+        let group_size = self.pipeline.get_workgroup_size();
+        pc.dispatch([
+            (self.config.photon_count + group_size[0] - 1) / group_size[0],
+            1,
+            1,
+        ]);
+    }
+}
@@ -27,6 +27,7 @@ struct DebugDrawData {
     camera: super::CameraParams,
     debug_lines: blade_graphics::BufferPiece,
     depth: blade_graphics::TextureView,
+    viewport: super::ViewportParams,
 }
 
 #[repr(C)]
@@ -64,7 +65,8 @@ pub struct DebugEntry {
     pub tex_coords: [f32; 2],
     pub base_color_texture: u32,
     pub normal_texture: u32,
-    pad: [u32; 2],
+    pub geometry_index: u32,
+    pad: u32,
     pub position: [f32; 3],
     position_w: f32,
     pub normal: [f32; 3],
@@ -78,6 +80,7 @@ fn create_draw_pipeline(
 ) -> blade_graphics::RenderPipeline {
     shader.check_struct_size::<DebugPoint>();
     shader.check_struct_size::<DebugLine>();
+    shader.check_struct_size::<super::ViewportParams>();
     let layout = <DebugDrawData as blade_graphics::ShaderData>::layout();
     gpu.create_render_pipeline(blade_graphics::RenderPipelineDesc {
         name: "debug-draw",
@@ -265,8 +268,13 @@ impl DebugRender {
         debug_lines: &[DebugLine],
         camera: super::CameraParams,
         depth: blade_graphics::TextureView,
+        viewport_origin: [i32; 2],
         pass: &mut blade_graphics::RenderCommandEncoder,
     ) {
+        let viewport = super::ViewportParams {
+            origin: viewport_origin,
+            pad: [0; 2],
+        };
         let mut pc = pass.with(&self.draw_pipeline);
         let lines_offset = 32 + mem::size_of::<DebugVariance>() + mem::size_of::<DebugEntry>();
         pc.bind(
@@ -275,6 +283,7 @@ impl DebugRender {
                 camera,
                 debug_lines: self.buffer.at(lines_offset as u64),
                 depth,
+                viewport,
             },
         );
         pc.draw_indirect(self.buffer.at(0));
@@ -287,6 +296,7 @@ impl DebugRender {
                     camera,
                     debug_lines: lines_buf,
                     depth,
+                    viewport,
                 },
             );
             pc.draw(0, 2, 0, count);
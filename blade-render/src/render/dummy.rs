@@ -8,6 +8,8 @@ pub struct DummyResources {
     pub black_view: blade_graphics::TextureView,
     pub red_texture: blade_graphics::Texture,
     pub red_view: blade_graphics::TextureView,
+    pub white_volume_texture: blade_graphics::Texture,
+    pub white_volume_view: blade_graphics::TextureView,
     staging_buf: blade_graphics::Buffer,
 }
 
@@ -16,11 +18,7 @@ impl DummyResources {
         command_encoder: &mut blade_graphics::CommandEncoder,
         gpu: &blade_graphics::Context,
     ) -> Self {
-        let size = blade_graphics::Extent {
-            width: 1,
-            height: 1,
-            depth: 1,
-        };
+        let size = blade_graphics::Extent::new_2d(1, 1);
         let white_texture = gpu.create_texture(blade_graphics::TextureDesc {
             name: "dummy/white",
             format: blade_graphics::TextureFormat::Rgba8Unorm,
@@ -78,10 +76,30 @@ impl DummyResources {
                 subresources: &blade_graphics::TextureSubresources::default(),
             },
         );
+        let white_volume_texture = gpu.create_texture(blade_graphics::TextureDesc {
+            name: "dummy/white-volume",
+            format: blade_graphics::TextureFormat::Rgba8Unorm,
+            size,
+            array_layer_count: 1,
+            mip_level_count: 1,
+            dimension: blade_graphics::TextureDimension::D3,
+            usage: blade_graphics::TextureUsage::COPY | blade_graphics::TextureUsage::RESOURCE,
+            sample_count: 1,
+        });
+        let white_volume_view = gpu.create_texture_view(
+            white_volume_texture,
+            blade_graphics::TextureViewDesc {
+                name: "dummy/white-volume",
+                format: blade_graphics::TextureFormat::Rgba8Unorm,
+                dimension: blade_graphics::ViewDimension::D3,
+                subresources: &blade_graphics::TextureSubresources::default(),
+            },
+        );
 
         command_encoder.init_texture(white_texture);
         command_encoder.init_texture(black_texture);
         command_encoder.init_texture(red_texture);
+        command_encoder.init_texture(white_volume_texture);
         let mut transfers = command_encoder.transfer("init dummy");
         let staging_buf = gpu.create_buffer(blade_graphics::BufferDesc {
             name: "dummy/staging",
@@ -97,6 +115,7 @@ impl DummyResources {
         transfers.copy_buffer_to_texture(staging_buf.at(0), 4, white_texture.into(), size);
         transfers.copy_buffer_to_texture(staging_buf.at(4), 4, black_texture.into(), size);
         transfers.copy_buffer_to_texture(staging_buf.at(8), 4, red_texture.into(), size);
+        transfers.copy_buffer_to_texture(staging_buf.at(0), 4, white_volume_texture.into(), size);
 
         Self {
             size,
@@ -106,6 +125,8 @@ impl DummyResources {
             black_view,
             red_texture,
             red_view,
+            white_volume_texture,
+            white_volume_view,
             staging_buf,
         }
     }
@@ -117,6 +138,8 @@ impl DummyResources {
         gpu.destroy_texture(self.black_texture);
         gpu.destroy_texture_view(self.red_view);
         gpu.destroy_texture(self.red_texture);
+        gpu.destroy_texture_view(self.white_volume_view);
+        gpu.destroy_texture(self.white_volume_texture);
         gpu.destroy_buffer(self.staging_buf);
     }
 }
@@ -15,6 +15,14 @@ struct EnvPreprocData {
     params: EnvPreprocParams,
 }
 
+/// Environment map with a hierarchy of luminance weights.
+///
+/// Each mip of the weight texture stores the weights of the 2x2 texel
+/// quads of the level below, forming a discrete 2D distribution over
+/// the environment, proportional to the luminance and the solid angle
+/// of each texel. Descending the hierarchy picks bright regions, such as
+/// the sun disk, with a probability matching their contribution, which
+/// greatly reduces the noise of the environment lighting.
 pub struct EnvironmentMap {
     pub main_view: blade_graphics::TextureView,
     pub size: blade_graphics::Extent,
@@ -65,21 +73,25 @@ impl EnvironmentMap {
 
     fn weight_size(&self) -> blade_graphics::Extent {
         // The weight texture has to include all of the edge pixels, starting at mip 1
-        blade_graphics::Extent {
-            width: self.size.width.next_power_of_two() / 2,
-            height: self.size.height.next_power_of_two() / 2,
-            depth: 1,
-        }
+        blade_graphics::Extent::new_2d(
+            (self.size.width.next_power_of_two() / 2).max(1),
+            (self.size.height.next_power_of_two() / 2).max(1),
+        )
     }
 
-    pub fn destroy(&mut self, gpu: &blade_graphics::Context) {
+    fn release_weights(&mut self, gpu: &blade_graphics::Context) {
         if self.weight_texture != blade_graphics::Texture::default() {
             gpu.destroy_texture(self.weight_texture);
             gpu.destroy_texture_view(self.weight_view);
+            self.weight_texture = blade_graphics::Texture::default();
         }
         for view in self.weight_mips.drain(..) {
             gpu.destroy_texture_view(view);
         }
+    }
+
+    pub fn destroy(&mut self, gpu: &blade_graphics::Context) {
+        self.release_weights(gpu);
         gpu.destroy_compute_pipeline(&mut self.prepare_pipeline);
     }
 
@@ -95,15 +107,12 @@ impl EnvironmentMap {
         }
         self.main_view = view;
         self.size = extent;
-        self.destroy(gpu);
+        // the pipeline is kept for the new weights
+        self.release_weights(gpu);
 
-        let mip_level_count = extent
-            .width
-            .max(extent.height)
-            .next_power_of_two()
-            .trailing_zeros();
         let weight_extent = self.weight_size();
-        let format = blade_graphics::TextureFormat::Rgba16Float;
+        let mip_level_count = weight_extent.mip_level_count();
+        let format = blade_graphics::TextureFormat::Rgba32Float;
         self.weight_texture = gpu.create_texture(blade_graphics::TextureDesc {
             name: "env-weight",
             format,
@@ -141,7 +150,13 @@ impl EnvironmentMap {
         }
 
         encoder.init_texture(self.weight_texture);
-        for target_level in 0..mip_level_count {
+        self.prepare(encoder);
+    }
+
+    /// Compute the weights again, after the contents of the assigned map change.
+    pub fn prepare(&self, encoder: &mut blade_graphics::CommandEncoder) {
+        let weight_extent = self.weight_size();
+        for target_level in 0..self.weight_mips.len() as u32 {
             let groups = self
                 .prepare_pipeline
                 .get_dispatch_for(weight_extent.at_mip_level(target_level));
@@ -151,7 +166,7 @@ impl EnvironmentMap {
                 0,
                 &EnvPreprocData {
                     source: if target_level == 0 {
-                        view
+                        self.main_view
                     } else {
                         self.weight_mips[target_level as usize - 1]
                     },
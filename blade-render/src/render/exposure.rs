@@ -0,0 +1,178 @@
+use std::{mem, ptr};
+
+/// Ratio between the average luminance and the exposure value at ISO 100.
+const EV100_LUMINANCE_SCALE: f32 = 12.5 / 100.0;
+
+/// Automatic adaptation of the tone mapping to the scene brightness.
+#[derive(Clone, Copy, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct AutoExposureConfig {
+    pub enabled: bool,
+    /// Range of the exposure values (EV100) the adaptation can reach.
+    pub min_ev: f32,
+    pub max_ev: f32,
+    /// Rate of the adaptation, per second. Higher values adapt faster.
+    pub speed: f32,
+}
+impl Default for AutoExposureConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            min_ev: -6.0,
+            max_ev: 16.0,
+            speed: 1.5,
+        }
+    }
+}
+
+#[repr(C)]
+#[derive(Clone, Copy, Debug, bytemuck::Zeroable, bytemuck::Pod)]
+struct ExposureParams {
+    min_log_lum: f32,
+    max_log_lum: f32,
+    adaptation: f32,
+    pad: u32,
+}
+
+// Has to match the shader!
+#[repr(C)]
+#[derive(Clone, Copy, Debug)]
+struct ExposureState {
+    average_lum: f32,
+    measured_log_lum: f32,
+}
+
+#[derive(blade_macros::ShaderData)]
+struct ExposureData {
+    params: ExposureParams,
+    t_albedo: blade_graphics::TextureView,
+    light_diffuse: blade_graphics::TextureView,
+    state: blade_graphics::BufferPiece,
+}
+
+fn create_pipeline(
+    shader: &blade_graphics::Shader,
+    gpu: &blade_graphics::Context,
+) -> blade_graphics::ComputePipeline {
+    shader.check_struct_size::<ExposureParams>();
+    shader.check_struct_size::<ExposureState>();
+    let layout = <ExposureData as blade_graphics::ShaderData>::layout();
+    gpu.create_compute_pipeline(blade_graphics::ComputePipelineDesc {
+        name: "exposure",
+        data_layouts: &[&layout],
+        compute: shader.at("measure"),
+    })
+}
+
+/// Adaptation state of a single view.
+pub(super) struct ExposureHistory {
+    state_buffer: blade_graphics::Buffer,
+    last_time: Option<std::time::Instant>,
+}
+
+impl ExposureHistory {
+    pub(super) fn new(gpu: &blade_graphics::Context) -> Self {
+        let state_buffer = gpu.create_buffer(blade_graphics::BufferDesc {
+            name: "exposure state",
+            size: mem::size_of::<ExposureState>() as u64,
+            memory: blade_graphics::Memory::Shared,
+        });
+        unsafe {
+            ptr::write(
+                state_buffer.data() as *mut ExposureState,
+                ExposureState {
+                    average_lum: 0.0,
+                    measured_log_lum: 0.0,
+                },
+            );
+        }
+        Self {
+            state_buffer,
+            last_time: None,
+        }
+    }
+
+    pub(super) fn destroy(&self, gpu: &blade_graphics::Context) {
+        gpu.destroy_buffer(self.state_buffer);
+    }
+}
+
+/// Measures the luminance of the rendered image and adapts to it over time.
+pub struct ExposureRender {
+    pipeline: blade_graphics::ComputePipeline,
+    pub(super) history: ExposureHistory,
+    pub(super) config: AutoExposureConfig,
+}
+
+impl ExposureRender {
+    pub(super) fn init(gpu: &blade_graphics::Context, shader: &blade_graphics::Shader) -> Self {
+        Self {
+            pipeline: create_pipeline(shader, gpu),
+            history: ExposureHistory::new(gpu),
+            config: AutoExposureConfig::default(),
+        }
+    }
+
+    pub(super) fn destroy(&mut self, gpu: &blade_graphics::Context) {
+        gpu.destroy_compute_pipeline(&mut self.pipeline);
+        self.history.destroy(gpu);
+    }
+
+    pub(super) fn recreate_pipeline(
+        &mut self,
+        shader: &blade_graphics::Shader,
+        gpu: &blade_graphics::Context,
+    ) {
+        self.pipeline = create_pipeline(shader, gpu);
+    }
+
+    pub(super) fn state(&self) -> blade_graphics::BufferPiece {
+        self.history.state_buffer.into()
+    }
+
+    /// Get the adapted exposure value, as last written by the GPU.
+    pub(super) fn current_ev(&self) -> Option<f32> {
+        let state = unsafe { &*(self.history.state_buffer.data() as *const ExposureState) };
+        if self.config.enabled && state.average_lum > 0.0 {
+            Some((state.average_lum / EV100_LUMINANCE_SCALE).log2())
+        } else {
+            None
+        }
+    }
+
+    pub(super) fn measure(
+        &mut self,
+        command_encoder: &mut blade_graphics::CommandEncoder,
+        t_albedo: blade_graphics::TextureView,
+        light_diffuse: blade_graphics::TextureView,
+    ) {
+        let now = std::time::Instant::now();
+        let delta = match self.history.last_time.replace(now) {
+            Some(time) => (now - time).as_secs_f32(),
+            None => 0.0,
+        };
+        if !self.config.enabled {
+            return;
+        }
+        let ev_to_log_lum = EV100_LUMINANCE_SCALE.log2();
+        let params = ExposureParams {
+            min_log_lum: self.config.min_ev + ev_to_log_lum,
+            max_log_lum: self.config.max_ev + ev_to_log_lum,
+            adaptation: 1.0 - (-delta * self.config.speed).exp(),
+            pad: 0,
+        };
+
+        let mut pass = command_encoder.compute("exposure");
+        let mut pc = pass.with(&self.pipeline);
+        pc.bind(
+            0,
+            &ExposureData {
+                params,
+                t_albedo,
+                light_diffuse,
+                state: self.history.state_buffer.into(),
+            },
+        );
+        pc.dispatch([1; 3]);
+    }
+}
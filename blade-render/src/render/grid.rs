@@ -0,0 +1,135 @@
+/// Appearance of the ground grid.
+#[derive(Clone, Copy, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct GridConfig {
+    /// Distance between the lines, in world units.
+    pub spacing: f32,
+    pub color: [f32; 4],
+    /// Distance from the camera where the grid fades out completely.
+    pub fade_distance: f32,
+    /// Height of the grid plane, which is horizontal.
+    pub height: f32,
+    /// Highlight the X axis in red and the Z axis in blue.
+    pub axes: bool,
+}
+impl Default for GridConfig {
+    fn default() -> Self {
+        Self {
+            spacing: 1.0,
+            color: [0.5, 0.5, 0.5, 0.6],
+            fade_distance: 50.0,
+            height: 0.0,
+            axes: true,
+        }
+    }
+}
+
+#[repr(C)]
+#[derive(Clone, Copy, bytemuck::Zeroable, bytemuck::Pod)]
+struct GridParams {
+    color: [f32; 4],
+    spacing: f32,
+    fade_distance: f32,
+    height: f32,
+    axes: u32,
+    viewport_origin: [i32; 2],
+    pad: [u32; 2],
+}
+
+#[derive(blade_macros::ShaderData)]
+struct GridData {
+    camera: super::CameraParams,
+    params: GridParams,
+    depth: blade_graphics::TextureView,
+}
+
+fn create_pipeline(
+    shader: &blade_graphics::Shader,
+    format: blade_graphics::TextureFormat,
+    gpu: &blade_graphics::Context,
+) -> blade_graphics::RenderPipeline {
+    shader.check_struct_size::<GridParams>();
+    let layout = <GridData as blade_graphics::ShaderData>::layout();
+    gpu.create_render_pipeline(blade_graphics::RenderPipelineDesc {
+        name: "grid",
+        data_layouts: &[&layout],
+        vertex: shader.at("grid_vs"),
+        vertex_fetches: &[],
+        primitive: blade_graphics::PrimitiveState {
+            topology: blade_graphics::PrimitiveTopology::TriangleStrip,
+            ..Default::default()
+        },
+        depth_stencil: None,
+        fragment: Some(shader.at("grid_fs")),
+        color_targets: &[blade_graphics::ColorTargetState {
+            format,
+            blend: Some(blade_graphics::BlendState::ALPHA_BLENDING),
+            write_mask: blade_graphics::ColorWrites::all(),
+        }],
+        multisample_state: blade_graphics::MultisampleState::default(),
+    })
+}
+
+/// Raster overlay of a ground plane grid, occluded by the traced depth.
+pub struct GridRender {
+    surface_format: blade_graphics::TextureFormat,
+    pipeline: blade_graphics::RenderPipeline,
+    pub(super) config: Option<GridConfig>,
+}
+
+impl GridRender {
+    pub(super) fn init(
+        gpu: &blade_graphics::Context,
+        shader: &blade_graphics::Shader,
+        surface_info: blade_graphics::SurfaceInfo,
+    ) -> Self {
+        Self {
+            surface_format: surface_info.format,
+            pipeline: create_pipeline(shader, surface_info.format, gpu),
+            config: None,
+        }
+    }
+
+    pub(super) fn destroy(&mut self, gpu: &blade_graphics::Context) {
+        gpu.destroy_render_pipeline(&mut self.pipeline);
+    }
+
+    pub(super) fn recreate_pipeline(
+        &mut self,
+        shader: &blade_graphics::Shader,
+        gpu: &blade_graphics::Context,
+    ) {
+        self.pipeline = create_pipeline(shader, self.surface_format, gpu);
+    }
+
+    pub(super) fn render(
+        &self,
+        camera: super::CameraParams,
+        depth: blade_graphics::TextureView,
+        viewport_origin: [i32; 2],
+        pass: &mut blade_graphics::RenderCommandEncoder,
+    ) {
+        let config = match self.config {
+            Some(ref config) => config,
+            None => return,
+        };
+        let mut pc = pass.with(&self.pipeline);
+        pc.bind(
+            0,
+            &GridData {
+                camera,
+                params: GridParams {
+                    color: config.color,
+                    spacing: config.spacing,
+                    fade_distance: config.fade_distance,
+                    height: config.height,
+                    axes: config.axes as u32,
+                    viewport_origin,
+                    pad: [0; 2],
+                },
+                depth,
+            },
+        );
+        pc.draw(0, 3, 0, 1);
+    }
+}
@@ -1,19 +1,46 @@
+mod aov;
+mod caustics;
 mod debug;
 mod dummy;
 mod env_map;
+mod exposure;
+mod grid;
+mod particles;
+mod sky;
+mod wireframe;
 
+use aov::AovRender;
+use caustics::{CausticsRender, CausticsScene};
 use debug::{DebugEntry, DebugRender, DebugVariance};
+use exposure::ExposureRender;
+use grid::GridRender;
+use particles::ParticleRender;
+use sky::{SkyParams, SkyRender};
+use wireframe::WireframeRender;
 
+pub use aov::Aov;
+pub use caustics::CausticsConfig;
 pub use debug::{DebugBlit, DebugLine, DebugPoint};
 pub use dummy::DummyResources;
 pub use env_map::EnvironmentMap;
+pub use exposure::AutoExposureConfig;
+pub use grid::GridConfig;
+pub use particles::{ParticleDesc, ParticleSystemId};
+pub use sky::Background;
+pub use wireframe::{CullingStats, WireframeConfig};
 
-use std::{collections::HashMap, mem, num::NonZeroU32, path::Path, ptr};
+use std::{cell::RefCell, collections::HashMap, mem, num::NonZeroU32, ops, path::Path, ptr, time};
 
 const MAX_RESOURCES: u32 = 8192;
+/// Number of frames after the last change when the accumulation
+/// is considered converged. See `Renderer::needs_redraw`.
+const DEFAULT_CONVERGENCE_FRAME_COUNT: u32 = 256;
+const DEFAULT_MAX_BOUNCES: u32 = 8;
+/// Has to match the shaders!
+pub const MAX_CLIP_PLANES: usize = 4;
 const RADIANCE_FORMAT: blade_graphics::TextureFormat = blade_graphics::TextureFormat::Rgba16Float;
 
-fn mat4_transform(t: &blade_graphics::Transform) -> glam::Mat4 {
+pub(crate) fn mat4_transform(t: &blade_graphics::Transform) -> glam::Mat4 {
     glam::Mat4 {
         x_axis: t.x.into(),
         y_axis: t.y.into(),
@@ -57,7 +84,10 @@ pub enum DebugMode {
     Motion = 8,
     HitConsistency = 9,
     SampleReuse = 10,
+    Occlusion = 11,
     Variance = 15,
+    /// Number of samples per pixel chosen by the adaptive sampling.
+    SampleDensity = 16,
 }
 
 impl Default for DebugMode {
@@ -66,6 +96,67 @@ impl Default for DebugMode {
     }
 }
 
+/// Source of the random numbers used for sampling the lights.
+#[derive(Clone, Copy, Debug, PartialEq, blade_macros::AsPrimitive, strum::EnumIter)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[repr(u32)]
+pub enum SampleStrategy {
+    /// Independent pseudo-random numbers, showing as white noise.
+    Random = 0,
+    /// Low-discrepancy sequence over frames, rotated per pixel,
+    /// showing as blue noise. Converges faster at low sample counts.
+    BlueNoise = 1,
+}
+
+impl Default for SampleStrategy {
+    fn default() -> Self {
+        Self::Random
+    }
+}
+
+/// How the lighting of the visible surfaces is computed.
+#[derive(Clone, Copy, Debug, PartialEq, blade_macros::AsPrimitive, strum::EnumIter)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[repr(u32)]
+pub enum RenderMode {
+    /// Stochastic sampling of the environment lighting, converging over time.
+    PathTraced = 0,
+    /// Only the `PreviewLight` with a shadow ray, plus constant ambient.
+    /// Noise-free and fast, for responsive navigation.
+    DirectOnly = 1,
+    /// Only the material capture set by `Renderer::set_matcap`, looked up
+    /// by the view-space normal and modulated by the albedo.
+    /// Noise-free and instant, for inspecting the shapes.
+    Matcap = 2,
+}
+
+impl Default for RenderMode {
+    fn default() -> Self {
+        Self::PathTraced
+    }
+}
+
+/// Lighting used by `RenderMode::DirectOnly`.
+#[derive(Clone, Copy, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct PreviewLight {
+    /// Direction towards the light.
+    pub direction: mint::Vector3<f32>,
+    pub color: [f32; 3],
+    /// Constant light coming from everywhere, unshadowed.
+    pub ambient: f32,
+}
+
+impl Default for PreviewLight {
+    fn default() -> Self {
+        Self {
+            direction: [0.3, 1.0, 0.5].into(),
+            color: [1.0; 3],
+            ambient: 0.2,
+        }
+    }
+}
+
 bitflags::bitflags! {
     #[derive(Copy, Clone, Debug, Default, Hash, Eq, PartialEq, PartialOrd)]
     pub struct DebugDrawFlags: u32 {
@@ -92,9 +183,19 @@ pub struct DebugConfig {
 }
 
 #[derive(Clone, Copy, Debug, PartialEq, PartialOrd)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct RayConfig {
     pub num_environment_samples: u32,
+    /// Sample the environment map proportionally to its luminance,
+    /// instead of uniformly over the sphere. This is prepared
+    /// automatically when the environment map is set, and is
+    /// strongly recommended for HDRIs with a bright sun.
     pub environment_importance_sampling: bool,
+    /// Extra candidates drawn proportionally to the BRDF, which helps
+    /// where the environment sampling misses. Both kinds are weighted
+    /// with the power heuristic.
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub num_brdf_samples: u32,
     pub tap_count: u32,
     pub tap_radius: u32,
     pub tap_confidence_near: u32,
@@ -108,18 +209,63 @@ pub struct RayConfig {
     pub defensive_mis: f32,
 }
 
+/// Distribution of the light samples according to the noise of each pixel.
+#[derive(Clone, Copy, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct AdaptiveConfig {
+    /// Relative variance of the pixel luminance, as measured over
+    /// the recent frames, that a single sample is expected to reach.
+    /// Noisier pixels get proportionally more samples.
+    pub target_variance: f32,
+    /// Maximum multiplier of the samples in `RayConfig`.
+    pub max_samples: u32,
+}
+impl Default for AdaptiveConfig {
+    fn default() -> Self {
+        Self {
+            target_variance: 0.05,
+            max_samples: 8,
+        }
+    }
+}
+
 #[derive(Clone, Copy, Debug, PartialEq, PartialOrd)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct DenoiserConfig {
     pub num_passes: u32,
     pub temporal_weight: f32,
 }
 
+/// Mapping of the linear radiance into the output color.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum OutputTransform {
+    /// Write the linear values, clamped to [0, 1].
+    None,
+    /// Raise the linear values to the power of one over the given gamma.
+    Gamma(f32),
+    /// Apply the exposure and tone mapping of the `PostProcConfig`,
+    /// followed by the color grading LUT.
+    /// The operator can be replaced by `Renderer::set_custom_tonemap`.
+    #[default]
+    Tonemap,
+}
+
 #[derive(Clone, Copy, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct PostProcConfig {
     //TODO: compute automatically
     pub average_luminocity: f32,
     pub exposure_key_value: f32,
     pub white_level: f32,
+    /// Bypassing the tone mapping helps telling the issues
+    /// in the rendered data apart from the ones in the tone mapping.
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub output_transform: OutputTransform,
+    /// Region of the render target to draw into, if not the whole target.
+    /// Its size is expected to match the one given to `resize_screen`.
+    #[cfg_attr(feature = "serde", serde(skip))]
+    pub viewport: Option<blade_graphics::ScissorRect>,
 }
 impl Default for PostProcConfig {
     fn default() -> Self {
@@ -127,14 +273,136 @@ impl Default for PostProcConfig {
             average_luminocity: 1.0,
             exposure_key_value: 1.0,
             white_level: 1.0,
+            output_transform: OutputTransform::Tonemap,
+            viewport: None,
+        }
+    }
+}
+
+/// Persistent settings of a `Renderer`, for saving and restoring
+/// the look of a shot together with the `Camera`.
+///
+/// See `Renderer::export_settings` and `Renderer::import_settings`.
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(default))]
+pub struct RenderSettings {
+    pub mode: RenderMode,
+    pub preview_light: PreviewLight,
+    pub sample_strategy: SampleStrategy,
+    pub mip_bias: f32,
+    pub firefly_clamp: f32,
+    pub dispersion: bool,
+    pub max_bounces: u32,
+    pub fog: FogConfig,
+    pub auto_exposure: AutoExposureConfig,
+    pub caustics: CausticsConfig,
+    pub wireframe: Option<WireframeConfig>,
+    pub grid: Option<GridConfig>,
+    pub adaptive_sampling: Option<AdaptiveConfig>,
+    pub clip_planes: Vec<crate::Plane>,
+    pub clip_cap_color: Option<[f32; 3]>,
+}
+
+impl Default for RenderSettings {
+    fn default() -> Self {
+        Self {
+            mode: RenderMode::default(),
+            preview_light: PreviewLight::default(),
+            sample_strategy: SampleStrategy::default(),
+            mip_bias: 0.0,
+            firefly_clamp: f32::INFINITY,
+            dispersion: false,
+            max_bounces: DEFAULT_MAX_BOUNCES,
+            fog: FogConfig::default(),
+            auto_exposure: AutoExposureConfig::default(),
+            caustics: CausticsConfig::default(),
+            wireframe: None,
+            grid: None,
+            adaptive_sampling: None,
+            clip_planes: Vec::new(),
+            clip_cap_color: None,
         }
     }
 }
 
+/// Height and distance fog, applied to the primary hits.
+#[derive(Clone, Copy, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct FogConfig {
+    /// Radiance of the fog itself.
+    pub color: [f32; 3],
+    /// Extinction per unit of distance at zero height.
+    /// Zero disables the fog.
+    pub density: f32,
+    /// How quickly the density decreases with height.
+    /// Zero makes the fog uniform.
+    pub height_falloff: f32,
+    /// Radiance of the sun scattered by the fog towards the camera.
+    /// Zero disables the in-scattering.
+    pub sun_color: [f32; 3],
+    /// Direction towards the sun.
+    pub sun_direction: [f32; 3],
+    /// Henyey-Greenstein anisotropy of the scattering, in (-1, 1).
+    pub sun_anisotropy: f32,
+}
+impl Default for FogConfig {
+    fn default() -> Self {
+        Self {
+            color: [0.5, 0.6, 0.7],
+            density: 0.0,
+            height_falloff: 0.0,
+            sun_color: [0.0; 3],
+            sun_direction: [0.0, 1.0, 0.0],
+            sun_anisotropy: 0.7,
+        }
+    }
+}
+
+/// Identifies a geometry instance in the scene acceleration structure.
+///
+/// The top-level instances follow the order of objects given to
+/// `Renderer::build_scene`, and the geometries follow `Model::geometries`.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub struct InstanceId {
+    /// Index of the object, which is also the TLAS instance index.
+    pub object: u32,
+    /// Index of the geometry within the model of the object.
+    pub geometry: u32,
+}
+
+/// Memory and build time of an acceleration structure.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct AccelerationStructureInfo {
+    pub name: String,
+    /// Number of triangles of a BLAS, or instances of the TLAS.
+    pub primitive_count: u64,
+    /// GPU memory, in bytes.
+    pub size: u64,
+    /// GPU time of the build, if it was found in the timings.
+    pub build_time: Option<std::time::Duration>,
+}
+
+/// Acceleration structures of the scene, as of the last `Renderer::build_scene`.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct AccelerationStructureReport {
+    pub top_level: AccelerationStructureInfo,
+    /// One per distinct model in the scene, the largest first.
+    pub bottom_level: Vec<AccelerationStructureInfo>,
+}
+
+impl AccelerationStructureReport {
+    /// GPU memory of all the acceleration structures, in bytes.
+    pub fn total_size(&self) -> u64 {
+        self.top_level.size + self.bottom_level.iter().map(|info| info.size).sum::<u64>()
+    }
+}
+
 pub struct SelectionInfo {
     pub std_deviation: mint::Vector3<f32>,
     pub std_deviation_history: u32,
     pub custom_index: u32,
+    pub instance: InstanceId,
     pub depth: f32,
     pub position: mint::Vector3<f32>,
     pub normal: mint::Vector3<f32>,
@@ -148,6 +416,7 @@ impl Default for SelectionInfo {
             std_deviation: [0.0; 3].into(),
             std_deviation_history: 0,
             custom_index: 0,
+            instance: InstanceId::default(),
             depth: 0.0,
             position: [0.0; 3].into(),
             normal: [0.0; 3].into(),
@@ -177,7 +446,9 @@ impl<const N: usize> RenderTarget<N> {
             dimension: blade_graphics::TextureDimension::D2,
             array_layer_count: N as u32,
             mip_level_count: 1,
-            usage: blade_graphics::TextureUsage::RESOURCE | blade_graphics::TextureUsage::STORAGE,
+            usage: blade_graphics::TextureUsage::RESOURCE
+                | blade_graphics::TextureUsage::STORAGE
+                | blade_graphics::TextureUsage::COPY,
             sample_count: 1,
         });
         encoder.init_texture(texture);
@@ -210,6 +481,137 @@ impl<const N: usize> RenderTarget<N> {
     }
 }
 
+const INSTANCE_BOUNDS_COLOR: u32 = 0xFFFF00;
+
+/// Add the 12 edges of a box to the debug lines.
+fn add_box_lines(lines: &mut Vec<DebugLine>, bounds: &crate::BoundingBox, color: u32) {
+    if bounds.is_empty() {
+        return;
+    }
+    let corner = |i: usize| DebugPoint {
+        pos: [
+            if i & 1 != 0 {
+                bounds.max.x
+            } else {
+                bounds.min.x
+            },
+            if i & 2 != 0 {
+                bounds.max.y
+            } else {
+                bounds.min.y
+            },
+            if i & 4 != 0 {
+                bounds.max.z
+            } else {
+                bounds.min.z
+            },
+        ],
+        color,
+    };
+    for i in 0..8 {
+        // connect to the corners that differ in one higher coordinate
+        for bit in [1, 2, 4] {
+            if i & bit == 0 {
+                lines.push(DebugLine {
+                    a: corner(i),
+                    b: corner(i | bit),
+                });
+            }
+        }
+    }
+}
+
+/// Counts the light samples traced over a window of wall-clock time.
+struct SampleRate {
+    start: time::Instant,
+    samples: u64,
+    per_second: f64,
+}
+
+impl SampleRate {
+    const WINDOW: time::Duration = time::Duration::from_millis(500);
+
+    fn new() -> Self {
+        Self {
+            start: time::Instant::now(),
+            samples: 0,
+            per_second: 0.0,
+        }
+    }
+
+    fn add(&mut self, samples: u64) {
+        self.samples += samples;
+        let elapsed = self.start.elapsed();
+        if elapsed >= Self::WINDOW {
+            self.per_second = self.samples as f64 / elapsed.as_secs_f64();
+            self.start = time::Instant::now();
+            self.samples = 0;
+        }
+    }
+
+    /// Forget the current window, so that idle time isn't counted.
+    fn restart(&mut self) {
+        self.start = time::Instant::now();
+        self.samples = 0;
+    }
+}
+
+/// Snapshot of the image taken by `Renderer::transition_to_scene`,
+/// faded out on top of the new scene.
+struct SceneFade {
+    light: RenderTarget<1>,
+    albedo: RenderTarget<1>,
+    depth: RenderTarget<1>,
+    size: blade_graphics::Extent,
+    start: std::time::Instant,
+    duration: std::time::Duration,
+}
+
+impl SceneFade {
+    fn new(
+        size: blade_graphics::Extent,
+        encoder: &mut blade_graphics::CommandEncoder,
+        gpu: &blade_graphics::Context,
+    ) -> Self {
+        Self {
+            light: RenderTarget::new("fade-light", RADIANCE_FORMAT, size, encoder, gpu),
+            albedo: RenderTarget::new(
+                "fade-albedo",
+                blade_graphics::TextureFormat::Rgba8Unorm,
+                size,
+                encoder,
+                gpu,
+            ),
+            depth: RenderTarget::new(
+                "fade-depth",
+                blade_graphics::TextureFormat::R32Float,
+                size,
+                encoder,
+                gpu,
+            ),
+            size,
+            start: std::time::Instant::now(),
+            duration: std::time::Duration::ZERO,
+        }
+    }
+
+    fn destroy(&self, gpu: &blade_graphics::Context) {
+        self.light.destroy(gpu);
+        self.albedo.destroy(gpu);
+        self.depth.destroy(gpu);
+    }
+
+    /// Weight of the old image, going down from 1 to 0 over the duration.
+    fn weight(&self) -> f32 {
+        let elapsed = self.start.elapsed().as_secs_f32();
+        if elapsed >= self.duration.as_secs_f32() {
+            0.0
+        } else {
+            1.0 - elapsed / self.duration.as_secs_f32()
+        }
+    }
+}
+
 struct RestirTargets {
     reservoir_buf: [blade_graphics::Buffer; 2],
     debug: RenderTarget<1>,
@@ -218,7 +620,14 @@ struct RestirTargets {
     flat_normal: RenderTarget<2>,
     albedo: RenderTarget<1>,
     motion: RenderTarget<1>,
+    transmission: RenderTarget<1>,
+    /// Index of the visible object plus one, or zero for the sky.
+    instance: RenderTarget<1>,
     light_diffuse: RenderTarget<3>,
+    /// Moments of the luminance for the adaptive sampling.
+    variance: RenderTarget<2>,
+    /// Lighting deposited by the photons, see `CausticsRender`.
+    caustics_buf: blade_graphics::Buffer,
     camera_params: [CameraParams; 2],
 }
 
@@ -238,6 +647,11 @@ impl RestirTargets {
                 memory: blade_graphics::Memory::Device,
             });
         }
+        let caustics_buf = gpu.create_buffer(blade_graphics::BufferDesc {
+            name: "caustics",
+            size: 3 * mem::size_of::<u32>() as u64 * total_reservoirs as u64,
+            memory: blade_graphics::Memory::Device,
+        });
 
         Self {
             reservoir_buf,
@@ -283,7 +697,23 @@ impl RestirTargets {
                 encoder,
                 gpu,
             ),
+            transmission: RenderTarget::new("transmission", RADIANCE_FORMAT, size, encoder, gpu),
+            instance: RenderTarget::new(
+                "instance",
+                blade_graphics::TextureFormat::R32Uint,
+                size,
+                encoder,
+                gpu,
+            ),
             light_diffuse: RenderTarget::new("light-diffuse", RADIANCE_FORMAT, size, encoder, gpu),
+            variance: RenderTarget::new(
+                "variance",
+                blade_graphics::TextureFormat::Rgba32Float,
+                size,
+                encoder,
+                gpu,
+            ),
+            caustics_buf,
             camera_params: [CameraParams::default(); 2],
         }
     }
@@ -298,10 +728,36 @@ impl RestirTargets {
         self.flat_normal.destroy(gpu);
         self.albedo.destroy(gpu);
         self.motion.destroy(gpu);
+        self.transmission.destroy(gpu);
+        self.instance.destroy(gpu);
         self.light_diffuse.destroy(gpu);
+        self.variance.destroy(gpu);
+        gpu.destroy_buffer(self.caustics_buf);
     }
 }
 
+/// Additional destination of a `Renderer`, with its own size, G-buffer,
+/// and accumulation history, but sharing the scene with the other views.
+///
+/// See `Renderer::with_view`.
+pub struct RenderView {
+    targets: RestirTargets,
+    post_proc_input_index: usize,
+    surface_size: blade_graphics::Extent,
+    frame_index: usize,
+    frame_scene_built: usize,
+    seen_scene_revision: usize,
+    scene_fade: Option<SceneFade>,
+    exposure_history: exposure::ExposureHistory,
+    aov_targets: Option<aov::AovTargets>,
+    frames_since_change: u32,
+    last_settings: RenderSettings,
+    is_accumulation_paused: bool,
+    adaptive_reset_frame: usize,
+    sample_rate: SampleRate,
+    next_tile: u32,
+}
+
 struct Blur {
     temporal_accum_pipeline: blade_graphics::ComputePipeline,
     a_trous_pipeline: blade_graphics::ComputePipeline,
@@ -321,7 +777,8 @@ pub struct Renderer {
     targets: RestirTargets,
     post_proc_input_index: usize,
     fill_pipeline: blade_graphics::ComputePipeline,
-    main_pipeline: blade_graphics::ComputePipeline,
+    /// Variants of the main pipeline, specialized for each `RenderMode`.
+    main_pipelines: Vec<blade_graphics::ComputePipeline>,
     post_proc_pipeline: blade_graphics::RenderPipeline,
     blur: Blur,
     acceleration_structure: blade_graphics::AccelerationStructure,
@@ -329,21 +786,100 @@ pub struct Renderer {
     env_map: EnvironmentMap,
     dummy: DummyResources,
     hit_buffer: blade_graphics::Buffer,
+    light_buffer: blade_graphics::Buffer,
+    /// Cumulative distribution of the light power, for sampling the lights.
+    light_cdf_buffer: blade_graphics::Buffer,
+    light_count: u32,
+    instance_data: Vec<[f32; 4]>,
+    /// Alternated between frames, since they are written by the host.
+    instance_buffers: [blade_graphics::Buffer; 2],
+    instance_capacity: usize,
     vertex_buffers: blade_graphics::BufferArray<MAX_RESOURCES>,
     index_buffers: blade_graphics::BufferArray<MAX_RESOURCES>,
     textures: blade_graphics::TextureArray<MAX_RESOURCES>,
     samplers: Samplers,
     reservoir_size: u32,
+    pipeline_log: PipelineLog,
     debug: DebugRender,
+    wireframe: WireframeRender,
+    grid: GridRender,
+    particles: ParticleRender,
+    exposure: ExposureRender,
+    caustics: CausticsRender,
+    aov: AovRender,
+    sky: SkyRender,
     surface_size: blade_graphics::Extent,
     surface_info: blade_graphics::SurfaceInfo,
     frame_index: usize,
     frame_scene_built: usize,
+    seen_scene_revision: usize,
+    scene_revision: usize,
     is_frozen: bool,
+    is_accumulation_paused: bool,
+    is_scene_dirty: bool,
+    frames_since_change: u32,
+    convergence_frame_count: u32,
+    last_settings: RenderSettings,
+    tile_size: Option<blade_graphics::Extent>,
+    /// Tile to be traced by the next `ray_trace_tile`.
+    next_tile: u32,
+    sample_strategy: SampleStrategy,
+    render_mode: RenderMode,
+    shading_module: Option<String>,
+    tonemap_module: Option<String>,
+    preview_light: PreviewLight,
+    clip_params: ClipParams,
+    mip_bias: f32,
+    firefly_clamp: f32,
+    dispersion: bool,
+    max_bounces: u32,
+    adaptive_sampling: Option<AdaptiveConfig>,
+    /// Frame at which the luminance moments start accumulating again.
+    adaptive_reset_frame: usize,
+    samples_per_frame: u32,
+    sample_rate: SampleRate,
+    fog_config: FogConfig,
+    environment_params: EnvironmentParams,
+    /// The reservoirs refer to the environment before the last change.
+    is_environment_dirty: bool,
+    background: Background,
+    /// The sky map has to be baked again.
+    is_sky_dirty: bool,
+    color_lut: ColorLut,
+    scene_fade: Option<SceneFade>,
+    matcap_view: blade_graphics::TextureView,
+    scene_objects: Vec<crate::Object>,
+    /// Index of the first geometry of each object.
+    scene_geometry_offsets: Vec<u32>,
+    /// glTF node index of every geometry in the scene.
+    scene_geometry_nodes: Vec<usize>,
+    scene_bounds: crate::BoundingBox,
+    /// World-space bounds of every object, including its pose.
+    scene_instance_bounds: Vec<crate::BoundingBox>,
+    draw_instance_bounds: bool,
+    tlas_size: u64,
+    scene_env_map: Option<blade_asset::Handle<crate::Texture>>,
     //TODO: refactor `ResourceArray` to not carry the freelist logic
     // This way we can embed user info into the allocator.
     texture_resource_lookup:
         HashMap<blade_graphics::ResourceIndex, blade_asset::Handle<crate::Texture>>,
+    texture_overrides: HashMap<blade_asset::Handle<crate::Texture>, TextureOverride>,
+    /// Geometry transforms set by `set_object_pose`, by object index.
+    object_poses: HashMap<usize, Vec<blade_graphics::Transform>>,
+    /// Poses applied by the previous scene build, for the motion vectors.
+    prev_object_poses: HashMap<usize, Vec<blade_graphics::Transform>>,
+    /// BLASes built for the posed objects by the last scene build.
+    posed_acceleration_structures: Vec<blade_graphics::AccelerationStructure>,
+    /// Posed BLASes referenced by `prev_acceleration_structure`.
+    prev_posed_acceleration_structures: Vec<blade_graphics::AccelerationStructure>,
+}
+
+/// Contents of a texture set by `Renderer::replace_texture`.
+struct TextureOverride {
+    texture: blade_graphics::Texture,
+    view: blade_graphics::TextureView,
+    format: blade_graphics::TextureFormat,
+    extent: blade_graphics::Extent,
 }
 
 #[repr(C)]
@@ -380,6 +916,71 @@ struct MainParams {
     use_pairwise_mis: u32,
     defensive_mis: f32,
     use_motion_vectors: u32,
+    sample_strategy: u32,
+    firefly_clamp: f32,
+    light_count: u32,
+    tile_origin: [u32; 2],
+    num_brdf_samples: u32,
+    adaptive_max_samples: u32,
+    adaptive_target_variance: f32,
+    adaptive_reset: u32,
+    caustics_weight: f32,
+    samples_per_frame: u32,
+}
+
+#[repr(C)]
+#[derive(Clone, Copy, Debug, bytemuck::Zeroable, bytemuck::Pod)]
+struct PreviewParams {
+    sun_direction: [f32; 3],
+    ambient: f32,
+    sun_color: [f32; 3],
+    pad: u32,
+}
+
+#[repr(C)]
+#[derive(Clone, Copy, Debug, Default, bytemuck::Zeroable, bytemuck::Pod)]
+struct ClipParams {
+    planes: [[f32; 4]; MAX_CLIP_PLANES],
+    cap_color: [f32; 4],
+    count: u32,
+    pad: [u32; 3],
+}
+
+#[repr(C)]
+#[derive(Clone, Copy, Debug, bytemuck::Zeroable, bytemuck::Pod)]
+struct LodParams {
+    mip_bias: f32,
+    spread_angle: f32,
+    pad: [u32; 2],
+}
+
+#[repr(C)]
+#[derive(Clone, Copy, Debug, PartialEq, bytemuck::Zeroable, bytemuck::Pod)]
+struct EnvironmentParams {
+    rotation: [f32; 4],
+    intensity: f32,
+    pad: [u32; 3],
+}
+
+impl Default for EnvironmentParams {
+    fn default() -> Self {
+        Self {
+            rotation: [0.0, 0.0, 0.0, 1.0],
+            intensity: 1.0,
+            pad: [0; 3],
+        }
+    }
+}
+
+#[repr(C)]
+#[derive(Clone, Copy, Debug, bytemuck::Zeroable, bytemuck::Pod)]
+struct TransmissionParams {
+    frame_index: u32,
+    dispersion: u32,
+    max_bounces: u32,
+    mirror_roughness: f32,
+    light_count: u32,
+    pad: [u32; 3],
 }
 
 #[derive(blade_macros::ShaderData)]
@@ -387,18 +988,26 @@ struct FillData<'a> {
     camera: CameraParams,
     prev_camera: CameraParams,
     debug: DebugParams,
+    clip: ClipParams,
+    lod_params: LodParams,
+    transmission_params: TransmissionParams,
+    environment: EnvironmentParams,
     acc_struct: blade_graphics::AccelerationStructure,
     hit_entries: blade_graphics::BufferPiece,
     index_buffers: &'a blade_graphics::BufferArray<MAX_RESOURCES>,
     vertex_buffers: &'a blade_graphics::BufferArray<MAX_RESOURCES>,
     textures: &'a blade_graphics::TextureArray<MAX_RESOURCES>,
     sampler_linear: blade_graphics::Sampler,
+    env_map: blade_graphics::TextureView,
+    lights: blade_graphics::BufferPiece,
     debug_buf: blade_graphics::BufferPiece,
     out_depth: blade_graphics::TextureView,
     out_basis: blade_graphics::TextureView,
     out_flat_normal: blade_graphics::TextureView,
     out_albedo: blade_graphics::TextureView,
     out_motion: blade_graphics::TextureView,
+    out_transmission: blade_graphics::TextureView,
+    out_instance: blade_graphics::TextureView,
     out_debug: blade_graphics::TextureView,
 }
 
@@ -408,12 +1017,15 @@ struct MainData {
     prev_camera: CameraParams,
     debug: DebugParams,
     parameters: MainParams,
+    preview: PreviewParams,
+    environment: EnvironmentParams,
     acc_struct: blade_graphics::AccelerationStructure,
     prev_acc_struct: blade_graphics::AccelerationStructure,
     sampler_linear: blade_graphics::Sampler,
     sampler_nearest: blade_graphics::Sampler,
     env_map: blade_graphics::TextureView,
     env_weights: blade_graphics::TextureView,
+    t_matcap: blade_graphics::TextureView,
     t_depth: blade_graphics::TextureView,
     t_prev_depth: blade_graphics::TextureView,
     t_basis: blade_graphics::TextureView,
@@ -421,11 +1033,20 @@ struct MainData {
     t_flat_normal: blade_graphics::TextureView,
     t_prev_flat_normal: blade_graphics::TextureView,
     t_motion: blade_graphics::TextureView,
+    t_albedo: blade_graphics::TextureView,
+    t_transmission: blade_graphics::TextureView,
+    t_instance: blade_graphics::TextureView,
+    t_prev_variance: blade_graphics::TextureView,
+    lights: blade_graphics::BufferPiece,
+    light_cdf: blade_graphics::BufferPiece,
+    instance_data: blade_graphics::BufferPiece,
     debug_buf: blade_graphics::BufferPiece,
     reservoirs: blade_graphics::BufferPiece,
     prev_reservoirs: blade_graphics::BufferPiece,
     out_diffuse: blade_graphics::TextureView,
     out_debug: blade_graphics::TextureView,
+    out_variance: blade_graphics::TextureView,
+    caustics: blade_graphics::BufferPiece,
 }
 
 #[repr(C)]
@@ -464,21 +1085,74 @@ struct ATrousData {
 #[repr(C)]
 #[derive(Clone, Copy, Default, bytemuck::Zeroable, bytemuck::Pod)]
 struct ToneMapParams {
-    enabled: u32,
+    output_transform: u32,
     average_lum: f32,
     key_value: f32,
     white_level: f32,
+    lut_strength: f32,
+    lut_scale: f32,
+    lut_offset: f32,
+    auto_exposure: u32,
+    gamma: f32,
+    fade: f32,
+    pad: [u32; 2],
+}
+
+#[repr(C)]
+#[derive(Clone, Copy, Debug, Default, bytemuck::Zeroable, bytemuck::Pod)]
+struct ViewportParams {
+    origin: [i32; 2],
+    pad: [u32; 2],
+}
+
+struct ColorLut {
+    view: blade_graphics::TextureView,
+    size: u32,
+    strength: f32,
+}
+
+#[repr(C)]
+#[derive(Clone, Copy, Debug, Default, bytemuck::Zeroable, bytemuck::Pod)]
+struct FogParams {
+    color_density: [f32; 4],
+    sun_color_falloff: [f32; 4],
+    sun_dir_anisotropy: [f32; 4],
+}
+
+impl From<FogConfig> for FogParams {
+    fn from(config: FogConfig) -> Self {
+        let [r, g, b] = config.color;
+        let [sr, sg, sb] = config.sun_color;
+        let sun_dir = glam::Vec3::from(config.sun_direction).normalize_or_zero();
+        Self {
+            color_density: [r, g, b, config.density],
+            sun_color_falloff: [sr, sg, sb, config.height_falloff],
+            sun_dir_anisotropy: [sun_dir.x, sun_dir.y, sun_dir.z, config.sun_anisotropy],
+        }
+    }
 }
 
 #[derive(blade_macros::ShaderData)]
 struct PostProcData {
+    camera: CameraParams,
+    fog: FogParams,
+    t_depth: blade_graphics::TextureView,
     t_albedo: blade_graphics::TextureView,
     light_diffuse: blade_graphics::TextureView,
     t_debug: blade_graphics::TextureView,
+    color_lut: blade_graphics::TextureView,
+    t_fade_light: blade_graphics::TextureView,
+    t_fade_albedo: blade_graphics::TextureView,
+    t_fade_depth: blade_graphics::TextureView,
+    sampler_linear: blade_graphics::Sampler,
     tone_map_params: ToneMapParams,
+    exposure: blade_graphics::BufferPiece,
     debug_params: DebugParams,
+    viewport: ViewportParams,
 }
 
+const HIT_FLAG_DOUBLE_SIDED: u32 = 1;
+
 #[repr(C)]
 #[derive(Debug)]
 struct HitEntry {
@@ -490,10 +1164,61 @@ struct HitEntry {
     // but it's aligned and sized the same way as `mat4`.
     geometry_to_object: mint::ColumnMatrix4<f32>,
     prev_object_to_world: mint::ColumnMatrix4<f32>,
+    /// Geometry transform of the pose at the previous scene build.
+    prev_geometry_to_object: mint::ColumnMatrix4<f32>,
     base_color_texture: u32,
     base_color_factor: [u8; 4],
     normal_texture: u32,
     normal_scale: f32,
+    // bit per texture, set if it uses the secondary UV set
+    tex_coord_sets: u32,
+    occlusion_texture: u32,
+    occlusion_strength: f32,
+    flags: u32,
+    transmission: f32,
+    ior: f32,
+    dispersion: f32,
+    metallic: f32,
+    uv_transforms: [[[f32; 2]; 3]; 4],
+    roughness: f32,
+    iridescence: f32,
+    iridescence_ior: f32,
+    iridescence_thickness: f32,
+    metallic_roughness_texture: u32,
+    // see `ChannelMap::pack`
+    channel_map: u32,
+    sheen_color: [u8; 4],
+    sheen_roughness: f32,
+    subsurface_color: [u8; 4],
+    // zero if there is no subsurface scattering
+    subsurface_distance: f32,
+    // WGSL rounds the size up to the alignment of the matrices
+    pad: [u32; 2],
+}
+
+// Has to match the shader!
+#[repr(C)]
+#[derive(Clone, Copy, Debug, bytemuck::Zeroable, bytemuck::Pod)]
+struct LightEntry {
+    position: [f32; 3],
+    // zero for unlimited range
+    range: f32,
+    direction: [f32; 3],
+    // cosine of the outer cone angle, -1 for point lights
+    cos_outer: f32,
+    radiance: [f32; 3],
+    cos_inner: f32,
+}
+
+impl LightEntry {
+    /// Estimate of the emitted power, up to a constant factor,
+    /// which is the intensity integrated over the cone of the light.
+    fn power(&self) -> f32 {
+        let luminance =
+            glam::Vec3::from(self.radiance).dot(glam::Vec3::new(0.2126, 0.7152, 0.0722));
+        let cos_cone = 0.5 * (self.cos_inner + self.cos_outer);
+        luminance * 2.0 * std::f32::consts::PI * (1.0 - cos_cone)
+    }
 }
 
 #[derive(Clone, PartialEq)]
@@ -505,6 +1230,13 @@ pub struct Shaders {
     post_proc: blade_asset::Handle<crate::Shader>,
     debug_draw: blade_asset::Handle<crate::Shader>,
     debug_blit: blade_asset::Handle<crate::Shader>,
+    wireframe: blade_asset::Handle<crate::Shader>,
+    grid: blade_asset::Handle<crate::Shader>,
+    particle: blade_asset::Handle<crate::Shader>,
+    exposure: blade_asset::Handle<crate::Shader>,
+    caustics: blade_asset::Handle<crate::Shader>,
+    aov: blade_asset::Handle<crate::Shader>,
+    sky: blade_asset::Handle<crate::Shader>,
 }
 
 impl Shaders {
@@ -518,73 +1250,149 @@ impl Shaders {
             post_proc: ctx.load_shader("post-proc.wgsl"),
             debug_draw: ctx.load_shader("debug-draw.wgsl"),
             debug_blit: ctx.load_shader("debug-blit.wgsl"),
+            wireframe: ctx.load_shader("wireframe.wgsl"),
+            grid: ctx.load_shader("grid.wgsl"),
+            particle: ctx.load_shader("particle.wgsl"),
+            exposure: ctx.load_shader("exposure.wgsl"),
+            caustics: ctx.load_shader("caustics.wgsl"),
+            aov: ctx.load_shader("aov.wgsl"),
+            sky: ctx.load_shader("sky.wgsl"),
         };
         (shaders, ctx.close())
     }
 }
 
+/// Pipeline compiled by the renderer, as listed by `Renderer::pipeline_report`.
+#[derive(Clone, Debug, PartialEq)]
+pub struct PipelineInfo {
+    pub name: String,
+    /// Specialization constants of the variant.
+    pub constants: Vec<(&'static str, f64)>,
+    /// Whether the custom WGSL of `set_shading_module` or `set_custom_tonemap`
+    /// was compiled into it.
+    pub custom_module: bool,
+    /// Time spent in the pipeline creation, including the backend compilation.
+    pub compile_time: time::Duration,
+}
+
+/// Latest compilation of every pipeline of the main passes.
+#[derive(Default)]
+struct PipelineLog(RefCell<Vec<PipelineInfo>>);
+
+impl PipelineLog {
+    fn record<P>(
+        &self,
+        name: &str,
+        constants: &[(&'static str, f64)],
+        custom_module: bool,
+        create: impl FnOnce() -> P,
+    ) -> P {
+        let start = time::Instant::now();
+        let pipeline = create();
+        let info = PipelineInfo {
+            name: name.to_string(),
+            constants: constants.to_vec(),
+            custom_module,
+            compile_time: start.elapsed(),
+        };
+        let mut infos = self.0.borrow_mut();
+        match infos.iter_mut().find(|other| other.name == info.name) {
+            Some(other) => *other = info,
+            None => infos.push(info),
+        }
+        pipeline
+    }
+}
+
 struct ShaderPipelines {
     fill: blade_graphics::ComputePipeline,
-    main: blade_graphics::ComputePipeline,
+    main: Vec<blade_graphics::ComputePipeline>,
     temporal_accum: blade_graphics::ComputePipeline,
     a_trous: blade_graphics::ComputePipeline,
     post_proc: blade_graphics::RenderPipeline,
     env_prepare: blade_graphics::ComputePipeline,
     reservoir_size: u32,
+    log: PipelineLog,
 }
 
 impl ShaderPipelines {
     fn create_gbuf_fill(
         shader: &blade_graphics::Shader,
         gpu: &blade_graphics::Context,
+        log: &PipelineLog,
     ) -> blade_graphics::ComputePipeline {
         shader.check_struct_size::<crate::Vertex>();
         shader.check_struct_size::<HitEntry>();
+        shader.check_struct_size::<ClipParams>();
+        shader.check_struct_size::<LodParams>();
+        shader.check_struct_size::<TransmissionParams>();
+        shader.check_struct_size::<EnvironmentParams>();
         let layout = <FillData as blade_graphics::ShaderData>::layout();
-        gpu.create_compute_pipeline(blade_graphics::ComputePipelineDesc {
-            name: "fill-gbuf",
-            data_layouts: &[&layout],
-            compute: shader.at("main"),
+        log.record("fill-gbuf", &[], false, || {
+            gpu.create_compute_pipeline(blade_graphics::ComputePipelineDesc {
+                name: "fill-gbuf",
+                data_layouts: &[&layout],
+                compute: shader.at("main"),
+            })
         })
     }
     fn create_ray_trace(
         shader: &blade_graphics::Shader,
         gpu: &blade_graphics::Context,
-    ) -> blade_graphics::ComputePipeline {
+        log: &PipelineLog,
+        custom_module: bool,
+    ) -> Vec<blade_graphics::ComputePipeline> {
+        use strum::IntoEnumIterator as _;
         shader.check_struct_size::<CameraParams>();
         shader.check_struct_size::<DebugParams>();
         shader.check_struct_size::<MainParams>();
+        shader.check_struct_size::<LightEntry>();
+        shader.check_struct_size::<PreviewParams>();
         shader.check_struct_size::<DebugVariance>();
         shader.check_struct_size::<DebugEntry>();
         let layout = <MainData as blade_graphics::ShaderData>::layout();
-        gpu.create_compute_pipeline(blade_graphics::ComputePipelineDesc {
-            name: "ray-trace",
-            data_layouts: &[&layout],
-            compute: shader.at("main"),
-        })
+        RenderMode::iter()
+            .map(|mode| {
+                let constants = [("RENDER_MODE", mode as u32 as f64)];
+                let name = format!("ray-trace-{:?}", mode);
+                log.record(&name, &constants, custom_module, || {
+                    gpu.create_compute_pipeline(blade_graphics::ComputePipelineDesc {
+                        name: &name,
+                        data_layouts: &[&layout],
+                        compute: shader.at("main").with_constants(&constants),
+                    })
+                })
+            })
+            .collect()
     }
 
     fn create_temporal_accum(
         shader: &blade_graphics::Shader,
         gpu: &blade_graphics::Context,
+        log: &PipelineLog,
     ) -> blade_graphics::ComputePipeline {
         let layout = <TemporalAccumData as blade_graphics::ShaderData>::layout();
-        gpu.create_compute_pipeline(blade_graphics::ComputePipelineDesc {
-            name: "temporal-accum",
-            data_layouts: &[&layout],
-            compute: shader.at("temporal_accum"),
+        log.record("temporal-accum", &[], false, || {
+            gpu.create_compute_pipeline(blade_graphics::ComputePipelineDesc {
+                name: "temporal-accum",
+                data_layouts: &[&layout],
+                compute: shader.at("temporal_accum"),
+            })
         })
     }
 
     fn create_a_trous(
         shader: &blade_graphics::Shader,
         gpu: &blade_graphics::Context,
+        log: &PipelineLog,
     ) -> blade_graphics::ComputePipeline {
         let layout = <ATrousData as blade_graphics::ShaderData>::layout();
-        gpu.create_compute_pipeline(blade_graphics::ComputePipelineDesc {
-            name: "a-trous",
-            data_layouts: &[&layout],
-            compute: shader.at("atrous3x3"),
+        log.record("a-trous", &[], false, || {
+            gpu.create_compute_pipeline(blade_graphics::ComputePipelineDesc {
+                name: "a-trous",
+                data_layouts: &[&layout],
+                compute: shader.at("atrous3x3"),
+            })
         })
     }
 
@@ -592,21 +1400,28 @@ impl ShaderPipelines {
         shader: &blade_graphics::Shader,
         info: blade_graphics::SurfaceInfo,
         gpu: &blade_graphics::Context,
+        log: &PipelineLog,
+        custom_module: bool,
     ) -> blade_graphics::RenderPipeline {
+        shader.check_struct_size::<ViewportParams>();
+        shader.check_struct_size::<ToneMapParams>();
+        shader.check_struct_size::<FogParams>();
         let layout = <PostProcData as blade_graphics::ShaderData>::layout();
-        gpu.create_render_pipeline(blade_graphics::RenderPipelineDesc {
-            name: "main",
-            data_layouts: &[&layout],
-            primitive: blade_graphics::PrimitiveState {
-                topology: blade_graphics::PrimitiveTopology::TriangleStrip,
-                ..Default::default()
-            },
-            vertex: shader.at("postfx_vs"),
-            vertex_fetches: &[],
-            fragment: Some(shader.at("postfx_fs")),
-            color_targets: &[info.format.into()],
-            depth_stencil: None,
-            multisample_state: blade_graphics::MultisampleState::default(),
+        log.record("post-proc", &[], custom_module, || {
+            gpu.create_render_pipeline(blade_graphics::RenderPipelineDesc {
+                name: "main",
+                data_layouts: &[&layout],
+                primitive: blade_graphics::PrimitiveState {
+                    topology: blade_graphics::PrimitiveTopology::TriangleStrip,
+                    ..Default::default()
+                },
+                vertex: shader.at("postfx_vs"),
+                vertex_fetches: &[],
+                fragment: Some(shader.at("postfx_fs")),
+                color_targets: &[info.format.into()],
+                depth_stencil: None,
+                multisample_state: blade_graphics::MultisampleState::default(),
+            })
         })
     }
 
@@ -618,21 +1433,29 @@ impl ShaderPipelines {
     ) -> Result<Self, &'static str> {
         let sh_main = shader_man[shaders.ray_trace].raw.as_ref().unwrap();
         let sh_a_trous = shader_man[shaders.a_trous].raw.as_ref().unwrap();
+        let log = PipelineLog::default();
         Ok(Self {
-            fill: Self::create_gbuf_fill(shader_man[shaders.fill_gbuf].raw.as_ref().unwrap(), gpu),
-            main: Self::create_ray_trace(sh_main, gpu),
-            temporal_accum: Self::create_temporal_accum(sh_a_trous, gpu),
-            a_trous: Self::create_a_trous(sh_a_trous, gpu),
+            fill: Self::create_gbuf_fill(
+                shader_man[shaders.fill_gbuf].raw.as_ref().unwrap(),
+                gpu,
+                &log,
+            ),
+            main: Self::create_ray_trace(sh_main, gpu, &log, false),
+            temporal_accum: Self::create_temporal_accum(sh_a_trous, gpu, &log),
+            a_trous: Self::create_a_trous(sh_a_trous, gpu, &log),
             post_proc: Self::create_post_proc(
                 shader_man[shaders.post_proc].raw.as_ref().unwrap(),
                 config.surface_info,
                 gpu,
+                &log,
+                false,
             ),
             env_prepare: EnvironmentMap::init_pipeline(
                 shader_man[shaders.env_prepare].raw.as_ref().unwrap(),
                 gpu,
             )?,
             reservoir_size: sh_main.get_struct_size("StoredReservoir"),
+            log,
         })
     }
 }
@@ -650,6 +1473,8 @@ pub struct FrameConfig {
 pub struct FrameResources {
     pub buffers: Vec<blade_graphics::Buffer>,
     pub acceleration_structures: Vec<blade_graphics::AccelerationStructure>,
+    pub textures: Vec<blade_graphics::Texture>,
+    pub texture_views: Vec<blade_graphics::TextureView>,
 }
 
 impl Renderer {
@@ -657,6 +1482,11 @@ impl Renderer {
     ///
     /// Panics if the system is not compatible.
     /// Records initialization routines into the given command encoder.
+    ///
+    /// All the pipelines are compiled here, including the variant of
+    /// every `RenderMode`, so nothing is compiled on first use later.
+    /// Only `hot_reload`, `set_shading_module`, and `set_custom_tonemap`
+    /// compile them again.
     #[profiling::function]
     pub fn new(
         encoder: &mut blade_graphics::CommandEncoder,
@@ -683,6 +1513,27 @@ impl Renderer {
                 config.surface_info,
             )
         };
+        let wireframe = WireframeRender::init(
+            gpu,
+            shader_man[shaders.wireframe].raw.as_ref().unwrap(),
+            config.surface_info,
+        );
+        let grid = GridRender::init(
+            gpu,
+            shader_man[shaders.grid].raw.as_ref().unwrap(),
+            config.surface_info,
+        );
+        let particles = ParticleRender::init(
+            gpu,
+            shader_man[shaders.particle].raw.as_ref().unwrap(),
+            config.surface_info,
+        );
+        let exposure =
+            ExposureRender::init(gpu, shader_man[shaders.exposure].raw.as_ref().unwrap());
+        let caustics =
+            CausticsRender::init(gpu, shader_man[shaders.caustics].raw.as_ref().unwrap());
+        let aov = AovRender::init(gpu, shader_man[shaders.aov].raw.as_ref().unwrap());
+        let sky = SkyRender::init(gpu, shader_man[shaders.sky].raw.as_ref().unwrap());
 
         let targets = RestirTargets::new(config.surface_size, sp.reservoir_size, encoder, gpu);
         let dummy = DummyResources::new(encoder, gpu);
@@ -711,7 +1562,7 @@ impl Renderer {
             targets,
             post_proc_input_index: 0,
             fill_pipeline: sp.fill,
-            main_pipeline: sp.main,
+            main_pipelines: sp.main,
             post_proc_pipeline: sp.post_proc,
             blur: Blur {
                 temporal_accum_pipeline: sp.temporal_accum,
@@ -722,18 +1573,80 @@ impl Renderer {
             env_map: EnvironmentMap::with_pipeline(&dummy, sp.env_prepare),
             dummy,
             hit_buffer: blade_graphics::Buffer::default(),
+            light_buffer: blade_graphics::Buffer::default(),
+            light_cdf_buffer: blade_graphics::Buffer::default(),
+            light_count: 0,
+            instance_data: Vec::new(),
+            instance_buffers: [blade_graphics::Buffer::default(); 2],
+            instance_capacity: 0,
             vertex_buffers: blade_graphics::BufferArray::new(),
             index_buffers: blade_graphics::BufferArray::new(),
             textures: blade_graphics::TextureArray::new(),
             samplers,
             reservoir_size: sp.reservoir_size,
+            pipeline_log: sp.log,
             debug,
+            wireframe,
+            grid,
+            particles,
+            exposure,
+            caustics,
+            aov,
+            sky,
             surface_size: config.surface_size,
             surface_info: config.surface_info,
             frame_index: 0,
             frame_scene_built: 0,
+            seen_scene_revision: 0,
+            scene_revision: 0,
             is_frozen: false,
+            is_accumulation_paused: false,
+            is_scene_dirty: true,
+            frames_since_change: 0,
+            convergence_frame_count: DEFAULT_CONVERGENCE_FRAME_COUNT,
+            last_settings: RenderSettings::default(),
+            tile_size: None,
+            next_tile: 0,
+            sample_strategy: SampleStrategy::default(),
+            render_mode: RenderMode::default(),
+            shading_module: None,
+            tonemap_module: None,
+            preview_light: PreviewLight::default(),
+            clip_params: ClipParams::default(),
+            mip_bias: 0.0,
+            firefly_clamp: f32::INFINITY,
+            dispersion: false,
+            max_bounces: DEFAULT_MAX_BOUNCES,
+            adaptive_sampling: None,
+            adaptive_reset_frame: 0,
+            samples_per_frame: 1,
+            sample_rate: SampleRate::new(),
+            fog_config: FogConfig::default(),
+            environment_params: EnvironmentParams::default(),
+            is_environment_dirty: false,
+            background: Background::default(),
+            is_sky_dirty: false,
+            color_lut: ColorLut {
+                view: dummy.white_volume_view,
+                size: 1,
+                strength: 0.0,
+            },
+            scene_fade: None,
+            matcap_view: dummy.white_view,
+            scene_objects: Vec::new(),
+            scene_geometry_offsets: Vec::new(),
+            scene_geometry_nodes: Vec::new(),
+            scene_bounds: crate::BoundingBox::default(),
+            scene_instance_bounds: Vec::new(),
+            draw_instance_bounds: false,
+            tlas_size: 0,
+            scene_env_map: None,
             texture_resource_lookup: HashMap::default(),
+            texture_overrides: HashMap::default(),
+            object_poses: HashMap::default(),
+            prev_object_poses: HashMap::default(),
+            posed_acceleration_structures: Vec::new(),
+            prev_posed_acceleration_structures: Vec::new(),
         }
     }
 
@@ -741,17 +1654,49 @@ impl Renderer {
     pub fn destroy(&mut self, gpu: &blade_graphics::Context) {
         // internal resources
         self.targets.destroy(gpu);
+        if let Some(fade) = self.scene_fade.take() {
+            fade.destroy(gpu);
+        }
         if self.hit_buffer != blade_graphics::Buffer::default() {
             gpu.destroy_buffer(self.hit_buffer);
         }
+        if self.light_buffer != blade_graphics::Buffer::default() {
+            gpu.destroy_buffer(self.light_buffer);
+        }
+        if self.light_cdf_buffer != blade_graphics::Buffer::default() {
+            gpu.destroy_buffer(self.light_cdf_buffer);
+        }
+        for buffer in self.instance_buffers.iter() {
+            if *buffer != blade_graphics::Buffer::default() {
+                gpu.destroy_buffer(*buffer);
+            }
+        }
         gpu.destroy_acceleration_structure(self.acceleration_structure);
         if self.prev_acceleration_structure != blade_graphics::AccelerationStructure::default() {
             gpu.destroy_acceleration_structure(self.prev_acceleration_structure);
         }
+        for blas in self
+            .posed_acceleration_structures
+            .drain(..)
+            .chain(self.prev_posed_acceleration_structures.drain(..))
+        {
+            gpu.destroy_acceleration_structure(blas);
+        }
+        for (_, ov) in self.texture_overrides.drain() {
+            gpu.destroy_texture_view(ov.view);
+            gpu.destroy_texture(ov.texture);
+        }
         // env map, dummy, and debug
         self.env_map.destroy(gpu);
         self.dummy.destroy(gpu);
         self.debug.destroy(gpu);
+        self.wireframe.destroy(gpu);
+        self.grid.destroy(gpu);
+        self.particles.destroy(gpu);
+        self.exposure.destroy(gpu);
+        self.caustics.destroy(gpu);
+        self.aov.destroy(gpu);
+        self.sky.destroy(gpu);
         // samplers
         gpu.destroy_sampler(self.samplers.nearest);
         gpu.destroy_sampler(self.samplers.linear);
@@ -759,7 +1704,7 @@ impl Renderer {
         gpu.destroy_compute_pipeline(&mut self.blur.temporal_accum_pipeline);
         gpu.destroy_compute_pipeline(&mut self.blur.a_trous_pipeline);
         gpu.destroy_compute_pipeline(&mut self.fill_pipeline);
-        gpu.destroy_compute_pipeline(&mut self.main_pipeline);
+        self.destroy_main_pipelines(gpu);
         gpu.destroy_render_pipeline(&mut self.post_proc_pipeline);
     }
 
@@ -779,6 +1724,13 @@ impl Renderer {
         tasks.extend(asset_hub.shaders.hot_reload(&mut self.shaders.post_proc));
         tasks.extend(asset_hub.shaders.hot_reload(&mut self.shaders.debug_draw));
         tasks.extend(asset_hub.shaders.hot_reload(&mut self.shaders.debug_blit));
+        tasks.extend(asset_hub.shaders.hot_reload(&mut self.shaders.wireframe));
+        tasks.extend(asset_hub.shaders.hot_reload(&mut self.shaders.grid));
+        tasks.extend(asset_hub.shaders.hot_reload(&mut self.shaders.particle));
+        tasks.extend(asset_hub.shaders.hot_reload(&mut self.shaders.exposure));
+        tasks.extend(asset_hub.shaders.hot_reload(&mut self.shaders.caustics));
+        tasks.extend(asset_hub.shaders.hot_reload(&mut self.shaders.aov));
+        tasks.extend(asset_hub.shaders.hot_reload(&mut self.shaders.sky));
 
         if tasks.is_empty() {
             return false;
@@ -792,7 +1744,8 @@ impl Renderer {
 
         if self.shaders.fill_gbuf != old.fill_gbuf {
             if let Ok(ref shader) = asset_hub.shaders[self.shaders.fill_gbuf].raw {
-                self.fill_pipeline = ShaderPipelines::create_gbuf_fill(shader, gpu);
+                self.fill_pipeline =
+                    ShaderPipelines::create_gbuf_fill(shader, gpu, &self.pipeline_log);
             }
         }
         if self.shaders.ray_trace != old.ray_trace {
@@ -801,20 +1754,32 @@ impl Renderer {
                     shader.get_struct_size("StoredReservoir"),
                     self.reservoir_size
                 );
-                self.main_pipeline = ShaderPipelines::create_ray_trace(shader, gpu);
+                match self.create_main_pipelines(shader, gpu) {
+                    Ok(pipelines) => {
+                        self.destroy_main_pipelines(gpu);
+                        self.main_pipelines = pipelines;
+                    }
+                    Err(e) => log::warn!("Unable to apply the shading module: {e}"),
+                }
             }
         }
         if self.shaders.a_trous != old.a_trous {
             if let Ok(ref shader) = asset_hub.shaders[self.shaders.a_trous].raw {
                 self.blur.temporal_accum_pipeline =
-                    ShaderPipelines::create_temporal_accum(shader, gpu);
-                self.blur.a_trous_pipeline = ShaderPipelines::create_a_trous(shader, gpu);
+                    ShaderPipelines::create_temporal_accum(shader, gpu, &self.pipeline_log);
+                self.blur.a_trous_pipeline =
+                    ShaderPipelines::create_a_trous(shader, gpu, &self.pipeline_log);
             }
         }
         if self.shaders.post_proc != old.post_proc {
             if let Ok(ref shader) = asset_hub.shaders[self.shaders.post_proc].raw {
-                self.post_proc_pipeline =
-                    ShaderPipelines::create_post_proc(shader, self.surface_info, gpu);
+                match self.create_post_proc_pipeline(shader, gpu) {
+                    Ok(pipeline) => {
+                        gpu.destroy_render_pipeline(&mut self.post_proc_pipeline);
+                        self.post_proc_pipeline = pipeline;
+                    }
+                    Err(e) => log::warn!("Unable to apply the tonemap module: {e}"),
+                }
             }
         }
         if self.shaders.debug_draw != old.debug_draw {
@@ -827,7 +1792,44 @@ impl Renderer {
                 self.debug.recreate_blit_pipeline(shader, gpu);
             }
         }
+        if self.shaders.wireframe != old.wireframe {
+            if let Ok(ref shader) = asset_hub.shaders[self.shaders.wireframe].raw {
+                self.wireframe.recreate_pipeline(shader, gpu);
+            }
+        }
+        if self.shaders.grid != old.grid {
+            if let Ok(ref shader) = asset_hub.shaders[self.shaders.grid].raw {
+                self.grid.recreate_pipeline(shader, gpu);
+            }
+        }
+        if self.shaders.particle != old.particle {
+            if let Ok(ref shader) = asset_hub.shaders[self.shaders.particle].raw {
+                self.particles.recreate_pipelines(shader, gpu);
+            }
+        }
+        if self.shaders.exposure != old.exposure {
+            if let Ok(ref shader) = asset_hub.shaders[self.shaders.exposure].raw {
+                self.exposure.recreate_pipeline(shader, gpu);
+            }
+        }
+        if self.shaders.caustics != old.caustics {
+            if let Ok(ref shader) = asset_hub.shaders[self.shaders.caustics].raw {
+                self.caustics.recreate_pipeline(shader, gpu);
+            }
+        }
+        if self.shaders.aov != old.aov {
+            if let Ok(ref shader) = asset_hub.shaders[self.shaders.aov].raw {
+                self.aov.recreate_pipeline(shader, gpu);
+            }
+        }
+        if self.shaders.sky != old.sky {
+            if let Ok(ref shader) = asset_hub.shaders[self.shaders.sky].raw {
+                self.sky.recreate_pipeline(shader, gpu);
+                self.is_sky_dirty = true;
+            }
+        }
 
+        self.request_redraw();
         true
     }
 
@@ -835,28 +1837,870 @@ impl Renderer {
         self.surface_size
     }
 
-    pub fn view_dummy_white(&self) -> blade_graphics::TextureView {
-        self.dummy.white_view
+    /// Toggle the wireframe overlay on top of the shaded image.
+    pub fn set_wireframe(&mut self, enabled: bool) {
+        self.wireframe.enabled = enabled;
     }
-    pub fn view_environment_main(&self) -> blade_graphics::TextureView {
-        self.env_map.main_view
+
+    /// Change the color and thickness of the wireframe overlay.
+    pub fn set_wireframe_config(&mut self, config: WireframeConfig) {
+        self.wireframe.config = config;
     }
-    pub fn view_environment_weight(&self) -> blade_graphics::TextureView {
-        self.env_map.weight_view
+
+    /// Toggle skipping the raster draws of the geometries outside of
+    /// the camera frustum. Enabled by default.
+    /// The ray tracing is not affected, since it sees the whole scene.
+    pub fn set_frustum_culling(&mut self, enabled: bool) {
+        self.wireframe.frustum_culling = enabled;
     }
 
-    #[profiling::function]
-    pub fn resize_screen(
+    /// Get the number of raster draws done and skipped by the frustum
+    /// culling in the last `post_proc`.
+    pub fn culling_stats(&self) -> CullingStats {
+        self.wireframe.stats.get()
+    }
+
+    /// Draw a horizontal reference grid on top of the shaded image,
+    /// hidden behind the scene geometry, or remove it if `None`.
+    pub fn set_grid(&mut self, config: Option<GridConfig>) {
+        self.grid.config = config;
+    }
+
+    /// Add a particle system, simulated by `update_particles` and drawn
+    /// by `post_proc` on top of the shaded image.
+    pub fn add_particle_system(
         &mut self,
-        size: blade_graphics::Extent,
-        encoder: &mut blade_graphics::CommandEncoder,
+        desc: ParticleDesc,
+        gpu: &blade_graphics::Context,
+    ) -> ParticleSystemId {
+        self.particles.add(desc, gpu)
+    }
+
+    /// Change the emission and appearance of a particle system.
+    /// The capacity can't be changed.
+    pub fn set_particle_system(&mut self, id: ParticleSystemId, desc: ParticleDesc) {
+        self.particles.set(id, desc);
+    }
+
+    pub fn remove_particle_system(&mut self, id: ParticleSystemId, temp: &mut FrameResources) {
+        self.particles.remove(id, &mut temp.buffers);
+    }
+
+    /// Emit and move the particles of all the systems by `time_delta` seconds.
+    pub fn update_particles(
+        &mut self,
+        command_encoder: &mut blade_graphics::CommandEncoder,
+        time_delta: f32,
+    ) {
+        self.particles.update(command_encoder, time_delta);
+    }
+
+    /// Choose how the random numbers for sampling are generated.
+    pub fn set_sampler(&mut self, strategy: SampleStrategy) {
+        self.sample_strategy = strategy;
+    }
+
+    fn destroy_main_pipelines(&mut self, gpu: &blade_graphics::Context) {
+        for pipeline in self.main_pipelines.iter_mut() {
+            gpu.destroy_compute_pipeline(pipeline);
+        }
+    }
+
+    fn create_main_pipelines(
+        &self,
+        shader: &blade_graphics::Shader,
+        gpu: &blade_graphics::Context,
+    ) -> Result<Vec<blade_graphics::ComputePipeline>, &'static str> {
+        const BEGIN: &str = "//shading-begin\n";
+        const END: &str = "//shading-end\n";
+        let code = match self.shading_module {
+            Some(ref code) => code,
+            None => {
+                return Ok(ShaderPipelines::create_ray_trace(
+                    shader,
+                    gpu,
+                    &self.pipeline_log,
+                    false,
+                ))
+            }
+        };
+        let base = shader.source();
+        let start = base.find(BEGIN).ok_or("shading hook is not found")? + BEGIN.len();
+        let end = start
+            + base[start..]
+                .find(END)
+                .ok_or("shading hook is not closed")?;
+        let source = format!("{}{}\n{}", &base[..start], code, &base[end..]);
+        let custom = gpu.try_create_shader(blade_graphics::ShaderDesc { source: &source })?;
+        Ok(ShaderPipelines::create_ray_trace(
+            &custom,
+            gpu,
+            &self.pipeline_log,
+            true,
+        ))
+    }
+
+    /// Replace the shading of the visible surfaces with custom WGSL code.
+    ///
+    /// The code has to define `fn shade(input: ShadingInput) -> vec3<f32>`,
+    /// where the inputs are described in "shading.inc.wgsl". The result
+    /// replaces the diffuse lighting, and is modulated by the albedo later.
+    /// Passing `None` restores the default shading.
+    ///
+    /// On compilation errors, the current shading is left intact.
+    /// The module is preserved across shader hot reloads.
+    /// The GPU has to be done with the previous frames, since
+    /// the old pipelines are destroyed here.
+    pub fn set_shading_module(
+        &mut self,
+        code: Option<&str>,
+        asset_hub: &crate::AssetHub,
+        gpu: &blade_graphics::Context,
+    ) -> Result<(), &'static str> {
+        let shader = match asset_hub.shaders[self.shaders.ray_trace].raw {
+            Ok(ref shader) => shader,
+            Err(e) => return Err(e),
+        };
+        let old_module = mem::replace(&mut self.shading_module, code.map(String::from));
+        match self.create_main_pipelines(shader, gpu) {
+            Ok(pipelines) => {
+                self.destroy_main_pipelines(gpu);
+                self.main_pipelines = pipelines;
+                self.request_redraw();
+                Ok(())
+            }
+            Err(e) => {
+                self.shading_module = old_module;
+                Err(e)
+            }
+        }
+    }
+
+    fn create_post_proc_pipeline(
+        &self,
+        shader: &blade_graphics::Shader,
+        gpu: &blade_graphics::Context,
+    ) -> Result<blade_graphics::RenderPipeline, &'static str> {
+        const BEGIN: &str = "//tonemap-begin\n";
+        const END: &str = "//tonemap-end\n";
+        let code = match self.tonemap_module {
+            Some(ref code) => code,
+            None => {
+                return Ok(ShaderPipelines::create_post_proc(
+                    shader,
+                    self.surface_info,
+                    gpu,
+                    &self.pipeline_log,
+                    false,
+                ))
+            }
+        };
+        let base = shader.source();
+        let start = base.find(BEGIN).ok_or("tonemap hook is not found")? + BEGIN.len();
+        let end = start
+            + base[start..]
+                .find(END)
+                .ok_or("tonemap hook is not closed")?;
+        let source = format!("{}{}\n{}", &base[..start], code, &base[end..]);
+        let custom = gpu.try_create_shader(blade_graphics::ShaderDesc { source: &source })?;
+        Ok(ShaderPipelines::create_post_proc(
+            &custom,
+            self.surface_info,
+            gpu,
+            &self.pipeline_log,
+            true,
+        ))
+    }
+
+    /// Replace the tone mapping operator with custom WGSL code.
+    ///
+    /// The code has to define `fn tonemap(color: vec3<f32>) -> vec3<f32>`,
+    /// mapping the exposed linear color into the [0, 1] display range.
+    /// It applies with `OutputTransform::Tonemap`, after the exposure and
+    /// before the color grading LUT, and can read `tone_map_params`
+    /// from "post-proc.wgsl". Passing `None` restores the built-in operator.
+    ///
+    /// On compilation errors, the current operator is left intact.
+    /// The module is preserved across shader hot reloads, and calling this
+    /// again with the edited code reloads it. The GPU has to be done with
+    /// the previous frames, since the old pipeline is destroyed here.
+    pub fn set_custom_tonemap(
+        &mut self,
+        code: Option<&str>,
+        asset_hub: &crate::AssetHub,
+        gpu: &blade_graphics::Context,
+    ) -> Result<(), &'static str> {
+        let shader = match asset_hub.shaders[self.shaders.post_proc].raw {
+            Ok(ref shader) => shader,
+            Err(e) => return Err(e),
+        };
+        let old_module = mem::replace(&mut self.tonemap_module, code.map(String::from));
+        match self.create_post_proc_pipeline(shader, gpu) {
+            Ok(pipeline) => {
+                gpu.destroy_render_pipeline(&mut self.post_proc_pipeline);
+                self.post_proc_pipeline = pipeline;
+                Ok(())
+            }
+            Err(e) => {
+                self.tonemap_module = old_module;
+                Err(e)
+            }
+        }
+    }
+
+    /// List the pipelines of the main passes, as last compiled, including
+    /// every specialized variant of the ray tracing pass.
+    ///
+    /// Useful for keeping track of the number of variants and
+    /// of their startup cost.
+    pub fn pipeline_report(&self) -> Vec<PipelineInfo> {
+        self.pipeline_log.0.borrow().clone()
+    }
+
+    /// Switch between the full quality and the fast preview lighting.
+    ///
+    /// The accumulated history is not valid across the switch,
+    /// so `FrameConfig::reset_reservoirs` should be set on the next frame.
+    /// Each mode has its own specialized pipeline, so switching is free.
+    pub fn set_mode(&mut self, mode: RenderMode) {
+        self.render_mode = mode;
+    }
+
+    /// Change the light used by `RenderMode::DirectOnly`.
+    pub fn set_preview_light(&mut self, light: PreviewLight) {
+        self.preview_light = light;
+    }
+
+    /// Cut away the geometry on the positive side of any of the planes
+    /// from the primary rays, for cross-section views.
+    ///
+    /// Up to `MAX_CLIP_PLANES` planes are supported.
+    pub fn set_clip_planes(&mut self, planes: &[crate::Plane]) {
+        assert!(planes.len() <= MAX_CLIP_PLANES);
+        for (dst, plane) in self.clip_params.planes.iter_mut().zip(planes) {
+            *dst = [plane.normal.x, plane.normal.y, plane.normal.z, plane.offset];
+        }
+        self.clip_params.count = planes.len() as u32;
+    }
+
+    /// Fill the cut surfaces of the closed geometry with a solid color,
+    /// or leave them open if `None`.
+    pub fn set_clip_cap_color(&mut self, color: Option<[f32; 3]>) {
+        self.clip_params.cap_color = match color {
+            Some([r, g, b]) => [r, g, b, 1.0],
+            None => [0.0; 4],
+        };
+    }
+
+    /// Split the ray tracing of a frame into multiple dispatches,
+    /// each covering up to a tile of the given size.
+    ///
+    /// This keeps every GPU operation short when rendering large images
+    /// on slow hardware, which could otherwise trigger a driver timeout.
+    /// `ray_trace` records all the tiles into the same submission,
+    /// so use `ray_trace_tile` to submit them one by one instead.
+    /// Passing `None` traces the whole frame at once, which is the default.
+    pub fn set_tile_size(&mut self, tile_size: Option<blade_graphics::Extent>) {
+        if let Some(size) = tile_size {
+            assert!(!size.is_empty(), "Empty tile size");
+        }
+        self.tile_size = tile_size;
+        self.next_tile = 0;
+    }
+
+    /// Offset the mip level selected for the material textures.
+    ///
+    /// Positive values make textures blurrier, negative values sharper.
+    pub fn set_mip_bias(&mut self, bias: f32) {
+        self.mip_bias = bias;
+    }
+
+    /// Limit the luminance of every sample before it gets accumulated,
+    /// trading a bit of energy for the removal of bright speckles.
+    ///
+    /// Pass `f32::INFINITY` to disable the clamp, which is the default.
+    pub fn set_firefly_clamp(&mut self, max_luminance: f32) {
+        self.firefly_clamp = max_luminance;
+    }
+
+    /// Trace the light refracted by transmissive materials with a random
+    /// wavelength per path, splitting it into colors according to
+    /// `Material::dispersion`.
+    ///
+    /// This adds color noise that takes more frames to converge.
+    /// Disabled by default, in which case all the wavelengths refract the same.
+    pub fn set_dispersion(&mut self, enabled: bool) {
+        self.dispersion = enabled;
+    }
+
+    /// Set how many times a path can be reflected by mirrors
+    /// or refracted by transmissive materials before it's terminated.
+    ///
+    /// Facing mirrors show that many reflections of each other.
+    pub fn set_max_bounces(&mut self, count: u32) {
+        self.max_bounces = count;
+    }
+
+    /// Spend more light samples on the pixels that stay noisy,
+    /// or the same number everywhere if `None`.
+    ///
+    /// The chosen density is shown by `DebugMode::SampleDensity`.
+    pub fn set_adaptive_sampling(&mut self, config: Option<AdaptiveConfig>) {
+        if self.adaptive_sampling.is_none() && config.is_some() {
+            // the moments are not tracked while it's disabled
+            self.adaptive_reset_frame = self.frame_index + 1;
+        }
+        self.adaptive_sampling = config;
+    }
+
+    /// Multiply the light samples taken by every pixel in a frame.
+    ///
+    /// More samples per frame converge the image in fewer frames,
+    /// at the cost of a longer frame on the GPU. Together with the GPU
+    /// timings, this lets the application keep a stable framerate while
+    /// converging as fast as the hardware allows.
+    /// With the adaptive sampling, this is the minimum per pixel.
+    /// The default is 1.
+    pub fn set_samples_per_frame(&mut self, count: u32) {
+        assert_ne!(count, 0, "At least one sample per frame is required");
+        self.samples_per_frame = count;
+    }
+
+    /// Number of the light samples traced per second, over all pixels,
+    /// measured across the recent frames.
+    ///
+    /// This doesn't include the extra samples of the adaptive sampling.
+    pub fn samples_per_second(&self) -> f64 {
+        self.sample_rate.per_second
+    }
+
+    /// Adapt the tone mapping to the brightness of the rendered image,
+    /// overriding `PostProcConfig::average_luminocity` when enabled.
+    pub fn set_auto_exposure(&mut self, config: AutoExposureConfig) {
+        self.exposure.config = config;
+    }
+
+    /// Trace photons from the punctual lights every frame, adding the light
+    /// focused by mirrors and refractive surfaces to the path traced image.
+    ///
+    /// The environment map doesn't cast caustics.
+    pub fn set_caustics(&mut self, config: CausticsConfig) {
+        self.caustics.config = config;
+    }
+
+    /// Get the exposure value (EV100) that auto exposure has adapted to,
+    /// or `None` if it's disabled.
+    pub fn auto_exposure_ev(&self) -> Option<f32> {
+        self.exposure.current_ev()
+    }
+
+    /// Get the current settings, to be restored later with `import_settings`.
+    pub fn export_settings(&self) -> RenderSettings {
+        let clip_count = self.clip_params.count as usize;
+        RenderSettings {
+            mode: self.render_mode,
+            preview_light: self.preview_light,
+            sample_strategy: self.sample_strategy,
+            mip_bias: self.mip_bias,
+            firefly_clamp: self.firefly_clamp,
+            dispersion: self.dispersion,
+            max_bounces: self.max_bounces,
+            fog: self.fog_config,
+            auto_exposure: self.exposure.config,
+            caustics: self.caustics.config,
+            wireframe: if self.wireframe.enabled {
+                Some(self.wireframe.config)
+            } else {
+                None
+            },
+            grid: self.grid.config,
+            adaptive_sampling: self.adaptive_sampling,
+            clip_planes: self.clip_params.planes[..clip_count]
+                .iter()
+                .map(|&[x, y, z, offset]| crate::Plane {
+                    normal: [x, y, z].into(),
+                    offset,
+                })
+                .collect(),
+            clip_cap_color: match self.clip_params.cap_color {
+                [r, g, b, a] if a > 0.0 => Some([r, g, b]),
+                _ => None,
+            },
+        }
+    }
+
+    /// Apply all the settings previously returned by `export_settings`.
+    pub fn import_settings(&mut self, settings: &RenderSettings) {
+        self.set_mode(settings.mode);
+        self.set_preview_light(settings.preview_light);
+        self.set_sampler(settings.sample_strategy);
+        self.set_mip_bias(settings.mip_bias);
+        self.set_firefly_clamp(settings.firefly_clamp);
+        self.set_dispersion(settings.dispersion);
+        self.set_max_bounces(settings.max_bounces);
+        self.set_fog(settings.fog);
+        self.set_auto_exposure(settings.auto_exposure);
+        self.set_caustics(settings.caustics);
+        self.set_wireframe(settings.wireframe.is_some());
+        if let Some(config) = settings.wireframe {
+            self.set_wireframe_config(config);
+        }
+        self.set_grid(settings.grid);
+        self.set_adaptive_sampling(settings.adaptive_sampling);
+        self.set_clip_planes(&settings.clip_planes);
+        self.set_clip_cap_color(settings.clip_cap_color);
+    }
+
+    /// Rotate the environment map around the scene, independently of the camera.
+    ///
+    /// This is a cheap update of the shader parameters, so it can be animated
+    /// every frame. The accumulated lighting is reset on the next `prepare`.
+    pub fn set_environment_rotation(&mut self, rotation: mint::Quaternion<f32>) {
+        let rotation = glam::Quat::from(rotation).normalize().to_array();
+        if self.environment_params.rotation != rotation {
+            self.environment_params.rotation = rotation;
+            self.is_environment_dirty = true;
+            // the sun stays in place, so the sky map is rotated the other way
+            self.is_sky_dirty = self.background != Background::EnvironmentMap;
+        }
+    }
+
+    /// Scale the radiance of the environment map, which is one by default.
+    ///
+    /// Like `set_environment_rotation`, this is cheap to change every frame,
+    /// and resets the accumulated lighting.
+    pub fn set_environment_intensity(&mut self, intensity: f32) {
+        if self.environment_params.intensity != intensity {
+            self.environment_params.intensity = intensity;
+            self.is_environment_dirty = true;
+        }
+    }
+
+    /// Choose what the rays that miss the scene see, lighting it as well.
+    ///
+    /// The procedural sky replaces the environment map given to
+    /// `build_scene`, and is baked into a map of its own on the next
+    /// `prepare`, along with the importance sampling weights. Changing the
+    /// sun every frame is possible, but resets the accumulated lighting.
+    pub fn set_background(&mut self, background: Background) {
+        if self.background == background {
+            return;
+        }
+        let is_sky = background != Background::EnvironmentMap;
+        if is_sky != (self.background != Background::EnvironmentMap) {
+            // the map is chosen by `build_scene`
+            self.is_scene_dirty = true;
+        }
+        self.background = background;
+        self.is_sky_dirty = is_sky;
+    }
+
+    pub fn background(&self) -> Background {
+        self.background
+    }
+
+    /// Fade the distant surfaces into fog.
+    pub fn set_fog(&mut self, config: FogConfig) {
+        self.fog_config = config;
+    }
+
+    /// Apply a 3D color grading LUT after tone mapping, blended
+    /// with the original color by `strength` in [0, 1].
+    ///
+    /// The LUT texture is expected to be loaded from a `.cube` file.
+    /// Passing `None` disables color grading.
+    pub fn set_color_lut(&mut self, lut: Option<&crate::Texture>, strength: f32) {
+        self.request_redraw();
+        self.color_lut = match lut {
+            Some(texture) => {
+                assert_eq!(texture.extent.width, texture.extent.depth);
+                ColorLut {
+                    view: texture.view,
+                    size: texture.extent.width,
+                    strength: strength.clamp(0.0, 1.0),
+                }
+            }
+            None => ColorLut {
+                view: self.dummy.white_volume_view,
+                size: 1,
+                strength: 0.0,
+            },
+        };
+    }
+
+    /// Set the material capture texture used by `RenderMode::Matcap`.
+    ///
+    /// It's expected to show a lit sphere that fills the image.
+    /// Passing `None` shows plain white instead.
+    pub fn set_matcap(&mut self, matcap: Option<&crate::Texture>) {
+        self.request_redraw();
+        self.matcap_view = match matcap {
+            Some(texture) => texture.view,
+            None => self.dummy.white_view,
+        };
+    }
+
+    pub fn view_dummy_white(&self) -> blade_graphics::TextureView {
+        self.dummy.white_view
+    }
+    pub fn view_environment_main(&self) -> blade_graphics::TextureView {
+        self.env_map.main_view
+    }
+    pub fn view_environment_weight(&self) -> blade_graphics::TextureView {
+        self.env_map.weight_view
+    }
+
+    #[profiling::function]
+    pub fn resize_screen(
+        &mut self,
+        size: blade_graphics::Extent,
+        encoder: &mut blade_graphics::CommandEncoder,
         gpu: &blade_graphics::Context,
     ) {
         self.surface_size = size;
         self.targets.destroy(gpu);
+        self.aov.release(gpu);
+        if let Some(fade) = self.scene_fade.take() {
+            fade.destroy(gpu);
+        }
+        self.request_redraw();
         self.targets = RestirTargets::new(size, self.reservoir_size, encoder, gpu);
     }
 
+    /// Create an additional view to render the same scene into,
+    /// for example from a different camera or at a different resolution.
+    pub fn create_view(
+        &self,
+        size: blade_graphics::Extent,
+        encoder: &mut blade_graphics::CommandEncoder,
+        gpu: &blade_graphics::Context,
+    ) -> RenderView {
+        RenderView {
+            targets: RestirTargets::new(size, self.reservoir_size, encoder, gpu),
+            post_proc_input_index: 0,
+            surface_size: size,
+            frame_index: 0,
+            frame_scene_built: 0,
+            seen_scene_revision: self.scene_revision,
+            scene_fade: None,
+            exposure_history: exposure::ExposureHistory::new(gpu),
+            aov_targets: None,
+            frames_since_change: 0,
+            last_settings: self.last_settings.clone(),
+            is_accumulation_paused: false,
+            adaptive_reset_frame: 0,
+            sample_rate: SampleRate::new(),
+            next_tile: 0,
+        }
+    }
+
+    pub fn destroy_view(&self, view: RenderView, gpu: &blade_graphics::Context) {
+        view.targets.destroy(gpu);
+        if let Some(fade) = view.scene_fade {
+            fade.destroy(gpu);
+        }
+        view.exposure_history.destroy(gpu);
+        if let Some(targets) = view.aov_targets {
+            targets.destroy(gpu);
+        }
+    }
+
+    /// Run `fun` with the `view` being the destination of `prepare`, `ray_trace`,
+    /// `denoise`, `post_proc`, and `resize_screen`.
+    ///
+    /// The acceleration structures, geometry, and materials are shared between
+    /// the views, so the scene only needs to be built once per frame.
+    /// Accumulation and denoising history are kept separately for each view,
+    /// as well as the auto exposure, the AOVs, the convergence of `needs_redraw`,
+    /// the pause of the accumulation, the sample rate, and the `ray_trace_tile` progress.
+    /// The view has to be post-processed into a target of the same format as the main surface.
+    pub fn with_view<T>(&mut self, view: &mut RenderView, fun: impl FnOnce(&mut Self) -> T) -> T {
+        self.swap_view(view);
+        let result = fun(self);
+        self.swap_view(view);
+        result
+    }
+
+    /// Ray trace the left and the right eye views, for VR or anaglyph output.
+    ///
+    /// Each eye keeps its own accumulation in a `RenderView`, while the scene
+    /// is shared, so `build_scene` still runs once per frame before this.
+    /// The cameras are typically derived with `Camera::stereo_pair`.
+    /// The eyes are denoised if a `denoiser` is given, and then the views
+    /// can be post-processed with `post_proc_stereo`, or individually
+    /// with `with_view`, e.g. into the layers of a texture array.
+    #[allow(clippy::too_many_arguments)]
+    pub fn render_stereo(
+        &mut self,
+        command_encoder: &mut blade_graphics::CommandEncoder,
+        views: &mut [RenderView; 2],
+        cameras: &[crate::Camera; 2],
+        config: FrameConfig,
+        debug_config: DebugConfig,
+        ray_config: RayConfig,
+        denoiser: Option<DenoiserConfig>,
+    ) {
+        for (view, camera) in views.iter_mut().zip(cameras.iter()) {
+            self.with_view(view, |renderer| {
+                renderer.prepare(command_encoder, camera, config);
+                renderer.ray_trace(command_encoder, debug_config, ray_config);
+                if let Some(denoiser_config) = denoiser {
+                    renderer.denoise(command_encoder, denoiser_config);
+                }
+            });
+        }
+    }
+
+    /// Post-process the eyes of `render_stereo` side by side into a render pass,
+    /// with the left eye starting at the `origin` and the right eye following it.
+    ///
+    /// Both views need to have the same size.
+    pub fn post_proc_stereo(
+        &mut self,
+        pass: &mut blade_graphics::RenderCommandEncoder,
+        views: &mut [RenderView; 2],
+        origin: [i32; 2],
+        debug_config: DebugConfig,
+        pp_config: PostProcConfig,
+    ) {
+        let mut x = origin[0];
+        for view in views.iter_mut() {
+            let size = view.surface_size;
+            let config = PostProcConfig {
+                viewport: Some(blade_graphics::ScissorRect {
+                    x,
+                    y: origin[1],
+                    w: size.width,
+                    h: size.height,
+                }),
+                ..pp_config
+            };
+            self.with_view(view, |renderer| {
+                renderer.post_proc(pass, debug_config, config, &[], &[]);
+            });
+            x += size.width as i32;
+        }
+    }
+
+    fn swap_view(&mut self, view: &mut RenderView) {
+        mem::swap(&mut self.targets, &mut view.targets);
+        mem::swap(
+            &mut self.post_proc_input_index,
+            &mut view.post_proc_input_index,
+        );
+        mem::swap(&mut self.surface_size, &mut view.surface_size);
+        mem::swap(&mut self.frame_index, &mut view.frame_index);
+        mem::swap(&mut self.frame_scene_built, &mut view.frame_scene_built);
+        mem::swap(&mut self.seen_scene_revision, &mut view.seen_scene_revision);
+        mem::swap(&mut self.scene_fade, &mut view.scene_fade);
+        mem::swap(&mut self.exposure.history, &mut view.exposure_history);
+        mem::swap(&mut self.aov.targets, &mut view.aov_targets);
+        mem::swap(&mut self.frames_since_change, &mut view.frames_since_change);
+        mem::swap(&mut self.last_settings, &mut view.last_settings);
+        mem::swap(
+            &mut self.is_accumulation_paused,
+            &mut view.is_accumulation_paused,
+        );
+        mem::swap(
+            &mut self.adaptive_reset_frame,
+            &mut view.adaptive_reset_frame,
+        );
+        mem::swap(&mut self.sample_rate, &mut view.sample_rate);
+        mem::swap(&mut self.next_tile, &mut view.next_tile);
+    }
+
+    /// Force the next `build_scene` to rebuild everything,
+    /// even if the objects and the environment map are the same.
+    ///
+    /// Needed when the contents of a model or a texture change in place,
+    /// for example after a hot reload of the asset.
+    pub fn mark_dirty(&mut self) {
+        self.is_scene_dirty = true;
+    }
+
+    /// Override the transforms of the geometries of an object, relative
+    /// to the object, for example to play an animation back with
+    /// `AnimationPlayer::pose`. `None` returns to the transforms of the model.
+    ///
+    /// The pose is applied by the next `build_scene`, which builds a separate
+    /// BLAS for the object, since the one of the model is shared.
+    /// There is no refitting, so the BLAS is rebuilt every time the pose changes,
+    /// but this only costs as much as the animated objects.
+    /// The pose is ignored if its length doesn't match the geometries of the model.
+    ///
+    /// The pose of the previous build is kept for the motion vectors,
+    /// so that the temporal passes follow the animated geometries.
+    /// A pose that stops changing is rebuilt once more, to settle the motion.
+    pub fn set_object_pose(
+        &mut self,
+        object_index: usize,
+        pose: Option<&[blade_graphics::Transform]>,
+    ) {
+        let changed = match pose {
+            Some(transforms) => {
+                let old = self.object_poses.insert(object_index, transforms.to_vec());
+                old.as_deref() != Some(transforms)
+            }
+            None => self.object_poses.remove(&object_index).is_some(),
+        };
+        // a pose that stops changing still has the motion of the last change
+        let moved =
+            self.prev_object_poses.get(&object_index) != self.object_poses.get(&object_index);
+        self.is_scene_dirty |= changed || moved;
+    }
+
+    /// Replace the contents of a material texture, for example
+    /// to show the edits made in a painting tool without reloading the model.
+    ///
+    /// The texture handles are found in `Model::materials`. The `data` contains
+    /// the texels of a single mip level, with the rows tightly packed.
+    /// The replacement is kept when the scene is rebuilt, and it's reused by
+    /// the following calls as long as the format and the extent don't change.
+    /// Restarts the accumulation, like a scene rebuild.
+    #[allow(clippy::too_many_arguments)]
+    pub fn replace_texture(
+        &mut self,
+        handle: blade_asset::Handle<crate::Texture>,
+        data: &[u8],
+        format: blade_graphics::TextureFormat,
+        extent: blade_graphics::Extent,
+        command_encoder: &mut blade_graphics::CommandEncoder,
+        gpu: &blade_graphics::Context,
+        temp: &mut FrameResources,
+    ) {
+        let block_info = format.block_info();
+        let (block_width, block_height) = (
+            block_info.dimensions.0 as u32,
+            block_info.dimensions.1 as u32,
+        );
+        let blocks_per_row = (extent.width + block_width - 1) / block_width;
+        let block_rows = (extent.height + block_height - 1) / block_height;
+        let bytes_per_row = blocks_per_row * block_info.size as u32;
+        assert_eq!(
+            data.len(),
+            (bytes_per_row * block_rows) as usize,
+            "Texture data doesn't match the extent"
+        );
+
+        let is_compatible = match self.texture_overrides.get(&handle) {
+            Some(ov) => ov.format == format && ov.extent == extent,
+            None => false,
+        };
+        if !is_compatible {
+            if let Some(ov) = self.texture_overrides.remove(&handle) {
+                temp.texture_views.push(ov.view);
+                temp.textures.push(ov.texture);
+            }
+            let texture = gpu.create_texture(blade_graphics::TextureDesc {
+                name: "replaced",
+                format,
+                size: extent,
+                array_layer_count: 1,
+                mip_level_count: 1,
+                dimension: blade_graphics::TextureDimension::D2,
+                usage: blade_graphics::TextureUsage::COPY | blade_graphics::TextureUsage::RESOURCE,
+                sample_count: 1,
+            });
+            let view = gpu.create_texture_view(
+                texture,
+                blade_graphics::TextureViewDesc {
+                    name: "replaced",
+                    format,
+                    dimension: blade_graphics::ViewDimension::D2,
+                    subresources: &Default::default(),
+                },
+            );
+            command_encoder.init_texture(texture);
+            self.texture_overrides.insert(
+                handle,
+                TextureOverride {
+                    texture,
+                    view,
+                    format,
+                    extent,
+                },
+            );
+        }
+        let ov = &self.texture_overrides[&handle];
+
+        let stage = gpu.create_buffer(blade_graphics::BufferDesc {
+            name: "replaced texture stage",
+            size: data.len() as u64,
+            memory: blade_graphics::Memory::Upload,
+        });
+        unsafe {
+            ptr::copy_nonoverlapping(data.as_ptr(), stage.data(), data.len());
+        }
+        temp.buffers.push(stage);
+        let mut transfer = command_encoder.transfer("replace texture");
+        transfer.copy_buffer_to_texture(stage.into(), bytes_per_row, ov.texture.into(), extent);
+
+        for (&res_id, &res_handle) in self.texture_resource_lookup.iter() {
+            if res_handle == handle {
+                self.textures[res_id] = ov.view;
+            }
+        }
+        self.scene_revision += 1;
+    }
+
+    /// Switch to a different scene, crossfading from the current image
+    /// to the new one over the given duration.
+    ///
+    /// The last image is kept, and blended linearly on top of the new scene
+    /// by `post_proc` while it converges. The models of the new objects
+    /// are expected to be loaded already: until then, the application can
+    /// keep rendering the old scene, while the asset hub loads them
+    /// and builds their acceleration structures in the background.
+    /// The snapshot textures are kept for the next transitions.
+    pub fn transition_to_scene(
+        &mut self,
+        command_encoder: &mut blade_graphics::CommandEncoder,
+        objects: &[crate::Object],
+        env_map: Option<blade_asset::Handle<crate::Texture>>,
+        duration: std::time::Duration,
+        asset_hub: &crate::AssetHub,
+        gpu: &blade_graphics::Context,
+        temp: &mut FrameResources,
+    ) {
+        let size = self.surface_size;
+        let fade = self
+            .scene_fade
+            .get_or_insert_with(|| SceneFade::new(size, command_encoder, gpu));
+        fade.start = std::time::Instant::now();
+        fade.duration = duration;
+
+        let cur = self.frame_index % 2;
+        let mut transfer = command_encoder.transfer("scene fade");
+        for (src, layer, dst) in [
+            (
+                self.targets.light_diffuse.texture,
+                self.post_proc_input_index,
+                fade.light.texture,
+            ),
+            (self.targets.albedo.texture, 0, fade.albedo.texture),
+            (self.targets.depth.texture, cur, fade.depth.texture),
+        ] {
+            transfer.copy_texture_to_texture(
+                blade_graphics::TexturePiece {
+                    texture: src,
+                    mip_level: 0,
+                    array_layer: layer as u32,
+                    origin: [0; 3],
+                },
+                dst.into(),
+                size,
+            );
+        }
+        drop(transfer);
+
+        self.build_scene(command_encoder, objects, env_map, asset_hub, gpu, temp);
+    }
+
+    /// Build the scene for ray tracing.
+    ///
+    /// Does nothing, and doesn't allocate any temporary resources,
+    /// if neither the objects nor the environment map have changed
+    /// since the last build, and the renderer isn't marked dirty.
     #[profiling::function]
     pub fn build_scene(
         &mut self,
@@ -867,12 +2711,26 @@ impl Renderer {
         gpu: &blade_graphics::Context,
         temp: &mut FrameResources,
     ) {
-        let (env_view, env_extent) = match env_map {
-            Some(handle) => {
+        if !self.is_scene_dirty
+            && self.scene_env_map == env_map
+            && self.scene_objects.as_slice() == objects
+        {
+            return;
+        }
+        self.is_scene_dirty = false;
+        self.scene_env_map = env_map;
+        self.scene_objects.clear();
+        self.scene_objects.extend_from_slice(objects);
+
+        let (env_view, env_extent) = match (self.background, env_map) {
+            (Background::ProceduralSky { .. }, _) => self.sky.map(command_encoder, gpu),
+            (Background::EnvironmentMap, Some(handle)) => {
                 let asset = &asset_hub.textures[handle];
                 (asset.view, asset.extent)
             }
-            None => (self.dummy.white_view, blade_graphics::Extent::default()),
+            (Background::EnvironmentMap, None) => {
+                (self.dummy.white_view, blade_graphics::Extent::default())
+            }
         };
         self.env_map
             .assign(env_view, env_extent, command_encoder, gpu);
@@ -882,6 +2740,12 @@ impl Renderer {
                 .push(self.prev_acceleration_structure);
         }
         self.prev_acceleration_structure = self.acceleration_structure;
+        temp.acceleration_structures
+            .append(&mut self.prev_posed_acceleration_structures);
+        mem::swap(
+            &mut self.prev_posed_acceleration_structures,
+            &mut self.posed_acceleration_structures,
+        );
 
         let geometry_count = objects
             .iter()
@@ -914,36 +2778,94 @@ impl Renderer {
         self.vertex_buffers.clear();
         self.index_buffers.clear();
         self.textures.clear();
+        self.wireframe.clear();
         let dummy_white = self.textures.alloc(self.dummy.white_view);
         let dummy_black = self.textures.alloc(self.dummy.black_view);
 
         let mut geometry_index = 0;
+        self.scene_geometry_offsets.clear();
+        self.scene_geometry_nodes.clear();
+        self.scene_bounds = crate::BoundingBox::default();
+        self.scene_instance_bounds.clear();
         let mut instances = Vec::with_capacity(objects.len());
         let mut blases = Vec::with_capacity(objects.len());
         let mut texture_indices = HashMap::new();
+        let prev_poses = mem::replace(&mut self.prev_object_poses, self.object_poses.clone());
 
-        for object in objects {
+        for (object_index, object) in objects.iter().enumerate() {
             let m3_object = mat3_transform(&object.transform);
+            let m4_object = mat4_transform(&object.transform);
             let model = &asset_hub.models[object.model];
+            assert!(
+                !model.geometry_only,
+                "Model '{}' is loaded as geometry only, and can't be rendered",
+                model.name
+            );
+            let pose = self
+                .object_poses
+                .get(&object_index)
+                .filter(|pose| pose.len() == model.geometries.len());
+            // the newly posed objects start without motion
+            let prev_pose = prev_poses
+                .get(&object_index)
+                .filter(|pose| pose.len() == model.geometries.len())
+                .or(pose);
             instances.push(blade_graphics::AccelerationStructureInstance {
                 acceleration_structure_index: blases.len() as u32,
                 transform: object.transform,
                 mask: 0xFF,
                 custom_index: geometry_index as u32,
             });
-            blases.push(model.acceleration_structure);
+            match pose {
+                Some(transforms) => {
+                    let blas = self.build_posed_blas(model, transforms, command_encoder, gpu, temp);
+                    self.posed_acceleration_structures.push(blas);
+                    blases.push(blas);
+                }
+                None => blases.push(model.acceleration_structure),
+            }
+            self.scene_geometry_offsets.push(geometry_index as u32);
+            self.scene_bounds
+                .union(&model.bounds.transformed(&object.transform));
+            let mut instance_bounds = crate::BoundingBox::default();
 
-            for geometry in model.geometries.iter() {
+            for (local_index, geometry) in model.geometries.iter().enumerate() {
+                let geometry_transform = match pose {
+                    Some(transforms) => transforms[local_index],
+                    None => geometry.transform,
+                };
+                let prev_geometry_transform = match prev_pose {
+                    Some(transforms) => transforms[local_index],
+                    None => geometry.transform,
+                };
+                self.scene_geometry_nodes.push(geometry.node_index);
+                instance_bounds.union(&geometry.bounds.transformed(&geometry_transform));
                 let material = &model.materials[geometry.material_index];
                 let vertex_offset =
                     geometry.vertex_range.start as u64 * mem::size_of::<crate::Vertex>() as u64;
+                let m3_geo_to_world = m3_object * mat3_transform(&geometry_transform);
                 let geometry_to_world_rotation = {
-                    let m3_geo = mat3_transform(&geometry.transform);
-                    let m3_normal = (m3_object * m3_geo).inverse().transpose();
+                    let m3_normal = m3_geo_to_world.inverse().transpose();
                     let quat = glam::Quat::from_mat3(&m3_normal);
                     let qv = glam::Vec4::from(quat) * 127.0;
                     [qv.x as i8, qv.y as i8, qv.z as i8, qv.w as i8]
                 };
+                // Mirroring transforms reverse the winding, as glTF specifies
+                // for the nodes with a negative determinant.
+                let winding = if m3_geo_to_world.determinant() < 0.0 {
+                    -model.winding
+                } else {
+                    model.winding
+                };
+                self.wireframe.add(
+                    &(m4_object * mat4_transform(&geometry_transform)),
+                    model.vertex_buffer.at(vertex_offset),
+                    geometry
+                        .index_type
+                        .map(|_| model.index_buffer.at(geometry.index_offset)),
+                    geometry.triangle_count,
+                    &geometry.bounds,
+                );
 
                 let hit_entry = HitEntry {
                     index_buf: match geometry.index_type {
@@ -955,19 +2877,23 @@ impl Renderer {
                     vertex_buf: self
                         .vertex_buffers
                         .alloc(model.vertex_buffer.at(vertex_offset)),
-                    winding: model.winding,
+                    winding,
                     geometry_to_world_rotation,
                     geometry_to_object: mint::ColumnMatrix4::from(mint::RowMatrix4 {
-                        x: geometry.transform.x,
-                        y: geometry.transform.y,
-                        z: geometry.transform.z,
+                        x: geometry_transform.x,
+                        y: geometry_transform.y,
+                        z: geometry_transform.z,
                         w: [0.0, 0.0, 0.0, 1.0].into(),
                     }),
                     prev_object_to_world: mat4_transform(&object.prev_transform).into(),
+                    prev_geometry_to_object: mat4_transform(&prev_geometry_transform).into(),
                     base_color_texture: match material.base_color_texture {
                         Some(handle) => *texture_indices.entry(handle).or_insert_with(|| {
-                            let texture = &asset_hub.textures[handle];
-                            self.textures.alloc(texture.view)
+                            let view = match self.texture_overrides.get(&handle) {
+                                Some(ov) => ov.view,
+                                None => asset_hub.textures[handle].view,
+                            };
+                            self.textures.alloc(view)
                         }),
                         None => dummy_white,
                     },
@@ -982,12 +2908,81 @@ impl Renderer {
                     },
                     normal_texture: match material.normal_texture {
                         Some(handle) => *texture_indices.entry(handle).or_insert_with(|| {
-                            let texture = &asset_hub.textures[handle];
-                            self.textures.alloc(texture.view)
+                            let view = match self.texture_overrides.get(&handle) {
+                                Some(ov) => ov.view,
+                                None => asset_hub.textures[handle].view,
+                            };
+                            self.textures.alloc(view)
                         }),
                         None => dummy_black,
                     },
                     normal_scale: material.normal_scale,
+                    tex_coord_sets: material.base_color_tex_coord
+                        | (material.normal_tex_coord << 1)
+                        | (material.occlusion_tex_coord << 2)
+                        | (material.metallic_roughness_tex_coord << 3),
+                    occlusion_texture: match material.occlusion_texture {
+                        Some(handle) => *texture_indices.entry(handle).or_insert_with(|| {
+                            let view = match self.texture_overrides.get(&handle) {
+                                Some(ov) => ov.view,
+                                None => asset_hub.textures[handle].view,
+                            };
+                            self.textures.alloc(view)
+                        }),
+                        None => dummy_white,
+                    },
+                    occlusion_strength: material.occlusion_strength,
+                    flags: if material.double_sided {
+                        HIT_FLAG_DOUBLE_SIDED
+                    } else {
+                        0
+                    },
+                    transmission: material.transmission,
+                    ior: material.ior,
+                    dispersion: material.dispersion,
+                    metallic: material.metallic,
+                    uv_transforms: [
+                        material.base_color_transform.to_matrix(),
+                        material.normal_transform.to_matrix(),
+                        material.occlusion_transform.to_matrix(),
+                        material.metallic_roughness_transform.to_matrix(),
+                    ],
+                    roughness: material.roughness,
+                    iridescence: material.iridescence,
+                    iridescence_ior: material.iridescence_ior,
+                    iridescence_thickness: material.iridescence_thickness,
+                    metallic_roughness_texture: match material.metallic_roughness_texture {
+                        Some(handle) => *texture_indices.entry(handle).or_insert_with(|| {
+                            let view = match self.texture_overrides.get(&handle) {
+                                Some(ov) => ov.view,
+                                None => asset_hub.textures[handle].view,
+                            };
+                            self.textures.alloc(view)
+                        }),
+                        None => dummy_white,
+                    },
+                    channel_map: material.channel_map.pack(),
+                    sheen_color: {
+                        let c = material.sheen_color;
+                        [
+                            (c[0] * 255.0) as u8,
+                            (c[1] * 255.0) as u8,
+                            (c[2] * 255.0) as u8,
+                            255,
+                        ]
+                    },
+                    sheen_roughness: material.sheen_roughness,
+                    subsurface_color: {
+                        let c = material.subsurface_color;
+                        [
+                            (c[0] * 255.0) as u8,
+                            (c[1] * 255.0) as u8,
+                            (c[2] * 255.0) as u8,
+                            255,
+                        ]
+                    },
+                    subsurface_distance: material.subsurface_distance,
+                    pad: [0; 2],
                 };
 
                 log::debug!("Entry[{geometry_index}] = {hit_entry:?}");
@@ -999,6 +2994,101 @@ impl Renderer {
                 }
                 geometry_index += 1;
             }
+            self.scene_instance_bounds
+                .push(instance_bounds.transformed(&object.transform));
+        }
+
+        let mut lights = Vec::new();
+        for object in objects {
+            let model = &asset_hub.models[object.model];
+            let m4_object = mat4_transform(&object.transform);
+            for light in model.lights.iter() {
+                let (cos_inner, cos_outer) = match light.kind {
+                    crate::LightKind::Point => (-1.0, -1.0),
+                    crate::LightKind::Spot {
+                        inner_cone_angle,
+                        outer_cone_angle,
+                    } => (inner_cone_angle.cos(), outer_cone_angle.cos()),
+                };
+                let radiance = glam::Vec3::from(light.color) * light.intensity;
+                lights.push(LightEntry {
+                    position: m4_object.transform_point3(light.position.into()).into(),
+                    range: if light.range.is_finite() {
+                        light.range
+                    } else {
+                        0.0
+                    },
+                    direction: m4_object
+                        .transform_vector3(light.direction.into())
+                        .normalize_or_zero()
+                        .into(),
+                    cos_outer,
+                    radiance: radiance.into(),
+                    cos_inner,
+                });
+            }
+        }
+        if self.light_buffer != blade_graphics::Buffer::default() {
+            temp.buffers.push(self.light_buffer);
+        }
+        self.light_buffer = gpu.create_buffer(blade_graphics::BufferDesc {
+            name: "lights",
+            size: (lights.len().max(1) * mem::size_of::<LightEntry>()) as u64,
+            memory: blade_graphics::Memory::Shared,
+        });
+        unsafe {
+            ptr::copy_nonoverlapping(
+                lights.as_ptr(),
+                self.light_buffer.data() as *mut LightEntry,
+                lights.len(),
+            );
+        }
+        self.light_count = lights.len() as u32;
+
+        let mut light_cdf = Vec::with_capacity(lights.len());
+        let mut total_power = 0.0;
+        for light in lights.iter() {
+            total_power += light.power();
+            light_cdf.push(total_power);
+        }
+        let light_count = light_cdf.len();
+        for (index, value) in light_cdf.iter_mut().enumerate() {
+            // lights without power are picked uniformly
+            *value = if total_power > 0.0 {
+                *value / total_power
+            } else {
+                (index + 1) as f32 / light_count as f32
+            };
+        }
+        if self.light_cdf_buffer != blade_graphics::Buffer::default() {
+            temp.buffers.push(self.light_cdf_buffer);
+        }
+        self.light_cdf_buffer = gpu.create_buffer(blade_graphics::BufferDesc {
+            name: "light CDF",
+            size: (light_cdf.len().max(1) * mem::size_of::<f32>()) as u64,
+            memory: blade_graphics::Memory::Shared,
+        });
+        unsafe {
+            ptr::copy_nonoverlapping(
+                light_cdf.as_ptr(),
+                self.light_cdf_buffer.data() as *mut f32,
+                light_cdf.len(),
+            );
+        }
+
+        self.instance_data.resize(objects.len(), [0.0; 4]);
+        if self.instance_capacity < objects.len().max(1) {
+            self.instance_capacity = objects.len().max(1);
+            for buffer in self.instance_buffers.iter_mut() {
+                if *buffer != blade_graphics::Buffer::default() {
+                    temp.buffers.push(*buffer);
+                }
+                *buffer = gpu.create_buffer(blade_graphics::BufferDesc {
+                    name: "instance data",
+                    size: (self.instance_capacity * mem::size_of::<[f32; 4]>()) as u64,
+                    memory: blade_graphics::Memory::Shared,
+                });
+            }
         }
 
         self.texture_resource_lookup.clear();
@@ -1014,6 +3104,13 @@ impl Renderer {
 
         // Needs to be a separate encoder in order to force synchronization
         let sizes = gpu.get_top_level_acceleration_structure_sizes(instances.len() as u32);
+        log::info!(
+            "TLAS of {} instances: {} KB, scratch {} KB",
+            instances.len(),
+            sizes.data >> 10,
+            sizes.scratch >> 10,
+        );
+        self.tlas_size = sizes.data;
         self.acceleration_structure =
             gpu.create_acceleration_structure(blade_graphics::AccelerationStructureDesc {
                 name: "TLAS",
@@ -1038,7 +3135,46 @@ impl Renderer {
 
         temp.buffers.push(instance_buf);
         temp.buffers.push(scratch_buf);
-        self.frame_scene_built = self.frame_index + 1;
+        self.scene_revision += 1;
+    }
+
+    fn build_posed_blas(
+        &self,
+        model: &crate::Model,
+        transforms: &[blade_graphics::Transform],
+        command_encoder: &mut blade_graphics::CommandEncoder,
+        gpu: &blade_graphics::Context,
+        temp: &mut FrameResources,
+    ) -> blade_graphics::AccelerationStructure {
+        let transform_buffer = gpu.create_buffer(blade_graphics::BufferDesc {
+            name: &format!("{}/pose", model.name),
+            size: mem::size_of_val(transforms) as u64,
+            memory: blade_graphics::Memory::Shared,
+        });
+        unsafe {
+            ptr::copy_nonoverlapping(
+                transforms.as_ptr(),
+                transform_buffer.data() as *mut blade_graphics::Transform,
+                transforms.len(),
+            );
+        }
+        let meshes = model.acceleration_structure_meshes(transform_buffer);
+        let sizes = gpu.get_bottom_level_acceleration_structure_sizes(&meshes);
+        let blas = gpu.create_acceleration_structure(blade_graphics::AccelerationStructureDesc {
+            name: &format!("{}/posed BLAS", model.name),
+            ty: blade_graphics::AccelerationStructureType::BottomLevel,
+            size: sizes.data,
+        });
+        let scratch = gpu.create_buffer(blade_graphics::BufferDesc {
+            name: &format!("{}/posed BLAS scratch", model.name),
+            size: sizes.scratch,
+            memory: blade_graphics::Memory::Device,
+        });
+        let mut pass = command_encoder.acceleration_structure("posed BLAS");
+        pass.build_bottom_level(blas, &meshes, scratch.at(0));
+        temp.buffers.push(transform_buffer);
+        temp.buffers.push(scratch);
+        blas
     }
 
     fn make_debug_params(&self, config: &DebugConfig) -> DebugParams {
@@ -1071,6 +3207,36 @@ impl Renderer {
         (cur, prev)
     }
 
+    /// Bake the procedural sky and refresh its importance sampling,
+    /// once `build_scene` has made it the environment map.
+    fn bake_sky(&mut self, command_encoder: &mut blade_graphics::CommandEncoder) {
+        let (sun_direction, turbidity, ground_albedo) = match self.background {
+            Background::ProceduralSky {
+                sun_direction,
+                turbidity,
+                ground_albedo,
+            } => (sun_direction, turbidity, ground_albedo),
+            Background::EnvironmentMap => {
+                self.is_sky_dirty = false;
+                return;
+            }
+        };
+        let env_rotation = glam::Quat::from_array(self.environment_params.rotation);
+        let sun = env_rotation.inverse() * glam::Vec3::from(sun_direction).normalize_or_zero();
+        let params = SkyParams {
+            sun_direction: sun.into(),
+            turbidity,
+            ground_albedo,
+            pad: 0,
+        };
+        if self.sky.bake(command_encoder, params) {
+            self.env_map.prepare(command_encoder);
+            self.is_sky_dirty = false;
+            self.is_environment_dirty = true;
+            self.request_redraw();
+        }
+    }
+
     /// Prepare to render a frame.
     #[profiling::function]
     pub fn prepare(
@@ -1079,6 +3245,14 @@ impl Renderer {
         camera: &crate::Camera,
         config: FrameConfig,
     ) {
+        if self.is_accumulation_paused {
+            self.frames_since_change = self.frames_since_change.saturating_add(1);
+            self.sample_rate.restart();
+            return;
+        }
+        if self.is_sky_dirty {
+            self.bake_sky(command_encoder);
+        }
         let mut transfer = command_encoder.transfer("prepare");
 
         if config.debug_draw {
@@ -1088,14 +3262,16 @@ impl Renderer {
             self.debug.enable_draw(&mut transfer, false);
         }
 
-        if config.reset_reservoirs || config.reset_variance {
+        // the stored light samples are in the space of the environment map
+        let reset_reservoirs = config.reset_reservoirs || mem::take(&mut self.is_environment_dirty);
+        if reset_reservoirs || config.reset_variance {
             self.debug.reset_variance(&mut transfer);
         } else {
             self.debug.update_variance(&mut transfer);
         }
         self.debug.update_entry(&mut transfer);
 
-        if config.reset_reservoirs {
+        if reset_reservoirs {
             if !config.debug_draw {
                 self.debug.reset_lines(&mut transfer);
             }
@@ -1108,18 +3284,129 @@ impl Renderer {
                 );
             }
         }
+        drop(transfer);
+
+        // The previous frame is complete at this point, so measure its brightness
+        if self.frame_index != 0 {
+            self.exposure.measure(
+                command_encoder,
+                self.targets.albedo.views[0],
+                self.targets.light_diffuse.views[self.post_proc_input_index],
+            );
+        }
+
+        let camera_params = self.make_camera_params(camera);
+        let settings = self.export_settings();
+        let has_camera_changed = bytemuck::bytes_of(&camera_params)
+            != bytemuck::bytes_of(&self.targets.camera_params[self.frame_index % 2]);
+        if reset_reservoirs
+            || has_camera_changed
+            || settings != self.last_settings
+            || self.seen_scene_revision != self.scene_revision
+        {
+            self.frames_since_change = 0;
+            self.last_settings = settings;
+        } else {
+            self.frames_since_change = self.frames_since_change.saturating_add(1);
+        }
 
         if !config.frozen {
             self.frame_index += 1;
+            let pixels = self.surface_size.width as u64 * self.surface_size.height as u64;
+            self.sample_rate.add(pixels * self.samples_per_frame as u64);
+        } else {
+            self.sample_rate.restart();
+        }
+        if reset_reservoirs {
+            self.adaptive_reset_frame = self.frame_index;
+        }
+        if self.seen_scene_revision != self.scene_revision {
+            self.seen_scene_revision = self.scene_revision;
+            self.frame_scene_built = self.frame_index;
         }
         self.is_frozen = config.frozen;
-        self.targets.camera_params[self.frame_index % 2] = self.make_camera_params(camera);
+        self.targets.camera_params[self.frame_index % 2] = camera_params;
         self.post_proc_input_index = self.frame_index % 2;
+
+        let instance_buffer = self.instance_buffers[self.frame_index % 2];
+        if instance_buffer != blade_graphics::Buffer::default() {
+            unsafe {
+                ptr::copy_nonoverlapping(
+                    self.instance_data.as_ptr(),
+                    instance_buffer.data() as *mut [f32; 4],
+                    self.instance_data.len(),
+                );
+            }
+        }
+    }
+
+    /// Set custom data of an object, given its index in the list
+    /// passed to `build_scene`.
+    ///
+    /// The data is available to the shading hook as `ShadingInput::instance_data`,
+    /// see `set_shading_module`. It's zero until set, and it's preserved when
+    /// the scene is rebuilt with the same objects.
+    pub fn set_instance_data(&mut self, object_index: usize, data: [f32; 4]) {
+        if self.instance_data[object_index] != data {
+            self.instance_data[object_index] = data;
+            self.request_redraw();
+        }
+    }
+
+    /// Check if rendering another frame would change the image.
+    ///
+    /// Returns false once the accumulation has converged, with no changes
+    /// to the camera, the scene, or the renderer settings since. This allows
+    /// the application to wait for events instead of redrawing continuously.
+    /// Changes to the configurations passed to `ray_trace`, `denoise`,
+    /// and `post_proc` are not tracked, see `request_redraw`.
+    pub fn needs_redraw(&self) -> bool {
+        self.frames_since_change < self.convergence_frame_count
+            || self.is_scene_dirty
+            || self.is_environment_dirty
+            || self.export_settings() != self.last_settings
+            || !self.particles.is_empty()
+    }
+
+    /// Hold the accumulated image, or continue accumulating from it.
+    ///
+    /// While paused, `prepare`, `ray_trace`, and `denoise` don't record
+    /// anything, and the frame index stays the same, so `post_proc` keeps
+    /// showing the same image with the current tone mapping. Unlike
+    /// `FrameConfig::frozen`, no new samples are taken at all.
+    pub fn set_accumulation_paused(&mut self, paused: bool) {
+        self.is_accumulation_paused = paused;
+    }
+
+    pub fn is_accumulation_paused(&self) -> bool {
+        self.is_accumulation_paused
+    }
+
+    /// Restart the convergence countdown of `needs_redraw`,
+    /// for changes the renderer doesn't know about.
+    pub fn request_redraw(&mut self) {
+        self.frames_since_change = 0;
+    }
+
+    /// Set the number of frames it takes for the accumulation to converge,
+    /// after which `needs_redraw` returns false.
+    pub fn set_convergence_frame_count(&mut self, count: u32) {
+        self.convergence_frame_count = count;
+    }
+
+    /// Return the size of the tiles, and the number of them in a row and in total.
+    fn tile_layout(&self) -> (blade_graphics::Extent, u32, u32) {
+        let size = self.tile_size.unwrap_or(self.surface_size);
+        let (width, height) = (size.width.max(1), size.height.max(1));
+        let columns = (self.surface_size.width + width - 1) / width;
+        let rows = (self.surface_size.height + height - 1) / height;
+        (size, columns, columns * rows)
     }
 
     /// Ray trace the scene.
     ///
     /// The result is stored internally in an HDR render target.
+    /// All the tiles set by `set_tile_size` are recorded into the encoder.
     #[profiling::function]
     pub fn ray_trace(
         &self,
@@ -1127,89 +3414,297 @@ impl Renderer {
         debug_config: DebugConfig,
         ray_config: RayConfig,
     ) {
+        if self.is_accumulation_paused {
+            return;
+        }
+        let (_, _, tile_count) = self.tile_layout();
+        self.record_ray_trace(command_encoder, debug_config, ray_config, 0..tile_count);
+    }
+
+    /// Ray trace the next tile of the scene, as set by `set_tile_size`,
+    /// so that every tile can go into its own submission.
+    ///
+    /// The G-buffer of the frame is filled together with the first tile.
+    /// Returns true once the last tile of the frame is recorded, after which
+    /// the frame can be denoised and post-processed. Between the calls
+    /// the encoder has to be submitted and started again, e.g.:
+    /// ```ignore
+    /// while !renderer.ray_trace_tile(&mut encoder, debug_config, ray_config) {
+    ///     context.submit(&mut encoder);
+    ///     encoder.start();
+    /// }
+    /// ```
+    #[profiling::function]
+    pub fn ray_trace_tile(
+        &mut self,
+        command_encoder: &mut blade_graphics::CommandEncoder,
+        debug_config: DebugConfig,
+        ray_config: RayConfig,
+    ) -> bool {
+        if self.is_accumulation_paused {
+            return true;
+        }
+        let (_, _, tile_count) = self.tile_layout();
+        let tile = self.next_tile.min(tile_count - 1);
+        self.record_ray_trace(command_encoder, debug_config, ray_config, tile..tile + 1);
+        self.next_tile = tile + 1;
+        if self.next_tile == tile_count {
+            self.next_tile = 0;
+            true
+        } else {
+            false
+        }
+    }
+
+    /// Record the per-frame passes if the range starts at the first tile,
+    /// followed by the ray tracing of the tiles in the range.
+    fn record_ray_trace(
+        &self,
+        command_encoder: &mut blade_graphics::CommandEncoder,
+        debug_config: DebugConfig,
+        ray_config: RayConfig,
+        tiles: ops::Range<u32>,
+    ) {
+        let is_first_tile = tiles.start == 0;
         let debug = self.make_debug_params(&debug_config);
         let (cur, prev) = self.work_indices();
         assert_eq!(cur, self.post_proc_input_index);
 
-        if let mut pass = command_encoder.compute("fill-gbuf") {
-            let mut pc = pass.with(&self.fill_pipeline);
-            let groups = self.fill_pipeline.get_dispatch_for(self.surface_size);
-            pc.bind(
-                0,
-                &FillData {
-                    camera: self.targets.camera_params[cur],
-                    prev_camera: self.targets.camera_params[prev],
-                    debug,
-                    acc_struct: self.acceleration_structure,
-                    hit_entries: self.hit_buffer.into(),
-                    index_buffers: &self.index_buffers,
-                    vertex_buffers: &self.vertex_buffers,
-                    textures: &self.textures,
-                    sampler_linear: self.samplers.linear,
-                    debug_buf: self.debug.buffer_resource(),
-                    out_depth: self.targets.depth.views[cur],
-                    out_basis: self.targets.basis.views[cur],
-                    out_flat_normal: self.targets.flat_normal.views[cur],
-                    out_albedo: self.targets.albedo.views[0],
-                    out_motion: self.targets.motion.views[0],
-                    out_debug: self.targets.debug.views[0],
+        // The passes are declared with the targets they access, and the graph
+        // orders them. The caustics buffer isn't a texture, so it relies on
+        // the declaration order, which the graph keeps when it can.
+        let mut graph = crate::util::RenderGraph::new();
+        let targets = &self.targets;
+        let depth = graph.import_texture("depth", targets.depth.views[cur]);
+        let prev_depth = graph.import_texture("prev depth", targets.depth.views[prev]);
+        let basis = graph.import_texture("basis", targets.basis.views[cur]);
+        let prev_basis = graph.import_texture("prev basis", targets.basis.views[prev]);
+        let flat_normal = graph.import_texture("flat normal", targets.flat_normal.views[cur]);
+        let prev_flat_normal =
+            graph.import_texture("prev flat normal", targets.flat_normal.views[prev]);
+        let albedo = graph.import_texture("albedo", targets.albedo.views[0]);
+        let motion = graph.import_texture("motion", targets.motion.views[0]);
+        let transmission = graph.import_texture("transmission", targets.transmission.views[0]);
+        let instance = graph.import_texture("instance", targets.instance.views[0]);
+        let debug_target = graph.import_texture("debug", targets.debug.views[0]);
+        let diffuse = graph.import_texture("diffuse", targets.light_diffuse.views[cur]);
+        let variance = graph.import_texture("variance", targets.variance.views[cur]);
+        let prev_variance = graph.import_texture("prev variance", targets.variance.views[prev]);
+
+        if is_first_tile {
+            graph.add_pass(
+                "fill-gbuf",
+                &[],
+                &[
+                    depth,
+                    basis,
+                    flat_normal,
+                    albedo,
+                    motion,
+                    transmission,
+                    instance,
+                    debug_target,
+                ],
+                move |encoder, res| {
+                    if let mut pass = encoder.compute("fill-gbuf") {
+                        let mut pc = pass.with(&self.fill_pipeline);
+                        let groups = self.fill_pipeline.get_dispatch_for(self.surface_size);
+                        pc.bind(
+                            0,
+                            &FillData {
+                                camera: self.targets.camera_params[cur],
+                                prev_camera: self.targets.camera_params[prev],
+                                debug,
+                                clip: self.clip_params,
+                                lod_params: {
+                                    let camera = &self.targets.camera_params[cur];
+                                    let pixel_angle = 2.0 * (0.5 * camera.fov[1]).tan()
+                                        / camera.target_size[1] as f32;
+                                    LodParams {
+                                        mip_bias: self.mip_bias,
+                                        spread_angle: pixel_angle.atan(),
+                                        pad: [0; 2],
+                                    }
+                                },
+                                transmission_params: TransmissionParams {
+                                    frame_index: self.frame_index as u32,
+                                    dispersion: self.dispersion as u32,
+                                    max_bounces: self.max_bounces,
+                                    mirror_roughness: crate::model::MIRROR_ROUGHNESS,
+                                    light_count: self.light_count,
+                                    pad: [0; 3],
+                                },
+                                environment: self.environment_params,
+                                acc_struct: self.acceleration_structure,
+                                hit_entries: self.hit_buffer.into(),
+                                index_buffers: &self.index_buffers,
+                                vertex_buffers: &self.vertex_buffers,
+                                textures: &self.textures,
+                                sampler_linear: self.samplers.linear,
+                                env_map: self.env_map.main_view,
+                                lights: self.light_buffer.into(),
+                                debug_buf: self.debug.buffer_resource(),
+                                out_depth: res.view(depth),
+                                out_basis: res.view(basis),
+                                out_flat_normal: res.view(flat_normal),
+                                out_albedo: res.view(albedo),
+                                out_motion: res.view(motion),
+                                out_transmission: res.view(transmission),
+                                out_instance: res.view(instance),
+                                out_debug: res.view(debug_target),
+                            },
+                        );
+                        pc.dispatch(groups);
+                    }
                 },
             );
-            pc.dispatch(groups);
         }
 
-        if let mut pass = command_encoder.compute("ray-trace") {
-            let mut pc = pass.with(&self.main_pipeline);
-            let groups = self.main_pipeline.get_dispatch_for(self.surface_size);
-            pc.bind(
-                0,
-                &MainData {
-                    camera: self.targets.camera_params[cur],
-                    prev_camera: self.targets.camera_params[prev],
-                    debug,
-                    parameters: MainParams {
-                        frame_index: self.frame_index as u32,
-                        num_environment_samples: ray_config.num_environment_samples,
-                        environment_importance_sampling: ray_config.environment_importance_sampling
-                            as u32,
-                        tap_count: ray_config.tap_count,
-                        tap_radius: ray_config.tap_radius as f32,
-                        tap_confidence_near: ray_config.tap_confidence_near as f32,
-                        tap_confidence_far: ray_config.tap_confidence_far as f32,
-                        t_start: ray_config.t_start,
-                        use_pairwise_mis: ray_config.pairwise_mis as u32,
-                        defensive_mis: ray_config.defensive_mis,
-                        use_motion_vectors: (self.frame_scene_built >= self.frame_index) as u32,
-                    },
-                    acc_struct: self.acceleration_structure,
-                    prev_acc_struct: if self.frame_scene_built < self.frame_index
-                        || self.prev_acceleration_structure
-                            == blade_graphics::AccelerationStructure::default()
-                    {
-                        self.acceleration_structure
-                    } else {
-                        self.prev_acceleration_structure
+        if is_first_tile && self.caustics.config.enabled {
+            graph.add_pass("caustics", &[depth], &[], move |encoder, res| {
+                let total_pixels = self.surface_size.width as u64 * self.surface_size.height as u64;
+                let mut transfer = encoder.transfer("clear caustics");
+                transfer.fill_buffer(
+                    self.targets.caustics_buf.into(),
+                    3 * mem::size_of::<u32>() as u64 * total_pixels,
+                    0,
+                );
+                drop(transfer);
+                self.caustics.trace(
+                    encoder,
+                    CausticsScene {
+                        acc_struct: self.acceleration_structure,
+                        hit_entries: self.hit_buffer.into(),
+                        index_buffers: &self.index_buffers,
+                        vertex_buffers: &self.vertex_buffers,
+                        lights: self.light_buffer.into(),
+                        light_count: self.light_count,
                     },
-                    sampler_linear: self.samplers.linear,
-                    sampler_nearest: self.samplers.nearest,
-                    env_map: self.env_map.main_view,
-                    env_weights: self.env_map.weight_view,
-                    t_depth: self.targets.depth.views[cur],
-                    t_prev_depth: self.targets.depth.views[prev],
-                    t_basis: self.targets.basis.views[cur],
-                    t_prev_basis: self.targets.basis.views[prev],
-                    t_flat_normal: self.targets.flat_normal.views[cur],
-                    t_prev_flat_normal: self.targets.flat_normal.views[prev],
-                    t_motion: self.targets.motion.views[0],
-                    debug_buf: self.debug.buffer_resource(),
-                    reservoirs: self.targets.reservoir_buf[cur].into(),
-                    prev_reservoirs: self.targets.reservoir_buf[prev].into(),
-                    out_diffuse: self.targets.light_diffuse.views[cur],
-                    out_debug: self.targets.debug.views[0],
-                },
-            );
-            pc.dispatch(groups);
+                    self.targets.camera_params[cur],
+                    res.view(depth),
+                    self.targets.caustics_buf.into(),
+                    self.frame_index as u32,
+                );
+            });
         }
+
+        graph.add_pass(
+            "ray-trace",
+            &[
+                depth,
+                prev_depth,
+                basis,
+                prev_basis,
+                flat_normal,
+                prev_flat_normal,
+                motion,
+                albedo,
+                transmission,
+                instance,
+                prev_variance,
+            ],
+            &[diffuse, debug_target, variance],
+            move |encoder, res| {
+                if let mut pass = encoder.compute("ray-trace") {
+                    let main_pipeline = &self.main_pipelines[self.render_mode as usize];
+                    let mut pc = pass.with(main_pipeline);
+                    let mut main_data = MainData {
+                        camera: self.targets.camera_params[cur],
+                        prev_camera: self.targets.camera_params[prev],
+                        debug,
+                        parameters: MainParams {
+                            frame_index: self.frame_index as u32,
+                            num_environment_samples: ray_config.num_environment_samples,
+                            environment_importance_sampling: ray_config
+                                .environment_importance_sampling
+                                as u32,
+                            tap_count: ray_config.tap_count,
+                            tap_radius: ray_config.tap_radius as f32,
+                            tap_confidence_near: ray_config.tap_confidence_near as f32,
+                            tap_confidence_far: ray_config.tap_confidence_far as f32,
+                            t_start: ray_config.t_start,
+                            use_pairwise_mis: ray_config.pairwise_mis as u32,
+                            defensive_mis: ray_config.defensive_mis,
+                            use_motion_vectors: (self.frame_scene_built >= self.frame_index) as u32,
+                            sample_strategy: self.sample_strategy as u32,
+                            firefly_clamp: self.firefly_clamp,
+                            light_count: self.light_count,
+                            tile_origin: [0; 2],
+                            num_brdf_samples: ray_config.num_brdf_samples,
+                            adaptive_max_samples: match self.adaptive_sampling {
+                                Some(ref config) => config.max_samples.max(1),
+                                None => 0,
+                            },
+                            adaptive_target_variance: match self.adaptive_sampling {
+                                Some(ref config) => config.target_variance,
+                                None => 0.0,
+                            },
+                            adaptive_reset: (self.adaptive_reset_frame == self.frame_index) as u32,
+                            caustics_weight: self.caustics.config.weight(),
+                            samples_per_frame: self.samples_per_frame,
+                        },
+                        preview: PreviewParams {
+                            sun_direction: glam::Vec3::from(self.preview_light.direction)
+                                .normalize_or_zero()
+                                .into(),
+                            ambient: self.preview_light.ambient,
+                            sun_color: self.preview_light.color,
+                            pad: 0,
+                        },
+                        environment: self.environment_params,
+                        acc_struct: self.acceleration_structure,
+                        prev_acc_struct: if self.frame_scene_built < self.frame_index
+                            || self.prev_acceleration_structure
+                                == blade_graphics::AccelerationStructure::default()
+                        {
+                            self.acceleration_structure
+                        } else {
+                            self.prev_acceleration_structure
+                        },
+                        sampler_linear: self.samplers.linear,
+                        sampler_nearest: self.samplers.nearest,
+                        env_map: self.env_map.main_view,
+                        env_weights: self.env_map.weight_view,
+                        t_matcap: self.matcap_view,
+                        t_depth: res.view(depth),
+                        t_prev_depth: res.view(prev_depth),
+                        t_basis: res.view(basis),
+                        t_prev_basis: res.view(prev_basis),
+                        t_flat_normal: res.view(flat_normal),
+                        t_prev_flat_normal: res.view(prev_flat_normal),
+                        t_motion: res.view(motion),
+                        t_albedo: res.view(albedo),
+                        t_transmission: res.view(transmission),
+                        t_instance: res.view(instance),
+                        t_prev_variance: res.view(prev_variance),
+                        lights: self.light_buffer.into(),
+                        light_cdf: self.light_cdf_buffer.into(),
+                        instance_data: self.instance_buffers[cur].into(),
+                        debug_buf: self.debug.buffer_resource(),
+                        reservoirs: self.targets.reservoir_buf[cur].into(),
+                        prev_reservoirs: self.targets.reservoir_buf[prev].into(),
+                        out_diffuse: res.view(diffuse),
+                        out_debug: res.view(debug_target),
+                        out_variance: res.view(variance),
+                        caustics: self.targets.caustics_buf.into(),
+                    };
+                    let (tile_size, columns, _) = self.tile_layout();
+                    for tile in tiles {
+                        let x = (tile % columns) * tile_size.width;
+                        let y = (tile / columns) * tile_size.height;
+                        let tile_extent = blade_graphics::Extent::new_2d(
+                            tile_size.width.min(self.surface_size.width - x),
+                            tile_size.height.min(self.surface_size.height - y),
+                        );
+                        main_data.parameters.tile_origin = [x, y];
+                        pc.bind(0, &main_data);
+                        pc.dispatch(main_pipeline.get_dispatch_for(tile_extent));
+                    }
+                }
+            },
+        );
+
+        graph.execute_imported(command_encoder);
     }
 
     /// Perform noise reduction using SVGF.
@@ -1219,6 +3714,9 @@ impl Renderer {
         command_encoder: &mut blade_graphics::CommandEncoder,
         denoiser_config: DenoiserConfig,
     ) {
+        if self.is_accumulation_paused {
+            return;
+        }
         let mut params = BlurParams {
             extent: [self.surface_size.width, self.surface_size.height],
             temporal_weight: denoiser_config.temporal_weight,
@@ -1279,6 +3777,37 @@ impl Renderer {
         }
     }
 
+    /// Write the auxiliary output variables for external denoisers.
+    ///
+    /// The color is captured as it is at this point, so call this right
+    /// after `ray_trace` to get the noisy input, or after `denoise` to get
+    /// the filtered one. The buffers are only allocated once this is called.
+    pub fn capture_aovs(
+        &mut self,
+        command_encoder: &mut blade_graphics::CommandEncoder,
+        gpu: &blade_graphics::Context,
+    ) {
+        let cur = self.frame_index % 2;
+        self.aov.capture(
+            command_encoder,
+            self.surface_size,
+            self.targets.depth.views[cur],
+            self.targets.basis.views[cur],
+            self.targets.albedo.views[0],
+            self.targets.light_diffuse.views[self.post_proc_input_index],
+            gpu,
+        );
+    }
+
+    /// Read back an output variable written by `capture_aovs`.
+    ///
+    /// The encoder with the capture has to be submitted before. This blocks
+    /// until the GPU is done. Returns an empty vector if nothing was captured
+    /// since the last resize. See `Aov` for the layout of the data.
+    pub fn read_aov(&self, aov: Aov, gpu: &blade_graphics::Context) -> Vec<f32> {
+        self.aov.read(aov, gpu)
+    }
+
     /// Blit the rendering result into a specified render pass.
     #[profiling::function]
     pub fn post_proc(
@@ -1290,36 +3819,219 @@ impl Renderer {
         debug_blits: &[DebugBlit],
     ) {
         let cur = self.frame_index % 2;
+        let viewport = match pp_config.viewport {
+            Some(rect) => {
+                pass.set_viewport(&blade_graphics::Viewport {
+                    x: rect.x as f32,
+                    y: rect.y as f32,
+                    w: rect.w as f32,
+                    h: rect.h as f32,
+                    depth: 0.0..1.0,
+                });
+                pass.set_scissor_rect(&rect);
+                ViewportParams {
+                    origin: [rect.x, rect.y],
+                    pad: [0; 2],
+                }
+            }
+            None => ViewportParams::default(),
+        };
+        let fade = self
+            .scene_fade
+            .as_ref()
+            .filter(|fade| fade.size == self.surface_size && fade.weight() > 0.0);
         if let mut pc = pass.with(&self.post_proc_pipeline) {
             let debug_params = self.make_debug_params(&debug_config);
             pc.bind(
                 0,
                 &PostProcData {
+                    camera: self.targets.camera_params[cur],
+                    fog: self.fog_config.into(),
+                    t_depth: self.targets.depth.views[cur],
                     t_albedo: self.targets.albedo.views[0],
                     light_diffuse: self.targets.light_diffuse.views[self.post_proc_input_index],
                     t_debug: self.targets.debug.views[0],
+                    color_lut: self.color_lut.view,
+                    t_fade_light: match fade {
+                        Some(fade) => fade.light.views[0],
+                        None => self.targets.light_diffuse.views[self.post_proc_input_index],
+                    },
+                    t_fade_albedo: match fade {
+                        Some(fade) => fade.albedo.views[0],
+                        None => self.targets.albedo.views[0],
+                    },
+                    t_fade_depth: match fade {
+                        Some(fade) => fade.depth.views[0],
+                        None => self.targets.depth.views[cur],
+                    },
+                    sampler_linear: self.samplers.linear,
                     tone_map_params: ToneMapParams {
-                        enabled: 1,
+                        output_transform: match pp_config.output_transform {
+                            OutputTransform::None => 0,
+                            OutputTransform::Gamma(_) => 1,
+                            OutputTransform::Tonemap => 2,
+                        },
                         average_lum: pp_config.average_luminocity,
                         key_value: pp_config.exposure_key_value,
                         white_level: pp_config.white_level,
+                        lut_strength: self.color_lut.strength,
+                        lut_scale: (self.color_lut.size - 1) as f32 / self.color_lut.size as f32,
+                        lut_offset: 0.5 / self.color_lut.size as f32,
+                        auto_exposure: self.exposure.config.enabled as u32,
+                        gamma: match pp_config.output_transform {
+                            OutputTransform::Gamma(gamma) => gamma,
+                            _ => 1.0,
+                        },
+                        fade: fade.map_or(0.0, SceneFade::weight),
+                        pad: [0; 2],
                     },
+                    exposure: self.exposure.state(),
                     debug_params,
+                    viewport,
                 },
             );
             pc.draw(0, 3, 0, 1);
         }
 
+        self.grid.render(
+            self.targets.camera_params[cur],
+            self.targets.depth.views[cur],
+            viewport.origin,
+            pass,
+        );
+        self.particles.render(
+            self.targets.camera_params[cur],
+            self.targets.depth.views[cur],
+            viewport.origin,
+            pass,
+        );
+        self.wireframe.render(
+            self.targets.camera_params[cur],
+            self.targets.depth.views[cur],
+            viewport.origin,
+            pass,
+        );
+        let mut bounds_lines = Vec::new();
+        if self.draw_instance_bounds {
+            bounds_lines.extend_from_slice(debug_lines);
+            for bounds in self.scene_instance_bounds.iter() {
+                add_box_lines(&mut bounds_lines, bounds, INSTANCE_BOUNDS_COLOR);
+            }
+        }
         self.debug.render_lines(
-            debug_lines,
+            if self.draw_instance_bounds {
+                &bounds_lines
+            } else {
+                debug_lines
+            },
             self.targets.camera_params[cur],
             self.targets.depth.views[cur],
+            viewport.origin,
             pass,
         );
         self.debug
             .render_blits(debug_blits, self.samplers.linear, self.surface_size, pass);
     }
 
+    /// Get the world-space bounds of all the objects, as of the last `build_scene`.
+    pub fn scene_bounds(&self) -> crate::BoundingBox {
+        self.scene_bounds
+    }
+
+    /// Get the world-space bounds of each object, indexed by its position
+    /// in the list passed to the last `build_scene`.
+    ///
+    /// These are the bounds of the instances in the top-level acceleration
+    /// structure, including the pose of animated objects, which helps
+    /// telling apart the transform and unit problems.
+    pub fn instance_aabbs(&self) -> &[crate::BoundingBox] {
+        &self.scene_instance_bounds
+    }
+
+    /// Overlay the bounds of every object as wireframe boxes in `post_proc`,
+    /// see `instance_aabbs`.
+    pub fn debug_draw_aabbs(&mut self, enabled: bool) {
+        self.draw_instance_bounds = enabled;
+    }
+
+    /// Get the sizes of the acceleration structures of the scene.
+    ///
+    /// The build times are looked up in `timings`, which requires
+    /// `ContextDesc::timing`. They are only present for the frame that
+    /// recorded the builds, e.g. in `FramePacer::timings` once that
+    /// frame's command encoder comes back. Models uploaded with
+    /// `model::Baker::submit` are built outside of the frames and not timed.
+    pub fn acceleration_structure_report(
+        &self,
+        asset_hub: &crate::AssetHub,
+        timings: &blade_graphics::Timings,
+    ) -> AccelerationStructureReport {
+        let find_time = |label: &str| {
+            timings
+                .iter()
+                .find(|&&(ref name, _)| name == label)
+                .map(|&(_, time)| time)
+        };
+        let mut bottom_level = Vec::<AccelerationStructureInfo>::new();
+        let mut seen_models = Vec::new();
+        for object in self.scene_objects.iter() {
+            if seen_models.contains(&object.model) {
+                continue;
+            }
+            seen_models.push(object.model);
+            let model = &asset_hub.models[object.model];
+            bottom_level.push(AccelerationStructureInfo {
+                name: model.name.clone(),
+                primitive_count: model
+                    .geometries
+                    .iter()
+                    .map(|geo| geo.triangle_count as u64)
+                    .sum(),
+                size: model.acceleration_structure_size,
+                build_time: find_time(&crate::model::blas_pass_label(&model.name)),
+            });
+        }
+        bottom_level.sort_by(|a, b| b.size.cmp(&a.size));
+        AccelerationStructureReport {
+            top_level: AccelerationStructureInfo {
+                name: "TLAS".to_string(),
+                primitive_count: self.scene_objects.len() as u64,
+                size: self.tlas_size,
+                build_time: find_time("TLAS"),
+            },
+            bottom_level,
+        }
+    }
+
+    fn object_geometry_nodes(&self, object_index: usize) -> Option<&[usize]> {
+        let start = *self.scene_geometry_offsets.get(object_index)? as usize;
+        let end = self
+            .scene_geometry_offsets
+            .get(object_index + 1)
+            .map_or(self.scene_geometry_nodes.len(), |&offset| offset as usize);
+        Some(&self.scene_geometry_nodes[start..end])
+    }
+
+    /// Find the first geometry produced by a glTF node of the given object,
+    /// as of the last `build_scene`.
+    pub fn instance_of_node(&self, object_index: usize, node_index: usize) -> Option<InstanceId> {
+        let geometry = self
+            .object_geometry_nodes(object_index)?
+            .iter()
+            .position(|&node| node == node_index)?;
+        Some(InstanceId {
+            object: object_index as u32,
+            geometry: geometry as u32,
+        })
+    }
+
+    /// Get the glTF node index that produced the given geometry instance.
+    pub fn node_of_instance(&self, id: InstanceId) -> Option<usize> {
+        self.object_geometry_nodes(id.object as usize)?
+            .get(id.geometry as usize)
+            .cloned()
+    }
+
     #[profiling::function]
     pub fn read_debug_selection_info(&self) -> SelectionInfo {
         let (db_v, db_e) = self.debug.read_shared_data();
@@ -1338,6 +4050,16 @@ impl Renderer {
             },
             std_deviation_history: db_v.count,
             custom_index: db_e.custom_index,
+            instance: {
+                let object = self
+                    .scene_geometry_offsets
+                    .partition_point(|&offset| offset <= db_e.custom_index)
+                    .saturating_sub(1);
+                InstanceId {
+                    object: object as u32,
+                    geometry: db_e.geometry_index,
+                }
+            },
             depth: db_e.depth,
             position: db_e.position.into(),
             normal: db_e.normal.into(),
@@ -1353,3 +4075,65 @@ impl Renderer {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use crate::shader::Expansion;
+    use std::{collections::HashMap, fs, path::Path};
+
+    /// Load a shader of the renderer the way `AssetHub` cooks it, but without a GPU.
+    fn load_shader(name: &str) -> blade_graphics::Shader {
+        let code_path = Path::new(env!("CARGO_MANIFEST_DIR")).join("code");
+        let mut expansions = HashMap::default();
+        expansions.insert("DEBUG_MODE".to_string(), Expansion::Bool(true));
+        expansions.insert(
+            "DebugMode".to_string(),
+            Expansion::from_enum::<super::DebugMode>(),
+        );
+        expansions.insert(
+            "SampleStrategy".to_string(),
+            Expansion::from_enum::<super::SampleStrategy>(),
+        );
+        expansions.insert(
+            "RenderMode".to_string(),
+            Expansion::from_enum::<super::RenderMode>(),
+        );
+        expansions.insert(
+            "DebugDrawFlags".to_string(),
+            Expansion::from_bitflags::<super::DebugDrawFlags>(),
+        );
+        expansions.insert(
+            "DebugTextureFlags".to_string(),
+            Expansion::from_bitflags::<super::DebugTextureFlags>(),
+        );
+        let cooker = blade_asset::Cooker::new(&code_path, Default::default());
+        let source = fs::read(code_path.join(name)).unwrap();
+        let text = crate::shader::parse_shader(&source, &cooker, &expansions);
+        blade_graphics::Shader::parse(&text).unwrap()
+    }
+
+    #[test]
+    fn struct_layouts() {
+        let shader = load_shader("fill-gbuf.wgsl");
+        shader.check_struct_size::<crate::Vertex>();
+        shader.check_struct_size::<super::HitEntry>();
+        shader.check_struct_size::<super::ClipParams>();
+        shader.check_struct_size::<super::LodParams>();
+        shader.check_struct_size::<super::TransmissionParams>();
+        shader.check_struct_size::<super::EnvironmentParams>();
+
+        let shader = load_shader("ray-trace.wgsl");
+        shader.check_struct_size::<super::CameraParams>();
+        shader.check_struct_size::<super::DebugParams>();
+        shader.check_struct_size::<super::MainParams>();
+        shader.check_struct_size::<super::LightEntry>();
+        shader.check_struct_size::<super::PreviewParams>();
+        shader.check_struct_size::<super::DebugVariance>();
+        shader.check_struct_size::<super::DebugEntry>();
+
+        let shader = load_shader("post-proc.wgsl");
+        shader.check_struct_size::<super::ViewportParams>();
+        shader.check_struct_size::<super::ToneMapParams>();
+        shader.check_struct_size::<super::FogParams>();
+    }
+}
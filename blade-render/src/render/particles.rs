@@ -0,0 +1,337 @@
+use std::mem;
+
+/// Description of a particle system, emitting from a point.
+#[derive(Clone, Copy, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ParticleDesc {
+    /// Maximum number of particles alive at once.
+    pub capacity: u32,
+    pub origin: [f32; 3],
+    /// Main direction of the emission.
+    pub direction: [f32; 3],
+    /// Half-angle of the emission cone, in radians.
+    pub spread: f32,
+    /// Average starting speed, in world units per second.
+    pub speed: f32,
+    /// Constant acceleration, such as gravity.
+    pub acceleration: [f32; 3],
+    /// Number of particles emitted per second.
+    pub emit_rate: f32,
+    /// Average life time of a particle, in seconds.
+    pub life: f32,
+    /// Half-size of the particle quad, in world units.
+    pub size: f32,
+    /// Linear color, added to the image.
+    pub color: [f32; 3],
+}
+impl Default for ParticleDesc {
+    fn default() -> Self {
+        Self {
+            capacity: 1024,
+            origin: [0.0; 3],
+            direction: [0.0, 1.0, 0.0],
+            spread: 0.3,
+            speed: 2.0,
+            acceleration: [0.0, -9.8, 0.0],
+            emit_rate: 200.0,
+            life: 2.0,
+            size: 0.02,
+            color: [1.0, 0.5, 0.1],
+        }
+    }
+}
+
+/// Handle of a particle system added to the renderer.
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub struct ParticleSystemId(u32);
+
+// Has to match the shader!
+#[repr(C)]
+#[derive(Clone, Copy, Debug, bytemuck::Zeroable, bytemuck::Pod)]
+struct Particle {
+    position: [f32; 3],
+    age: f32,
+    velocity: [f32; 3],
+    life: f32,
+}
+
+#[repr(C)]
+#[derive(Clone, Copy, Debug, bytemuck::Zeroable, bytemuck::Pod)]
+struct ParticleParams {
+    origin: [f32; 3],
+    life: f32,
+    direction: [f32; 3],
+    spread: f32,
+    acceleration: [f32; 3],
+    speed: f32,
+    color: [f32; 3],
+    size: f32,
+    time_delta: f32,
+    emit_start: u32,
+    emit_count: u32,
+    capacity: u32,
+    viewport_origin: [i32; 2],
+    seed: u32,
+    pad: u32,
+}
+
+#[derive(blade_macros::ShaderData)]
+struct UpdateData {
+    params: ParticleParams,
+    particles: blade_graphics::BufferPiece,
+}
+
+#[derive(blade_macros::ShaderData)]
+struct DrawData {
+    camera: super::CameraParams,
+    params: ParticleParams,
+    particles: blade_graphics::BufferPiece,
+    depth: blade_graphics::TextureView,
+}
+
+struct ParticleSystem {
+    id: ParticleSystemId,
+    desc: ParticleDesc,
+    buffer: blade_graphics::Buffer,
+    /// Parameters of the last update, reused for drawing.
+    params: ParticleParams,
+    /// Next slot of the ring buffer to emit into.
+    cursor: u32,
+    /// Fractional number of particles left to emit.
+    emit_debt: f32,
+    /// The buffer contents are undefined until the first update.
+    is_initialized: bool,
+}
+
+impl ParticleSystem {
+    fn prepare(&mut self, time_delta: f32) {
+        let capacity = self.desc.capacity;
+        self.emit_debt += self.desc.emit_rate.max(0.0) * time_delta;
+        let emit_count = (self.emit_debt as u32).min(capacity);
+        self.emit_debt -= emit_count as f32;
+        // don't accumulate the debt while the ring is saturated
+        self.emit_debt = self.emit_debt.min(capacity as f32);
+        self.params = ParticleParams {
+            origin: self.desc.origin,
+            life: self.desc.life,
+            direction: self.desc.direction,
+            spread: self.desc.spread,
+            acceleration: self.desc.acceleration,
+            speed: self.desc.speed,
+            color: self.desc.color,
+            size: self.desc.size,
+            time_delta,
+            emit_start: self.cursor,
+            emit_count,
+            capacity,
+            viewport_origin: [0; 2],
+            seed: self.params.seed.wrapping_add(1),
+            pad: 0,
+        };
+        self.cursor = (self.cursor + emit_count) % capacity;
+    }
+}
+
+fn create_update_pipeline(
+    shader: &blade_graphics::Shader,
+    gpu: &blade_graphics::Context,
+) -> blade_graphics::ComputePipeline {
+    shader.check_struct_size::<Particle>();
+    shader.check_struct_size::<ParticleParams>();
+    let layout = <UpdateData as blade_graphics::ShaderData>::layout();
+    gpu.create_compute_pipeline(blade_graphics::ComputePipelineDesc {
+        name: "particle-update",
+        data_layouts: &[&layout],
+        compute: shader.at("update"),
+    })
+}
+
+fn create_draw_pipeline(
+    shader: &blade_graphics::Shader,
+    format: blade_graphics::TextureFormat,
+    gpu: &blade_graphics::Context,
+) -> blade_graphics::RenderPipeline {
+    let layout = <DrawData as blade_graphics::ShaderData>::layout();
+    gpu.create_render_pipeline(blade_graphics::RenderPipelineDesc {
+        name: "particle-draw",
+        data_layouts: &[&layout],
+        vertex: shader.at("draw_vs"),
+        vertex_fetches: &[],
+        primitive: blade_graphics::PrimitiveState {
+            topology: blade_graphics::PrimitiveTopology::TriangleStrip,
+            ..Default::default()
+        },
+        depth_stencil: None,
+        fragment: Some(shader.at("draw_fs")),
+        color_targets: &[blade_graphics::ColorTargetState {
+            format,
+            blend: Some(blade_graphics::BlendState::ADDITIVE),
+            write_mask: blade_graphics::ColorWrites::all(),
+        }],
+        multisample_state: blade_graphics::MultisampleState::default(),
+    })
+}
+
+/// Particle systems simulated in compute, and drawn as camera-facing
+/// quads blended on top of the shaded image, occluded by the traced depth.
+pub struct ParticleRender {
+    surface_format: blade_graphics::TextureFormat,
+    update_pipeline: blade_graphics::ComputePipeline,
+    draw_pipeline: blade_graphics::RenderPipeline,
+    systems: Vec<ParticleSystem>,
+    next_id: u32,
+}
+
+impl ParticleRender {
+    pub(super) fn init(
+        gpu: &blade_graphics::Context,
+        shader: &blade_graphics::Shader,
+        surface_info: blade_graphics::SurfaceInfo,
+    ) -> Self {
+        Self {
+            surface_format: surface_info.format,
+            update_pipeline: create_update_pipeline(shader, gpu),
+            draw_pipeline: create_draw_pipeline(shader, surface_info.format, gpu),
+            systems: Vec::new(),
+            next_id: 0,
+        }
+    }
+
+    pub(super) fn destroy(&mut self, gpu: &blade_graphics::Context) {
+        gpu.destroy_compute_pipeline(&mut self.update_pipeline);
+        gpu.destroy_render_pipeline(&mut self.draw_pipeline);
+        for system in self.systems.drain(..) {
+            gpu.destroy_buffer(system.buffer);
+        }
+    }
+
+    pub(super) fn recreate_pipelines(
+        &mut self,
+        shader: &blade_graphics::Shader,
+        gpu: &blade_graphics::Context,
+    ) {
+        self.update_pipeline = create_update_pipeline(shader, gpu);
+        self.draw_pipeline = create_draw_pipeline(shader, self.surface_format, gpu);
+    }
+
+    pub(super) fn is_empty(&self) -> bool {
+        self.systems.is_empty()
+    }
+
+    pub(super) fn add(
+        &mut self,
+        desc: ParticleDesc,
+        gpu: &blade_graphics::Context,
+    ) -> ParticleSystemId {
+        assert_ne!(desc.capacity, 0, "Particle system has no capacity");
+        let id = ParticleSystemId(self.next_id);
+        self.next_id += 1;
+        let buffer = gpu.create_buffer(blade_graphics::BufferDesc {
+            name: "particles",
+            size: desc.capacity as u64 * mem::size_of::<Particle>() as u64,
+            memory: blade_graphics::Memory::Device,
+        });
+        self.systems.push(ParticleSystem {
+            id,
+            desc,
+            buffer,
+            params: bytemuck::Zeroable::zeroed(),
+            cursor: 0,
+            emit_debt: 0.0,
+            is_initialized: false,
+        });
+        id
+    }
+
+    /// Change the description of a system. The capacity is fixed at creation.
+    pub(super) fn set(&mut self, id: ParticleSystemId, desc: ParticleDesc) {
+        let system = self
+            .systems
+            .iter_mut()
+            .find(|s| s.id == id)
+            .expect("Unknown particle system");
+        assert_eq!(
+            desc.capacity, system.desc.capacity,
+            "Particle system capacity can't be changed"
+        );
+        system.desc = desc;
+    }
+
+    pub(super) fn remove(
+        &mut self,
+        id: ParticleSystemId,
+        buffers: &mut Vec<blade_graphics::Buffer>,
+    ) {
+        if let Some(index) = self.systems.iter().position(|s| s.id == id) {
+            let system = self.systems.swap_remove(index);
+            // the GPU may still be using it
+            buffers.push(system.buffer);
+        }
+    }
+
+    pub(super) fn update(
+        &mut self,
+        command_encoder: &mut blade_graphics::CommandEncoder,
+        time_delta: f32,
+    ) {
+        if self.systems.iter().any(|s| !s.is_initialized) {
+            let mut transfer = command_encoder.transfer("particle-init");
+            for system in self.systems.iter_mut().filter(|s| !s.is_initialized) {
+                // zero age and life make every particle dead
+                let size = system.desc.capacity as u64 * mem::size_of::<Particle>() as u64;
+                transfer.fill_buffer(system.buffer.into(), size, 0);
+                system.is_initialized = true;
+            }
+        }
+        if self.systems.is_empty() {
+            return;
+        }
+
+        let mut pass = command_encoder.compute("particle-update");
+        let mut pc = pass.with(&self.update_pipeline);
+        let group_size = self.update_pipeline.get_workgroup_size();
+        for system in self.systems.iter_mut() {
+            system.prepare(time_delta);
+            pc.bind(
+                0,
+                &UpdateData {
+                    params: system.params,
+                    particles: system.buffer.into(),
+                },
+            );
+            pc.dispatch([
+                (system.desc.capacity + group_size[0] - 1) / group_size[0],
+                1,
+                1,
+            ]);
+        }
+    }
+
+    pub(super) fn render(
+        &self,
+        camera: super::CameraParams,
+        depth: blade_graphics::TextureView,
+        viewport_origin: [i32; 2],
+        pass: &mut blade_graphics::RenderCommandEncoder,
+    ) {
+        if self.systems.iter().all(|s| !s.is_initialized) {
+            return;
+        }
+        let mut pc = pass.with(&self.draw_pipeline);
+        for system in self.systems.iter().filter(|s| s.is_initialized) {
+            pc.bind(
+                0,
+                &DrawData {
+                    camera,
+                    params: ParticleParams {
+                        viewport_origin,
+                        ..system.params
+                    },
+                    particles: system.buffer.into(),
+                    depth,
+                },
+            );
+            pc.draw(0, 4, 0, system.desc.capacity);
+        }
+    }
+}
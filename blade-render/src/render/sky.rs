@@ -0,0 +1,154 @@
+const SKY_FORMAT: blade_graphics::TextureFormat = blade_graphics::TextureFormat::Rgba32Float;
+const SKY_SIZE: blade_graphics::Extent = blade_graphics::Extent {
+    width: 1024,
+    height: 512,
+    depth: 1,
+};
+
+/// What the rays that miss the scene geometry see,
+/// which also lights the scene as the environment.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Background {
+    /// The environment map given to `Renderer::build_scene`,
+    /// or uniform white if there is none.
+    #[default]
+    EnvironmentMap,
+    /// Analytic daylight sky of Preetham et al., with the sun disk.
+    ///
+    /// The radiance is in kcd/m², so the environment intensity
+    /// or the exposure has to be adjusted accordingly.
+    ProceduralSky {
+        /// Direction towards the sun. The sky fades into the night
+        /// as the sun goes below the horizon.
+        sun_direction: mint::Vector3<f32>,
+        /// Haziness of the atmosphere, from 2 for a clear sky to 10.
+        turbidity: f32,
+        /// Linear color of the ground below the horizon.
+        ground_albedo: [f32; 3],
+    },
+}
+
+// Has to match the shader!
+#[repr(C)]
+#[derive(Clone, Copy, Debug, bytemuck::Zeroable, bytemuck::Pod)]
+pub(super) struct SkyParams {
+    pub sun_direction: [f32; 3],
+    pub turbidity: f32,
+    pub ground_albedo: [f32; 3],
+    pub pad: u32,
+}
+
+#[derive(blade_macros::ShaderData)]
+struct SkyData {
+    params: SkyParams,
+    output: blade_graphics::TextureView,
+}
+
+fn create_pipeline(
+    shader: &blade_graphics::Shader,
+    gpu: &blade_graphics::Context,
+) -> blade_graphics::ComputePipeline {
+    shader.check_struct_size::<SkyParams>();
+    let layout = <SkyData as blade_graphics::ShaderData>::layout();
+    gpu.create_compute_pipeline(blade_graphics::ComputePipelineDesc {
+        name: "sky",
+        data_layouts: &[&layout],
+        compute: shader.at("bake"),
+    })
+}
+
+struct SkyTarget {
+    texture: blade_graphics::Texture,
+    view: blade_graphics::TextureView,
+}
+
+/// Bakes the procedural sky into an equirectangular map, which takes
+/// the place of the environment map, including its importance sampling.
+/// The map is only allocated once the sky is used.
+pub struct SkyRender {
+    pipeline: blade_graphics::ComputePipeline,
+    target: Option<SkyTarget>,
+}
+
+impl SkyRender {
+    pub(super) fn init(gpu: &blade_graphics::Context, shader: &blade_graphics::Shader) -> Self {
+        Self {
+            pipeline: create_pipeline(shader, gpu),
+            target: None,
+        }
+    }
+
+    pub(super) fn destroy(&mut self, gpu: &blade_graphics::Context) {
+        gpu.destroy_compute_pipeline(&mut self.pipeline);
+        if let Some(target) = self.target.take() {
+            gpu.destroy_texture_view(target.view);
+            gpu.destroy_texture(target.texture);
+        }
+    }
+
+    pub(super) fn recreate_pipeline(
+        &mut self,
+        shader: &blade_graphics::Shader,
+        gpu: &blade_graphics::Context,
+    ) {
+        self.pipeline = create_pipeline(shader, gpu);
+    }
+
+    /// Get the view and the size of the sky map, allocating it on first use.
+    pub(super) fn map(
+        &mut self,
+        command_encoder: &mut blade_graphics::CommandEncoder,
+        gpu: &blade_graphics::Context,
+    ) -> (blade_graphics::TextureView, blade_graphics::Extent) {
+        let target = self.target.get_or_insert_with(|| {
+            let texture = gpu.create_texture(blade_graphics::TextureDesc {
+                name: "sky",
+                format: SKY_FORMAT,
+                size: SKY_SIZE,
+                dimension: blade_graphics::TextureDimension::D2,
+                array_layer_count: 1,
+                mip_level_count: 1,
+                usage: blade_graphics::TextureUsage::RESOURCE
+                    | blade_graphics::TextureUsage::STORAGE,
+                sample_count: 1,
+            });
+            let view = gpu.create_texture_view(
+                texture,
+                blade_graphics::TextureViewDesc {
+                    name: "sky",
+                    format: SKY_FORMAT,
+                    dimension: blade_graphics::ViewDimension::D2,
+                    subresources: &Default::default(),
+                },
+            );
+            command_encoder.init_texture(texture);
+            SkyTarget { texture, view }
+        });
+        (target.view, SKY_SIZE)
+    }
+
+    /// Bake the sky into its map. Returns false if the map isn't allocated.
+    pub(super) fn bake(
+        &self,
+        command_encoder: &mut blade_graphics::CommandEncoder,
+        params: SkyParams,
+    ) -> bool {
+        let target = match self.target {
+            Some(ref target) => target,
+            None => return false,
+        };
+        let groups = self.pipeline.get_dispatch_for(SKY_SIZE);
+        let mut pass = command_encoder.compute("sky");
+        let mut pc = pass.with(&self.pipeline);
+        pc.bind(
+            0,
+            &SkyData {
+                params,
+                output: target.view,
+            },
+        );
+        pc.dispatch(groups);
+        true
+    }
+}
@@ -0,0 +1,227 @@
+use std::cell::Cell;
+
+/// Appearance of the wireframe overlay.
+#[derive(Clone, Copy, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct WireframeConfig {
+    pub color: [f32; 4],
+    /// Line thickness, in pixels.
+    pub thickness: f32,
+}
+impl Default for WireframeConfig {
+    fn default() -> Self {
+        Self {
+            color: [1.0, 0.6, 0.1, 1.0],
+            thickness: 1.0,
+        }
+    }
+}
+
+/// Number of geometries drawn and skipped by the frustum culling
+/// in the last render of the wireframe overlay.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct CullingStats {
+    pub drawn: u32,
+    pub culled: u32,
+}
+
+#[repr(C)]
+#[derive(Clone, Copy, bytemuck::Zeroable, bytemuck::Pod)]
+struct WireframeParams {
+    transform: [[f32; 4]; 3],
+    color: [f32; 4],
+    thickness: f32,
+    indexed: u32,
+    viewport_origin: [i32; 2],
+}
+
+#[derive(blade_macros::ShaderData)]
+struct WireframeData {
+    camera: super::CameraParams,
+    params: WireframeParams,
+    vertices: blade_graphics::BufferPiece,
+    indices: blade_graphics::BufferPiece,
+    depth: blade_graphics::TextureView,
+}
+
+struct WireframeDraw {
+    transform: [[f32; 4]; 3],
+    vertex_buf: blade_graphics::BufferPiece,
+    index_buf: Option<blade_graphics::BufferPiece>,
+    triangle_count: u32,
+    /// World-space bounds of the geometry.
+    bounds: crate::BoundingBox,
+}
+
+/// Check if a box is at least partially within the view of the camera.
+/// The test is conservative: boxes crossing the frustum corners may pass.
+fn is_in_frustum(bounds: &crate::BoundingBox, camera: &super::CameraParams) -> bool {
+    if bounds.is_empty() {
+        return false;
+    }
+    let inv_rotation = glam::Quat::from_array(camera.orientation).inverse();
+    let position = glam::Vec3::from(camera.position);
+    let tan_x = (0.5 * camera.fov[0]).tan();
+    let tan_y = (0.5 * camera.fov[1]).tan();
+    let min = glam::Vec3::from(bounds.min);
+    let max = glam::Vec3::from(bounds.max);
+    // the box is outside if all of its corners are behind the same plane
+    let mut outside = [true; 6];
+    for i in 0..8 {
+        let corner = glam::Vec3::select(
+            glam::BVec3::new(i & 1 != 0, i & 2 != 0, i & 4 != 0),
+            max,
+            min,
+        );
+        // the camera looks towards -Z
+        let local = inv_rotation * (corner - position);
+        let distance = -local.z;
+        outside[0] &= distance < crate::Camera::NEAR_PLANE;
+        outside[1] &= distance > camera.depth;
+        outside[2] &= local.x > distance * tan_x;
+        outside[3] &= local.x < -distance * tan_x;
+        outside[4] &= local.y > distance * tan_y;
+        outside[5] &= local.y < -distance * tan_y;
+    }
+    !outside.contains(&true)
+}
+
+fn create_pipeline(
+    shader: &blade_graphics::Shader,
+    format: blade_graphics::TextureFormat,
+    gpu: &blade_graphics::Context,
+) -> blade_graphics::RenderPipeline {
+    shader.check_struct_size::<crate::Vertex>();
+    shader.check_struct_size::<WireframeParams>();
+    let layout = <WireframeData as blade_graphics::ShaderData>::layout();
+    gpu.create_render_pipeline(blade_graphics::RenderPipelineDesc {
+        name: "wireframe",
+        data_layouts: &[&layout],
+        vertex: shader.at("wire_vs"),
+        vertex_fetches: &[],
+        primitive: blade_graphics::PrimitiveState {
+            topology: blade_graphics::PrimitiveTopology::TriangleList,
+            ..Default::default()
+        },
+        depth_stencil: None,
+        fragment: Some(shader.at("wire_fs")),
+        color_targets: &[blade_graphics::ColorTargetState {
+            format,
+            blend: Some(blade_graphics::BlendState::ALPHA_BLENDING),
+            write_mask: blade_graphics::ColorWrites::all(),
+        }],
+        multisample_state: blade_graphics::MultisampleState::default(),
+    })
+}
+
+/// Raster overlay drawing the edges of the scene geometry.
+pub struct WireframeRender {
+    surface_format: blade_graphics::TextureFormat,
+    pipeline: blade_graphics::RenderPipeline,
+    draws: Vec<WireframeDraw>,
+    pub(super) enabled: bool,
+    pub(super) config: WireframeConfig,
+    pub(super) frustum_culling: bool,
+    pub(super) stats: Cell<CullingStats>,
+}
+
+impl WireframeRender {
+    pub(super) fn init(
+        gpu: &blade_graphics::Context,
+        shader: &blade_graphics::Shader,
+        surface_info: blade_graphics::SurfaceInfo,
+    ) -> Self {
+        Self {
+            surface_format: surface_info.format,
+            pipeline: create_pipeline(shader, surface_info.format, gpu),
+            draws: Vec::new(),
+            enabled: false,
+            config: WireframeConfig::default(),
+            frustum_culling: true,
+            stats: Cell::new(CullingStats::default()),
+        }
+    }
+
+    pub(super) fn destroy(&mut self, gpu: &blade_graphics::Context) {
+        gpu.destroy_render_pipeline(&mut self.pipeline);
+    }
+
+    pub(super) fn recreate_pipeline(
+        &mut self,
+        shader: &blade_graphics::Shader,
+        gpu: &blade_graphics::Context,
+    ) {
+        self.pipeline = create_pipeline(shader, self.surface_format, gpu);
+    }
+
+    pub(super) fn clear(&mut self) {
+        self.draws.clear();
+    }
+
+    pub(super) fn add(
+        &mut self,
+        geometry_to_world: &glam::Mat4,
+        vertex_buf: blade_graphics::BufferPiece,
+        index_buf: Option<blade_graphics::BufferPiece>,
+        triangle_count: u32,
+        bounds: &crate::BoundingBox,
+    ) {
+        let transform = [
+            geometry_to_world.row(0).to_array(),
+            geometry_to_world.row(1).to_array(),
+            geometry_to_world.row(2).to_array(),
+        ];
+        self.draws.push(WireframeDraw {
+            transform,
+            vertex_buf,
+            index_buf,
+            triangle_count,
+            bounds: bounds.transformed(&mint::RowMatrix3x4 {
+                x: transform[0].into(),
+                y: transform[1].into(),
+                z: transform[2].into(),
+            }),
+        });
+    }
+
+    pub(super) fn render(
+        &self,
+        camera: super::CameraParams,
+        depth: blade_graphics::TextureView,
+        viewport_origin: [i32; 2],
+        pass: &mut blade_graphics::RenderCommandEncoder,
+    ) {
+        if !self.enabled || self.draws.is_empty() {
+            self.stats.set(CullingStats::default());
+            return;
+        }
+        let mut stats = CullingStats::default();
+        let mut pc = pass.with(&self.pipeline);
+        for draw in self.draws.iter() {
+            if self.frustum_culling && !is_in_frustum(&draw.bounds, &camera) {
+                stats.culled += 1;
+                continue;
+            }
+            stats.drawn += 1;
+            pc.bind(
+                0,
+                &WireframeData {
+                    camera,
+                    params: WireframeParams {
+                        transform: draw.transform,
+                        color: self.config.color,
+                        thickness: self.config.thickness,
+                        indexed: draw.index_buf.is_some() as u32,
+                        viewport_origin,
+                    },
+                    vertices: draw.vertex_buf,
+                    // the binding has to be valid even if not used
+                    indices: draw.index_buf.unwrap_or(draw.vertex_buf),
+                    depth,
+                },
+            );
+            pc.draw(0, draw.triangle_count * 18, 0, 1);
+        }
+        self.stats.set(stats);
+    }
+}
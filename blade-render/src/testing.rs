@@ -0,0 +1,387 @@
+//! Headless rendering for regression tests.
+//!
+//! Renders a model into an image without any window, so that the result
+//! can be compared against a reference with `perceptual_diff`,
+//! or measures the frame times with `benchmark`.
+
+use std::{path::Path, sync::Arc, time::Duration};
+
+/// Parameters of `render_to_image`, besides the camera.
+#[derive(Clone, Debug)]
+pub struct TestSettings {
+    pub size: blade_graphics::Extent,
+    pub render: crate::RenderSettings,
+    pub ray: crate::RayConfig,
+    /// Apply the denoiser to every frame, if set.
+    pub denoiser: Option<crate::DenoiserConfig>,
+}
+
+impl Default for TestSettings {
+    fn default() -> Self {
+        Self {
+            size: blade_graphics::Extent::new_2d(256, 256),
+            // Converges in a single frame, which is the most stable to compare.
+            render: crate::RenderSettings {
+                mode: crate::RenderMode::DirectOnly,
+                ..Default::default()
+            },
+            ray: crate::RayConfig {
+                num_environment_samples: 1,
+                environment_importance_sampling: false,
+                num_brdf_samples: 0,
+                tap_count: 2,
+                tap_radius: 20,
+                tap_confidence_near: 15,
+                tap_confidence_far: 10,
+                t_start: 0.01,
+                pairwise_mis: true,
+                defensive_mis: 0.1,
+            },
+            denoiser: None,
+        }
+    }
+}
+
+/// Linear radiance of a rendered image, before post-processing.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Image {
+    pub size: blade_graphics::Extent,
+    /// RGBA per pixel, with the rows going from top to bottom.
+    pub pixels: Vec<[f32; 4]>,
+}
+
+impl Image {
+    /// Hash of the pixels quantized to 8 bits, which is stable against
+    /// tiny floating-point differences but not across GPUs in general.
+    pub fn hash(&self) -> u64 {
+        // FNV-1a
+        let mut hash = 0xcbf29ce484222325u64;
+        for pixel in self.pixels.iter() {
+            for &channel in pixel.iter() {
+                hash ^= (tone_map(channel) * 255.0 + 0.5) as u8 as u64;
+                hash = hash.wrapping_mul(0x100000001b3);
+            }
+        }
+        hash
+    }
+
+    /// Save as an OpenEXR file.
+    pub fn save(&self, path: &Path) -> Result<(), exr::error::Error> {
+        let width = self.size.width as usize;
+        exr::prelude::write_rgba_file(path, width, self.size.height as usize, |x, y| {
+            let [r, g, b, a] = self.pixels[y * width + x];
+            (r, g, b, a)
+        })
+    }
+
+    /// Load an image previously written with `save`.
+    pub fn load(path: &Path) -> Result<Self, exr::error::Error> {
+        let image = exr::prelude::read_first_rgba_layer_from_file(
+            path,
+            |resolution: exr::math::Vec2<usize>, _: &exr::prelude::RgbaChannels| Self {
+                size: blade_graphics::Extent::new_2d(
+                    resolution.width() as u32,
+                    resolution.height() as u32,
+                ),
+                pixels: vec![[0.0; 4]; resolution.width() * resolution.height()],
+            },
+            |image: &mut Self,
+             position: exr::math::Vec2<usize>,
+             (r, g, b, a): (f32, f32, f32, f32)| {
+                let index = position.y() * image.size.width as usize + position.x();
+                image.pixels[index] = [r, g, b, a];
+            },
+        )?;
+        Ok(image.layer_data.channel_data.pixels)
+    }
+}
+
+fn tone_map(value: f32) -> f32 {
+    // Reinhard, followed by the gamma, to compare the values
+    // roughly the way they are perceived.
+    let v = value.max(0.0);
+    (v / (1.0 + v)).powf(1.0 / 2.2)
+}
+
+/// Average difference between two images of the same size, computed in
+/// a tone-mapped space. Zero means equal, and one means as different
+/// as black and white.
+pub fn perceptual_diff(a: &Image, b: &Image) -> f32 {
+    assert_eq!(a.size, b.size, "Image sizes don't match");
+    if a.pixels.is_empty() {
+        return 0.0;
+    }
+    let total = a
+        .pixels
+        .iter()
+        .zip(b.pixels.iter())
+        .map(|(pa, pb)| {
+            let sum = (0..3)
+                .map(|i| (tone_map(pa[i]) - tone_map(pb[i])).powi(2))
+                .sum::<f32>();
+            (sum / 3.0).sqrt()
+        })
+        .sum::<f32>();
+    total / a.pixels.len() as f32
+}
+
+/// Everything needed to render a model without a window.
+struct Headless {
+    context: Arc<blade_graphics::Context>,
+    _workers: Vec<choir::WorkerHandle>,
+    asset_hub: crate::AssetHub,
+    pacer: crate::util::FramePacer,
+    renderer: crate::Renderer,
+    objects: [crate::Object; 1],
+    frame_index: u32,
+}
+
+impl Headless {
+    fn new(scene_path: &Path, settings: &TestSettings, timing: bool) -> Self {
+        let context = Arc::new(unsafe {
+            blade_graphics::Context::init(blade_graphics::ContextDesc {
+                timing,
+                ..Default::default()
+            })
+            .expect("Unable to initialize the GPU context")
+        });
+        let choir = choir::Choir::new();
+        let workers = (0..2)
+            .map(|i| choir.add_worker(&format!("Worker-{}", i)))
+            .collect::<Vec<_>>();
+        let cache_dir = std::env::temp_dir().join("blade-test-cache");
+        let asset_hub = crate::AssetHub::new(&cache_dir, &choir, &context);
+        let shader_path = Path::new(env!("CARGO_MANIFEST_DIR")).join("code");
+        let (shaders, shader_task) = crate::Shaders::load(&shader_path, &asset_hub);
+        let mut load_finish = choir.spawn("load finish").init_dummy();
+        let (model, model_task) = asset_hub.models.load(
+            scene_path,
+            crate::model::Meta {
+                generate_tangents: true,
+                ..Default::default()
+            },
+        );
+        load_finish.depend_on(model_task);
+        shader_task.join();
+        load_finish.run().join();
+
+        let mut pacer = crate::util::FramePacer::new(&context);
+        let (command_encoder, _) = pacer.begin_frame();
+        let mut renderer = crate::Renderer::new(
+            command_encoder,
+            &context,
+            shaders,
+            &asset_hub.shaders,
+            &crate::RenderConfig {
+                surface_size: settings.size,
+                surface_info: blade_graphics::SurfaceInfo {
+                    format: blade_graphics::TextureFormat::Rgba8Unorm,
+                    alpha: blade_graphics::AlphaMode::Ignored,
+                },
+                max_debug_lines: 0,
+            },
+        );
+        renderer.import_settings(&settings.render);
+        pacer.end_frame(&context);
+
+        Self {
+            context,
+            _workers: workers,
+            asset_hub,
+            pacer,
+            renderer,
+            objects: [crate::Object::from(model)],
+            frame_index: 0,
+        }
+    }
+
+    /// Render a frame, building the scene on the first one.
+    ///
+    /// Returns the GPU timings of the submission before the previous one,
+    /// since the encoder is double-buffered.
+    fn render_frame(
+        &mut self,
+        camera: &crate::Camera,
+        settings: &TestSettings,
+        capture: bool,
+    ) -> blade_graphics::Timings {
+        let (command_encoder, temp) = self.pacer.begin_frame();
+        let timings = command_encoder.timings().clone();
+        self.asset_hub.flush(command_encoder, &mut temp.buffers);
+        let is_first = self.frame_index == 0;
+        if is_first {
+            self.renderer.build_scene(
+                command_encoder,
+                &self.objects,
+                None,
+                &self.asset_hub,
+                &self.context,
+                temp,
+            );
+        }
+        self.renderer.prepare(
+            command_encoder,
+            camera,
+            crate::FrameConfig {
+                frozen: false,
+                debug_draw: false,
+                reset_variance: false,
+                reset_reservoirs: is_first,
+            },
+        );
+        self.renderer
+            .ray_trace(command_encoder, crate::DebugConfig::default(), settings.ray);
+        if let Some(config) = settings.denoiser {
+            self.renderer.denoise(command_encoder, config);
+        }
+        if capture {
+            self.renderer.capture_aovs(command_encoder, &self.context);
+        }
+        self.pacer.end_frame(&self.context);
+        self.frame_index += 1;
+        timings
+    }
+
+    /// Submit an empty frame, only to get the timings of an earlier one.
+    fn flush_timings(&mut self) -> blade_graphics::Timings {
+        let (command_encoder, _) = self.pacer.begin_frame();
+        let timings = command_encoder.timings().clone();
+        self.pacer.end_frame(&self.context);
+        timings
+    }
+
+    fn destroy(mut self) {
+        self.renderer.destroy(&self.context);
+        self.pacer.destroy(&self.context);
+        self.asset_hub.destroy();
+    }
+}
+
+/// Render a glTF model with no environment map, headless.
+///
+/// Every run with the same inputs produces the same random sequences,
+/// so the result only depends on the GPU and the driver.
+/// The image is the lighting multiplied by the albedo, read back
+/// after `frames` are rendered, without fog and tone mapping.
+///
+/// Panics if there is no GPU with ray tracing support.
+pub fn render_to_image(
+    scene_path: &Path,
+    camera: &crate::Camera,
+    settings: &TestSettings,
+    frames: u32,
+) -> Image {
+    let mut headless = Headless::new(scene_path, settings, false);
+    let frames = frames.max(1);
+    for frame in 0..frames {
+        headless.render_frame(camera, settings, frame + 1 == frames);
+    }
+    headless.pacer.wait_for_previous_frame(&headless.context);
+
+    let context = &headless.context;
+    // the color AOV is already multiplied by the albedo
+    let color = headless.renderer.read_aov(crate::Aov::Color, context);
+    let image = Image {
+        size: settings.size,
+        pixels: color.chunks(4).map(|c| [c[0], c[1], c[2], 1.0]).collect(),
+    };
+    headless.destroy();
+    image
+}
+
+/// Frame time statistics produced by `benchmark`.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct BenchReport {
+    /// Number of measured frames.
+    pub frame_count: u32,
+    /// True if the times come from the GPU timestamps,
+    /// false if they are measured on the CPU around each frame.
+    pub is_gpu_timed: bool,
+    pub min: Duration,
+    pub mean: Duration,
+    pub p50: Duration,
+    pub p95: Duration,
+    pub p99: Duration,
+    pub max: Duration,
+    /// Pixel samples traced per second of the frame time.
+    pub samples_per_second: f64,
+}
+
+impl BenchReport {
+    fn new(mut frame_times: Vec<Duration>, is_gpu_timed: bool, pixel_count: u64) -> Self {
+        if frame_times.is_empty() {
+            return Self::default();
+        }
+        frame_times.sort();
+        let count = frame_times.len();
+        let total = frame_times.iter().sum::<Duration>();
+        // nearest-rank percentiles
+        let percentile = |p: f64| {
+            let rank = (p * count as f64).ceil() as usize;
+            frame_times[rank.clamp(1, count) - 1]
+        };
+        Self {
+            frame_count: count as u32,
+            is_gpu_timed,
+            min: frame_times[0],
+            mean: total / count as u32,
+            p50: percentile(0.50),
+            p95: percentile(0.95),
+            p99: percentile(0.99),
+            max: frame_times[count - 1],
+            samples_per_second: if total.is_zero() {
+                0.0
+            } else {
+                (pixel_count * count as u64) as f64 / total.as_secs_f64()
+            },
+        }
+    }
+}
+
+/// Render a glTF model headless for a number of frames, and report
+/// the statistics of their times.
+///
+/// The first frame builds the scene and isn't measured. The times are
+/// the sums of the GPU timestamps of all the passes of a frame, if the
+/// device supports them, or the CPU waits on every frame otherwise.
+///
+/// Panics if there is no GPU with ray tracing support.
+pub fn benchmark(
+    scene_path: &Path,
+    camera: &crate::Camera,
+    settings: &TestSettings,
+    frames: u32,
+) -> BenchReport {
+    let mut headless = Headless::new(scene_path, settings, true);
+    headless.render_frame(camera, settings, false);
+    headless.pacer.wait_for_previous_frame(&headless.context);
+
+    // Each frame gets the timings of the one submitted 2 frames before,
+    // starting with the renderer creation and the warm-up frame.
+    let mut all_timings = Vec::with_capacity(frames as usize + 2);
+    let mut cpu_times = Vec::with_capacity(frames as usize);
+    for _ in 0..frames {
+        let start = std::time::Instant::now();
+        all_timings.push(headless.render_frame(camera, settings, false));
+        headless.pacer.wait_for_previous_frame(&headless.context);
+        cpu_times.push(start.elapsed());
+    }
+    for _ in 0..2 {
+        all_timings.push(headless.flush_timings());
+    }
+    headless.destroy();
+
+    let gpu_times = all_timings
+        .into_iter()
+        .skip(2)
+        .take(frames as usize)
+        .filter(|timings| !timings.is_empty())
+        .map(|timings| timings.iter().map(|timing| timing.1).sum::<Duration>())
+        .collect::<Vec<_>>();
+    let pixel_count = settings.size.width as u64 * settings.size.height as u64;
+    if gpu_times.len() == frames as usize {
+        BenchReport::new(gpu_times, true, pixel_count)
+    } else {
+        BenchReport::new(cpu_times, false, pixel_count)
+    }
+}
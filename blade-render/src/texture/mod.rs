@@ -25,6 +25,13 @@ pub struct Meta {
     pub format: blade_graphics::TextureFormat,
     pub generate_mips: bool,
     pub y_flip: bool,
+    /// Downscale LDR images, preserving the aspect ratio,
+    /// so that neither side exceeds this size.
+    /// Defaults to the maximum 2D texture size of the device.
+    pub max_dimension: Option<u32>,
+    /// Decode the sRGB-encoded LDR data into linear values on load,
+    /// for the linear formats of images that were authored in sRGB.
+    pub decode_srgb: bool,
 }
 
 impl fmt::Display for Meta {
@@ -37,14 +44,20 @@ pub struct Texture {
     pub object: blade_graphics::Texture,
     pub view: blade_graphics::TextureView,
     pub extent: blade_graphics::Extent,
+    /// Size of the texel data in all the mips, in bytes.
+    pub data_size: u64,
 }
 
 struct Initialization {
     dst: blade_graphics::Texture,
 }
 
+/// Alignment of each upload within the shared staging buffer,
+/// sufficient for any texel block size.
+const STAGE_ALIGNMENT: usize = 16;
+
 struct Transfer {
-    stage: blade_graphics::Buffer,
+    data: Vec<u8>,
     bytes_per_row: u32,
     dst: blade_graphics::Texture,
     extent: blade_graphics::Extent,
@@ -61,6 +74,7 @@ struct PendingOperations {
 pub struct Baker {
     gpu_context: Arc<blade_graphics::Context>,
     pending_operations: Mutex<PendingOperations>,
+    max_texture_size: u32,
 }
 
 impl Baker {
@@ -68,6 +82,7 @@ impl Baker {
         Self {
             gpu_context: Arc::clone(gpu_context),
             pending_operations: Mutex::new(PendingOperations::default()),
+            max_texture_size: gpu_context.capabilities().max_texture_2d_size,
         }
     }
 
@@ -81,8 +96,28 @@ impl Baker {
             encoder.init_texture(init.dst);
         }
         if !pending_ops.transfers.is_empty() {
+            // All the uploads go through a single staging buffer.
+            let total_size = pending_ops.transfers.iter().fold(0, |offset, transfer| {
+                blade_asset::round_up(offset, STAGE_ALIGNMENT) + transfer.data.len()
+            });
+            let stage = self.gpu_context.create_buffer(blade_graphics::BufferDesc {
+                name: "texture stage",
+                size: total_size as u64,
+                memory: blade_graphics::Memory::Upload,
+            });
+            temp_buffers.push(stage);
+
             let mut pass = encoder.transfer("init textures");
+            let mut offset = 0;
             for transfer in pending_ops.transfers.drain(..) {
+                offset = blade_asset::round_up(offset, STAGE_ALIGNMENT);
+                unsafe {
+                    ptr::copy_nonoverlapping(
+                        transfer.data.as_ptr(),
+                        stage.data().add(offset),
+                        transfer.data.len(),
+                    );
+                }
                 let dst = blade_graphics::TexturePiece {
                     texture: transfer.dst,
                     mip_level: transfer.mip_level,
@@ -90,12 +125,12 @@ impl Baker {
                     origin: [0; 3],
                 };
                 pass.copy_buffer_to_texture(
-                    transfer.stage.into(),
+                    stage.at(offset as u64),
                     transfer.bytes_per_row,
                     dst,
                     transfer.extent,
                 );
-                temp_buffers.push(transfer.stage);
+                offset += transfer.data.len();
             }
         }
     }
@@ -124,6 +159,7 @@ impl blade_asset::Baker for Baker {
         struct PlainImage {
             width: usize,
             height: usize,
+            depth: usize,
             data: PlainData,
         }
 
@@ -147,6 +183,7 @@ impl blade_asset::Baker for Baker {
                 PlainImage {
                     width: info.width,
                     height: info.height,
+                    depth: 1,
                     data: PlainData::Ldr(data),
                 }
             }
@@ -169,6 +206,7 @@ impl blade_asset::Baker for Baker {
                 PlainImage {
                     width: info.width as usize,
                     height: info.height as usize,
+                    depth: 1,
                     data: PlainData::Ldr(data),
                 }
             }
@@ -192,6 +230,7 @@ impl blade_asset::Baker for Baker {
                 PlainImage {
                     width,
                     height,
+                    depth: 1,
                     data: PlainData::Hdr(data),
                 }
             }
@@ -222,9 +261,53 @@ impl blade_asset::Baker for Baker {
                 PlainImage {
                     width: image.layer_data.size.width(),
                     height: image.layer_data.size.height(),
+                    depth: 1,
                     data: PlainData::Hdr(image.layer_data.channel_data.pixels.data),
                 }
             }
+            #[cfg(feature = "asset")]
+            "cube" => {
+                profiling::scope!("parse cube LUT");
+                let text = str::from_utf8(source).unwrap();
+                let mut size = 0;
+                let mut data = Vec::new();
+                for line in text.lines() {
+                    let line = line.trim();
+                    if line.is_empty() || line.starts_with('#') {
+                        continue;
+                    }
+                    let words = line.split_whitespace().collect::<Vec<_>>();
+                    let parse_color = |values: &[&str]| {
+                        let mut texel = [0f32; 3];
+                        for (t, value) in texel.iter_mut().zip(values) {
+                            *t = value.parse().unwrap();
+                        }
+                        texel
+                    };
+                    match words[0] {
+                        "LUT_3D_SIZE" => size = words[1].parse().unwrap(),
+                        "LUT_1D_SIZE" => panic!("1D LUTs are not supported"),
+                        "DOMAIN_MIN" | "DOMAIN_MAX" => {
+                            let expected = if words[0] == "DOMAIN_MIN" { 0.0 } else { 1.0 };
+                            let domain = parse_color(&words[1..]);
+                            if domain != [expected; 3] {
+                                log::warn!("Ignoring LUT {} of {:?}", words[0], domain);
+                            }
+                        }
+                        keyword if keyword.starts_with(|c: char| c.is_ascii_alphabetic()) => {
+                            log::debug!("Ignoring LUT keyword {}", keyword);
+                        }
+                        _ => data.push(parse_color(&words)),
+                    }
+                }
+                assert_eq!(data.len(), size * size * size);
+                PlainImage {
+                    width: size,
+                    height: size,
+                    depth: size,
+                    data: PlainData::Hdr(data),
+                }
+            }
             other => panic!("Unknown texture extension: {}", other),
         };
 
@@ -235,6 +318,63 @@ impl blade_asset::Baker for Baker {
                     profiling::scope!("y-flip");
                     zune_imageprocs::flip::vertical_flip(&mut data, src.width);
                 }
+                if meta.decode_srgb {
+                    profiling::scope!("decode sRGB");
+                    let table: [u8; 256] = std::array::from_fn(|i| {
+                        let c = i as f32 / 255.0;
+                        let linear = if c <= 0.04045 {
+                            c / 12.92
+                        } else {
+                            ((c + 0.055) / 1.055).powf(2.4)
+                        };
+                        (linear * 255.0 + 0.5) as u8
+                    });
+                    // alpha is always linear
+                    for texel in data.iter_mut() {
+                        for c in texel[..3].iter_mut() {
+                            *c = table[*c as usize];
+                        }
+                    }
+                }
+
+                let max_dimension = meta
+                    .max_dimension
+                    .map_or(self.max_texture_size, |max| max.min(self.max_texture_size));
+                let (width, height) = match max_dimension {
+                    max if src.width.max(src.height) > max as usize => {
+                        profiling::scope!("downscale");
+                        let scale = max as f32 / src.width.max(src.height) as f32;
+                        let width = ((src.width as f32 * scale) as usize).clamp(1, max as usize);
+                        let height = ((src.height as f32 * scale) as usize).clamp(1, max as usize);
+                        log::info!(
+                            "Downscaling {}x{} image to {}x{}",
+                            src.width,
+                            src.height,
+                            width,
+                            height
+                        );
+                        let mut scaled = vec![[0u8; 4]; width * height];
+                        zune_imageprocs::resize::resize(
+                            unsafe {
+                                slice::from_raw_parts(data.as_ptr() as *const u8, data.len() * 4)
+                            },
+                            unsafe {
+                                slice::from_raw_parts_mut(
+                                    scaled.as_mut_ptr() as *mut u8,
+                                    scaled.len() * 4,
+                                )
+                            },
+                            zune_imageprocs::resize::ResizeMethod::Bilinear,
+                            src.width as _,
+                            src.height as _,
+                            width as _,
+                            height as _,
+                        );
+                        data = scaled;
+                        (width, height)
+                    }
+                    _ => (src.width, src.height),
+                };
 
                 let dst_format = match meta.format {
                     Tf::Bc1Unorm | Tf::Bc1UnormSrgb => texpresso::Format::Bc1,
@@ -247,14 +387,10 @@ impl blade_asset::Baker for Baker {
 
                 let mut src_mips = vec![data];
                 let mut mips = {
-                    let compressed_size = dst_format.compressed_size(src.width, src.height);
+                    let compressed_size = dst_format.compressed_size(width, height);
                     vec![vec![0u8; compressed_size]]
                 };
-                let base_extent = blade_graphics::Extent {
-                    width: src.width as u32,
-                    height: src.height as u32,
-                    depth: 1,
-                };
+                let base_extent = blade_graphics::Extent::new_2d(width as u32, height as u32);
                 if meta.generate_mips {
                     profiling::scope!("generate mipmap");
                     for i in 1..base_extent.max_mip_levels() {
@@ -367,7 +503,7 @@ impl blade_asset::Baker for Baker {
                 }
                 cooker.finish(CookedImage {
                     name: &[],
-                    extent: [src.width as u32, src.height as u32, 1],
+                    extent: [src.width as u32, src.height as u32, src.depth as u32],
                     format: TextureFormatWrap(meta.format),
                     mips: vec![CookedMip { data: &buf }],
                 });
@@ -394,7 +530,11 @@ impl blade_asset::Baker for Baker {
                 size: base_extent,
                 array_layer_count: 1,
                 mip_level_count: image.mips.len() as u32,
-                dimension: blade_graphics::TextureDimension::D2,
+                dimension: if base_extent.depth > 1 {
+                    blade_graphics::TextureDimension::D3
+                } else {
+                    blade_graphics::TextureDimension::D2
+                },
                 usage: blade_graphics::TextureUsage::COPY | blade_graphics::TextureUsage::RESOURCE,
                 sample_count: 1,
             });
@@ -403,7 +543,11 @@ impl blade_asset::Baker for Baker {
             blade_graphics::TextureViewDesc {
                 name,
                 format: image.format.0,
-                dimension: blade_graphics::ViewDimension::D2,
+                dimension: if base_extent.depth > 1 {
+                    blade_graphics::ViewDimension::D3
+                } else {
+                    blade_graphics::ViewDimension::D2
+                },
                 subresources: &Default::default(),
             },
         );
@@ -414,15 +558,6 @@ impl blade_asset::Baker for Baker {
             .push(Initialization { dst: texture });
 
         for (i, mip) in image.mips.iter().enumerate() {
-            let stage = self.gpu_context.create_buffer(blade_graphics::BufferDesc {
-                name: &format!("{name}[{i}]/stage"),
-                size: mip.data.len() as u64,
-                memory: blade_graphics::Memory::Upload,
-            });
-            unsafe {
-                ptr::copy_nonoverlapping(mip.data.as_ptr(), stage.data(), mip.data.len());
-            }
-
             let block_info = image.format.0.block_info();
             let extent = base_extent.at_mip_level(i as u32);
             let bytes_per_row = ((extent.width + block_info.dimensions.0 as u32 - 1)
@@ -436,7 +571,7 @@ impl blade_asset::Baker for Baker {
 
             let mut pending_ops = self.pending_operations.lock().unwrap();
             pending_ops.transfers.push(Transfer {
-                stage,
+                data: mip.data.to_vec(),
                 bytes_per_row,
                 dst: texture,
                 extent,
@@ -448,6 +583,7 @@ impl blade_asset::Baker for Baker {
             object: texture,
             view,
             extent: base_extent,
+            data_size: image.mips.iter().map(|mip| mip.data.len() as u64).sum(),
         }
     }
 
@@ -38,6 +38,12 @@ impl FramePacer {
         for accel_structure in self.prev_resources.acceleration_structures.drain(..) {
             context.destroy_acceleration_structure(accel_structure);
         }
+        for view in self.prev_resources.texture_views.drain(..) {
+            context.destroy_texture_view(view);
+        }
+        for texture in self.prev_resources.textures.drain(..) {
+            context.destroy_texture(texture);
+        }
     }
 
     pub fn last_sync_point(&self) -> Option<&blade_graphics::SyncPoint> {
@@ -45,6 +51,7 @@ impl FramePacer {
     }
 
     pub fn destroy(&mut self, context: &blade_graphics::Context) {
+        context.wait_idle();
         self.wait_for_previous_frame(context);
         context.destroy_command_encoder(&mut self.command_encoder);
     }
@@ -1,6 +1,8 @@
 mod frame_pacer;
+mod render_graph;
 
 pub use self::frame_pacer::*;
+pub use self::render_graph::*;
 
 pub fn align_to(offset: u64, alignment: u64) -> u64 {
     let rem = offset & (alignment - 1);
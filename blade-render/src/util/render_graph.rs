@@ -0,0 +1,413 @@
+//! A small render graph.
+//!
+//! Passes declare the textures they read and write. The graph derives
+//! the execution order from that, skips the passes that don't contribute
+//! to any imported texture, and allocates the transient textures, sharing
+//! them between the resources whose lifetimes don't overlap.
+//!
+//! Blade synchronizes every pass with the previous ones,
+//! so there are no barriers to compute here.
+
+/// Texture known to a `RenderGraph`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct ResourceId(usize);
+
+/// Texture that only lives within a single execution of the graph.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct TransientTextureDesc {
+    pub format: blade_graphics::TextureFormat,
+    pub size: blade_graphics::Extent,
+    pub usage: blade_graphics::TextureUsage,
+}
+
+enum ResourceKind {
+    Imported(blade_graphics::TextureView),
+    Transient(TransientTextureDesc),
+}
+
+struct Resource {
+    name: String,
+    kind: ResourceKind,
+}
+
+/// Views of the graph resources, as seen by the passes.
+pub struct PassResources {
+    views: Vec<blade_graphics::TextureView>,
+}
+
+impl PassResources {
+    pub fn view(&self, id: ResourceId) -> blade_graphics::TextureView {
+        self.views[id.0]
+    }
+}
+
+type PassFn<'a> = Box<dyn FnOnce(&mut blade_graphics::CommandEncoder, &PassResources) + 'a>;
+
+struct Pass<'a> {
+    name: String,
+    reads: Vec<ResourceId>,
+    writes: Vec<ResourceId>,
+    run: PassFn<'a>,
+}
+
+/// Order of the passes to run, and the pool slots of the transient resources.
+#[derive(Debug, PartialEq)]
+struct Schedule {
+    passes: Vec<usize>,
+    slots: Vec<Option<usize>>,
+    slot_descs: Vec<TransientTextureDesc>,
+}
+
+/// Passes of a frame, together with the textures they access.
+#[derive(Default)]
+pub struct RenderGraph<'a> {
+    resources: Vec<Resource>,
+    passes: Vec<Pass<'a>>,
+}
+
+impl<'a> RenderGraph<'a> {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add an existing texture. Passes writing to it are always executed.
+    pub fn import_texture(&mut self, name: &str, view: blade_graphics::TextureView) -> ResourceId {
+        self.resources.push(Resource {
+            name: name.to_string(),
+            kind: ResourceKind::Imported(view),
+        });
+        ResourceId(self.resources.len() - 1)
+    }
+
+    /// Add a texture allocated by the graph. Its contents are undefined
+    /// before the first pass writing to it.
+    pub fn create_texture(&mut self, name: &str, desc: TransientTextureDesc) -> ResourceId {
+        self.resources.push(Resource {
+            name: name.to_string(),
+            kind: ResourceKind::Transient(desc),
+        });
+        ResourceId(self.resources.len() - 1)
+    }
+
+    /// Add a pass, which records its own commands into the encoder.
+    ///
+    /// A read sees the last write declared before the pass, or the first
+    /// one declared after it if there is none before, so the passes
+    /// don't have to be declared in the execution order. A pass that only
+    /// modifies a texture, like a blend on top of it, has to list it
+    /// in both `reads` and `writes`.
+    pub fn add_pass(
+        &mut self,
+        name: &str,
+        reads: &[ResourceId],
+        writes: &[ResourceId],
+        run: impl FnOnce(&mut blade_graphics::CommandEncoder, &PassResources) + 'a,
+    ) {
+        self.passes.push(Pass {
+            name: name.to_string(),
+            reads: reads.to_vec(),
+            writes: writes.to_vec(),
+            run: Box::new(run),
+        });
+    }
+
+    /// Find the pass producing the contents that `reader` sees in `res`.
+    fn find_writer(&self, reader: usize, res: ResourceId) -> Option<usize> {
+        let writes = |pass: &Pass| pass.writes.contains(&res);
+        if let Some(index) = self.passes[..reader].iter().rposition(writes) {
+            Some(index)
+        } else if self.passes[reader].writes.contains(&res) {
+            // reading the initial contents
+            None
+        } else {
+            self.passes[reader + 1..]
+                .iter()
+                .position(writes)
+                .map(|index| reader + 1 + index)
+        }
+    }
+
+    fn schedule(&self) -> Schedule {
+        let count = self.passes.len();
+        // passes producing the data each pass reads
+        let mut producers = vec![Vec::new(); count];
+        for (index, pass) in self.passes.iter().enumerate() {
+            for &res in pass.reads.iter() {
+                match self.find_writer(index, res) {
+                    Some(writer) => producers[index].push(writer),
+                    None => {
+                        let resource = &self.resources[res.0];
+                        assert!(
+                            matches!(resource.kind, ResourceKind::Imported(_)),
+                            "Pass '{}' reads '{}', which is never written",
+                            pass.name,
+                            resource.name,
+                        );
+                    }
+                }
+            }
+        }
+        // passes that have to finish before each pass overwrites their data
+        let mut predecessors = producers.clone();
+        for (index, pass) in self.passes.iter().enumerate() {
+            for &res in pass.writes.iter() {
+                if let Some(writer) = self.passes[..index]
+                    .iter()
+                    .rposition(|p| p.writes.contains(&res))
+                {
+                    predecessors[index].push(writer);
+                }
+                for (other, other_pass) in self.passes.iter().enumerate() {
+                    if other == index || !other_pass.reads.contains(&res) {
+                        continue;
+                    }
+                    match self.find_writer(other, res) {
+                        Some(writer) if writer >= index => {}
+                        _ => predecessors[index].push(other),
+                    }
+                }
+            }
+        }
+
+        // keep the passes that contribute to the imported textures
+        let mut needed = vec![false; count];
+        let mut stack =
+            self.passes
+                .iter()
+                .enumerate()
+                .filter(|&(_, pass)| {
+                    pass.writes.is_empty()
+                        || pass.writes.iter().any(|res| {
+                            matches!(self.resources[res.0].kind, ResourceKind::Imported(_))
+                        })
+                })
+                .map(|(index, _)| index)
+                .collect::<Vec<_>>();
+        while let Some(index) = stack.pop() {
+            if !needed[index] {
+                needed[index] = true;
+                stack.extend_from_slice(&producers[index]);
+            }
+        }
+
+        // order them, preferring the declaration order
+        let needed_count = needed.iter().filter(|&&n| n).count();
+        let mut passes = Vec::with_capacity(needed_count);
+        let mut done = vec![false; count];
+        while passes.len() < needed_count {
+            let next = (0..count)
+                .find(|&index| {
+                    needed[index]
+                        && !done[index]
+                        && predecessors[index]
+                            .iter()
+                            .all(|&other| done[other] || !needed[other])
+                })
+                .expect("Render graph has a dependency cycle");
+            done[next] = true;
+            passes.push(next);
+        }
+
+        // assign the transient resources to the slots, reusing the ones
+        // of the resources that are no longer used
+        let mut last_use = vec![0; self.resources.len()];
+        for (position, &index) in passes.iter().enumerate() {
+            let pass = &self.passes[index];
+            for res in pass.reads.iter().chain(pass.writes.iter()) {
+                last_use[res.0] = position;
+            }
+        }
+        let mut slots = vec![None; self.resources.len()];
+        let mut slot_descs = Vec::<TransientTextureDesc>::new();
+        let mut free_slots = Vec::new();
+        for (position, &index) in passes.iter().enumerate() {
+            let pass = &self.passes[index];
+            for res in pass.reads.iter().chain(pass.writes.iter()) {
+                let desc = match self.resources[res.0].kind {
+                    ResourceKind::Transient(ref desc) => desc,
+                    ResourceKind::Imported(_) => continue,
+                };
+                if slots[res.0].is_none() {
+                    let slot = match free_slots.iter().position(|&s| slot_descs[s] == *desc) {
+                        Some(i) => free_slots.swap_remove(i),
+                        None => {
+                            slot_descs.push(*desc);
+                            slot_descs.len() - 1
+                        }
+                    };
+                    slots[res.0] = Some(slot);
+                }
+            }
+            for res in pass.reads.iter().chain(pass.writes.iter()) {
+                if let Some(slot) = slots[res.0] {
+                    if last_use[res.0] == position && !free_slots.contains(&slot) {
+                        free_slots.push(slot);
+                    }
+                }
+            }
+        }
+
+        Schedule {
+            passes,
+            slots,
+            slot_descs,
+        }
+    }
+
+    /// Run the needed passes in order, with the transient textures from the pool.
+    pub fn execute(
+        self,
+        encoder: &mut blade_graphics::CommandEncoder,
+        pool: &mut TransientPool,
+        gpu: &blade_graphics::Context,
+    ) {
+        let schedule = self.schedule();
+        let slot_views = pool.acquire(&schedule.slot_descs, encoder, gpu);
+        self.run(schedule, &slot_views, encoder);
+    }
+
+    /// Run the needed passes in order, for a graph that only has imported
+    /// textures, and therefore doesn't need a pool.
+    pub fn execute_imported(self, encoder: &mut blade_graphics::CommandEncoder) {
+        let schedule = self.schedule();
+        assert!(
+            schedule.slot_descs.is_empty(),
+            "Render graph has transient textures"
+        );
+        self.run(schedule, &[], encoder);
+    }
+
+    fn run(
+        self,
+        schedule: Schedule,
+        slot_views: &[blade_graphics::TextureView],
+        encoder: &mut blade_graphics::CommandEncoder,
+    ) {
+        let views = self
+            .resources
+            .iter()
+            .zip(schedule.slots.iter())
+            .map(|(resource, slot)| match (&resource.kind, slot) {
+                (&ResourceKind::Imported(view), _) => view,
+                (&ResourceKind::Transient(_), &Some(slot)) => slot_views[slot],
+                // not used by any of the passes
+                (&ResourceKind::Transient(_), &None) => blade_graphics::TextureView::default(),
+            })
+            .collect();
+        let resources = PassResources { views };
+
+        let mut passes = self.passes.into_iter().map(Some).collect::<Vec<_>>();
+        for index in schedule.passes {
+            let pass = passes[index].take().unwrap();
+            (pass.run)(encoder, &resources);
+        }
+    }
+}
+
+struct PoolEntry {
+    desc: TransientTextureDesc,
+    texture: blade_graphics::Texture,
+    view: blade_graphics::TextureView,
+}
+
+/// Textures backing the transient resources of render graphs,
+/// kept between the frames.
+#[derive(Default)]
+pub struct TransientPool {
+    entries: Vec<PoolEntry>,
+}
+
+impl TransientPool {
+    fn acquire(
+        &mut self,
+        descs: &[TransientTextureDesc],
+        encoder: &mut blade_graphics::CommandEncoder,
+        gpu: &blade_graphics::Context,
+    ) -> Vec<blade_graphics::TextureView> {
+        let mut taken = vec![false; self.entries.len()];
+        let mut views = Vec::with_capacity(descs.len());
+        for desc in descs {
+            let existing = self
+                .entries
+                .iter()
+                .enumerate()
+                .position(|(i, entry)| !taken[i] && entry.desc == *desc);
+            let index = match existing {
+                Some(index) => index,
+                None => {
+                    let texture = gpu.create_texture(blade_graphics::TextureDesc {
+                        name: "transient",
+                        format: desc.format,
+                        size: desc.size,
+                        dimension: blade_graphics::TextureDimension::D2,
+                        array_layer_count: 1,
+                        mip_level_count: 1,
+                        usage: desc.usage,
+                        sample_count: 1,
+                    });
+                    let view = gpu.create_texture_view(
+                        texture,
+                        blade_graphics::TextureViewDesc {
+                            name: "transient",
+                            format: desc.format,
+                            dimension: blade_graphics::ViewDimension::D2,
+                            subresources: &Default::default(),
+                        },
+                    );
+                    encoder.init_texture(texture);
+                    self.entries.push(PoolEntry {
+                        desc: *desc,
+                        texture,
+                        view,
+                    });
+                    taken.push(false);
+                    self.entries.len() - 1
+                }
+            };
+            taken[index] = true;
+            views.push(self.entries[index].view);
+        }
+        views
+    }
+
+    /// Free all the textures. The GPU must not be using them any more,
+    /// so this is also the way to drop the old sizes after a resize.
+    pub fn destroy(&mut self, gpu: &blade_graphics::Context) {
+        for entry in self.entries.drain(..) {
+            gpu.destroy_texture_view(entry.view);
+            gpu.destroy_texture(entry.texture);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{RenderGraph, TransientTextureDesc};
+
+    #[test]
+    fn schedule() {
+        let desc = TransientTextureDesc {
+            format: blade_graphics::TextureFormat::Rgba16Float,
+            size: blade_graphics::Extent::new_2d(4, 4),
+            usage: blade_graphics::TextureUsage::RESOURCE | blade_graphics::TextureUsage::STORAGE,
+        };
+        let mut graph = RenderGraph::new();
+        let target = graph.import_texture("target", blade_graphics::TextureView::default());
+        let gbuf = graph.create_texture("gbuf", desc);
+        let lit = graph.create_texture("lit", desc);
+        let blurred = graph.create_texture("blurred", desc);
+        let unused = graph.create_texture("unused", desc);
+        // declared before the pass producing its input
+        graph.add_pass("post", &[blurred], &[target], |_, _| {});
+        graph.add_pass("gbuf", &[], &[gbuf], |_, _| {});
+        graph.add_pass("debug", &[gbuf], &[unused], |_, _| {});
+        graph.add_pass("shade", &[gbuf], &[lit], |_, _| {});
+        graph.add_pass("blur", &[lit], &[blurred], |_, _| {});
+
+        let schedule = graph.schedule();
+        assert_eq!(schedule.passes, [1, 3, 4, 0]);
+        // "blurred" takes the place of "gbuf", which is no longer needed
+        assert_eq!(schedule.slots, [None, Some(0), Some(1), Some(0), None]);
+        assert_eq!(schedule.slot_descs, [desc, desc]);
+    }
+}
@@ -0,0 +1,31 @@
+//! Frame time benchmark of the bundled assets.
+//!
+//! Needs a GPU with ray tracing, so it's ignored by default:
+//! `cargo test -p blade-render --release --test benchmark -- --ignored --nocapture`
+//! The number of frames can be changed with `BLADE_BENCH_FRAMES`.
+
+use std::path::Path;
+
+#[test]
+#[ignore]
+fn monkey() {
+    let root = Path::new(env!("CARGO_MANIFEST_DIR"));
+    let scene_path = root.join("../examples/scene/data/monkey.gltf");
+    let camera = blade_render::Camera {
+        pos: [0.0, 0.0, 3.0].into(),
+        rot: [0.0, 0.0, 0.0, 1.0].into(),
+        fov_y: 1.0,
+        depth: 100.0,
+    };
+    let settings = blade_render::testing::TestSettings {
+        size: blade_graphics::Extent::new_2d(1280, 720),
+        ..Default::default()
+    };
+    let frames = std::env::var("BLADE_BENCH_FRAMES")
+        .ok()
+        .and_then(|value| value.parse().ok())
+        .unwrap_or(100);
+    let report = blade_render::benchmark(&scene_path, &camera, &settings, frames);
+    println!("{report:#?}");
+    assert_eq!(report.frame_count, frames);
+}
@@ -0,0 +1,62 @@
+//! Golden image tests, comparing the renders of the bundled assets
+//! against the references in "tests/reference".
+//!
+//! These need a GPU with ray tracing, so they are ignored by default:
+//! `cargo test -p blade-render -- --ignored`
+//! A missing reference is written out instead of compared, and setting
+//! `BLADE_BLESS=1` overwrites the existing ones.
+
+use std::path::{Path, PathBuf};
+
+/// Maximum average difference, see `blade_render::testing::perceptual_diff`.
+const TOLERANCE: f32 = 0.01;
+
+fn check(name: &str, scene: &str, camera: blade_render::Camera) {
+    let root = Path::new(env!("CARGO_MANIFEST_DIR"));
+    let scene_path = root.join("../examples/scene/data").join(scene);
+    let settings = blade_render::testing::TestSettings::default();
+    let image = blade_render::testing::render_to_image(&scene_path, &camera, &settings, 1);
+
+    let reference_path: PathBuf = root.join("tests/reference").join(format!("{name}.exr"));
+    if !reference_path.exists() || std::env::var_os("BLADE_BLESS").is_some() {
+        image.save(&reference_path).unwrap();
+        println!("Written {}", reference_path.display());
+        return;
+    }
+    let reference = blade_render::testing::Image::load(&reference_path).unwrap();
+    let diff = blade_render::testing::perceptual_diff(&image, &reference);
+    assert!(
+        diff <= TOLERANCE,
+        "{name} differs from the reference by {diff}"
+    );
+}
+
+#[test]
+#[ignore]
+fn monkey() {
+    check(
+        "monkey",
+        "monkey.gltf",
+        blade_render::Camera {
+            pos: [0.0, 0.0, 3.0].into(),
+            rot: [0.0, 0.0, 0.0, 1.0].into(),
+            fov_y: 1.0,
+            depth: 100.0,
+        },
+    );
+}
+
+#[test]
+#[ignore]
+fn plane() {
+    check(
+        "plane",
+        "plane.glb",
+        blade_render::Camera {
+            pos: [2.7, 1.6, 2.1].into(),
+            rot: [-0.07, 0.36, 0.01, 0.93].into(),
+            fov_y: 1.0,
+            depth: 100.0,
+        },
+    );
+}
@@ -0,0 +1,102 @@
+//! Distribution of the low-discrepancy samples over the frames.
+//!
+//! Runs the sampler of "random.inc.wgsl" on the GPU, so it's ignored by default:
+//! `cargo test -p blade-render --test sampling -- --ignored`
+
+use std::{path::Path, slice};
+
+const PIXELS: u32 = 16;
+const FRAMES: u32 = 64;
+/// Number of dimensions drawn from every pixel in every frame.
+const DIMENSIONS: u32 = 17;
+/// Cells per side of the grid the 2D samples are counted in.
+const GRID: usize = 8;
+
+// Has to match `PIXELS` and `DIMENSIONS`!
+const ENTRY_POINT: &str = "
+var<storage, read_write> samples: array<f32>;
+
+@compute @workgroup_size(8, 8)
+fn main(@builtin(global_invocation_id) global_id: vec3<u32>) {
+    let pixel = global_id.x;
+    let frame = global_id.y;
+    var rng = random_init_low_discrepancy(vec2<u32>(pixel, 3u * pixel), frame);
+    let base = (frame * 16u + pixel) * 17u;
+    for (var i = 0u; i < 17u; i += 1u) {
+        samples[base + i] = random_gen(&rng);
+    }
+}
+";
+
+#[derive(blade_macros::ShaderData)]
+struct SampleData {
+    samples: blade_graphics::BufferPiece,
+}
+
+/// Any two consecutive dimensions, which make up a 2D sample like a direction
+/// on the hemisphere, need to cover the unit square over the frames.
+#[test]
+#[ignore]
+fn stratified_over_frames() {
+    let context = unsafe {
+        blade_graphics::Context::init(blade_graphics::ContextDesc::default())
+            .expect("Unable to initialize the GPU context")
+    };
+    let code_path = Path::new(env!("CARGO_MANIFEST_DIR")).join("code");
+    let source = std::fs::read_to_string(code_path.join("random.inc.wgsl")).unwrap() + ENTRY_POINT;
+    let shader = context.create_shader(blade_graphics::ShaderDesc { source: &source });
+    let mut pipeline = context.create_compute_pipeline(blade_graphics::ComputePipelineDesc {
+        name: "sampling",
+        data_layouts: &[&<SampleData as blade_graphics::ShaderData>::layout()],
+        compute: shader.at("main"),
+    });
+
+    let count = (PIXELS * FRAMES * DIMENSIONS) as usize;
+    let buffer = context.create_buffer(blade_graphics::BufferDesc {
+        name: "samples",
+        size: (count * 4) as u64,
+        memory: blade_graphics::Memory::Shared,
+    });
+    let mut encoder = context.create_command_encoder(blade_graphics::CommandEncoderDesc {
+        name: "sampling",
+        buffer_count: 1,
+    });
+    encoder.start();
+    {
+        let mut pass = encoder.compute("sampling");
+        let mut pc = pass.with(&pipeline);
+        pc.bind(
+            0,
+            &SampleData {
+                samples: buffer.into(),
+            },
+        );
+        pc.dispatch(pipeline.get_dispatch_for(blade_graphics::Extent::new_2d(PIXELS, FRAMES)));
+    }
+    let sync_point = context.submit(&mut encoder);
+    assert!(context.wait_for(&sync_point, 1000));
+    let samples = unsafe { slice::from_raw_parts(buffer.data() as *const f32, count) };
+
+    for pixel in 0..PIXELS {
+        for dim in 0..DIMENSIONS - 1 {
+            let mut cells = [[false; GRID]; GRID];
+            for frame in 0..FRAMES {
+                let base = ((frame * PIXELS + pixel) * DIMENSIONS + dim) as usize;
+                let cell = |value: f32| ((value * GRID as f32) as usize).min(GRID - 1);
+                cells[cell(samples[base])][cell(samples[base + 1])] = true;
+            }
+            let covered = cells.iter().flatten().filter(|&&c| c).count();
+            // Samples on a line cover about `2 * GRID` cells,
+            // and uniformly random ones about 40 out of 64.
+            assert!(
+                covered >= GRID * GRID / 2,
+                "Dimensions {dim} and {} of pixel {pixel} only cover {covered} cells",
+                dim + 1
+            );
+        }
+    }
+
+    context.destroy_buffer(buffer);
+    context.destroy_compute_pipeline(&mut pipeline);
+    context.destroy_command_encoder(&mut encoder);
+}
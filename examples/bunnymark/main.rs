@@ -64,11 +64,7 @@ impl Example {
     fn make_surface_config(size: winit::dpi::PhysicalSize<u32>) -> gpu::SurfaceConfig {
         log::info!("Window size: {:?}", size);
         gpu::SurfaceConfig {
-            size: gpu::Extent {
-                width: size.width,
-                height: size.height,
-                depth: 1,
-            },
+            size: gpu::Extent::new_2d(size.width, size.height),
             usage: gpu::TextureUsage::TARGET,
             display_sync: gpu::DisplaySync::Recent,
             ..Default::default()
@@ -84,10 +80,11 @@ impl Example {
                 capture: false,
                 overlay: true,
                 device_id: 0,
+                ..Default::default()
             })
             .unwrap()
         };
-        println!("{:?}", context.device_information());
+        println!("{} {:?}", context.backend(), context.device_information());
         let window_size = window.inner_size();
 
         let surface = context
@@ -126,11 +123,7 @@ impl Example {
             multisample_state: gpu::MultisampleState::default(),
         });
 
-        let extent = gpu::Extent {
-            width: 1,
-            height: 1,
-            depth: 1,
-        };
+        let extent = gpu::Extent::new_2d(1, 1);
         let texture = context.create_texture(gpu::TextureDesc {
             name: "texutre",
             format: gpu::TextureFormat::Rgba8Unorm,
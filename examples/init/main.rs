@@ -177,6 +177,8 @@ fn main() {
                 format: blade_graphics::TextureFormat::Rgba32Float,
                 generate_mips: false,
                 y_flip: false,
+                max_dimension: None,
+                decode_srgb: false,
             };
             let (texture, texture_task) = asset_hub.textures.load(arg, meta);
             load_finish.depend_on(texture_task);
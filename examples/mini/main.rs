@@ -45,11 +45,7 @@ fn main() {
         compute: shader.at("main"),
     });
 
-    let extent = gpu::Extent {
-        width: 16,
-        height: 16,
-        depth: 1,
-    };
+    let extent = gpu::Extent::new_2d(16, 16);
     let mip_level_count = extent.max_mip_levels();
     let texture = context.create_texture(gpu::TextureDesc {
         name: "input",
@@ -150,11 +146,7 @@ fn main() {
             },
             result_buffer.into(),
             4,
-            gpu::Extent {
-                width: 1,
-                height: 1,
-                depth: 1,
-            },
+            gpu::Extent::new_2d(1, 1),
         );
     }
     let sync_point = context.submit(&mut command_encoder);
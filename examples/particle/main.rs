@@ -47,11 +47,7 @@ impl Example {
             let msaa_texture = self.context.create_texture(gpu::TextureDesc {
                 name: "msaa texture",
                 format,
-                size: gpu::Extent {
-                    width,
-                    height,
-                    depth: 1,
-                },
+                size: gpu::Extent::new_2d(width, height),
                 sample_count: self.sample_count,
                 dimension: gpu::TextureDimension::D2,
                 usage: gpu::TextureUsage::TARGET,
@@ -76,11 +72,7 @@ impl Example {
     fn make_surface_config(size: winit::dpi::PhysicalSize<u32>) -> gpu::SurfaceConfig {
         log::info!("Window size: {:?}", size);
         gpu::SurfaceConfig {
-            size: gpu::Extent {
-                width: size.width,
-                height: size.height,
-                depth: 1,
-            },
+            size: gpu::Extent::new_2d(size.width, size.height),
             usage: gpu::TextureUsage::TARGET,
             display_sync: gpu::DisplaySync::Block,
             ..Default::default()
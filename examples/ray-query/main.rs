@@ -60,15 +60,11 @@ impl Example {
             .ray_query
             .contains(gpu::ShaderVisibility::COMPUTE));
 
-        let screen_size = gpu::Extent {
-            width: window_size.width,
-            height: window_size.height,
-            depth: 1,
-        };
+        let screen_size = gpu::Extent::new_2d(window_size.width, window_size.height);
         let surface_config = gpu::SurfaceConfig {
             size: screen_size,
             usage: gpu::TextureUsage::TARGET,
-            transparent: true,
+            composite_alpha: gpu::CompositeAlpha::PreMultiplied,
             ..Default::default()
         };
         let surface = context
@@ -333,8 +329,9 @@ fn main() {
     env_logger::init();
 
     let event_loop = winit::event_loop::EventLoop::new().unwrap();
-    let window_attributes =
-        winit::window::Window::default_attributes().with_title("blade-ray-query");
+    let window_attributes = winit::window::Window::default_attributes()
+        .with_title("blade-ray-query")
+        .with_transparent(true);
 
     let window = event_loop.create_window(window_attributes).unwrap();
 
@@ -97,6 +97,9 @@ impl TransformComponents {
 
 struct ObjectExtra {
     path: PathBuf,
+    animation: Option<blade_render::AnimationPlayer>,
+    /// Time of the animation the renderer has the pose of.
+    posed_time: Option<f32>,
 }
 
 #[derive(serde::Deserialize, serde::Serialize)]
@@ -131,6 +134,16 @@ struct ConfigScene {
     objects: Vec<ConfigObject>,
 }
 
+/// Everything needed to reproduce a shot, apart from the scene itself.
+#[derive(serde::Deserialize, serde::Serialize)]
+struct ConfigView {
+    camera: blade_render::Camera,
+    ray: blade_render::RayConfig,
+    denoiser: blade_render::DenoiserConfig,
+    post_proc: blade_render::PostProcConfig,
+    settings: blade_render::RenderSettings,
+}
+
 struct Example {
     scene_path: PathBuf,
     scene_environment_map: String,
@@ -142,6 +155,9 @@ struct Example {
     context: Arc<gpu::Context>,
     surface: gpu::Surface,
     environment_map: Option<blade_asset::Handle<blade_render::Texture>>,
+    /// Rotation of the environment around the vertical axis, in degrees.
+    environment_yaw: f32,
+    environment_intensity: f32,
     objects: Vec<blade_render::Object>,
     object_extras: Vec<ObjectExtra>,
     selected_object_index: Option<usize>,
@@ -156,11 +172,21 @@ struct Example {
     is_point_selected: bool,
     is_file_hovered: bool,
     ray_config: blade_render::RayConfig,
+    mip_bias: f32,
+    firefly_clamp: f32,
+    render_mode: blade_render::RenderMode,
     denoiser_enabled: bool,
+    draw_instance_bounds: bool,
     denoiser_config: blade_render::DenoiserConfig,
     post_proc_config: blade_render::PostProcConfig,
+    fog_config: blade_render::FogConfig,
+    grid: Option<blade_render::GridConfig>,
+    adaptive_sampling: Option<blade_render::AdaptiveConfig>,
+    auto_exposure: blade_render::AutoExposureConfig,
+    caustics: blade_render::CausticsConfig,
     debug_blit: Option<blade_render::DebugBlit>,
     debug_blit_input: DebugBlitInput,
+    last_frame_time: time::Instant,
     workers: Vec<choir::WorkerHandle>,
     choir: Arc<choir::Choir>,
 }
@@ -168,11 +194,7 @@ struct Example {
 impl Example {
     fn make_surface_config(physical_size: winit::dpi::PhysicalSize<u32>) -> gpu::SurfaceConfig {
         gpu::SurfaceConfig {
-            size: gpu::Extent {
-                width: physical_size.width,
-                height: physical_size.height,
-                depth: 1,
-            },
+            size: gpu::Extent::new_2d(physical_size.width, physical_size.height),
             usage: gpu::TextureUsage::TARGET,
             display_sync: gpu::DisplaySync::Block,
             ..Default::default()
@@ -241,6 +263,8 @@ impl Example {
             context,
             surface,
             environment_map: None,
+            environment_yaw: 0.0,
+            environment_intensity: 1.0,
             objects: Vec::new(),
             object_extras: Vec::new(),
             selected_object_index: None,
@@ -255,7 +279,11 @@ impl Example {
             is_point_selected: false,
             is_file_hovered: false,
             ray_config: blade_helpers::default_ray_config(),
+            mip_bias: 0.0,
+            firefly_clamp: f32::INFINITY,
+            render_mode: blade_render::RenderMode::default(),
             denoiser_enabled: true,
+            draw_instance_bounds: false,
             denoiser_config: blade_render::DenoiserConfig {
                 num_passes: 3,
                 temporal_weight: 0.1,
@@ -264,9 +292,17 @@ impl Example {
                 average_luminocity: 1.0,
                 exposure_key_value: 1.0 / 9.6,
                 white_level: 1.0,
+                output_transform: blade_render::OutputTransform::Tonemap,
+                viewport: None,
             },
+            fog_config: blade_render::FogConfig::default(),
+            grid: None,
+            adaptive_sampling: None,
+            auto_exposure: blade_render::AutoExposureConfig::default(),
+            caustics: blade_render::CausticsConfig::default(),
             debug_blit: None,
             debug_blit_input: DebugBlitInput::None,
+            last_frame_time: time::Instant::now(),
             workers,
             choir,
         }
@@ -281,21 +317,76 @@ impl Example {
         self.context.destroy_surface(&mut self.surface);
     }
 
-    pub fn load_scene(&mut self, scene_path: &Path) {
-        if self.scene_load_task.is_some() {
-            log::error!("Unable to reload the scene while something is loading");
-            return;
+    fn clear_scene(&mut self) {
+        for index in 0..self.objects.len() {
+            self.renderer.set_object_pose(index, None);
         }
-
         self.objects.clear();
         self.object_extras.clear();
         self.selected_object_index = None;
         self.have_objects_changed = true;
+        self.environment_map = None;
+    }
+
+    /// Load the Cornell box built into the renderer.
+    pub fn load_default_scene(&mut self) {
+        if self.scene_load_task.is_some() {
+            log::error!("Unable to reload the scene while something is loading");
+            return;
+        }
+        self.clear_scene();
+
+        log::info!("Loading the default scene");
+        self.camera.inner = blade_render::Camera::default_scene();
+        self.camera.fly_speed = 1.0;
+        self.ray_config.environment_importance_sampling = false;
+
+        let (model, model_task) = self.asset_hub.load_default_scene();
+        let mut load_finish = self.choir.spawn("load finish").init_dummy();
+        load_finish.depend_on(model_task);
+        let transform = gpu::IDENTITY_TRANSFORM;
+        self.objects.push(blade_render::Object {
+            model,
+            transform,
+            prev_transform: transform,
+        });
+        self.object_extras.push(ObjectExtra {
+            path: PathBuf::from("cornell-box.gltf"),
+            animation: None,
+            posed_time: None,
+        });
+
+        self.scene_load_task = Some(load_finish.run());
+        // there is no file to save it to
+        self.scene_path = PathBuf::new();
+        self.scene_environment_map = String::new();
+    }
+
+    /// Load a scene file, falling back to the default scene
+    /// if it can't be read.
+    pub fn load_scene(&mut self, scene_path: &Path) {
+        if self.scene_load_task.is_some() {
+            log::error!("Unable to reload the scene while something is loading");
+            return;
+        }
 
         log::info!("Loading scene from: {}", scene_path.display());
-        let config_scene: ConfigScene =
-            ron::de::from_bytes(&fs::read(scene_path).expect("Unable to open the scene file"))
-                .expect("Unable to parse the scene file");
+        let config_scene: ConfigScene = match fs::read(scene_path) {
+            Ok(data) => match ron::de::from_bytes(&data) {
+                Ok(config_scene) => config_scene,
+                Err(e) => {
+                    log::error!("Unable to parse the scene file: {}", e);
+                    self.load_default_scene();
+                    return;
+                }
+            },
+            Err(e) => {
+                log::error!("Unable to open the scene file: {}", e);
+                self.load_default_scene();
+                return;
+            }
+        };
+        self.clear_scene();
 
         self.camera.inner = blade_render::Camera {
             pos: config_scene.camera.position,
@@ -309,7 +400,6 @@ impl Example {
         self.ray_config.environment_importance_sampling = !config_scene.environment_map.is_empty();
         self.post_proc_config.average_luminocity = config_scene.average_luminocity;
 
-        self.environment_map = None;
         let parent = scene_path.parent().unwrap();
         let mut load_finish = self.choir.spawn("load finish").init_dummy();
 
@@ -318,6 +408,8 @@ impl Example {
                 format: gpu::TextureFormat::Rgba32Float,
                 generate_mips: false,
                 y_flip: false,
+                max_dimension: None,
+                decode_srgb: false,
             };
             let (texture, texture_task) = self
                 .asset_hub
@@ -342,6 +434,8 @@ impl Example {
             });
             self.object_extras.push(ObjectExtra {
                 path: PathBuf::from(config_object.path),
+                animation: None,
+                posed_time: None,
             });
         }
 
@@ -377,12 +471,103 @@ impl Example {
         log::info!("Saving scene to: {}", scene_path.display());
     }
 
+    fn view_path(&self) -> PathBuf {
+        self.scene_path.with_extension("view.ron")
+    }
+
+    fn save_view(&self) {
+        let config_view = ConfigView {
+            camera: self.camera.inner,
+            ray: self.ray_config,
+            denoiser: self.denoiser_config,
+            post_proc: self.post_proc_config,
+            settings: self.renderer.export_settings(),
+        };
+        let path = self.view_path();
+        let string = ron::ser::to_string_pretty(&config_view, ron::ser::PrettyConfig::default())
+            .expect("Unable to form the view file");
+        fs::write(&path, &string).expect("Unable to write the view file");
+        log::info!("Saving view to: {}", path.display());
+    }
+
+    fn load_view(&mut self) {
+        let path = self.view_path();
+        let config_view: ConfigView = match fs::read(&path) {
+            Ok(data) => ron::de::from_bytes(&data).expect("Unable to parse the view file"),
+            Err(e) => {
+                log::error!("Unable to open the view file {}: {:?}", path.display(), e);
+                return;
+            }
+        };
+        log::info!("Loading view from: {}", path.display());
+        self.camera.inner = config_view.camera;
+        self.ray_config = config_view.ray;
+        self.denoiser_config = config_view.denoiser;
+        self.post_proc_config = blade_render::PostProcConfig {
+            viewport: self.post_proc_config.viewport,
+            ..config_view.post_proc
+        };
+        // these are pushed into the renderer every frame
+        let settings = &config_view.settings;
+        self.render_mode = settings.mode;
+        self.mip_bias = settings.mip_bias;
+        self.firefly_clamp = settings.firefly_clamp;
+        self.fog_config = settings.fog;
+        self.grid = settings.grid;
+        self.adaptive_sampling = settings.adaptive_sampling;
+        self.auto_exposure = settings.auto_exposure;
+        self.caustics = settings.caustics;
+        self.renderer.import_settings(settings);
+        self.need_accumulation_reset = true;
+    }
+
     fn reset_object_motion(&mut self) {
         for object in self.objects.iter_mut() {
             object.prev_transform = object.transform;
         }
     }
 
+    fn needs_redraw(&self) -> bool {
+        self.renderer.needs_redraw()
+            || self.scene_load_task.is_some()
+            || self.have_objects_changed
+            || self.need_accumulation_reset
+            || self.is_point_selected
+            || self.is_file_hovered
+            || self
+                .object_extras
+                .iter()
+                .any(|extra| extra.animation.as_ref().map_or(false, |a| a.is_playing()))
+    }
+
+    fn update_animations(&mut self) {
+        let now = time::Instant::now();
+        let delta = now.duration_since(self.last_frame_time).as_secs_f32();
+        self.last_frame_time = now;
+        for (index, (object, extra)) in self
+            .objects
+            .iter()
+            .zip(self.object_extras.iter_mut())
+            .enumerate()
+        {
+            let model = &self.asset_hub.models[object.model];
+            if extra.animation.is_none() && !model.animations.is_empty() {
+                extra.animation = Some(blade_render::AnimationPlayer::new(model));
+            }
+            let player = match extra.animation {
+                Some(ref mut player) => player,
+                None => continue,
+            };
+            player.advance(delta);
+            if extra.posed_time != Some(player.time()) {
+                let pose = player.pose(model);
+                self.renderer.set_object_pose(index, Some(&pose));
+                extra.posed_time = Some(player.time());
+                self.have_objects_changed = true;
+            }
+        }
+    }
+
     #[profiling::function]
     fn render(
         &mut self,
@@ -430,6 +615,9 @@ impl Example {
             }
         }
 
+        if self.scene_load_task.is_none() {
+            self.update_animations();
+        }
         if self.scene_load_task.is_none() && self.have_objects_changed {
             assert_eq!(self.objects.len(), self.object_extras.len());
             self.renderer.build_scene(
@@ -460,6 +648,19 @@ impl Example {
                 },
             );
             self.need_accumulation_reset = false;
+            self.renderer.set_mip_bias(self.mip_bias);
+            self.renderer.set_firefly_clamp(self.firefly_clamp);
+            self.renderer.set_mode(self.render_mode);
+            self.renderer.set_fog(self.fog_config);
+            self.renderer.set_grid(self.grid);
+            self.renderer.set_adaptive_sampling(self.adaptive_sampling);
+            self.renderer.set_auto_exposure(self.auto_exposure);
+            self.renderer.set_caustics(self.caustics);
+            self.renderer.set_environment_rotation(
+                glam::Quat::from_rotation_y(self.environment_yaw.to_radians()).into(),
+            );
+            self.renderer
+                .set_environment_intensity(self.environment_intensity);
 
             //TODO: figure out why the main RT pipeline
             // causes a GPU crash when there are no objects
@@ -486,8 +687,9 @@ impl Example {
                 depth_stencil: None,
             },
         ) {
+            let surface_extent = self.surface.extent();
             let screen_desc = blade_egui::ScreenDescriptor {
-                physical_size: (physical_size.width, physical_size.height),
+                physical_size: (surface_extent.width, surface_extent.height),
                 scale_factor,
             };
             if do_render {
@@ -591,6 +793,12 @@ impl Example {
                     &self.asset_hub,
                     ui,
                 );
+                if ui
+                    .checkbox(&mut self.draw_instance_bounds, "Draw instance bounds")
+                    .changed()
+                {
+                    self.renderer.debug_draw_aabbs(self.draw_instance_bounds);
+                }
 
                 // blits
                 ui.label("Debug blit:");
@@ -645,12 +853,55 @@ impl Example {
             });
 
         let old_ray_config = self.ray_config;
+        let old_mip_bias = self.mip_bias;
+        let old_firefly_clamp = self.firefly_clamp;
+        let old_render_mode = self.render_mode;
         egui::CollapsingHeader::new("Ray Trace")
             .default_open(false)
             .show(ui, |ui| {
+                ui.horizontal(|ui| {
+                    ui.label("Mode:");
+                    ui.selectable_value(
+                        &mut self.render_mode,
+                        blade_render::RenderMode::PathTraced,
+                        "Path traced",
+                    );
+                    ui.selectable_value(
+                        &mut self.render_mode,
+                        blade_render::RenderMode::DirectOnly,
+                        "Direct only",
+                    );
+                    ui.selectable_value(
+                        &mut self.render_mode,
+                        blade_render::RenderMode::Matcap,
+                        "Matcap",
+                    );
+                });
+                let mut paused = self.renderer.is_accumulation_paused();
+                if ui.checkbox(&mut paused, "Pause accumulation").changed() {
+                    self.renderer.set_accumulation_paused(paused);
+                }
                 self.ray_config.populate_hud(ui);
+                ui.add(egui::Slider::new(&mut self.mip_bias, -4.0f32..=4.0f32).text("Mip bias"));
+                let mut clamp_enabled = self.firefly_clamp.is_finite();
+                ui.checkbox(&mut clamp_enabled, "Clamp fireflies");
+                if !clamp_enabled {
+                    self.firefly_clamp = f32::INFINITY;
+                } else {
+                    if !self.firefly_clamp.is_finite() {
+                        self.firefly_clamp = 10.0;
+                    }
+                    ui.add(
+                        egui::Slider::new(&mut self.firefly_clamp, 0.1f32..=100.0f32)
+                            .logarithmic(true)
+                            .text("Max luminance"),
+                    );
+                }
             });
-        self.need_accumulation_reset |= self.ray_config != old_ray_config;
+        self.need_accumulation_reset |= self.ray_config != old_ray_config
+            || self.mip_bias != old_mip_bias
+            || self.firefly_clamp != old_firefly_clamp
+            || self.render_mode != old_render_mode;
 
         egui::CollapsingHeader::new("Denoise")
             .default_open(false)
@@ -661,6 +912,49 @@ impl Example {
 
         egui::CollapsingHeader::new("Tone Map").show(ui, |ui| {
             self.post_proc_config.populate_hud(ui);
+            self.auto_exposure.populate_hud(ui);
+            if let Some(ev) = self.renderer.auto_exposure_ev() {
+                ui.label(format!("Current EV: {ev:.1}"));
+            }
+        });
+
+        egui::CollapsingHeader::new("Environment").show(ui, |ui| {
+            ui.add(
+                egui::Slider::new(&mut self.environment_yaw, -180.0f32..=180.0f32).text("Rotation"),
+            );
+            ui.add(
+                egui::Slider::new(&mut self.environment_intensity, 0.0f32..=10.0f32)
+                    .text("Intensity")
+                    .logarithmic(true),
+            );
+        });
+
+        egui::CollapsingHeader::new("Caustics").show(ui, |ui| {
+            self.caustics.populate_hud(ui);
+        });
+
+        egui::CollapsingHeader::new("Fog").show(ui, |ui| {
+            self.fog_config.populate_hud(ui);
+        });
+
+        egui::CollapsingHeader::new("Grid").show(ui, |ui| {
+            let mut enabled = self.grid.is_some();
+            ui.checkbox(&mut enabled, "Enabled");
+            match (enabled, self.grid.as_mut()) {
+                (true, Some(config)) => config.populate_hud(ui),
+                (true, None) => self.grid = Some(Default::default()),
+                (false, _) => self.grid = None,
+            }
+        });
+
+        egui::CollapsingHeader::new("Adaptive sampling").show(ui, |ui| {
+            let mut enabled = self.adaptive_sampling.is_some();
+            ui.checkbox(&mut enabled, "Enabled");
+            match (enabled, self.adaptive_sampling.as_mut()) {
+                (true, Some(config)) => config.populate_hud(ui),
+                (true, None) => self.adaptive_sampling = Some(Default::default()),
+                (false, _) => self.adaptive_sampling = None,
+            }
         });
     }
 
@@ -669,7 +963,11 @@ impl Example {
         ui.group(|ui| {
             ui.colored_label(egui::Color32::WHITE, self.scene_path.display().to_string());
             ui.horizontal(|ui| {
-                if ui.button("Save").clicked() {
+                let has_path = !self.scene_path.as_os_str().is_empty();
+                if ui
+                    .add_enabled(has_path, egui::Button::new("Save"))
+                    .clicked()
+                {
                     self.save_scene(&self.scene_path);
                 }
                 if ui.button("Reload").clicked() {
@@ -677,6 +975,14 @@ impl Example {
                     self.load_scene(&path);
                 }
             });
+            ui.horizontal(|ui| {
+                if ui.button("Save view").clicked() {
+                    self.save_view();
+                }
+                if ui.button("Load view").clicked() {
+                    self.load_view();
+                }
+            });
         });
 
         egui::CollapsingHeader::new("Objects")
@@ -697,14 +1003,42 @@ impl Example {
                     }
                     if ui.button("Delete!").clicked() {
                         self.selected_object_index = None;
+                        // the poses are by index, so the following objects need new ones
+                        for (i, extra) in self.object_extras.iter_mut().enumerate().skip(index) {
+                            self.renderer.set_object_pose(i, None);
+                            extra.posed_time = None;
+                        }
                         self.objects.remove(index);
                         self.object_extras.remove(index);
                         self.have_objects_changed = true;
                     }
-                })
+                });
+                if let Some(object) = self.objects.get(index) {
+                    let model = &self.asset_hub.models[object.model];
+                    let stats = model.stats(&self.asset_hub.textures);
+                    ui.label(format!(
+                        "{} geometries, {} vertices, {} triangles",
+                        stats.geometry_count, stats.vertex_count, stats.triangle_count
+                    ));
+                    ui.label(format!(
+                        "{} materials, {} textures",
+                        stats.material_count, stats.texture_count
+                    ));
+                    let mib = |bytes: u64| bytes as f64 / (1024.0 * 1024.0);
+                    ui.label(format!(
+                        "GPU memory: buffers {:.1} MiB, BLAS {:.1} MiB, textures {:.1} MiB",
+                        mib(stats.buffer_bytes),
+                        mib(stats.acceleration_structure_bytes),
+                        mib(stats.texture_bytes)
+                    ));
+                }
             });
         }
 
+        if let Some(index) = self.selected_object_index {
+            self.add_animation_timeline(index, ui);
+        }
+
         if let Some(index) = self.selected_object_index {
             egui::CollapsingHeader::new("Transform")
                 .default_open(true)
@@ -741,6 +1075,61 @@ impl Example {
         }
     }
 
+    fn add_animation_timeline(&mut self, obj_index: usize, ui: &mut egui::Ui) {
+        let model = match self.objects.get(obj_index) {
+            Some(object) => &self.asset_hub.models[object.model],
+            None => return,
+        };
+        let player = match self.object_extras[obj_index].animation {
+            Some(ref mut player) => player,
+            None => return,
+        };
+        egui::CollapsingHeader::new("Animation")
+            .default_open(true)
+            .show(ui, |ui| {
+                let clip_name = |index: usize| match model.animations[index].name.as_str() {
+                    "" => format!("Clip {index}"),
+                    name => name.to_string(),
+                };
+                let mut clip_index = player.clip_index();
+                egui::ComboBox::from_label("Clip")
+                    .selected_text(clip_name(clip_index))
+                    .show_ui(ui, |ui| {
+                        for index in 0..model.animations.len() {
+                            ui.selectable_value(&mut clip_index, index, clip_name(index));
+                        }
+                    });
+                if clip_index != player.clip_index() {
+                    player.select_clip(model, clip_index);
+                }
+                ui.horizontal(|ui| {
+                    if player.is_playing() {
+                        if ui.button("Pause").clicked() {
+                            player.pause();
+                        }
+                    } else if ui.button("Play").clicked() {
+                        player.play();
+                    }
+                    let mut looping = player.is_looping();
+                    if ui.checkbox(&mut looping, "Loop").changed() {
+                        player.set_looping(looping);
+                    }
+                });
+                let mut time = player.time();
+                let timeline = egui::Slider::new(&mut time, 0.0..=player.duration()).text("Time");
+                if ui.add(timeline).changed() {
+                    player.seek(time);
+                }
+                let mut speed = player.speed();
+                if ui
+                    .add(egui::Slider::new(&mut speed, -2.0..=2.0).text("Speed"))
+                    .changed()
+                {
+                    player.set_speed(speed);
+                }
+            });
+    }
+
     fn find_object(&self, geometry_index: u32) -> Option<usize> {
         let mut index = geometry_index as usize;
         for (obj_index, object) in self.objects.iter().enumerate() {
@@ -788,6 +1177,8 @@ impl Example {
         });
         self.object_extras.push(ObjectExtra {
             path: file_path.to_owned(),
+            animation: None,
+            posed_time: None,
         });
         true
     }
@@ -806,12 +1197,11 @@ fn main() {
     let mut egui_winit = egui_winit::State::new(egui_ctx, viewport_id, &window, None, None, None);
 
     let mut args = std::env::args();
-    let path_to_scene = args
-        .nth(1)
-        .unwrap_or("examples/scene/data/scene.ron".to_string());
-
     let mut example = Example::new(&window);
-    example.load_scene(Path::new(&path_to_scene));
+    match args.nth(1) {
+        Some(path_to_scene) => example.load_scene(Path::new(&path_to_scene)),
+        None => example.load_default_scene(),
+    }
 
     struct Drag {
         _screen_pos: glam::IVec2,
@@ -824,7 +1214,6 @@ fn main() {
     event_loop
         .run(|event, target| {
             example.choir.check_panic();
-            target.set_control_flow(winit::event_loop::ControlFlow::Poll);
 
             let delta = last_event.elapsed().as_secs_f32();
             let drag_speed = 0.01f32;
@@ -832,11 +1221,16 @@ fn main() {
 
             match event {
                 winit::event::Event::AboutToWait => {
-                    window.request_redraw();
+                    // Let the GPU idle once the image has converged
+                    if example.needs_redraw() {
+                        window.request_redraw();
+                    }
                 }
                 winit::event::Event::WindowEvent { event, .. } => {
                     let response = egui_winit.on_window_event(&window, &event);
-                    if response.repaint {
+                    if response.repaint
+                        || !matches!(event, winit::event::WindowEvent::RedrawRequested)
+                    {
                         window.request_redraw();
                     }
                     if response.consumed {
@@ -855,6 +1249,8 @@ fn main() {
                         } => {
                             if key_code == winit::keyboard::KeyCode::Escape {
                                 target.exit();
+                            } else if key_code == winit::keyboard::KeyCode::F12 {
+                                example.context.trigger_capture();
                             } else if drag_start.is_none() && example.camera.on_key(key_code, delta)
                             {
                                 example.debug.mouse_pos = None;